@@ -165,6 +165,7 @@ mod tests {
             ],
             links_to: Vec::new(),
             sharing: Sharing::Public,
+            countersigned: false,
         };
         assert_eq!(
             expected_definition,
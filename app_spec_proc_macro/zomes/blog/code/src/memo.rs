@@ -104,6 +104,7 @@ mod tests {
             linked_from: vec![],
             links_to: Vec::new(),
             sharing: Sharing::Private,
+            countersigned: false,
         };
         assert_eq!(
             expected_definition,
@@ -1,18 +1,334 @@
 use crate::error::DefaultResult;
 use colored::*;
-use holochain_core::agent::{
-    chain_store::ChainStore,
-    state::{AgentState, AgentStateSnapshot},
+use holochain_core::{
+    agent::{
+        chain_store::{ChainStore, ChainStoreQueryOptions, ChainStoreQueryResult},
+        chain_verification::verify_chain,
+        state::{AgentState, AgentStateSnapshot},
+    },
+    dht::dht_store::DhtStore,
+};
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    crud_status::CrudStatus,
+    eav::{Attribute, EntityAttributeValueIndex},
+    entry::Entry,
+    time::Iso8601,
+};
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent},
+        storage::ContentAddressableStorage,
+    },
+    eav::EntityAttributeValueStorage,
+};
+use holochain_persistence_file::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+use serde_json;
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
 };
-use holochain_core_types::{chain_header::ChainHeader, entry::Entry};
-use holochain_persistence_api::cas::content::Address;
-use holochain_persistence_file::cas::file::FilesystemStorage;
-use std::{convert::TryFrom, fs, path::PathBuf};
 
 // TODO: use system-agnostic default path
 const DEFAULT_CHAIN_PATH: &str = "TODO";
 
-pub fn chain_log(storage_path: Option<PathBuf>, instance_id: String) -> DefaultResult<()> {
+/// How `hc chain` prints the selected entries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChainLogFormat {
+    /// Colored, human readable text
+    Text,
+    /// One JSON array of all items
+    Json,
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl FromStr for ChainLogFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(ChainLogFormat::Text),
+            "json" => Ok(ChainLogFormat::Json),
+            "ndjson" => Ok(ChainLogFormat::Ndjson),
+            _ => Err(format!(
+                "Unknown format '{}', expected one of: text, json, ndjson",
+                format
+            )),
+        }
+    }
+}
+
+/// Which entries of a chain `hc chain` shows and how.
+/// Filters are applied in order: entry types, time range, then the offset/limit window.
+#[derive(Clone, Debug)]
+pub struct ChainLogOptions {
+    pub format: ChainLogFormat,
+    /// Entry type names or glob patterns, as understood by `ChainStore::query`.
+    /// Empty selects all entry types.
+    pub entry_types: Vec<String>,
+    /// Only headers with a timestamp at or after this one
+    pub since: Option<Iso8601>,
+    /// Only headers with a timestamp at or before this one
+    pub until: Option<Iso8601>,
+    /// Number of matching headers to skip, counted from the top of the chain
+    pub offset: usize,
+    /// Maximum number of headers to show
+    pub limit: Option<usize>,
+    /// Include the CRUD status and links held in the instance's EAV store
+    pub meta: bool,
+}
+
+impl Default for ChainLogOptions {
+    fn default() -> Self {
+        ChainLogOptions {
+            format: ChainLogFormat::Text,
+            entry_types: Vec::new(),
+            since: None,
+            until: None,
+            offset: 0,
+            limit: None,
+            meta: false,
+        }
+    }
+}
+
+/// One selected chain entry as printed by `hc chain --format json|ndjson`
+#[derive(Debug, Serialize)]
+pub struct ChainLogItem {
+    pub header_address: Address,
+    pub header: ChainHeader,
+    /// None if the entry is missing from the CAS
+    pub entry: Option<Entry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<EntryMeta>,
+}
+
+/// DHT metadata held for an entry
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct EntryMeta {
+    /// None if the entry is not held in the DHT, e.g. because it is private
+    pub crud_status: Option<CrudStatus>,
+    pub crud_link: Option<Address>,
+    pub links: Vec<LinkMeta>,
+}
+
+/// A link held on an entry, added or removed
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LinkMeta {
+    pub link_type: String,
+    pub tag: String,
+    /// Address of the LinkAdd entry, or of the LinkRemove entry if the link got removed
+    pub link_entry_address: Address,
+    /// Live or Deleted
+    pub status: CrudStatus,
+}
+
+pub fn chain_log(
+    storage_path: Option<PathBuf>,
+    instance_id: String,
+    options: ChainLogOptions,
+) -> DefaultResult<()> {
+    let (cas_path, agent) = load_agent_state(storage_path, instance_id)?;
+    let headers = select_headers(&agent, &options)?;
+    let dht_store = if options.meta {
+        Some(open_dht_store(&agent, &cas_path)?)
+    } else {
+        None
+    };
+
+    let cas_lock = agent.chain_store().content_storage();
+    let cas = cas_lock.read().unwrap();
+    let items = headers
+        .into_iter()
+        .map(|header| {
+            let entry = fetch_entry(&*cas, &header)?;
+            let meta = match dht_store {
+                Some(ref dht_store) => Some(entry_meta(dht_store, header.entry_address())?),
+                None => None,
+            };
+            Ok(ChainLogItem {
+                header_address: header.address(),
+                header,
+                entry,
+                meta,
+            })
+        })
+        .collect::<DefaultResult<Vec<_>>>()?;
+
+    match options.format {
+        ChainLogFormat::Text => {
+            println!(
+                "\nChain entries for '{}' (latest on top):\n",
+                cas_path.to_string_lossy()
+            );
+            for item in items.iter() {
+                display_item(item);
+            }
+        }
+        ChainLogFormat::Json => println!("{}", serde_json::to_string_pretty(&items)?),
+        ChainLogFormat::Ndjson => {
+            for item in items.iter() {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Headers of the chain that match the entry type, time range and window of `options`,
+/// latest first
+fn select_headers(
+    agent: &AgentState,
+    options: &ChainLogOptions,
+) -> DefaultResult<Vec<ChainHeader>> {
+    let entry_type_names: Vec<&str> = options.entry_types.iter().map(String::as_str).collect();
+    let headers = match agent
+        .chain_store()
+        .query(
+            &agent.top_chain_header(),
+            &entry_type_names,
+            ChainStoreQueryOptions {
+                start: 0,
+                limit: 0,
+                headers: true,
+                ..Default::default()
+            },
+        )
+        .map_err(|code| format_err!("Could not query chain: {}", code.as_str()))?
+    {
+        ChainStoreQueryResult::Headers(headers) => headers,
+        ChainStoreQueryResult::Addresses(_) => unreachable!(),
+    };
+    Ok(filter_headers(headers, options))
+}
+
+fn filter_headers(headers: Vec<ChainHeader>, options: &ChainLogOptions) -> Vec<ChainHeader> {
+    headers
+        .into_iter()
+        .filter(|header| match options.since {
+            Some(ref since) => header.timestamp() >= since,
+            None => true,
+        })
+        .filter(|header| match options.until {
+            Some(ref until) => header.timestamp() <= until,
+            None => true,
+        })
+        .skip(options.offset)
+        .take(options.limit.unwrap_or(usize::max_value()))
+        .collect()
+}
+
+fn fetch_entry(
+    cas: &dyn ContentAddressableStorage,
+    header: &ChainHeader,
+) -> DefaultResult<Option<Entry>> {
+    match cas.fetch(header.entry_address())? {
+        None => Ok(None),
+        Some(content) => Entry::try_from(content).map(Some).map_err(|err| {
+            format_err!(
+                "Invalid content for entry {}: {}",
+                header.entry_address(),
+                err
+            )
+        }),
+    }
+}
+
+/// Opens the DHT store of an instance, which shares the CAS with the chain
+/// and keeps its EAV next to it, like the conductor's file storage does
+fn open_dht_store(agent: &AgentState, cas_path: &Path) -> DefaultResult<DhtStore> {
+    let eav_path = cas_path
+        .parent()
+        .map(|instance_path| instance_path.join("eav"))
+        .ok_or_else(|| format_err!("Invalid storage path '{}'", cas_path.to_string_lossy()))?;
+    if !eav_path.is_dir() {
+        return Err(format_err!(
+            "No EAV storage found at '{}'",
+            eav_path.to_string_lossy()
+        ));
+    }
+    let eav: Arc<RwLock<EntityAttributeValueStorage<Attribute>>> =
+        Arc::new(RwLock::new(EavFileStorage::new(eav_path)?));
+    Ok(DhtStore::new(agent.chain_store().content_storage(), eav))
+}
+
+fn entry_meta(dht_store: &DhtStore, entry_address: &Address) -> DefaultResult<EntryMeta> {
+    Ok(meta_from_eavis(&dht_store.get_all_metas(entry_address)?))
+}
+
+/// Collects the CRUD status and links from the EAVIs of an entry.
+/// If several statuses are held, Deleted wins over Modified wins over Live,
+/// like when getting the entry.
+fn meta_from_eavis(eavis: &BTreeSet<EntityAttributeValueIndex>) -> EntryMeta {
+    let mut meta = EntryMeta::default();
+    for eavi in eavis.iter() {
+        match eavi.attribute() {
+            Attribute::CrudStatus => {
+                if let Ok(status) = CrudStatus::from_str(String::from(eavi.value()).as_ref()) {
+                    let priority = |status: &Option<CrudStatus>| match status {
+                        Some(CrudStatus::Deleted) => 3,
+                        Some(CrudStatus::Modified) => 2,
+                        Some(_) => 1,
+                        None => 0,
+                    };
+                    if priority(&Some(status)) > priority(&meta.crud_status) {
+                        meta.crud_status = Some(status);
+                    }
+                }
+            }
+            Attribute::CrudLink => meta.crud_link = Some(eavi.value()),
+            Attribute::LinkTag(link_type, tag) => meta.links.push(LinkMeta {
+                link_type,
+                tag,
+                link_entry_address: eavi.value(),
+                status: CrudStatus::Live,
+            }),
+            Attribute::RemovedLink(link_type, tag) => meta.links.push(LinkMeta {
+                link_type,
+                tag,
+                link_entry_address: eavi.value(),
+                status: CrudStatus::Deleted,
+            }),
+            _ => (),
+        }
+    }
+    meta
+}
+
+pub fn chain_verify(storage_path: Option<PathBuf>, instance_id: String) -> DefaultResult<()> {
+    let (cas_path, agent) = load_agent_state(storage_path, instance_id)?;
+    let report = verify_chain(&agent.chain_store(), &agent.top_chain_header())?;
+
+    println!(
+        "\nChecked {} headers of chain '{}'.\n",
+        report.headers_checked,
+        cas_path.to_string_lossy()
+    );
+    if report.is_valid() {
+        println!("{}", "Chain is intact.".green());
+        return Ok(());
+    }
+    for problem in report.problems.iter() {
+        println!("{} {}", "-".red(), problem);
+    }
+    Err(format_err!(
+        "Found {} problems in chain '{}'",
+        report.problems.len(),
+        cas_path.to_string_lossy()
+    ))
+}
+
+/// Loads the agent state of an instance from the snapshot in its CAS
+fn load_agent_state(
+    storage_path: Option<PathBuf>,
+    instance_id: String,
+) -> DefaultResult<(PathBuf, AgentState)> {
     // let storage_path = storage_path.unwrap_or_else(|| PathBuf::from(DEFAULT_CHAIN_PATH));
     let storage_path = storage_path.ok_or(format_err!(
         "Please specify the path to CAS storage with the --path option."
@@ -33,36 +349,20 @@ pub fn chain_log(storage_path: Option<PathBuf>, instance_id: String) -> DefaultR
         })
         .map(|snapshot| {
             let top_header = snapshot.top_chain_header().to_owned().clone();
-            AgentState::new_with_top_chain_header(chain_store, top_header.cloned(), Address::new())
+            AgentState::new_with_top_chain_header(
+                chain_store.clone(),
+                top_header.cloned(),
+                Address::new(),
+            )
         })
         .map_err(|err| {
             format_err!(
-                "Could not display chain for '{}': {}",
+                "Could not load chain for '{}': {}",
                 cas_path.to_string_lossy(),
                 err.to_string()
             )
         })?;
-
-    println!(
-        "\nChain entries for '{}' (latest on top):\n",
-        cas_path.to_string_lossy()
-    );
-    for ref header in agent.iter_chain() {
-        let content = cas
-            .fetch(header.entry_address())
-            .expect("Panic while fetching from CAS!")
-            .ok_or_else(|| {
-                println!(
-                    "{:?} referenced in header but not found in CAS!",
-                    header.entry_address(),
-                )
-            })
-            .unwrap();
-        let entry = Entry::try_from(content).expect("Invalid content");
-        display_header(&header, &entry);
-    }
-
-    Ok(())
+    Ok((cas_path, agent))
 }
 
 pub fn chain_list(path: Option<PathBuf>) {
@@ -75,12 +375,116 @@ pub fn chain_list(path: Option<PathBuf>) {
     }
 }
 
-fn display_header(header: &ChainHeader, entry: &Entry) {
+fn display_item(item: &ChainLogItem) {
     println!(
         "{} {}",
-        header.timestamp().to_string().bright_black(),
+        item.header.timestamp().to_string().bright_black(),
         // format!("{:?}", header.entry_type()).blue().bold(),
-        header.entry_address().to_string().yellow(),
+        item.header.entry_address().to_string().yellow(),
     );
-    println!("{:#?}", entry);
+    match item.entry {
+        Some(ref entry) => println!("{:#?}", entry),
+        None => println!(
+            "{}",
+            "Entry referenced in header but not found in CAS!".red()
+        ),
+    }
+    if let Some(ref meta) = item.meta {
+        let status = meta
+            .crud_status
+            .map(|status| format!("{:?}", status))
+            .unwrap_or_else(|| "not held".to_string());
+        println!("{} {}", "status:".bright_black(), status);
+        if let Some(ref crud_link) = meta.crud_link {
+            println!("{} {}", "updated/removed by:".bright_black(), crud_link);
+        }
+        for link in meta.links.iter() {
+            println!(
+                "{} {} [{}] {} ({:?})",
+                "link:".bright_black(),
+                link.link_type,
+                link.tag,
+                link.link_entry_address,
+                link.status
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_core_types::entry::{entry_type::EntryType, test_entry};
+
+    fn header_at(seconds: u64) -> ChainHeader {
+        ChainHeader::new(
+            &EntryType::App("testEntryType".into()),
+            &test_entry().address(),
+            &Vec::new(),
+            &None,
+            &None,
+            &None,
+            &Iso8601::from(seconds),
+        )
+    }
+
+    #[test]
+    fn parses_chain_log_formats() {
+        assert_eq!("text".parse(), Ok(ChainLogFormat::Text));
+        assert_eq!("json".parse(), Ok(ChainLogFormat::Json));
+        assert_eq!("ndjson".parse(), Ok(ChainLogFormat::Ndjson));
+        assert!("yaml".parse::<ChainLogFormat>().is_err());
+    }
+
+    #[test]
+    fn filters_headers_by_time_and_window() {
+        let headers: Vec<ChainHeader> = (0..5).rev().map(|i| header_at(i * 10)).collect();
+        let options = ChainLogOptions {
+            since: Some(Iso8601::from(10u64)),
+            until: Some(Iso8601::from(30u64)),
+            offset: 1,
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter_headers(headers.clone(), &options)
+                .iter()
+                .map(|header| header.timestamp().clone())
+                .collect::<Vec<_>>(),
+            vec![Iso8601::from(20u64)],
+        );
+        assert_eq!(
+            filter_headers(headers, &ChainLogOptions::default()).len(),
+            5
+        );
+    }
+
+    #[test]
+    fn collects_entry_meta() {
+        let entry = test_entry().address();
+        let eavi = |attribute: Attribute, value: &str| {
+            EntityAttributeValueIndex::new(&entry, &attribute, &Address::from(value)).unwrap()
+        };
+        let mut eavis = BTreeSet::new();
+        eavis.insert(eavi(Attribute::CrudStatus, "live"));
+        eavis.insert(eavi(Attribute::CrudStatus, "modified"));
+        eavis.insert(eavi(Attribute::CrudLink, "new_entry"));
+        eavis.insert(eavi(
+            Attribute::LinkTag("likes".into(), "cats".into()),
+            "link_add",
+        ));
+
+        let meta = meta_from_eavis(&eavis);
+        assert_eq!(meta.crud_status, Some(CrudStatus::Modified));
+        assert_eq!(meta.crud_link, Some(Address::from("new_entry")));
+        assert_eq!(
+            meta.links,
+            vec![LinkMeta {
+                link_type: "likes".into(),
+                tag: "cats".into(),
+                link_entry_address: Address::from("link_add"),
+                status: CrudStatus::Live,
+            }]
+        );
+    }
 }
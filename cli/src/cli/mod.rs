@@ -5,14 +5,16 @@ mod keygen;
 pub mod package;
 mod run;
 mod scaffold;
+mod storage;
 pub mod test;
 
 pub use self::{
-    chain_log::{chain_list, chain_log},
+    chain_log::{chain_list, chain_log, chain_verify, ChainLogFormat, ChainLogOptions},
     generate::generate,
     init::init,
     keygen::keygen,
     package::{package, unpack},
     run::{get_interface_type_string, hc_run_configuration, run},
+    storage::{storage_migrate, StorageSpec},
     test::{test, TEST_DIR_NAME},
 };
@@ -137,6 +137,7 @@ fn storage_configuration(persist: bool) -> DefaultResult<StorageConfiguration> {
 
         Ok(StorageConfiguration::Pickle {
             path: LOCAL_STORAGE_PATH.into(),
+            encrypted: false,
         })
     } else {
         Ok(StorageConfiguration::Memory)
@@ -152,6 +153,7 @@ fn instance_configuration(storage: StorageConfiguration) -> InstanceConfiguratio
         dna: DNA_CONFIG_ID.into(),
         agent: AGENT_CONFIG_ID.into(),
         storage,
+        persister: PersisterConfiguration::default(),
     }
 }
 
@@ -224,6 +226,8 @@ fn networking_configuration(networked: bool) -> Option<NetworkConfig> {
             .unwrap_or_else(default_n3h_persistence_path),
         n3h_ipc_uri: Default::default(),
         networking_config_file: EnvVar::NetworkingConfigFile.value().ok(),
+        tcp_bind_address: None,
+        tcp_advertise_address: None,
     })
 }
 
@@ -305,7 +309,8 @@ mod tests {
         assert_eq!(
             persist_store,
             StorageConfiguration::Pickle {
-                path: ".hc".to_string()
+                path: ".hc".to_string(),
+                encrypted: false,
             }
         );
     }
@@ -321,6 +326,7 @@ mod tests {
                 dna: "hc-run-dna".to_string(),
                 agent: "hc-run-agent".to_string(),
                 storage: StorageConfiguration::Memory,
+                persister: PersisterConfiguration::Simple,
             }
         )
     }
@@ -370,6 +376,8 @@ mod tests {
                 n3h_persistence_path: default_n3h_persistence_path(),
                 n3h_ipc_uri: Default::default(),
                 networking_config_file: None,
+                tcp_bind_address: None,
+                tcp_advertise_address: None,
             })
         );
 
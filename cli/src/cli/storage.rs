@@ -0,0 +1,364 @@
+use crate::error::DefaultResult;
+use holochain_conductor_api::{
+    conductor::instance_archive::ArchivedContent,
+    storage::kv::{EavKvStorage, KvStorage, KvStore},
+};
+use holochain_core::{
+    agent::{
+        chain_store::ChainStore,
+        state::{AgentState, AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
+    },
+    nucleus::state::NUCLEUS_SNAPSHOT_ADDRESS,
+};
+use holochain_core_types::eav::{Attribute, EntityAttributeValueIndex};
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent},
+        storage::ContentAddressableStorage,
+    },
+    eav::{query::EaviQuery, EntityAttributeValueStorage, IndexFilter},
+};
+use holochain_persistence_file::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+use holochain_persistence_pickle::{cas::pickle::PickleStorage, eav::pickle::EavPickleStorage};
+use std::{
+    collections::BTreeSet,
+    fmt, fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+/// A storage backend and its location, given on the command line as `<type>:<path>`.
+/// The path is the same as the `path` of the instance's storage config.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StorageSpec {
+    File(PathBuf),
+    Pickle(PathBuf),
+    Kv(PathBuf),
+}
+
+impl FromStr for StorageSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut parts = spec.splitn(2, ':');
+        let storage_type = parts.next().unwrap_or_default();
+        let path = parts.next().filter(|path| !path.is_empty()).map(PathBuf::from);
+        match (storage_type, path) {
+            ("file", Some(path)) => Ok(StorageSpec::File(path)),
+            ("pickle", Some(path)) => Ok(StorageSpec::Pickle(path)),
+            ("kv", Some(path)) => Ok(StorageSpec::Kv(path)),
+            ("file", None) | ("pickle", None) | ("kv", None) => Err(format!(
+                "Storage '{}' needs a path, e.g. '{}:/path/to/storage'",
+                spec, storage_type
+            )),
+            ("memory", _) => Err("Memory storage only lives inside a running conductor".into()),
+            _ => Err(format!(
+                "Unknown storage type '{}', expected one of: file, pickle, kv",
+                storage_type
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StorageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageSpec::File(path) => write!(f, "file:{}", path.to_string_lossy()),
+            StorageSpec::Pickle(path) => write!(f, "pickle:{}", path.to_string_lossy()),
+            StorageSpec::Kv(path) => write!(f, "kv:{}", path.to_string_lossy()),
+        }
+    }
+}
+
+/// CAS and EAV storage of one instance, opened with the same layout the conductor uses.
+struct InstanceStorage {
+    spec: StorageSpec,
+    cas: Arc<RwLock<ContentAddressableStorage>>,
+    eav: Arc<RwLock<EntityAttributeValueStorage<Attribute>>>,
+    kv_cas: Option<KvStorage>,
+}
+
+impl InstanceStorage {
+    fn open(spec: &StorageSpec) -> DefaultResult<Self> {
+        let (cas, eav, kv_cas): (
+            Arc<RwLock<ContentAddressableStorage>>,
+            Arc<RwLock<EntityAttributeValueStorage<Attribute>>>,
+            Option<KvStorage>,
+        ) = match spec {
+            StorageSpec::File(path) => {
+                fs::create_dir_all(path.join("cas"))?;
+                fs::create_dir_all(path.join("eav"))?;
+                (
+                    Arc::new(RwLock::new(FilesystemStorage::new(path.join("cas"))?)),
+                    Arc::new(RwLock::new(EavFileStorage::new(path.join("eav"))?)),
+                    None,
+                )
+            }
+            StorageSpec::Pickle(path) => {
+                fs::create_dir_all(path.join("cas"))?;
+                fs::create_dir_all(path.join("eav"))?;
+                (
+                    Arc::new(RwLock::new(PickleStorage::new(path.join("cas")))),
+                    Arc::new(RwLock::new(EavPickleStorage::new(path.join("eav")))),
+                    None,
+                )
+            }
+            StorageSpec::Kv(path) => {
+                fs::create_dir_all(path)?;
+                let store = KvStore::open(path)?;
+                let kv_cas = KvStorage::new(&store)?;
+                (
+                    Arc::new(RwLock::new(kv_cas.clone())),
+                    Arc::new(RwLock::new(EavKvStorage::new(&store)?)),
+                    Some(kv_cas),
+                )
+            }
+        };
+        Ok(InstanceStorage {
+            spec: spec.clone(),
+            cas,
+            eav,
+            kv_cas,
+        })
+    }
+
+    /// Addresses of all CAS items, for backends that can list their content.
+    /// The pickle backend can't, so this returns None for it.
+    fn list_addresses(&self) -> DefaultResult<Option<BTreeSet<Address>>> {
+        match (&self.spec, &self.kv_cas) {
+            (StorageSpec::Kv(_), Some(kv_cas)) => {
+                Ok(Some(kv_cas.addresses()?.into_iter().collect()))
+            }
+            (StorageSpec::File(path), _) => {
+                let mut addresses = BTreeSet::new();
+                for file in fs::read_dir(path.join("cas"))? {
+                    let file_path = file?.path();
+                    if file_path.extension().map(|ext| ext == "json") == Some(true) {
+                        if let Some(stem) = file_path.file_stem() {
+                            addresses.insert(Address::from(stem.to_string_lossy().to_string()));
+                        }
+                    }
+                }
+                Ok(Some(addresses))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Addresses of all CAS items that are referenced from the state snapshots,
+    /// the source chain or the EAV index.
+    /// Used for backends that can't list their content.
+    fn reachable_addresses(
+        &self,
+        eavis: &BTreeSet<EntityAttributeValueIndex>,
+    ) -> DefaultResult<BTreeSet<Address>> {
+        let mut addresses = BTreeSet::new();
+        addresses.insert(Address::from(AGENT_SNAPSHOT_ADDRESS));
+        addresses.insert(Address::from(NUCLEUS_SNAPSHOT_ADDRESS));
+
+        let snapshot = self
+            .cas
+            .read()
+            .unwrap()
+            .fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))?;
+        if let Some(snapshot_json) = snapshot {
+            let snapshot = AgentStateSnapshot::from_json_str(&snapshot_json.to_string())
+                .map_err(|_| format_err!("AgentState is malformed"))?;
+            let agent = AgentState::new_with_top_chain_header(
+                ChainStore::new(self.cas.clone()),
+                snapshot.top_chain_header().cloned(),
+                Address::new(),
+            );
+            for header in agent.iter_chain() {
+                addresses.insert(header.address());
+                addresses.insert(header.entry_address().clone());
+            }
+        }
+
+        for eavi in eavis.iter() {
+            addresses.insert(eavi.entity());
+            addresses.insert(eavi.value());
+        }
+
+        let cas = self.cas.read().unwrap();
+        let mut present = BTreeSet::new();
+        for address in addresses {
+            if cas.contains(&address)? {
+                present.insert(address);
+            }
+        }
+        Ok(present)
+    }
+
+    fn all_eavis(&self) -> DefaultResult<BTreeSet<EntityAttributeValueIndex>> {
+        Ok(self.eav.read().unwrap().fetch_eavi(&EaviQuery::new(
+            None.into(),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        ))?)
+    }
+}
+
+/// Copies every CAS item and EAVI tuple from one instance storage into another
+/// (empty) one and verifies the copy afterwards.
+pub fn storage_migrate(from: StorageSpec, to: StorageSpec) -> DefaultResult<()> {
+    if from == to {
+        bail!("Source and target storage are the same");
+    }
+    let source = InstanceStorage::open(&from)?;
+    let target = InstanceStorage::open(&to)?;
+
+    let target_not_empty = match target.list_addresses()? {
+        Some(addresses) => !addresses.is_empty(),
+        None => target
+            .cas
+            .read()
+            .unwrap()
+            .contains(&Address::from(AGENT_SNAPSHOT_ADDRESS))?,
+    };
+    if target_not_empty || !target.all_eavis()?.is_empty() {
+        bail!("Target storage '{}' is not empty", to);
+    }
+
+    let eavis = source.all_eavis()?;
+    let addresses = match source.list_addresses()? {
+        Some(addresses) => addresses,
+        None => source.reachable_addresses(&eavis)?,
+    };
+
+    println!("Copying {} CAS items from '{}' to '{}'...", addresses.len(), from, to);
+    {
+        let source_cas = source.cas.read().unwrap();
+        let mut target_cas = target.cas.write().unwrap();
+        for address in addresses.iter() {
+            let content = source_cas
+                .fetch(address)?
+                .ok_or_else(|| format_err!("CAS item {} vanished during migration", address))?;
+            target_cas.add(&ArchivedContent {
+                address: address.clone(),
+                content: String::from(content),
+            })?;
+        }
+    }
+
+    println!("Copying {} EAVI tuples...", eavis.len());
+    {
+        let mut target_eav = target.eav.write().unwrap();
+        for eavi in eavis.iter() {
+            target_eav.add_eavi(eavi)?;
+        }
+    }
+
+    verify_migration(&source, &target, &addresses, &eavis)?;
+    println!(
+        "Migrated {} CAS items and {} EAVI tuples from '{}' to '{}'",
+        addresses.len(),
+        eavis.len(),
+        from,
+        to
+    );
+    Ok(())
+}
+
+fn verify_migration(
+    source: &InstanceStorage,
+    target: &InstanceStorage,
+    addresses: &BTreeSet<Address>,
+    eavis: &BTreeSet<EntityAttributeValueIndex>,
+) -> DefaultResult<()> {
+    if let Some(target_addresses) = target.list_addresses()? {
+        if target_addresses != *addresses {
+            bail!(
+                "Verification failed: target holds {} CAS items but {} were copied",
+                target_addresses.len(),
+                addresses.len()
+            );
+        }
+    }
+
+    let source_cas = source.cas.read().unwrap();
+    let target_cas = target.cas.read().unwrap();
+    for address in addresses.iter() {
+        if target_cas.fetch(address)? != source_cas.fetch(address)? {
+            bail!(
+                "Verification failed: CAS item {} differs between source and target",
+                address
+            );
+        }
+    }
+
+    let target_eavis = target.all_eavis()?;
+    if target_eavis != *eavis {
+        bail!(
+            "Verification failed: target holds {} EAVI tuples but {} were copied",
+            target_eavis.len(),
+            eavis.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use super::*;
+    use holochain_core_types::entry::{test_entry_a, test_entry_b};
+
+    #[test]
+    fn parses_storage_specs() {
+        assert_eq!(
+            "kv:/tmp/storage".parse(),
+            Ok(StorageSpec::Kv(PathBuf::from("/tmp/storage")))
+        );
+        assert_eq!(
+            "file:storage".parse(),
+            Ok(StorageSpec::File(PathBuf::from("storage")))
+        );
+        assert!("pickle".parse::<StorageSpec>().is_err());
+        assert!("memory".parse::<StorageSpec>().is_err());
+        assert!("foo:/tmp".parse::<StorageSpec>().is_err());
+    }
+
+    #[test]
+    fn migrates_file_storage_to_kv() {
+        let dir = tempdir().unwrap();
+        let from = StorageSpec::File(dir.path().join("file"));
+        let to = StorageSpec::Kv(dir.path().join("kv"));
+
+        let entry_a = test_entry_a();
+        let entry_b = test_entry_b();
+        let eavi = EntityAttributeValueIndex::new(
+            &entry_a.address(),
+            &Attribute::LinkTag("foo".into(), "bar".into()),
+            &entry_b.address(),
+        )
+        .unwrap();
+        {
+            let source = InstanceStorage::open(&from).unwrap();
+            source.cas.write().unwrap().add(&entry_a).unwrap();
+            source.cas.write().unwrap().add(&entry_b).unwrap();
+            source.eav.write().unwrap().add_eavi(&eavi).unwrap();
+        }
+
+        storage_migrate(from.clone(), to.clone()).unwrap();
+
+        let target = InstanceStorage::open(&to).unwrap();
+        assert_eq!(
+            target.list_addresses().unwrap(),
+            Some(vec![entry_a.address(), entry_b.address()].into_iter().collect())
+        );
+        assert_eq!(
+            target.cas.read().unwrap().fetch(&entry_a.address()).unwrap(),
+            Some(entry_a.content())
+        );
+        assert_eq!(target.all_eavis().unwrap().len(), 1);
+        drop(target);
+
+        // A second run must not copy into the now filled target:
+        assert!(storage_migrate(from, to).is_err());
+    }
+}
@@ -7,6 +7,7 @@ extern crate holochain_core_types;
 extern crate holochain_json_api;
 extern crate holochain_persistence_api;
 extern crate holochain_persistence_file;
+extern crate holochain_persistence_pickle;
 extern crate holochain_wasm_utils;
 extern crate lib3h_sodium;
 extern crate structopt;
@@ -29,6 +30,7 @@ mod error;
 mod util;
 
 use crate::error::{HolochainError, HolochainResult};
+use holochain_core_types::time::Iso8601;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -169,6 +171,81 @@ enum Cli {
         path: Option<PathBuf>,
         #[structopt(long, short, help = "List available instances")]
         list: bool,
+        #[structopt(
+            long,
+            short,
+            help = "Output format, one of: text, json, ndjson",
+            default_value = "text"
+        )]
+        format: cli::ChainLogFormat,
+        #[structopt(
+            long = "type",
+            short = "t",
+            help = "Only show entries of this type, may contain glob patterns and be given several times"
+        )]
+        entry_types: Vec<String>,
+        #[structopt(
+            long,
+            help = "Only show entries with a header timestamp at or after this ISO 8601 time"
+        )]
+        since: Option<Iso8601>,
+        #[structopt(
+            long,
+            help = "Only show entries with a header timestamp at or before this ISO 8601 time"
+        )]
+        until: Option<Iso8601>,
+        #[structopt(
+            long,
+            help = "Number of matching entries to skip, counted from the latest",
+            default_value = "0"
+        )]
+        offset: usize,
+        #[structopt(long, help = "Maximum number of entries to show")]
+        limit: Option<usize>,
+        #[structopt(
+            long,
+            short,
+            help = "Include the CRUD status and links held for each entry in the instance's EAV store"
+        )]
+        meta: bool,
+        #[structopt(subcommand)]
+        command: Option<ChainCommand>,
+    },
+    #[structopt(name = "storage", about = "Manage the storage of instances")]
+    Storage {
+        #[structopt(subcommand)]
+        command: StorageCommand,
+    },
+}
+
+#[derive(StructOpt)]
+enum ChainCommand {
+    #[structopt(
+        name = "verify",
+        about = "Checks header links, entries, signatures and timestamps of a source chain"
+    )]
+    Verify {
+        #[structopt(name = "INSTANCE", help = "Instance ID to verify")]
+        instance_id: String,
+        #[structopt(long, short, help = "Location of chain storage")]
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(StructOpt)]
+enum StorageCommand {
+    #[structopt(
+        name = "migrate",
+        about = "Copies all data of an instance storage into a storage of another type and verifies the copy"
+    )]
+    Migrate {
+        #[structopt(
+            long,
+            help = "Storage to copy from, as <type>:<path> with type one of file, pickle, kv"
+        )]
+        from: cli::StorageSpec,
+        #[structopt(long, help = "Empty storage to copy to, as <type>:<path>")]
+        to: cli::StorageSpec,
     },
 }
 
@@ -258,17 +335,49 @@ fn run() -> HolochainResult<()> {
             instance_id,
             list,
             path,
-        } => match (list, instance_id) {
-            (true, _) => cli::chain_list(path),
-            (false, None) => {
+            format,
+            entry_types,
+            since,
+            until,
+            offset,
+            limit,
+            meta,
+            command,
+        } => match (command, list, instance_id) {
+            (
+                Some(ChainCommand::Verify {
+                    instance_id,
+                    path: verify_path,
+                }),
+                _,
+                _,
+            ) => {
+                cli::chain_verify(verify_path.or(path), instance_id)
+                    .map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
+            }
+            (None, true, _) => cli::chain_list(path),
+            (None, false, None) => {
                 Cli::clap().print_help().expect("Couldn't print help!");
                 println!("\n\nTry `hc help chain` for more info");
             }
-            (false, Some(instance_id)) => {
-                cli::chain_log(path, instance_id)
+            (None, false, Some(instance_id)) => {
+                let options = cli::ChainLogOptions {
+                    format,
+                    entry_types,
+                    since,
+                    until,
+                    offset,
+                    limit,
+                    meta,
+                };
+                cli::chain_log(path, instance_id, options)
                     .map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
             }
         },
+
+        Cli::Storage {
+            command: StorageCommand::Migrate { from, to },
+        } => cli::storage_migrate(from, to).map_err(HolochainError::Default)?,
     }
 
     Ok(())
@@ -1,15 +1,26 @@
 use crate::{
-    conductor::{base::notify, Conductor},
+    conductor::{base::notify, instance_archive::InstanceArchive, Conductor},
     config::{
         AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration,
-        InstanceReferenceConfiguration, InterfaceConfiguration, StorageConfiguration,
+        InstanceReferenceConfiguration, InterfaceConfiguration, PersisterConfiguration,
+        StorageConfiguration,
     },
     dpki_instance::DpkiInstance,
+    holochain::Holochain,
     keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
 };
-use holochain_core_types::error::HolochainError;
+use holochain_core::{
+    agent::chain_verification::{self, ChainVerificationReport},
+    context::Context,
+    scheduled_jobs::pending_validations::{self, PendingValidationInfo},
+    workflows::author_entry::{author_entry, validate_authored_entry},
+};
+use holochain_core_types::{chain_migrate::ChainMigrate, entry::Entry, error::HolochainError};
 
-use holochain_persistence_api::{cas::content::AddressableContent, hash::HashString};
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    hash::HashString,
+};
 
 use json_patch;
 use std::{
@@ -41,6 +52,30 @@ pub trait ConductorAdmin {
         agent_id: &String,
     ) -> Result<(), HolochainError>;
     fn remove_instance(&mut self, id: &String) -> Result<(), HolochainError>;
+    fn export_instance(&mut self, id: &String, path: PathBuf) -> Result<(), HolochainError>;
+    fn import_instance(
+        &mut self,
+        path: PathBuf,
+        id: &String,
+        agent_id: Option<String>,
+    ) -> Result<(), HolochainError>;
+    fn migrate_chain(
+        &mut self,
+        old_instance_id: &String,
+        new_instance_id: &String,
+    ) -> Result<(), HolochainError>;
+    fn verify_chain(&mut self, id: &String) -> Result<ChainVerificationReport, HolochainError>;
+    fn pending_validations(
+        &mut self,
+        id: &String,
+    ) -> Result<Vec<PendingValidationInfo>, HolochainError>;
+    fn clear_pending_validations(
+        &mut self,
+        id: &String,
+        all: bool,
+    ) -> Result<Vec<PendingValidationInfo>, HolochainError>;
+    fn blocked_agents(&mut self, dna_id: &String) -> Result<Vec<String>, HolochainError>;
+    fn unblock_agent(&mut self, dna_id: &String, agent_id: &String) -> Result<(), HolochainError>;
     fn add_interface(&mut self, new_instance: InterfaceConfiguration)
         -> Result<(), HolochainError>;
     fn remove_interface(&mut self, id: &String) -> Result<(), HolochainError>;
@@ -90,8 +125,9 @@ impl ConductorAdmin for Conductor {
         let path_string = path
             .to_str()
             .ok_or(HolochainError::ConfigError("invalid path".into()))?;
-        let mut dna =
-            Arc::get_mut(&mut self.dna_loader).unwrap()(&path_string.into()).map_err(|e| {
+        let mut dna = self
+            .load_dna_with_loader(&path_string.into())
+            .map_err(|e| {
                 HolochainError::ConfigError(format!(
                     "Could not load DNA file \"{}\", Error: {}",
                     path_string,
@@ -206,7 +242,9 @@ impl ConductorAdmin for Conductor {
                         format!("invalid path {:?}", storage_path).into(),
                     ))?
                     .into(),
+                encrypted: false,
             },
+            persister: PersisterConfiguration::default(),
         };
         new_config.instances.push(new_instance_config);
         new_config.check_consistency(&mut self.dna_loader)?;
@@ -247,6 +285,392 @@ impl ConductorAdmin for Conductor {
         Ok(())
     }
 
+    /// Writes a backup of the instance given by id to one archive file at the given path.
+    /// See `instance_archive` for what the archive contains.
+    fn export_instance(&mut self, id: &String, path: PathBuf) -> Result<(), HolochainError> {
+        let instance_config = self.config.instance_by_id(id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Instance with ID '{}' does not exist", id))
+        })?;
+        let agent_config = self.config.agent_by_id(&instance_config.agent)?;
+        let context = self.instances.get(id)?.read()?.context().clone();
+
+        let archive = InstanceArchive::from_instance(&instance_config, &agent_config, &context)?;
+        archive.save(&path)?;
+        notify(format!(
+            "Exported instance \"{}\" to {}",
+            id,
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// Restores an instance from an archive written by `export_instance` as new instance
+    /// with the given id.
+    /// The instance gets run by the given agent or, if none is given, by the configured
+    /// agent with the public address of the archived agent.
+    /// The DNA gets installed if no DNA with the same hash is configured yet.
+    /// The storage is of the same type as the archived instance's but placed in this
+    /// conductor's storage directory.
+    /// Nothing gets registered unless the archived chain is found to be intact.
+    /// If the archive can't be restored, the config, storage and DNA file are left as they
+    /// were before.
+    fn import_instance(
+        &mut self,
+        path: PathBuf,
+        id: &String,
+        agent_id: Option<String>,
+    ) -> Result<(), HolochainError> {
+        if self.config.instance_by_id(id).is_some() {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Instance with ID '{}' already exists",
+                id
+            )));
+        }
+        let archive = InstanceArchive::load(&path)?;
+
+        let agent_config = match agent_id {
+            Some(agent_id) => self.config.agent_by_id(&agent_id).ok_or_else(|| {
+                HolochainError::ErrorGeneric(format!("Agent with ID '{}' does not exist", agent_id))
+            })?,
+            None => self
+                .config
+                .agents
+                .iter()
+                .find(|agent| agent.public_address == archive.agent.public_address)
+                .cloned()
+                .ok_or_else(|| {
+                    HolochainError::ErrorGeneric(format!(
+                        "No agent with public address {} configured",
+                        archive.agent.public_address
+                    ))
+                })?,
+        };
+        archive.verify_chain(&agent_config.public_address)?;
+
+        let mut new_config = self.config.clone();
+        let dna_hash = archive.dna.address().to_string();
+        let mut existing_dna_id = None;
+        let mut saved_dna_path = None;
+        for dna_config in new_config.dnas.iter() {
+            let dna_file = PathBuf::from(&dna_config.file);
+            let loaded_dna = self.load_dna_with_loader(&dna_file);
+            if loaded_dna.map(|dna| dna.address() == archive.dna.address()) == Ok(true) {
+                existing_dna_id = Some(dna_config.id.clone());
+                break;
+            }
+        }
+        let dna_id = match existing_dna_id {
+            Some(dna_id) => dna_id,
+            None => {
+                let dna_id = archive.instance.dna.clone();
+                if new_config.dna_by_id(&dna_id).is_some() {
+                    return Err(HolochainError::ErrorGeneric(format!(
+                        "DNA with ID '{}' already exists with a different hash",
+                        dna_id
+                    )));
+                }
+                if !self.dna_file_path(&archive.dna).exists() {
+                    saved_dna_path = Some(self.dna_file_path(&archive.dna));
+                }
+                let dna_path = self.save_dna(&archive.dna)?;
+                new_config.dnas.push(DnaConfiguration {
+                    id: dna_id.clone(),
+                    file: dna_path
+                        .to_str()
+                        .ok_or(HolochainError::ConfigError("invalid path".into()))?
+                        .into(),
+                    hash: Some(dna_hash),
+                });
+                dna_id
+            }
+        };
+
+        let mut created_storage_path = None;
+        let storage = match archive.instance.storage {
+            StorageConfiguration::Memory => StorageConfiguration::Memory,
+            ref archived_storage => {
+                let storage_path = self.instance_storage_dir_path().join(id.clone());
+                if !storage_path.exists() {
+                    created_storage_path = Some(storage_path.clone());
+                }
+                fs::create_dir_all(&storage_path)?;
+                let path: String = storage_path
+                    .to_str()
+                    .ok_or(HolochainError::ConfigError(
+                        format!("invalid path {:?}", storage_path).into(),
+                    ))?
+                    .into();
+                let encrypted = archived_storage.encrypted();
+                match archived_storage {
+                    StorageConfiguration::File { .. } => {
+                        StorageConfiguration::File { path, encrypted }
+                    }
+                    StorageConfiguration::Pickle { .. } => {
+                        StorageConfiguration::Pickle { path, encrypted }
+                    }
+                    StorageConfiguration::Kv { .. } => StorageConfiguration::Kv { path, encrypted },
+                    StorageConfiguration::Memory => unreachable!(),
+                }
+            }
+        };
+
+        new_config.instances.push(InstanceConfiguration {
+            id: id.clone(),
+            dna: dna_id,
+            agent: agent_config.id.clone(),
+            storage,
+            persister: PersisterConfiguration::default(),
+        });
+        new_config.check_consistency(&mut self.dna_loader)?;
+
+        // The config gets saved before the storage is filled so that restored data never
+        // ends up without an instance. If anything fails, the import gets rolled back.
+        let old_config = self.config.clone();
+        self.config = new_config;
+        let restored = self.save_config().and_then(|_| {
+            let (context, _) = self.instance_context_from_config(id, None)?;
+            archive.restore(&context)?;
+            Holochain::load(context)
+        });
+        let instance = match restored {
+            Ok(instance) => instance,
+            Err(error) => {
+                self.config = old_config;
+                self.save_config()?;
+                if let Some(storage_path) = created_storage_path {
+                    fs::remove_dir_all(storage_path)?;
+                }
+                if let Some(dna_path) = saved_dna_path {
+                    fs::remove_file(dna_path)?;
+                }
+                return Err(error);
+            }
+        };
+        self.instances
+            .insert(id.clone(), Arc::new(RwLock::new(instance)));
+        let _ = self.start_signal_multiplexer();
+        notify(format!(
+            "Imported instance \"{}\" from {}",
+            id,
+            path.display()
+        ));
+        Ok(())
+    }
+
+    /// Moves the agent's source chain from the DNA of the old instance to the DNA of the
+    /// new instance.
+    /// Both instances have to be run by the same agent but with different DNAs.
+    /// The chain of the new instance gets opened with a `ChainMigrate` entry pointing back
+    /// to the old DNA, then the chain of the old instance gets closed with the matching entry
+    /// pointing to the new DNA.
+    /// Both chains get checked and both entries validated before any of them is committed.
+    /// If closing the old chain fails after the new one got opened, calling this again
+    /// only commits the missing entry.
+    /// The old instance keeps running but does not accept any further commits.
+    fn migrate_chain(
+        &mut self,
+        old_instance_id: &String,
+        new_instance_id: &String,
+    ) -> Result<(), HolochainError> {
+        let old_config = self.config.instance_by_id(old_instance_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!(
+                "Instance with ID '{}' does not exist",
+                old_instance_id
+            ))
+        })?;
+        let new_config = self.config.instance_by_id(new_instance_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!(
+                "Instance with ID '{}' does not exist",
+                new_instance_id
+            ))
+        })?;
+        if old_config.agent != new_config.agent {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Instances '{}' and '{}' are not run by the same agent",
+                old_instance_id, new_instance_id
+            )));
+        }
+
+        let old_context = self
+            .instances
+            .get(old_instance_id)?
+            .read()?
+            .context()
+            .clone();
+        let new_context = self
+            .instances
+            .get(new_instance_id)?
+            .read()?
+            .context()
+            .clone();
+        let old_dna_address = old_context
+            .get_dna()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Old instance has no DNA".into()))?
+            .address();
+        let new_dna_address = new_context
+            .get_dna()
+            .ok_or_else(|| HolochainError::ErrorGeneric("New instance has no DNA".into()))?
+            .address();
+        if old_dna_address == new_dna_address {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Instances '{}' and '{}' run the same DNA",
+                old_instance_id, new_instance_id
+            )));
+        }
+
+        // Entries are signed with the current key of each chain which differs from
+        // the configured agent key if it got rotated with update_agent:
+        let chain_agent_address = |context: &Arc<Context>| -> Result<Address, HolochainError> {
+            context
+                .state()
+                .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".into()))?
+                .agent()
+                .get_agent_address()
+        };
+        let close = ChainMigrate::close(
+            old_dna_address.clone(),
+            new_dna_address.clone(),
+            chain_agent_address(&old_context)?,
+        );
+        let open = ChainMigrate::open(
+            old_dna_address,
+            new_dna_address,
+            chain_agent_address(&new_context)?,
+        );
+        let close_entry = Entry::ChainMigrate(close.clone());
+        let open_entry = Entry::ChainMigrate(open);
+
+        let chain_state = |context: &Arc<Context>| {
+            context
+                .state()
+                .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".into()))
+                .map(|state| state.agent())
+        };
+        let old_chain = chain_state(&old_context)?;
+        let new_chain = chain_state(&new_context)?;
+        let closed = match old_chain.closing_migration() {
+            None => false,
+            Some(ref migration) if *migration == close => true,
+            Some(migration) => {
+                return Err(HolochainError::ErrorGeneric(format!(
+                    "Chain of instance '{}' was already migrated to DNA {}",
+                    old_instance_id,
+                    migration.new_dna_address()
+                )))
+            }
+        };
+        let opened = new_chain
+            .iter_chain()
+            .any(|header| header.entry_address() == &open_entry.address());
+        if closed && opened {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Chain of instance '{}' was already migrated to instance '{}'",
+                old_instance_id, new_instance_id
+            )));
+        }
+
+        // Validate both ends before committing any of them, so that a migration that
+        // can't go through leaves both chains untouched:
+        if !opened {
+            new_context.block_on(validate_authored_entry(
+                &open_entry,
+                None,
+                &new_context,
+                &vec![],
+            ))?;
+        }
+        if !closed {
+            old_context.block_on(validate_authored_entry(
+                &close_entry,
+                None,
+                &old_context,
+                &vec![],
+            ))?;
+        }
+
+        // Open the new chain first. A chain that got opened but whose counterpart is
+        // still open can get closed by running the migration again, while a closed
+        // chain without a counterpart could not take any commits anymore.
+        if !opened {
+            new_context.block_on(author_entry(&open_entry, None, &new_context, &vec![]))?;
+        }
+        if !closed {
+            old_context
+                .block_on(author_entry(&close_entry, None, &old_context, &vec![]))
+                .map_err(|error| {
+                    HolochainError::ErrorGeneric(format!(
+                        "Opened the chain of instance '{}' but could not close the chain of \
+                         instance '{}', run the migration again to close it: {}",
+                        new_instance_id, old_instance_id, error
+                    ))
+                })?;
+        }
+
+        notify(format!(
+            "Migrated chain of instance \"{}\" to instance \"{}\".",
+            old_instance_id, new_instance_id
+        ));
+        Ok(())
+    }
+
+    /// Checks the integrity of the stored source chain of an instance from the top
+    /// header back to genesis and returns a report of all problems found.
+    fn verify_chain(&mut self, id: &String) -> Result<ChainVerificationReport, HolochainError> {
+        let context = self.instances.get(id)?.read()?.context().clone();
+        let state = context
+            .state()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".into()))?;
+        let agent_state = state.agent();
+        chain_verification::verify_chain(
+            &agent_state.chain_store(),
+            &agent_state.top_chain_header(),
+        )
+    }
+
+    /// Lists the validations of an instance that wait for missing dependencies,
+    /// including the ones that are stuck after too many failed retries.
+    fn pending_validations(
+        &mut self,
+        id: &String,
+    ) -> Result<Vec<PendingValidationInfo>, HolochainError> {
+        let context = self.instances.get(id)?.read()?.context().clone();
+        Ok(pending_validations::list_pending_validations(&context))
+    }
+
+    /// Drops the stuck pending validations of an instance, or all of them if `all` is set.
+    fn clear_pending_validations(
+        &mut self,
+        id: &String,
+        all: bool,
+    ) -> Result<Vec<PendingValidationInfo>, HolochainError> {
+        let context = self.instances.get(id)?.read()?.context().clone();
+        Ok(pending_validations::clear_pending_validations(
+            &context, all,
+        ))
+    }
+
+    /// Lists the agents that got warranted for invalid data of the DNA and are blocked
+    /// by all instances of it
+    fn blocked_agents(&mut self, dna_id: &String) -> Result<Vec<String>, HolochainError> {
+        self.config.dna_by_id(dna_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("DNA with ID '{}' not found", dna_id))
+        })?;
+        Ok(self.blocklist.read()?.agents(dna_id))
+    }
+
+    /// Takes the agent off the blocklist of the DNA, so its instances accept the agent's
+    /// direct messages again. Agents that get warranted again will be blocked again.
+    fn unblock_agent(&mut self, dna_id: &String, agent_id: &String) -> Result<(), HolochainError> {
+        if self.blocklist.write()?.remove(dna_id, agent_id)? {
+            Ok(())
+        } else {
+            Err(HolochainError::ErrorGeneric(format!(
+                "Agent {} is not blocked for DNA {}",
+                agent_id, dna_id
+            )))
+        }
+    }
+
     fn add_interface(&mut self, interface: InterfaceConfiguration) -> Result<(), HolochainError> {
         let mut new_config = self.config.clone();
         if new_config.interfaces.iter().any(|i| i.id == interface.id) {
@@ -255,6 +679,7 @@ impl ConductorAdmin for Conductor {
                 interface.id
             )));
         }
+        self.check_interface_driver(&interface.driver)?;
         new_config.interfaces.push(interface.clone());
         new_config.check_consistency(&mut self.dna_loader)?;
         self.config = new_config;
@@ -589,7 +1014,7 @@ pub mod tests {
         keystore::test_hash_config,
     };
     use holochain_common::paths::DNA_EXTENSION;
-    use holochain_core_types::dna::Dna;
+    use holochain_core_types::{dna::Dna, entry::entry_type::EntryType};
     use holochain_json_api::json::JsonString;
     use std::{
         convert::TryFrom,
@@ -1668,4 +2093,224 @@ handle = 'my favourite instance!'"#,
 
         assert_eq!(config_contents, toml,);
     }
+
+    #[test]
+    fn test_export_and_import_instance() {
+        let test_name = "test_export_and_import_instance";
+        let mut conductor = create_test_conductor(test_name, 3012);
+        let archive_path = conductor
+            .instance_storage_dir_path()
+            .join("instance-1.archive");
+        fs::create_dir_all(conductor.instance_storage_dir_path()).unwrap();
+
+        assert_eq!(
+            conductor.export_instance(&String::from("test-instance-1"), archive_path.clone()),
+            Ok(()),
+        );
+        assert!(archive_path.is_file());
+
+        // The chain belongs to agent 1 so it can't be restored for agent 2:
+        assert!(conductor
+            .import_instance(
+                archive_path.clone(),
+                &String::from("restored-instance"),
+                Some(String::from("test-agent-2")),
+            )
+            .is_err());
+        assert!(conductor
+            .config()
+            .instance_by_id("restored-instance")
+            .is_none());
+
+        assert_eq!(
+            conductor.import_instance(
+                archive_path.clone(),
+                &String::from("restored-instance"),
+                None,
+            ),
+            Ok(()),
+        );
+        let restored_config = conductor
+            .config()
+            .instance_by_id("restored-instance")
+            .unwrap();
+        assert_eq!(restored_config.agent, "test-agent-1");
+        assert_eq!(restored_config.dna, "test-dna");
+        assert_eq!(restored_config.storage, StorageConfiguration::Memory);
+
+        let original_top = conductor
+            .instances
+            .get("test-instance-1")
+            .unwrap()
+            .read()
+            .unwrap()
+            .state()
+            .unwrap()
+            .agent()
+            .top_chain_header();
+        let restored_top = conductor
+            .instances
+            .get("restored-instance")
+            .unwrap()
+            .read()
+            .unwrap()
+            .state()
+            .unwrap()
+            .agent()
+            .top_chain_header();
+        assert!(original_top.is_some());
+        assert_eq!(original_top, restored_top);
+    }
+
+    #[test]
+    fn test_migrate_chain() {
+        let test_name = "test_migrate_chain";
+        let mut conductor = create_test_conductor(test_name, 3013);
+
+        assert!(conductor
+            .install_dna_from_file(
+                PathBuf::from("new-dna.dna.json"),
+                String::from("new-dna"),
+                false,
+                None,
+                None,
+                Some(String::from("migration")),
+            )
+            .is_ok());
+        assert_eq!(
+            conductor.add_instance(
+                &String::from("migrated-instance"),
+                &String::from("new-dna"),
+                &String::from("test-agent-1"),
+            ),
+            Ok(()),
+        );
+
+        // Chains of different agents can't be linked:
+        assert!(conductor
+            .migrate_chain(
+                &String::from("test-instance-2"),
+                &String::from("migrated-instance"),
+            )
+            .is_err());
+
+        assert_eq!(
+            conductor.migrate_chain(
+                &String::from("test-instance-1"),
+                &String::from("migrated-instance"),
+            ),
+            Ok(()),
+        );
+
+        let top_entry_type = |conductor: &Conductor, id: &str| {
+            conductor
+                .instances
+                .get(id)
+                .unwrap()
+                .read()
+                .unwrap()
+                .state()
+                .unwrap()
+                .agent()
+                .top_chain_header()
+                .unwrap()
+                .entry_type()
+                .clone()
+        };
+        assert_eq!(
+            top_entry_type(&conductor, "test-instance-1"),
+            EntryType::ChainMigrate
+        );
+        assert_eq!(
+            top_entry_type(&conductor, "migrated-instance"),
+            EntryType::ChainMigrate
+        );
+
+        // The old chain is closed now:
+        assert!(conductor
+            .migrate_chain(
+                &String::from("test-instance-1"),
+                &String::from("migrated-instance"),
+            )
+            .is_err());
+
+        // A migration that can't open the new chain leaves the old one open:
+        assert!(conductor
+            .migrate_chain(
+                &String::from("migrated-instance"),
+                &String::from("test-instance-1"),
+            )
+            .is_err());
+        let closing_migration = conductor
+            .instances
+            .get("migrated-instance")
+            .unwrap()
+            .read()
+            .unwrap()
+            .state()
+            .unwrap()
+            .agent()
+            .closing_migration();
+        assert_eq!(closing_migration, None);
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let test_name = "test_verify_chain";
+        let mut conductor = create_test_conductor(test_name, 3014);
+
+        let report = conductor
+            .verify_chain(&String::from("test-instance-1"))
+            .expect("Could not verify chain");
+        assert!(report.is_valid(), "{:?}", report.problems);
+        // At least the DNA and the agent entry
+        assert!(report.headers_checked >= 2);
+
+        assert!(conductor
+            .verify_chain(&String::from("unknown-instance"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_pending_validations() {
+        let test_name = "test_pending_validations";
+        let mut conductor = create_test_conductor(test_name, 3015);
+        let instance_id = String::from("test-instance-1");
+
+        assert_eq!(conductor.pending_validations(&instance_id), Ok(Vec::new()));
+        assert_eq!(
+            conductor.clear_pending_validations(&instance_id, true),
+            Ok(Vec::new())
+        );
+        assert!(conductor
+            .pending_validations(&String::from("unknown-instance"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_blocklist() {
+        let test_name = "test_blocklist";
+        let mut conductor = create_test_conductor(test_name, 3016);
+        let dna_id = String::from("test-dna");
+        let agent_id = String::from("warranted-agent");
+
+        assert_eq!(conductor.blocked_agents(&dna_id), Ok(Vec::new()));
+        conductor
+            .blocklist
+            .write()
+            .unwrap()
+            .add(&dna_id, &agent_id)
+            .unwrap();
+        assert_eq!(
+            conductor.blocked_agents(&dna_id),
+            Ok(vec![agent_id.clone()])
+        );
+        assert!(conductor
+            .blocked_agents(&String::from("unknown-dna"))
+            .is_err());
+
+        assert_eq!(conductor.unblock_agent(&dna_id, &agent_id), Ok(()));
+        assert_eq!(conductor.blocked_agents(&dna_id), Ok(Vec::new()));
+        assert!(conductor.unblock_agent(&dna_id, &agent_id).is_err());
+    }
 }
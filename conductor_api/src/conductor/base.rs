@@ -2,23 +2,26 @@ use crate::{
     conductor::broadcaster::Broadcaster,
     config::{
         serialize_configuration, Configuration, InterfaceConfiguration, InterfaceDriver,
-        StorageConfiguration,
+        PersisterConfiguration, StorageConfiguration,
     },
     context_builder::ContextBuilder,
     dpki_instance::DpkiInstance,
     error::HolochainInstanceError,
     keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
     logger::DebugLogger,
+    storage::encrypted::StorageKey,
     Holochain,
 };
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use holochain_common::paths::DNA_EXTENSION;
 use holochain_core::{
+    context::Context,
     logger::{ChannelLogger, Logger},
+    persister::{journal::DEFAULT_CHECKPOINT_INTERVAL, JournalPersister},
     signal::Signal,
 };
 use holochain_core_types::{
-    agent::AgentId,
+    agent::{AgentId, Base32},
     dna::Dna,
     error::{HcResult, HolochainError},
 };
@@ -42,14 +45,17 @@ use std::{
 };
 
 use boolinator::Boolinator;
-use conductor::passphrase_manager::{PassphraseManager, PassphraseServiceCmd};
+use conductor::{
+    blocklist::Blocklist,
+    passphrase_manager::{PassphraseManager, PassphraseServiceCmd},
+};
 use config::AgentConfiguration;
 use holochain_core_types::dna::bridges::BridgePresence;
 use holochain_net::{
     ipc::spawn::{ipc_spawn, SpawnResult},
     p2p_config::P2pConfig,
 };
-use interface::{ConductorApiBuilder, InstanceMap, Interface};
+use interface::{ConductorApiBuilder, InstanceMap, Interface, InterfaceFactory};
 use signal_wrapper::SignalWrapper;
 use static_file_server::StaticServer;
 
@@ -98,6 +104,10 @@ pub struct Conductor {
     pub key_loader: KeyLoader,
     pub(in crate::conductor) dna_loader: DnaLoader,
     pub(in crate::conductor) ui_dir_copier: UiDirCopier,
+    /// Agents that got warranted for invalid data, per DNA. Shared with all instances,
+    /// which refuse direct messages from the agents blocked for their DNA.
+    pub(in crate::conductor) blocklist: Arc<RwLock<Blocklist>>,
+    interface_factories: HashMap<String, InterfaceFactory>,
     signal_tx: Option<SignalSender>,
     logger: DebugLogger,
     p2p_config: Option<P2pConfig>,
@@ -141,6 +151,7 @@ pub fn notify(msg: String) {
 impl Conductor {
     pub fn from_config(config: Configuration) -> Self {
         let rules = config.logger.rules.clone();
+        let blocklist_path = config.persistence_dir.join("blocklist.json");
         lib3h_sodium::check_init();
 
         Conductor {
@@ -155,6 +166,8 @@ impl Conductor {
             key_loader: Arc::new(Box::new(Self::load_key)),
             dna_loader: Arc::new(Box::new(Self::load_dna)),
             ui_dir_copier: Arc::new(Box::new(Self::copy_ui_dir)),
+            blocklist: Arc::new(RwLock::new(Blocklist::new(blocklist_path))),
+            interface_factories: HashMap::new(),
             signal_tx: None,
             logger: DebugLogger::new(rules),
             p2p_config: None,
@@ -182,6 +195,33 @@ impl Conductor {
         self
     }
 
+    /// Registers a factory for interfaces configured with `type = "custom"` and
+    /// `name = <driver_name>`. Has to happen before the interfaces get started.
+    pub fn register_interface_driver<S: Into<String>>(
+        &mut self,
+        driver_name: S,
+        factory: InterfaceFactory,
+    ) {
+        self.interface_factories.insert(driver_name.into(), factory);
+    }
+
+    /// Checks that an interface with the given driver could be built by this conductor.
+    pub fn check_interface_driver(&self, driver: &InterfaceDriver) -> Result<(), String> {
+        match driver {
+            InterfaceDriver::Custom(_) => {
+                let name = driver.custom_driver_name()?;
+                self.interface_factories
+                    .contains_key(&name)
+                    .ok_or_else(|| format!("No custom interface driver registered as '{}'", name))
+            }
+            #[cfg(not(unix))]
+            InterfaceDriver::DomainSocket { .. } => Err(String::from(
+                "Domain socket interfaces are only available on Unix",
+            )),
+            _ => Ok(()),
+        }
+    }
+
     pub fn p2p_bindings(&self) -> Option<Vec<String>> {
         self.network_spawn
             .as_ref()
@@ -492,6 +532,14 @@ impl Conductor {
         // the ipc_uri for it and save it for future calls to `load_config` or
         // we use a (non-empty) uri value that was created from previous calls!
         let net_config = self.config.network.clone().unwrap();
+        // native TCP networking doesn't need a network process
+        if let Some(bind_address) = net_config.tcp_bind_address.clone() {
+            return P2pConfig::new_with_tcp_backend(
+                &bind_address,
+                net_config.tcp_advertise_address.as_ref().map(String::as_str),
+                &net_config.bootstrap_nodes,
+            );
+        }
         let uri = net_config
             .n3h_ipc_uri
             .clone()
@@ -517,6 +565,12 @@ impl Conductor {
     pub fn boot_from_config(&mut self) -> Result<(), String> {
         let _ = self.config.check_consistency(&mut self.dna_loader)?;
 
+        self.blocklist
+            .write()
+            .unwrap()
+            .load()
+            .map_err(|error| error.to_string())?;
+
         if self.p2p_config.is_none() {
             self.p2p_config = Some(self.initialize_p2p_config());
         }
@@ -576,6 +630,85 @@ impl Conductor {
         id: &String,
         maybe_config: Option<&Configuration>,
     ) -> Result<Holochain, String> {
+        let (context, dna) = self.instance_context_from_config(id, maybe_config)?;
+        let hc = Holochain::load(context.clone())
+            .and_then(|hc| {
+                notify(format!(
+                    "Successfully loaded instance {} from storage",
+                    id.clone()
+                ));
+                Ok(hc)
+            })
+            .or_else(|loading_error| {
+                // NoneError just means it didn't find a pre-existing state
+                // that's not a problem and so isn't logged as such
+                if loading_error == HolochainError::from(NoneError) {
+                    notify("No chain found in the store".to_string());
+                } else {
+                    notify(format!(
+                        "Failed to load instance {} from storage: {:?}",
+                        id.clone(),
+                        loading_error
+                    ));
+                }
+                notify("Initializing new chain...".to_string());
+                Holochain::new(dna, context).map_err(|hc_err| hc_err.to_string())
+            })?;
+
+        let self_config = self.config.clone();
+        let config = maybe_config.unwrap_or(&self_config);
+        self.use_rotated_agent_key(id, config, hc.context())
+            .map_err(|err| err.to_string())?;
+        Ok(hc)
+    }
+
+    /// If the agent's key was rotated on the loaded chain of the given instance, the chain
+    /// has to be continued with the latest key instead of the one in the agent config.
+    /// Rebuilds the instance's conductor API with that key in this case.
+    fn use_rotated_agent_key(
+        &mut self,
+        id: &String,
+        config: &Configuration,
+        context: &Arc<Context>,
+    ) -> Result<(), HolochainError> {
+        let instance_config = config.instance_by_id(id)?;
+        let agent_config = config.agent_by_id(&instance_config.agent)?;
+        if let Some(true) = agent_config.holo_remote_key {
+            return Ok(());
+        }
+        let maybe_chain_agent = context
+            .state()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".into()))?
+            .agent()
+            .get_agent();
+        let chain_agent = match maybe_chain_agent {
+            Ok(agent) => agent,
+            // A chain without agent entry can't have a rotated key
+            Err(_) => return Ok(()),
+        };
+        if chain_agent.pub_sign_key != agent_config.public_address {
+            notify(format!(
+                "Instance {} uses rotated agent key {}",
+                id, chain_agent.pub_sign_key
+            ));
+            let api = self.build_conductor_api_with_agent_key(
+                id.clone(),
+                config,
+                Some(&chain_agent.pub_sign_key),
+            )?;
+            context.conductor_api.reset(api);
+        }
+        Ok(())
+    }
+
+    /// Builds the context of one specific instance from a given Configuration
+    /// and loads its DNA, without loading or initializing the chain.
+    /// This lets callers fill the instance's storages before the chain gets loaded.
+    pub(crate) fn instance_context_from_config(
+        &mut self,
+        id: &String,
+        maybe_config: Option<&Configuration>,
+    ) -> Result<(Arc<Context>, Dna), String> {
         let self_config = self.config.clone();
         let config = maybe_config.unwrap_or(&self_config);
         let _ = config.check_consistency(&mut self.dna_loader)?;
@@ -605,7 +738,7 @@ impl Conductor {
 
                 // Storage:
                 match instance_config.storage {
-                    StorageConfiguration::File { path } => {
+                    StorageConfiguration::File { ref path, .. } => {
                         context_builder =
                             context_builder.with_file_storage(path).map_err(|hc_err| {
                                 format!("Error creating context: {}", hc_err.to_string())
@@ -614,7 +747,7 @@ impl Conductor {
                     StorageConfiguration::Memory => {
                         context_builder = context_builder.with_memory_storage()
                     }
-                    StorageConfiguration::Pickle { path } => {
+                    StorageConfiguration::Pickle { ref path, .. } => {
                         context_builder =
                             context_builder
                                 .with_pickle_storage(path)
@@ -622,6 +755,35 @@ impl Conductor {
                                     format!("Error creating context: {}", hc_err.to_string())
                                 })?
                     }
+                    StorageConfiguration::Kv { ref path, .. } => {
+                        context_builder =
+                            context_builder.with_kv_storage(path).map_err(|hc_err| {
+                                format!("Error creating context: {}", hc_err.to_string())
+                            })?
+                    }
+                }
+                if instance_config.storage.encrypted() {
+                    let key = self.get_storage_key_for_agent(&instance_config.agent)?;
+                    context_builder = context_builder.with_storage_encryption(key);
+                }
+
+                // Persister:
+                match instance_config.persister {
+                    PersisterConfiguration::Simple => (),
+                    PersisterConfiguration::Journal {
+                        ref path,
+                        checkpoint_interval,
+                    } => {
+                        let persister = JournalPersister::with_checkpoint_interval(
+                            path,
+                            checkpoint_interval.unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+                        )
+                        .map_err(|hc_err| {
+                            format!("Error creating context: {}", hc_err.to_string())
+                        })?;
+                        context_builder =
+                            context_builder.with_persister(Arc::new(Mutex::new(persister)));
+                    }
                 }
 
                 if config.logger.logger_type == "debug" {
@@ -640,37 +802,14 @@ impl Conductor {
                 // Get DNA
                 let dna_config = config.dna_by_id(&instance_config.dna).unwrap();
                 let dna_file = PathBuf::from(&dna_config.file);
-                let dna = Arc::get_mut(&mut self.dna_loader).unwrap()(&dna_file).map_err(|_| {
+                let dna = self.load_dna_with_loader(&dna_file).map_err(|_| {
                     HolochainError::ConfigError(format!(
                         "Could not load DNA file \"{}\"",
                         dna_config.file
                     ))
                 })?;
 
-                let context = Arc::new(context);
-                Holochain::load(context.clone())
-                    .and_then(|hc| {
-                        notify(format!(
-                            "Successfully loaded instance {} from storage",
-                            id.clone()
-                        ));
-                        Ok(hc)
-                    })
-                    .or_else(|loading_error| {
-                        // NoneError just means it didn't find a pre-existing state
-                        // that's not a problem and so isn't logged as such
-                        if loading_error == HolochainError::from(NoneError) {
-                            notify("No chain found in the store".to_string());
-                        } else {
-                            notify(format!(
-                                "Failed to load instance {} from storage: {:?}",
-                                id.clone(),
-                                loading_error
-                            ));
-                        }
-                        notify("Initializing new chain...".to_string());
-                        Holochain::new(dna, context).map_err(|hc_err| hc_err.to_string())
-                    })
+                Ok((Arc::new(context), dna))
             })
     }
 
@@ -678,6 +817,18 @@ impl Conductor {
         &mut self,
         instance_id: String,
         config: &Configuration,
+    ) -> Result<IoHandler, HolochainError> {
+        self.build_conductor_api_with_agent_key(instance_id, config, None)
+    }
+
+    /// Builds the conductor API of an instance like `build_conductor_api` but signs with
+    /// the agent key that has the given public address, if one is given.
+    /// This is needed for instances whose chain went through a key rotation.
+    fn build_conductor_api_with_agent_key(
+        &mut self,
+        instance_id: String,
+        config: &Configuration,
+        agent_key: Option<&Base32>,
     ) -> Result<IoHandler, HolochainError> {
         let instance_config = config.instance_by_id(&instance_id)?;
         let agent_id = instance_config.agent.clone();
@@ -696,16 +847,27 @@ impl Conductor {
                     .expect("holo_remote_key needs signing_service_uri set"),
             );
         } else {
-            api_builder = api_builder.with_agent_signature_callback(
-                self.get_keybundle_for_agent(&instance_config.agent)?,
-            );
-
             let keystore = self
                 .get_keystore_for_agent(&instance_config.agent)
                 .map_err(|err| format!("{}", err))?;
-            api_builder = api_builder.with_agent_keystore_functions(keystore);
+            let keybundle = match agent_key {
+                Some(public_address) => Arc::new(Mutex::new(
+                    keystore.lock().unwrap().get_agent_keybundle(public_address)?,
+                )),
+                None => self.get_keybundle_for_agent(&instance_config.agent)?,
+            };
+            api_builder = api_builder.with_agent_signature_callback(keybundle.clone());
+            api_builder = api_builder.with_agent_keystore_functions(keystore.clone());
+            api_builder = api_builder.with_agent_key_rotation_functions(
+                keystore,
+                keybundle,
+                PathBuf::from(agent_config.keystore_file.clone()),
+            );
         }
 
+        api_builder = api_builder
+            .with_blocklist_functions(self.blocklist.clone(), instance_config.dna.clone());
+
         // Bridges:
         let id = instance_config.id.clone();
         for bridge in config.bridge_dependencies(id.clone()) {
@@ -745,6 +907,21 @@ impl Conductor {
         })
     }
 
+    /// Derives the key for encrypting the storage of instances run by the given agent.
+    /// Loading the agent's keystore asks the passphrase manager for its passphrase
+    /// if it is not unlocked yet.
+    pub fn get_storage_key_for_agent(&mut self, agent_id: &String) -> Result<StorageKey, String> {
+        let keystore = self.get_keystore_for_agent(agent_id)?;
+        let mut keystore = keystore.lock().unwrap();
+        let key = keystore.derive_storage_key().map_err(|err| {
+            format!(
+                "Could not derive storage key for agent '{}': {}",
+                agent_id, err
+            )
+        })?;
+        Ok(StorageKey::new(key))
+    }
+
     /// Checks if the key for the given agent can be loaded or was already loaded.
     /// Will trigger loading if key is not loaded yet.
     /// Meant to be used in conductor executable to first try to load all keys (which will trigger
@@ -907,9 +1084,9 @@ impl Conductor {
         // The "kill switch" is the channel which allows the interface to be stopped from outside its thread
         let (kill_switch_tx, kill_switch_rx) = unbounded();
 
-        let iface = make_interface(&interface_config);
-        let (broadcaster, _handle) = iface
-            .run(dispatcher, kill_switch_rx)
+        let (broadcaster, _handle) = self
+            .make_interface(&interface_config)
+            .and_then(|iface| iface.run(dispatcher, kill_switch_rx))
             .map_err(|error| {
                 self.log(format!(
                     "err/conductor: Error running interface '{}': {}",
@@ -933,6 +1110,39 @@ impl Conductor {
         kill_switch_tx
     }
 
+    /// Builds the built-in Interface for the given config or, for custom drivers,
+    /// calls the factory registered under the driver's name.
+    fn make_interface(
+        &self,
+        interface_config: &InterfaceConfiguration,
+    ) -> Result<Box<Interface>, String> {
+        #[cfg(unix)]
+        use interface_impls::domain_socket::DomainSocketInterface;
+        use interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
+        self.check_interface_driver(&interface_config.driver)?;
+        match interface_config.driver {
+            InterfaceDriver::Websocket { port } => Ok(Box::new(WebsocketInterface::new(port))),
+            InterfaceDriver::Http { port } => Ok(Box::new(HttpInterface::new(port))),
+            #[cfg(unix)]
+            InterfaceDriver::DomainSocket { ref file } => {
+                Ok(Box::new(DomainSocketInterface::new(
+                    PathBuf::from(file),
+                    ChannelLogger::new(interface_config.id.clone(), self.logger.get_sender()),
+                )))
+            }
+            #[cfg(not(unix))]
+            InterfaceDriver::DomainSocket { .. } => unreachable!(),
+            InterfaceDriver::Custom(ref config) => {
+                let name = interface_config.driver.custom_driver_name()?;
+                let factory = self
+                    .interface_factories
+                    .get(&name)
+                    .expect("Custom driver checked to be registered above");
+                factory(config)
+            }
+        }
+    }
+
     fn log(&self, msg: String) {
         self.logger
             .get_sender()
@@ -977,11 +1187,24 @@ impl Conductor {
         Ok(())
     }
 
-    pub fn save_dna(&self, dna: &Dna) -> Result<PathBuf, HolochainError> {
-        let file_path = self
-            .dna_dir_path()
+    /// Loads the DNA file at the given path through the conductor's DNA loader.
+    /// The loader can only be called while it is not shared, which is reported as error.
+    pub fn load_dna_with_loader(&mut self, path: &PathBuf) -> Result<Dna, HolochainError> {
+        let dna_loader = Arc::get_mut(&mut self.dna_loader).ok_or_else(|| {
+            HolochainError::ConfigError("DNA loader is in use elsewhere".to_string())
+        })?;
+        dna_loader(path)
+    }
+
+    /// The path `save_dna` stores the given DNA at.
+    pub fn dna_file_path(&self, dna: &Dna) -> PathBuf {
+        self.dna_dir_path()
             .join(dna.address().to_string())
-            .with_extension(DNA_EXTENSION);
+            .with_extension(DNA_EXTENSION)
+    }
+
+    pub fn save_dna(&self, dna: &Dna) -> Result<PathBuf, HolochainError> {
+        let file_path = self.dna_file_path(dna);
         fs::create_dir_all(&self.dna_dir_path())?;
         self.save_dna_to(dna, file_path)
     }
@@ -1035,15 +1258,6 @@ impl Conductor {
     }
 }
 
-/// This can eventually be dependency injected for third party Interface definitions
-fn make_interface(interface_config: &InterfaceConfiguration) -> Box<Interface> {
-    use interface_impls::{http::HttpInterface, websocket::WebsocketInterface};
-    match interface_config.driver {
-        InterfaceDriver::Websocket { port } => Box::new(WebsocketInterface::new(port)),
-        InterfaceDriver::Http { port } => Box::new(HttpInterface::new(port)),
-        _ => unimplemented!(),
-    }
-}
 
 #[derive(Clone, Debug)]
 struct NullLogger {}
@@ -1082,6 +1296,7 @@ pub mod tests {
     };
     use std::collections::BTreeMap;
     use test_utils::*;
+    use toml;
 
     //    commented while test_signals_through_admin_websocket is broken
     //    extern crate ws;
@@ -1616,7 +1831,7 @@ pub mod tests {
             let context = instance.context();
             make_cap_request_for_call(
                 context.clone(),
-                Address::from(context.clone().agent_id.address()),
+                Address::from(context.clone().agent_id().address()),
                 "call_bridge",
                 JsonString::empty_object(),
             )
@@ -1648,7 +1863,7 @@ pub mod tests {
             let context = instance.context();
             make_cap_request_for_call(
                 context.clone(),
-                Address::from(context.clone().agent_id.address()),
+                Address::from(context.clone().agent_id().address()),
                 "call_bridge_error",
                 JsonString::empty_object(),
             )
@@ -1892,7 +2107,7 @@ pub mod tests {
                 let context = bridge_caller.context();
                 make_cap_request_for_call(
                     context.clone(),
-                    Address::from(context.clone().agent_id.address()),
+                    Address::from(context.clone().agent_id().address()),
                     "call_bridge",
                     JsonString::empty_object(),
                 )
@@ -1947,4 +2162,92 @@ pub mod tests {
             Err(HolochainInstanceError::InstanceNotActiveYet),
         );
     }
+
+    #[test]
+    fn test_journal_is_refused_for_encrypted_storage() {
+        let mut conductor = test_conductor(10071, 10072);
+        let tempdir = tempdir().unwrap();
+        let journal_path = tempdir.path().join("journal");
+        let mut config = conductor.config.clone();
+        {
+            let instance = config
+                .instances
+                .iter_mut()
+                .find(|instance| instance.id == "test-instance-2")
+                .unwrap();
+            instance.storage = StorageConfiguration::File {
+                path: tempdir.path().join("storage").to_str().unwrap().into(),
+                encrypted: true,
+            };
+            instance.persister = PersisterConfiguration::Journal {
+                path: journal_path.to_str().unwrap().into(),
+                checkpoint_interval: None,
+            };
+        }
+
+        assert!(conductor
+            .instantiate_from_config(&String::from("test-instance-2"), Some(&config))
+            .is_err());
+        // Nothing of the instance's data reaches the journal in plaintext
+        assert!(!journal_path.exists());
+    }
+
+    struct TestCustomInterface {
+        started: Arc<Mutex<Option<String>>>,
+        greeting: String,
+    }
+
+    impl Interface for TestCustomInterface {
+        fn run(
+            &self,
+            _handler: IoHandler,
+            kill_switch: Receiver<()>,
+        ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
+            *self.started.lock().unwrap() = Some(self.greeting.clone());
+            let handle = thread::spawn(move || {
+                let _ = kill_switch.recv();
+            });
+            Ok((Broadcaster::Noop, handle))
+        }
+    }
+
+    #[test]
+    fn test_custom_interface_driver() {
+        let mut conductor = test_conductor(10061, 10062);
+        let config = load_configuration::<InterfaceConfiguration>(
+            r#"
+    id = "custom interface"
+    [driver]
+    type = "custom"
+    name = "test-driver"
+    greeting = "hello"
+    "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            conductor.check_interface_driver(&config.driver),
+            Err(String::from(
+                "No custom interface driver registered as 'test-driver'"
+            )),
+        );
+
+        let started = Arc::new(Mutex::new(None));
+        let started_clone = started.clone();
+        conductor.register_interface_driver(
+            "test-driver",
+            Arc::new(Box::new(move |config: &toml::value::Value| {
+                Ok(Box::new(TestCustomInterface {
+                    started: started_clone.clone(),
+                    greeting: config["greeting"].as_str().unwrap().to_string(),
+                }) as Box<Interface>)
+            })),
+        );
+        assert_eq!(conductor.check_interface_driver(&config.driver), Ok(()));
+
+        conductor.config.interfaces.push(config.clone());
+        assert_eq!(conductor.start_interface(&config), Ok(()));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*started.lock().unwrap(), Some(String::from("hello")));
+    }
 }
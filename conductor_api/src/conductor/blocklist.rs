@@ -0,0 +1,144 @@
+use holochain_core_types::error::HolochainError;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+};
+
+/// Agents that got warranted for invalid data, per DNA.
+/// A warrant only tells about the rules of the DNA the warranted data belongs to,
+/// so agents only get blocked by the instances of that DNA.
+/// Every change gets written to a JSON file in the conductor's persistence dir,
+/// so blocked agents stay blocked across restarts.
+pub struct Blocklist {
+    path: PathBuf,
+    agents: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Blocklist {
+    /// An empty blocklist that gets saved to `path`.
+    /// Call `load()` to read the agents blocked before.
+    pub fn new(path: PathBuf) -> Self {
+        Blocklist {
+            path,
+            agents: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the blocklist saved before, if there is one
+    pub fn load(&mut self) -> Result<(), HolochainError> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(&self.path)?;
+        self.agents = serde_json::from_str(&content).map_err(|error| {
+            HolochainError::ErrorGeneric(format!(
+                "Could not read blocklist {:?}: {}",
+                self.path, error
+            ))
+        })?;
+        Ok(())
+    }
+
+    pub fn contains(&self, dna_id: &str, agent_id: &str) -> bool {
+        self.agents
+            .get(dna_id)
+            .map(|agents| agents.contains(agent_id))
+            .unwrap_or(false)
+    }
+
+    /// The blocked agents of the DNA, sorted
+    pub fn agents(&self, dna_id: &str) -> Vec<String> {
+        self.agents
+            .get(dna_id)
+            .map(|agents| agents.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, dna_id: &str, agent_id: &str) -> Result<(), HolochainError> {
+        let added = self
+            .agents
+            .entry(dna_id.to_string())
+            .or_insert_with(BTreeSet::new)
+            .insert(agent_id.to_string());
+        if added {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Returns false if the agent was not blocked
+    pub fn remove(&mut self, dna_id: &str, agent_id: &str) -> Result<bool, HolochainError> {
+        let removed = match self.agents.get_mut(dna_id) {
+            Some(agents) => agents.remove(agent_id),
+            None => false,
+        };
+        if removed {
+            if self.agents[dna_id].is_empty() {
+                self.agents.remove(dna_id);
+            }
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Writes to a temporary file first, so a crash can't leave a truncated blocklist
+    fn save(&self) -> Result<(), HolochainError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&self.agents).map_err(|error| {
+            HolochainError::ErrorGeneric(format!("Could not serialize blocklist: {}", error))
+        })?;
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn agents_are_blocked_per_dna() {
+        let dir = tempdir().unwrap();
+        let mut blocklist = Blocklist::new(dir.path().join("blocklist.json"));
+
+        blocklist.add("dna-1", "agent-b").unwrap();
+        blocklist.add("dna-1", "agent-a").unwrap();
+
+        assert!(blocklist.contains("dna-1", "agent-a"));
+        assert!(!blocklist.contains("dna-2", "agent-a"));
+        assert_eq!(
+            blocklist.agents("dna-1"),
+            vec!["agent-a".to_string(), "agent-b".to_string()]
+        );
+        assert_eq!(blocklist.agents("dna-2"), Vec::<String>::new());
+
+        assert_eq!(blocklist.remove("dna-2", "agent-a"), Ok(false));
+        assert_eq!(blocklist.remove("dna-1", "agent-a"), Ok(true));
+        assert!(!blocklist.contains("dna-1", "agent-a"));
+    }
+
+    #[test]
+    fn blocked_agents_survive_a_reload() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("blocklist.json");
+        let mut blocklist = Blocklist::new(path.clone());
+        blocklist.add("dna-1", "agent-a").unwrap();
+        blocklist.add("dna-1", "agent-b").unwrap();
+        blocklist.remove("dna-1", "agent-b").unwrap();
+
+        let mut reloaded = Blocklist::new(path);
+        assert!(!reloaded.contains("dna-1", "agent-a"));
+        reloaded.load().unwrap();
+        assert!(reloaded.contains("dna-1", "agent-a"));
+        assert!(!reloaded.contains("dna-1", "agent-b"));
+    }
+}
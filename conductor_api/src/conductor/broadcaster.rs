@@ -1,12 +1,21 @@
 use holochain_core_types::error::HolochainError;
 use holochain_json_api::json::JsonString;
+#[cfg(unix)]
+use interface_impls::domain_socket::{write_line, DomainSocketClient};
 use jsonrpc_ws_server::ws;
+#[cfg(unix)]
+use std::{
+    net::Shutdown,
+    sync::{Arc, Mutex},
+};
 
 /// An abstraction which represents the ability to (maybe) send a message to the client
 /// over the existing connection.
 #[derive(Debug)]
 pub enum Broadcaster {
     Ws(ws::Sender),
+    #[cfg(unix)]
+    DomainSocket(Arc<Mutex<Vec<DomainSocketClient>>>),
     Noop,
 }
 
@@ -14,6 +23,12 @@ impl Drop for Broadcaster {
     fn drop(&mut self) {
         match self {
             Broadcaster::Ws(sender) => sender.close(ws::CloseCode::Normal).unwrap_or(()),
+            #[cfg(unix)]
+            Broadcaster::DomainSocket(clients) => {
+                for client in clients.lock().unwrap().drain(..) {
+                    let _ = client.lock().unwrap().shutdown(Shutdown::Both);
+                }
+            }
             Broadcaster::Noop => (),
         }
     }
@@ -31,6 +46,15 @@ impl Broadcaster {
                 .map_err(|e| {
                     HolochainError::ErrorGeneric(format!("Broadcaster::Ws -- {}", e.to_string()))
                 })?,
+            #[cfg(unix)]
+            Broadcaster::DomainSocket(clients) => {
+                let msg = msg.into().to_string();
+                // Clients whose connection is gone are dropped from the list
+                clients
+                    .lock()
+                    .unwrap()
+                    .retain(|client| write_line(client, &msg).is_ok());
+            }
             Broadcaster::Noop => (),
         }
         Ok(())
@@ -0,0 +1,357 @@
+//! Archive format used to back up and restore instances through the admin API
+//! (`admin/instance/export` and `admin/instance/import`).
+//!
+//! An archive is one JSON file that holds everything needed to bring an instance
+//! back up on another conductor: the source chain content (headers, entries and
+//! state snapshots), the DHT content and EAV index held by the instance, the DNA
+//! and the instance and agent configuration.
+//! Secrets are never archived. The agent's keystore file is left out, so the agent
+//! has to be configured on the importing conductor with its own keystore.
+//!
+//! Storage backends don't offer a way to list their content, so the archive is
+//! built by walking the chain from the top header and by following all addresses
+//! mentioned in the EAV index. This works the same for all storage types.
+
+use crate::config::{AgentConfiguration, InstanceConfiguration};
+use holochain_core::{
+    agent::state::{AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
+    context::Context,
+    nucleus::state::NucleusStateSnapshot,
+};
+use holochain_core_types::{
+    agent::Base32,
+    chain_header::ChainHeader,
+    dna::Dna,
+    eav::{Attribute, EntityAttributeValueIndex},
+    entry::{entry_type::EntryType, Entry},
+    error::HolochainError,
+};
+use holochain_json_api::{error::JsonResult, json::JsonString};
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent, Content},
+    eav::{query::EaviQuery, IndexFilter},
+};
+use serde_json;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::File,
+    path::Path,
+    sync::Arc,
+};
+
+/// Version of the archive format written by this module.
+/// Archives with a different version are rejected on import.
+pub const INSTANCE_ARCHIVE_VERSION: u32 = 1;
+
+/// One item of a content addressable storage, stored under its original address.
+/// Keeping the address explicit is needed for items like the state snapshots
+/// which live at fixed addresses instead of the hash of their content.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedContent {
+    pub address: Address,
+    pub content: String,
+}
+
+impl ArchivedContent {
+    pub fn from_addressable(item: &AddressableContent) -> Self {
+        ArchivedContent {
+            address: item.address(),
+            content: String::from(item.content()),
+        }
+    }
+}
+
+impl AddressableContent for ArchivedContent {
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    fn content(&self) -> Content {
+        JsonString::from_json(&self.content)
+    }
+
+    fn try_from_content(content: &Content) -> JsonResult<Self> {
+        Ok(ArchivedContent {
+            address: content.address(),
+            content: String::from(content.to_owned()),
+        })
+    }
+}
+
+/// One EAVI tuple
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedEavi {
+    pub entity: Address,
+    pub attribute: Attribute,
+    pub value: Address,
+    pub index: i64,
+}
+
+impl From<&EntityAttributeValueIndex> for ArchivedEavi {
+    fn from(eavi: &EntityAttributeValueIndex) -> Self {
+        ArchivedEavi {
+            entity: eavi.entity(),
+            attribute: eavi.attribute(),
+            value: eavi.value(),
+            index: eavi.index(),
+        }
+    }
+}
+
+/// Agent configuration without the reference to the keystore
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedAgent {
+    pub id: String,
+    pub name: String,
+    pub public_address: Base32,
+    pub holo_remote_key: Option<bool>,
+}
+
+impl From<&AgentConfiguration> for ArchivedAgent {
+    fn from(agent: &AgentConfiguration) -> Self {
+        ArchivedAgent {
+            id: agent.id.clone(),
+            name: agent.name.clone(),
+            public_address: agent.public_address.clone(),
+            holo_remote_key: agent.holo_remote_key,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceArchive {
+    pub version: u32,
+    pub instance: InstanceConfiguration,
+    pub agent: ArchivedAgent,
+    pub dna: Dna,
+    pub chain: Vec<ArchivedContent>,
+    pub dht: Vec<ArchivedContent>,
+    pub eav: Vec<ArchivedEavi>,
+}
+
+impl InstanceArchive {
+    /// Collects the content of a running instance.
+    /// Chain and snapshots are taken from the same state so they are consistent
+    /// with each other even if the instance keeps committing while exporting.
+    pub fn from_instance(
+        instance: &InstanceConfiguration,
+        agent: &AgentConfiguration,
+        context: &Arc<Context>,
+    ) -> Result<InstanceArchive, HolochainError> {
+        let state = context
+            .state()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance is not initialized".into()))?
+            .clone();
+        let dna = state
+            .nucleus()
+            .dna()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no DNA".into()))?;
+
+        let headers: Vec<ChainHeader> = state.agent().iter_chain().collect();
+        let mut chain = Vec::new();
+        {
+            let chain_storage = context.chain_storage.read()?;
+            for header in headers.iter() {
+                chain.push(ArchivedContent::from_addressable(header));
+                let entry = chain_storage
+                    .fetch(header.entry_address())?
+                    .ok_or_else(|| {
+                        HolochainError::ErrorGeneric(format!(
+                            "Entry {} of header {} is missing in chain storage",
+                            header.entry_address(),
+                            header.address()
+                        ))
+                    })?;
+                chain.push(ArchivedContent {
+                    address: header.entry_address().clone(),
+                    content: String::from(entry),
+                });
+            }
+        }
+        chain.push(ArchivedContent::from_addressable(
+            &AgentStateSnapshot::from(&state),
+        ));
+        chain.push(ArchivedContent::from_addressable(
+            &NucleusStateSnapshot::from(&state),
+        ));
+
+        let eavis = context.eav_storage.read()?.fetch_eavi(&EaviQuery::new(
+            None.into(),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        ))?;
+
+        // Everything the DHT holds is either an own entry or mentioned in the EAV index:
+        let mut dht_addresses: BTreeSet<Address> = headers
+            .iter()
+            .map(|header| header.entry_address().clone())
+            .collect();
+        for eavi in eavis.iter() {
+            dht_addresses.insert(eavi.entity());
+            dht_addresses.insert(eavi.value());
+        }
+        let mut dht = Vec::new();
+        {
+            let dht_storage = context.dht_storage.read()?;
+            for address in dht_addresses {
+                if let Some(content) = dht_storage.fetch(&address)? {
+                    dht.push(ArchivedContent {
+                        address,
+                        content: String::from(content),
+                    });
+                }
+            }
+        }
+
+        Ok(InstanceArchive {
+            version: INSTANCE_ARCHIVE_VERSION,
+            instance: instance.clone(),
+            agent: ArchivedAgent::from(agent),
+            dna,
+            chain,
+            dht,
+            eav: eavis.iter().map(ArchivedEavi::from).collect(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), HolochainError> {
+        let file = File::create(path).map_err(|e| {
+            HolochainError::ErrorGeneric(format!(
+                "Could not create archive file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<InstanceArchive, HolochainError> {
+        let file = File::open(path).map_err(|e| {
+            HolochainError::ErrorGeneric(format!(
+                "Could not open archive file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let archive: InstanceArchive = serde_json::from_reader(file)?;
+        if archive.version != INSTANCE_ARCHIVE_VERSION {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Archive has version {} but only version {} is supported",
+                archive.version, INSTANCE_ARCHIVE_VERSION
+            )));
+        }
+        Ok(archive)
+    }
+
+    /// Checks the archived chain before it gets restored:
+    /// * every header and entry is present and matches its address,
+    /// * every header references an entry of the type it claims,
+    /// * the chain starts with the archived DNA and
+    /// * the first agent entry belongs to the given public address.
+    ///   Later agent entries are key rotations and carry other keys.
+    pub fn verify_chain(&self, public_address: &Base32) -> Result<(), HolochainError> {
+        let chain: HashMap<&Address, &ArchivedContent> = self
+            .chain
+            .iter()
+            .map(|item| (&item.address, item))
+            .collect();
+        let invalid = |message: String| {
+            HolochainError::ErrorGeneric(format!("Invalid chain in archive: {}", message))
+        };
+
+        let snapshot = chain
+            .get(&Address::from(AGENT_SNAPSHOT_ADDRESS))
+            .ok_or_else(|| invalid("agent state snapshot is missing".into()))?;
+        let snapshot = AgentStateSnapshot::try_from_content(&snapshot.content())?;
+
+        let mut next_header = snapshot.top_chain_header().map(|header| header.address());
+        let mut genesis_entry = None;
+        let mut first_agent = None;
+        while let Some(header_address) = next_header {
+            let header = chain
+                .get(&header_address)
+                .ok_or_else(|| invalid(format!("header {} is missing", header_address)))?;
+            let header = ChainHeader::try_from_content(&header.content())?;
+            if header.address() != header_address {
+                return Err(invalid(format!(
+                    "header {} does not match its address",
+                    header_address
+                )));
+            }
+
+            let entry_address = header.entry_address();
+            let entry = chain
+                .get(entry_address)
+                .ok_or_else(|| invalid(format!("entry {} is missing", entry_address)))?;
+            let entry = Entry::try_from_content(&entry.content())?;
+            if entry.address() != *entry_address {
+                return Err(invalid(format!(
+                    "entry {} does not match its address",
+                    entry_address
+                )));
+            }
+            if entry.entry_type() != *header.entry_type() {
+                return Err(invalid(format!(
+                    "entry {} is not of type {} as stated in its header",
+                    entry_address,
+                    header.entry_type()
+                )));
+            }
+
+            if let Entry::AgentId(ref agent_id) = entry {
+                first_agent = Some(agent_id.pub_sign_key.clone());
+            }
+
+            next_header = header.link();
+            genesis_entry = Some(entry);
+        }
+
+        if let Some(agent_key) = first_agent {
+            if agent_key != *public_address {
+                return Err(invalid(format!(
+                    "chain belongs to agent {} and not to {}",
+                    agent_key, public_address
+                )));
+            }
+        }
+
+        match genesis_entry {
+            Some(Entry::Dna(ref dna)) if dna.address() == self.dna.address() => Ok(()),
+            Some(_) => Err(invalid(format!(
+                "chain does not start with a {} entry of the archived DNA",
+                EntryType::Dna
+            ))),
+            None => Err(invalid("chain is empty".into())),
+        }
+    }
+
+    /// Writes the archived content into the storages of the given context.
+    /// Needs to happen before the instance gets loaded from that context.
+    pub fn restore(&self, context: &Arc<Context>) -> Result<(), HolochainError> {
+        {
+            let mut chain_storage = context.chain_storage.write()?;
+            for item in self.chain.iter() {
+                chain_storage.add(item)?;
+            }
+        }
+        {
+            let mut dht_storage = context.dht_storage.write()?;
+            for item in self.dht.iter() {
+                dht_storage.add(item)?;
+            }
+        }
+        let mut eav_storage = context.eav_storage.write()?;
+        for eavi in self.eav.iter() {
+            eav_storage.add_eavi(&EntityAttributeValueIndex::new_with_index(
+                &eavi.entity,
+                &eavi.attribute,
+                &eavi.value,
+                eavi.index,
+            )?)?;
+        }
+        Ok(())
+    }
+}
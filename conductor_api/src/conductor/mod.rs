@@ -1,6 +1,8 @@
 pub mod admin;
 pub mod base;
+pub mod blocklist;
 pub mod broadcaster;
+pub mod instance_archive;
 pub mod passphrase_manager;
 pub mod test_admin;
 pub mod ui_admin;
@@ -156,6 +156,14 @@ impl Configuration {
                     instance.agent, instance.id
                 )
             })?;
+            if let PersisterConfiguration::Journal { .. } = instance.persister {
+                (!instance.storage.encrypted()).ok_or_else(|| {
+                    format!(
+                        "Instance \"{}\" can't use the journal persister with encrypted storage since the journal is not encrypted",
+                        instance.id
+                    )
+                })?;
+            }
             let dna_config = self.dna_by_id(&instance.dna);
             dna_config.is_some().ok_or_else(|| {
                 format!(
@@ -194,6 +202,11 @@ impl Configuration {
                     )
                 })?;
             }
+            if let InterfaceDriver::Custom(_) = interface.driver {
+                interface.driver.custom_driver_name().map_err(|err| {
+                    format!("Invalid driver of interface \"{}\": {}", interface.id, err)
+                })?;
+            }
         }
 
         for bridge in self.bridges.iter() {
@@ -548,21 +561,87 @@ pub struct InstanceConfiguration {
     pub dna: String,
     pub agent: String,
     pub storage: StorageConfiguration,
+    #[serde(default, skip_serializing_if = "PersisterConfiguration::is_default")]
+    pub persister: PersisterConfiguration,
 }
 
 /// This configures the Content Addressable Storage (CAS) that
 /// the instance uses to store source chain and DHT shard in.
-/// There are two storage implementations in cas_implementations so far:
+/// There are four storage implementations so far:
 /// * memory
 /// * file
+/// * pickle
+/// * kv (embedded transactional key-value database, see `storage::kv`)
+///
+/// Persistent storages can be encrypted at rest by setting `encrypted = true`
+/// (see `storage::encrypted`). The key is derived from the agent's keystore.
 ///
 /// Projected are various DB adapters.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum StorageConfiguration {
     Memory,
-    File { path: String },
-    Pickle { path: String },
+    File {
+        path: String,
+        #[serde(default, skip_serializing_if = "is_false")]
+        encrypted: bool,
+    },
+    Pickle {
+        path: String,
+        #[serde(default, skip_serializing_if = "is_false")]
+        encrypted: bool,
+    },
+    Kv {
+        path: String,
+        #[serde(default, skip_serializing_if = "is_false")]
+        encrypted: bool,
+    },
+}
+
+impl StorageConfiguration {
+    pub fn encrypted(&self) -> bool {
+        match self {
+            StorageConfiguration::Memory => false,
+            StorageConfiguration::File { encrypted, .. }
+            | StorageConfiguration::Pickle { encrypted, .. }
+            | StorageConfiguration::Kv { encrypted, .. } => *encrypted,
+        }
+    }
+}
+
+/// This configures how the instance persists its state (source chain top, pending
+/// validations..) next to the CAS, so that it can resume after a restart.
+/// There are two persisters so far:
+/// * simple (default): saves a snapshot of the state to the instance's storage
+/// * journal: appends every durable action to a journal file and writes periodic
+///   checkpoints (see `holochain_core::persister::JournalPersister`). It also rebuilds
+///   the held DHT shard, which makes it the choice for `memory` storage. The journal is
+///   not encrypted, so instances with encrypted storage can't use it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PersisterConfiguration {
+    Simple,
+    Journal {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checkpoint_interval: Option<u64>,
+    },
+}
+
+impl Default for PersisterConfiguration {
+    fn default() -> Self {
+        PersisterConfiguration::Simple
+    }
+}
+
+impl PersisterConfiguration {
+    fn is_default(&self) -> bool {
+        *self == PersisterConfiguration::default()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 /// Here, interfaces are user facing and make available zome functions to
@@ -596,6 +675,21 @@ pub enum InterfaceDriver {
     Custom(toml::value::Value),
 }
 
+impl InterfaceDriver {
+    /// Custom drivers are looked up by the `name` entry of their config table
+    /// in the conductor's registry of interface factories.
+    pub fn custom_driver_name(&self) -> Result<String, String> {
+        match self {
+            InterfaceDriver::Custom(config) => config
+                .get("name")
+                .and_then(|name| name.as_str())
+                .map(String::from)
+                .ok_or_else(|| String::from("custom driver needs a `name` string")),
+            _ => Err(String::from("not a custom driver")),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct InstanceReferenceConfiguration {
     pub id: String,
@@ -673,6 +767,18 @@ pub struct NetworkConfig {
     /// filepath to the json file holding the network settings for n3h
     #[serde(default)]
     pub networking_config_file: Option<String>,
+    /// Address to listen on for connections from other nodes, using the native TCP
+    /// networking instead of n3h, e.g. "0.0.0.0:7700".
+    /// If this is set the n3h configs above are ignored and `bootstrap_nodes` have to be
+    /// the addresses of other nodes in the same form. Default is None.
+    #[serde(default)]
+    pub tcp_bind_address: Option<String>,
+    /// Address other nodes are told to connect to when using the native TCP networking,
+    /// e.g. "192.168.0.11:7700". It is required if `tcp_bind_address` is an unspecified
+    /// address like "0.0.0.0:7700", since other nodes can't connect to that.
+    /// Defaults to the bound address.
+    #[serde(default)]
+    pub tcp_advertise_address: Option<String>,
 }
 
 // note that this behaviour is documented within
@@ -883,6 +989,8 @@ pub mod tests {
                 networking_config_file: Some(String::from(
                     "/Users/cnorris/.holochain/network_config.json"
                 )),
+                tcp_bind_address: None,
+                tcp_advertise_address: None,
             }
         );
     }
@@ -1358,4 +1466,95 @@ pub mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_storage_encryption_flag() {
+        let storage: StorageConfiguration =
+            load_configuration("type = 'kv'\npath = 'storage'\nencrypted = true").unwrap();
+        assert!(storage.encrypted());
+
+        let storage: StorageConfiguration =
+            load_configuration("type = 'file'\npath = 'storage'").unwrap();
+        assert_eq!(
+            storage,
+            StorageConfiguration::File {
+                path: "storage".into(),
+                encrypted: false,
+            }
+        );
+        assert!(!toml::to_string(&storage).unwrap().contains("encrypted"));
+    }
+
+    #[test]
+    fn test_inconsistent_journal_with_encrypted_storage() {
+        let toml = r#"
+    [[agents]]
+    id = "test agent"
+    name = "Holo Tester 1"
+    public_address = "HoloTester1-------------------------------------------------------------------------AHi1"
+    keystore_file = "holo_tester.key"
+
+    [[dnas]]
+    id = "app spec rust"
+    file = "app_spec.dna.json"
+    hash = "Qm328wyq38924y"
+
+    [[instances]]
+    id = "app spec instance"
+    dna = "app spec rust"
+    agent = "test agent"
+        [instances.storage]
+        type = "kv"
+        path = "app_spec_storage"
+        encrypted = true
+        [instances.persister]
+        type = "journal"
+        path = "app_spec_journal"
+    "#;
+
+        let config = load_configuration::<Configuration>(toml).unwrap();
+
+        assert_eq!(
+            config.check_consistency(&mut test_dna_loader()),
+            Err("Instance \"app spec instance\" can't use the journal persister with encrypted storage since the journal is not encrypted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_persister_configuration() {
+        let instance: InstanceConfiguration = load_configuration(
+            r#"
+            id = "app spec instance"
+            agent = "test agent"
+            dna = "app spec rust"
+            [storage]
+            type = "memory"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(instance.persister, PersisterConfiguration::Simple);
+        assert!(!toml::to_string(&instance).unwrap().contains("persister"));
+
+        let instance: InstanceConfiguration = load_configuration(
+            r#"
+            id = "app spec instance"
+            agent = "test agent"
+            dna = "app spec rust"
+            [storage]
+            type = "memory"
+            [persister]
+            type = "journal"
+            path = "journal"
+            checkpoint_interval = 50
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            instance.persister,
+            PersisterConfiguration::Journal {
+                path: "journal".into(),
+                checkpoint_interval: Some(50),
+            }
+        );
+    }
 }
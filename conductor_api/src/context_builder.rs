@@ -4,6 +4,11 @@ use holochain_persistence_mem::{cas::memory::MemoryStorage, eav::memory::EavMemo
 
 use holochain_persistence_pickle::{cas::pickle::PickleStorage, eav::pickle::EavPickleStorage};
 
+use crate::storage::{
+    encrypted::{EncryptedEavStorage, EncryptedStorage, StorageKey},
+    kv::{EavKvStorage, KvStorage, KvStore},
+};
+
 use holochain_persistence_api::{
     cas::storage::ContentAddressableStorage, eav::EntityAttributeValueStorage,
 };
@@ -11,7 +16,7 @@ use holochain_persistence_api::{
 use holochain_core::{
     context::Context,
     logger::{Logger, SimpleLogger},
-    persister::SimplePersister,
+    persister::{Persister, SimplePersister},
     signal::SignalSender,
 };
 use holochain_core_types::{agent::AgentId, eav::Attribute, error::HolochainError};
@@ -34,9 +39,7 @@ use std::{
 pub struct ContextBuilder {
     agent_id: Option<AgentId>,
     logger: Option<Arc<Mutex<Logger>>>,
-    // Persister is currently set to a reasonable default in spawn().
-    // TODO: add with_persister() function to ContextBuilder.
-    //persister: Option<Arc<Mutex<Persister>>>,
+    persister: Option<Arc<Mutex<Persister>>>,
     chain_storage: Option<Arc<RwLock<ContentAddressableStorage>>>,
     dht_storage: Option<Arc<RwLock<ContentAddressableStorage>>>,
     eav_storage: Option<Arc<RwLock<EntityAttributeValueStorage<Attribute>>>>,
@@ -50,6 +53,7 @@ impl ContextBuilder {
         ContextBuilder {
             agent_id: None,
             logger: None,
+            persister: None,
             chain_storage: None,
             dht_storage: None,
             eav_storage: None,
@@ -114,6 +118,38 @@ impl ContextBuilder {
         Ok(self)
     }
 
+    /// Sets all three storages, chain, DHT and EAV storage, to one embedded KV database.
+    /// Chain and DHT storages get set to the same KV CAS.
+    /// Returns an error if the database can't be opened on the given path or was
+    /// written in an unsupported format version.
+    pub fn with_kv_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
+        fs::create_dir_all(path.as_ref())?;
+        let store = KvStore::open(path)?;
+        let cas_storage = Arc::new(RwLock::new(KvStorage::new(&store)?));
+        let eav_storage = Arc::new(RwLock::new(EavKvStorage::new(&store)?));
+        self.chain_storage = Some(cas_storage.clone());
+        self.dht_storage = Some(cas_storage);
+        self.eav_storage = Some(eav_storage);
+        Ok(self)
+    }
+
+    /// Wraps the chain, DHT and EAV storages set so far so that everything gets encrypted
+    /// with the given key before it is written.
+    /// Has to be called after one of the `with_*_storage` functions.
+    pub fn with_storage_encryption(mut self, key: StorageKey) -> Self {
+        let encrypt_cas = |storage: Arc<RwLock<ContentAddressableStorage>>| {
+            Arc::new(RwLock::new(EncryptedStorage::new(storage, key.clone())))
+                as Arc<RwLock<ContentAddressableStorage>>
+        };
+        self.chain_storage = self.chain_storage.map(&encrypt_cas);
+        self.dht_storage = self.dht_storage.map(&encrypt_cas);
+        self.eav_storage = self.eav_storage.map(|storage| {
+            Arc::new(RwLock::new(EncryptedEavStorage::new(storage, key.clone())))
+                as Arc<RwLock<EntityAttributeValueStorage<Attribute>>>
+        });
+        self
+    }
+
     /// Sets the network config.
     pub fn with_p2p_config(mut self, p2p_config: P2pConfig) -> Self {
         self.p2p_config = Some(p2p_config);
@@ -130,6 +166,13 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the persister that stores the instance state.
+    /// Defaults to a SimplePersister on the chain storage (see `spawn()`).
+    pub fn with_persister(mut self, persister: Arc<Mutex<Persister>>) -> Self {
+        self.persister = Some(persister);
+        self
+    }
+
     pub fn with_signals(mut self, signal_tx: SignalSender) -> Self {
         self.signal_tx = Some(signal_tx);
         self
@@ -138,7 +181,7 @@ impl ContextBuilder {
     /// Actually creates the context.
     /// Defaults to memory storages, an in-memory network config and a fake agent called "alice".
     /// The logger gets set to SimpleLogger.
-    /// The persister gets set to SimplePersister based on the chain storage.
+    /// The persister defaults to SimplePersister based on the chain storage.
     pub fn spawn(self) -> Context {
         let chain_storage = self
             .chain_storage
@@ -149,10 +192,13 @@ impl ContextBuilder {
         let eav_storage = self
             .eav_storage
             .unwrap_or(Arc::new(RwLock::new(EavMemoryStorage::new())));
+        let persister = self
+            .persister
+            .unwrap_or_else(|| Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))));
         Context::new(
             self.agent_id.unwrap_or(AgentId::generate_fake("alice")),
             self.logger.unwrap_or(Arc::new(Mutex::new(SimpleLogger {}))),
-            Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))),
+            persister,
             chain_storage,
             dht_storage,
             eav_storage,
@@ -178,7 +224,7 @@ mod tests {
         let context = ContextBuilder::new()
             .with_conductor_api(mock_conductor_api(agent.clone()))
             .spawn();
-        assert_eq!(context.agent_id, agent);
+        assert_eq!(context.agent_id(), agent);
         assert_eq!(P2pBackendKind::MEMORY, context.p2p_config.backend_kind);
     }
 
@@ -189,7 +235,7 @@ mod tests {
             .with_agent(agent.clone())
             .with_conductor_api(mock_conductor_api(agent.clone()))
             .spawn();
-        assert_eq!(context.agent_id, agent);
+        assert_eq!(context.agent_id(), agent);
     }
 
     #[test]
@@ -215,5 +261,11 @@ mod tests {
             .expect("Filestorage should get instantiated with tempdir")
             .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
             .spawn();
+        let temp = tempdir().expect("test was supposed to create temp dir");
+        let _ = ContextBuilder::new()
+            .with_kv_storage(temp.path().join("kv"))
+            .expect("KV storage should get instantiated with tempdir")
+            .with_conductor_api(mock_conductor_api(AgentId::generate_fake("alice")))
+            .spawn();
     }
 }
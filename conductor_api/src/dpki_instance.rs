@@ -28,7 +28,7 @@ fn dpki_cap_request(
     function: &str,
     parameters: &str,
 ) -> Result<CapabilityRequest, HolochainError> {
-    let token = Address::from(context.agent_id.address());
+    let token = Address::from(context.agent_id().address());
     Ok(make_cap_request_for_call(
         context.clone(),
         token,
@@ -98,7 +98,7 @@ use holochain_core::{
         ribosome::{run_dna, WasmCallData},
         ZomeFnCall,
     },
-    persister::{Persister, SimplePersister},
+    persister::Persister,
     state::State,
 };
 use holochain_core_types::{
@@ -165,8 +165,10 @@ impl Holochain {
     }
 
     pub fn load(context: Arc<Context>) -> Result<Self, HolochainError> {
-        let persister = SimplePersister::new(context.dht_storage.clone());
-        let loaded_state = persister
+        let loaded_state = context
+            .persister
+            .lock()
+            .map_err(|_| HolochainError::new("Could not get lock on persister"))?
             .load(context.clone())?
             .ok_or(HolochainError::ErrorGeneric(
                 "State could not be loaded due to NoneError".to_string(),
@@ -306,7 +308,7 @@ mod tests {
     fn cap_call(context: Arc<Context>, fn_name: &str, params: &str) -> CapabilityRequest {
         make_cap_request_for_call(
             context.clone(),
-            Address::from(context.clone().agent_id.address()),
+            Address::from(context.clone().agent_id().address()),
             fn_name,
             JsonString::from_json(params),
         )
@@ -322,7 +324,7 @@ mod tests {
         let hc = result.unwrap();
         assert_eq!(hc.instance.state().nucleus().dna(), Some(dna));
         assert!(!hc.active);
-        assert_eq!(hc.context.agent_id.nick, "bob".to_string());
+        assert_eq!(hc.context.agent_id().nick, "bob".to_string());
         let network_state = hc.context.state().unwrap().network().clone();
         assert_eq!(network_state.agent_id.is_some(), true);
         assert_eq!(network_state.dna_address.is_some(), true);
@@ -350,7 +352,7 @@ mod tests {
         assert!(result.is_ok());
         let loaded_holo = result.unwrap();
         assert!(!loaded_holo.active);
-        assert_eq!(loaded_holo.context.agent_id.nick, "bob".to_string());
+        assert_eq!(loaded_holo.context.agent_id().nick, "bob".to_string());
         let network_state = loaded_holo.context.state().unwrap().network().clone();
         assert!(network_state.agent_id.is_some());
         assert!(network_state.dna_address.is_some());
@@ -1,6 +1,6 @@
 use crate::holo_signing_service::request_signing_service;
 use base64;
-use conductor::broadcaster::Broadcaster;
+use conductor::{blocklist::Blocklist, broadcaster::Broadcaster};
 use crossbeam_channel::Receiver;
 use holochain_core::nucleus::actions::call_zome_function::make_cap_request_for_call;
 
@@ -28,8 +28,9 @@ use config::{
     InterfaceDriver, UiBundleConfiguration, UiInterfaceConfiguration,
 };
 use holochain_dpki::utils::SeedContext;
-use keystore::{KeyType, Keystore, Secret};
+use keystore::{agent_keybundle_id, KeyType, Keystore, Secret};
 use serde_json::{self, map::Map};
+use toml;
 
 pub type InterfaceError = String;
 pub type InstanceMap = HashMap<String, Arc<RwLock<Holochain>>>;
@@ -372,6 +373,65 @@ impl ConductorApiBuilder {
     ///     Removes an instance. Also remove its any uses of it in interfaces.
     ///     * `id`: [string] Which instance to remove?
     ///
+    ///  * `admin/instance/export`
+    ///     Writes a backup of an instance to one archive file: chain, DHT and EAV content,
+    ///     the DNA and the instance and agent config. Keystores are not included.
+    ///     Params:
+    ///     * `id`: [string] Which instance to export?
+    ///     * `path`: [string] Where to write the archive to
+    ///
+    ///  * `admin/instance/import`
+    ///     Restores an instance from an archive written by `admin/instance/export` and adds it
+    ///     to the config. Fails without changing anything if the archived chain is not intact.
+    ///     Installs the archived DNA if it is not installed yet.
+    ///     Does not start the instance nor add it to an interface.
+    ///     Params:
+    ///     * `id`: [string] Name for the restored instance
+    ///     * `path`: [string] Archive file to read
+    ///     * `agent_id`: [string] (optional) Agent to run the instance with. Defaults to the
+    ///        configured agent with the public address of the archived agent.
+    ///
+    ///  * `admin/instance/migrate_chain`
+    ///     Moves the agent's source chain from one DNA to another. Opens the chain of the new
+    ///     instance with a ChainMigrate entry and closes the chain of the old instance with the
+    ///     matching entry, after validating both. Both instances need to run with the same agent
+    ///     but different DNAs, and the new chain must not hold anything but genesis entries.
+    ///     The old instance does not accept any commits afterwards.
+    ///     Params:
+    ///     * `old_id`: [string] Instance whose chain gets closed
+    ///     * `new_id`: [string] Instance whose chain continues the old one
+    ///
+    ///  * `admin/instance/verify_chain`
+    ///     Checks the stored source chain of an instance from the top header back to genesis:
+    ///     header links, entries against their addresses, provenance signatures and timestamp
+    ///     order. Returns a report with the number of checked headers and all problems found.
+    ///     Params:
+    ///     * `id`: [string] Which instance's chain to check?
+    ///
+    ///  * `admin/instance/pending_validations`
+    ///     Lists the validations of an instance that wait for missing dependencies, with the
+    ///     number of failed retries. Stuck ones are not retried anymore.
+    ///     Params:
+    ///     * `id`: [string] Which instance's pending validations to list?
+    ///
+    ///  * `admin/instance/clear_pending_validations`
+    ///     Drops the stuck pending validations of an instance and returns them.
+    ///     Params:
+    ///     * `id`: [string] Which instance's pending validations to clear?
+    ///     * `all`: [bool] Drop all pending validations, not only stuck ones (optional)
+    ///
+    ///  * `admin/blocklist/list`
+    ///     Returns an array of the agents that got warranted for invalid data of a DNA.
+    ///     Direct messages from them are refused by all instances of that DNA.
+    ///     Params:
+    ///     * `dna_id`: [string] Which DNA's blocklist to list?
+    ///
+    ///  * `admin/blocklist/remove`
+    ///     Takes an agent off the blocklist of a DNA.
+    ///     Params:
+    ///     * `dna_id`: [string] Which DNA's blocklist to remove the agent from?
+    ///     * `agent_id`: [string] Which agent to unblock?
+    ///
     ///  * `admin/instance/start`
     ///     Starts a stopped instance or reports an error if the given instance is
     ///     running already
@@ -526,6 +586,68 @@ impl ConductorApiBuilder {
             Ok(json!({"success": true}))
         });
 
+        self.io.add_method("admin/instance/export", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let path = Self::get_as_string("path", &params_map)?;
+            conductor_call!(|c| c.export_instance(&id, PathBuf::from(path)))?;
+            Ok(json!({"success": true}))
+        });
+
+        self.io.add_method("admin/instance/import", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let path = Self::get_as_string("path", &params_map)?;
+            let agent_id = Self::get_as_string("agent_id", &params_map).ok();
+            conductor_call!(|c| c.import_instance(PathBuf::from(path), &id, agent_id))?;
+            Ok(json!({"success": true}))
+        });
+
+        self.io.add_method("admin/instance/migrate_chain", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let old_id = Self::get_as_string("old_id", &params_map)?;
+            let new_id = Self::get_as_string("new_id", &params_map)?;
+            conductor_call!(|c| c.migrate_chain(&old_id, &new_id))?;
+            Ok(json!({"success": true}))
+        });
+
+        self.io.add_method("admin/instance/verify_chain", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let report = conductor_call!(|c| c.verify_chain(&id))?;
+            Ok(serde_json::to_value(report).map_err(|_| jsonrpc_core::Error::internal_error())?)
+        });
+
+        self.io.add_method("admin/instance/pending_validations", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let pending = conductor_call!(|c| c.pending_validations(&id))?;
+            Ok(serde_json::to_value(pending).map_err(|_| jsonrpc_core::Error::internal_error())?)
+        });
+
+        self.io.add_method("admin/instance/clear_pending_validations", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let id = Self::get_as_string("id", &params_map)?;
+            let all = Self::get_as_bool("all", &params_map).unwrap_or(false);
+            let cleared = conductor_call!(|c| c.clear_pending_validations(&id, all))?;
+            Ok(serde_json::to_value(cleared).map_err(|_| jsonrpc_core::Error::internal_error())?)
+        });
+
+        self.io.add_method("admin/blocklist/list", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let dna_id = Self::get_as_string("dna_id", &params_map)?;
+            let agents = conductor_call!(|c| c.blocked_agents(&dna_id))?;
+            Ok(json!(agents))
+        });
+
+        self.io.add_method("admin/blocklist/remove", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let dna_id = Self::get_as_string("dna_id", &params_map)?;
+            let agent_id = Self::get_as_string("agent_id", &params_map)?;
+            conductor_call!(|c| c.unblock_agent(&dna_id, &agent_id))?;
+            Ok(json!({"success": true}))
+        });
+
         self.io.add_method("admin/instance/start", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let id = Self::get_as_string("id", &params_map)?;
@@ -592,21 +714,47 @@ impl ConductorApiBuilder {
             let id = Self::get_as_string("id", &params_map)?;
             let admin = Self::get_as_bool("admin", &params_map)?;
             let driver_type = Self::get_as_string("type", &params_map)?;
-            let port = u16::try_from(Self::get_as_int("port", &params_map)?).map_err(|_| {
-                jsonrpc_core::Error::invalid_params(String::from(
-                    "`port` has to be a 16bit integer",
-                ))
-            })?;
+            let get_port = || {
+                u16::try_from(Self::get_as_int("port", &params_map)?).map_err(|_| {
+                    jsonrpc_core::Error::invalid_params(String::from(
+                        "`port` has to be a 16bit integer",
+                    ))
+                })
+            };
 
             let new_interface = InterfaceConfiguration {
                 id: id.to_string(),
                 admin,
                 driver: match driver_type.as_ref() {
-                    "websocket" => InterfaceDriver::Websocket { port },
-                    "http" => InterfaceDriver::Http { port },
+                    "websocket" => InterfaceDriver::Websocket { port: get_port()? },
+                    "http" => InterfaceDriver::Http { port: get_port()? },
+                    "domainsocket" => InterfaceDriver::DomainSocket {
+                        file: Self::get_as_string("file", &params_map)?,
+                    },
+                    "custom" => {
+                        let name = Self::get_as_string("name", &params_map)?;
+                        let mut config = match params_map.get("config") {
+                            Some(config) => toml::value::Value::try_from(config).map_err(|e| {
+                                jsonrpc_core::Error::invalid_params(format!(
+                                    "`config` is not a valid driver config: {}",
+                                    e
+                                ))
+                            })?,
+                            None => toml::value::Value::Table(toml::value::Table::new()),
+                        };
+                        config
+                            .as_table_mut()
+                            .ok_or_else(|| {
+                                jsonrpc_core::Error::invalid_params(String::from(
+                                    "`config` has to be an object",
+                                ))
+                            })?
+                            .insert("name".into(), toml::value::Value::String(name));
+                        InterfaceDriver::Custom(config)
+                    }
                     _ => {
                         return Err(jsonrpc_core::Error::invalid_params(String::from(
-                            "`type` has to be either `websocket` or `http`",
+                            "`type` has to be one of `websocket`, `http`, `domainsocket` or `custom`",
                         )));
                     }
                 },
@@ -850,7 +998,47 @@ impl ConductorApiBuilder {
         self
     }
 
+    /// Adds the functions core uses to sign and decrypt with the agent's keybundle:
+    ///
+    /// - `agent/sign`
+    ///     Signs the given payload with the agent's signing key.
+    ///     Params:
+    ///         - payload [String]
+    ///     Returns: Json object containing the base64 encoded `signature`
+    ///
+    /// - `agent/encryption_key`
+    ///     Returns: Json object containing the agent's public encrypting key as `pub_key`
+    ///
+    /// - `agent/decrypt`
+    ///     Decrypts a payload that was encrypted for the agent's encrypting key.
+    ///     Params:
+    ///         - sender [String] public encrypting key the payload was encrypted with
+    ///         - payload [String] base64 encoded nonce and cipher text
+    ///     Returns: Json object containing the base64 encoded decrypted `payload`
     pub fn with_agent_signature_callback(mut self, keybundle: Arc<Mutex<KeyBundle>>) -> Self {
+        let k = keybundle.clone();
+        self.io.add_method("agent/encryption_key", move |_params| {
+            let pub_key = k.lock().unwrap().enc_keys.public.clone();
+            Ok(json!({ "pub_key": pub_key }))
+        });
+
+        let k = keybundle.clone();
+        self.io.add_method("agent/decrypt", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let sender = Self::get_as_string("sender", &params_map)?;
+            let payload = Self::get_as_string("payload", &params_map)?;
+            let cipher = base64::decode(&payload)
+                .map_err(|_| jsonrpc_core::Error::invalid_params("payload is not valid base64"))?;
+            let mut message = k
+                .lock()
+                .unwrap()
+                .enc_keys
+                .decrypt_from(&sender, &cipher)
+                .map_err(|err| jsonrpc_core::Error::invalid_params(err.to_string()))?;
+            let message = message.read_lock();
+            Ok(json!({ "payload": base64::encode(&**message) }))
+        });
+
         self.io.add_method("agent/sign", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let payload = Self::get_as_string("payload", &params_map)?;
@@ -874,6 +1062,120 @@ impl ConductorApiBuilder {
         self
     }
 
+    /// Adds the functions used by core to rotate the agent's key (see `hdk::update_agent`):
+    ///
+    /// - `agent/rotate_key/new`
+    ///     Derives the next generation of the agent's keybundle from the keystore's root seed,
+    ///     adds it to the keystore and saves the keystore to the given file before it gets
+    ///     used, so the key is never lost once the chain refers to it.
+    ///     The new key is kept pending, `agent/sign` still uses the current one.
+    ///     Returns: Json object containing the new public key as `pub_key`
+    ///
+    /// - `agent/rotate_key/sign`
+    ///     Signs the given payload with the pending key.
+    ///     Params:
+    ///         - payload [String]
+    ///     Returns: Json object containing the base64 encoded `signature`
+    ///
+    /// - `agent/rotate_key/activate`
+    ///     Makes `agent/sign` use the pending key.
+    pub fn with_agent_key_rotation_functions(
+        mut self,
+        keystore: Arc<Mutex<Keystore>>,
+        keybundle: Arc<Mutex<KeyBundle>>,
+        keystore_file: PathBuf,
+    ) -> Self {
+        let pending: Arc<Mutex<Option<KeyBundle>>> = Arc::new(Mutex::new(None));
+
+        let p = pending.clone();
+        self.io.add_method("agent/rotate_key/new", move |_params| {
+            let mut keystore = keystore.lock().unwrap();
+            let generation = keystore.agent_key_generation() + 1;
+            let mut new_keybundle = keystore.derive_agent_keybundle(generation).map_err(|err| {
+                jsonrpc_core::Error::invalid_params(format!("Could not derive key: {}", err))
+            })?;
+            keystore
+                .add_keybundle(&agent_keybundle_id(generation), &mut new_keybundle)
+                .and_then(|_| keystore.save(keystore_file.clone()))
+                .map_err(|err| {
+                    jsonrpc_core::Error::invalid_params(format!("Could not store new key: {}", err))
+                })?;
+            let pub_key = new_keybundle.get_id();
+            *p.lock().unwrap() = Some(new_keybundle);
+            Ok(json!({ "pub_key": pub_key }))
+        });
+
+        let p = pending.clone();
+        self.io.add_method("agent/rotate_key/sign", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let payload = Self::get_as_string("payload", &params_map)?;
+            let mut message = SecBuf::with_insecure_from_string(payload.clone());
+
+            let mut pending = p.lock().unwrap();
+            let new_keybundle = pending.as_mut().ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params("No key rotation in progress")
+            })?;
+            let mut message_signature = new_keybundle
+                .sign(&mut message)
+                .map_err(|_| jsonrpc_core::Error::internal_error())?;
+            let message_signature = message_signature.read_lock();
+            let signature = base64::encode(&**message_signature);
+
+            Ok(json!({ "signature": signature }))
+        });
+
+        let p = pending.clone();
+        self.io.add_method("agent/rotate_key/activate", move |_params| {
+            let new_keybundle = p.lock().unwrap().take().ok_or_else(|| {
+                jsonrpc_core::Error::invalid_params("No key rotation in progress")
+            })?;
+            *keybundle.lock().unwrap() = new_keybundle;
+            Ok(json!({"success": true}))
+        });
+
+        self
+    }
+
+    /// Adds the functions core uses to block agents that got warranted for invalid data.
+    /// Agents get blocked for the instance's DNA only:
+    ///
+    /// - `blocklist/add`
+    ///     Puts the agent on the conductor's blocklist of the DNA.
+    ///     Params:
+    ///         - agent_id [String] address of the agent to block
+    ///
+    /// - `blocklist/contains`
+    ///     Params:
+    ///         - agent_id [String] address of the agent to look up
+    ///     Returns: Json object with `blocked` set to true if the agent is on the blocklist
+    ///     of the DNA
+    pub fn with_blocklist_functions(
+        mut self,
+        blocklist: Arc<RwLock<Blocklist>>,
+        dna_id: String,
+    ) -> Self {
+        let b = blocklist.clone();
+        let dna = dna_id.clone();
+        self.io.add_method("blocklist/add", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let agent_id = Self::get_as_string("agent_id", &params_map)?;
+            b.write()
+                .unwrap()
+                .add(&dna, &agent_id)
+                .map_err(|_| jsonrpc_core::Error::internal_error())?;
+            Ok(json!({"success": true}))
+        });
+
+        self.io.add_method("blocklist/contains", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let agent_id = Self::get_as_string("agent_id", &params_map)?;
+            let blocked = blocklist.read().unwrap().contains(&dna_id, &agent_id);
+            Ok(json!({ "blocked": blocked }))
+        });
+
+        self
+    }
+
     /// Adds extra functionality for running tests via the RPC interface
     ///
     /// - `test/agent/add`
@@ -1057,10 +1359,22 @@ pub trait Interface {
     ) -> Result<(Broadcaster, thread::JoinHandle<()>), String>;
 }
 
+/// Builds an Interface from the config table of an `InterfaceDriver::Custom`.
+/// Factories are registered with the Conductor under a driver name
+/// (see `Conductor::register_interface_driver`), so embedding binaries can add
+/// their own transports without touching this crate.
+pub type InterfaceFactory =
+    Arc<Box<Fn(&toml::value::Value) -> Result<Box<Interface>, String> + Send + Sync>>;
+
 #[cfg(test)]
 pub mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
     use super::*;
-    use crate::{conductor::tests::test_conductor, config::Configuration};
+    use crate::{
+        conductor::tests::{test_conductor, test_keybundle, test_keystore},
+        config::Configuration,
+    };
 
     fn example_config_and_instances() -> (Configuration, InstanceMap) {
         let conductor = test_conductor(7777, 7778);
@@ -1141,6 +1455,46 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_agent_key_rotation_stores_key_before_activation() {
+        let tempdir = tempdir().unwrap();
+        let keystore_file = tempdir.path().join("holo_tester1.key");
+        let keystore = Arc::new(Mutex::new(test_keystore(1)));
+        let keybundle = Arc::new(Mutex::new(test_keybundle(1)));
+        let old_pub_key = keybundle.lock().unwrap().get_id();
+        let handler = ConductorApiBuilder::new()
+            .with_agent_key_rotation_functions(
+                keystore.clone(),
+                keybundle.clone(),
+                keystore_file.clone(),
+            )
+            .spawn();
+
+        let response_str = handler
+            .handle_request_sync(&create_call_str("agent/rotate_key/new", None))
+            .expect("Invalid call to handler");
+        let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+        let new_pub_key = response["result"]["pub_key"].as_str().unwrap().to_string();
+
+        // The new key is stored before anything can refer to it, but not used yet
+        assert!(keystore_file.exists());
+        assert_eq!(
+            keystore
+                .lock()
+                .unwrap()
+                .get_agent_keybundle(&new_pub_key)
+                .unwrap()
+                .get_id(),
+            new_pub_key
+        );
+        assert_eq!(keybundle.lock().unwrap().get_id(), old_pub_key);
+
+        handler
+            .handle_request_sync(&create_call_str("agent/rotate_key/activate", None))
+            .expect("Invalid call to handler");
+        assert_eq!(keybundle.lock().unwrap().get_id(), new_pub_key);
+    }
+
     #[test]
     fn test_rpc_call_method() {
         let (config, instances) = example_config_and_instances();
@@ -0,0 +1,199 @@
+use conductor::broadcaster::Broadcaster;
+use crossbeam_channel::{Receiver, TryRecvError};
+use holochain_core::logger::{ChannelLogger, Logger};
+use interface::Interface;
+use jsonrpc_core::IoHandler;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// How long the accept loop sleeps between polls of the listener and the kill switch
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A client connected to a domain socket interface.
+/// Responses and broadcasts share the same stream so writes are serialized through the mutex.
+pub type DomainSocketClient = Arc<Mutex<UnixStream>>;
+
+/// Serves the conductor API as newline delimited JSON-RPC over a Unix domain socket.
+/// Access control is left to the file permissions of the socket file.
+pub struct DomainSocketInterface {
+    file: PathBuf,
+    logger: ChannelLogger,
+}
+
+impl DomainSocketInterface {
+    pub fn new(file: PathBuf, logger: ChannelLogger) -> Self {
+        DomainSocketInterface { file, logger }
+    }
+}
+
+impl Interface for DomainSocketInterface {
+    fn run(
+        &self,
+        handler: IoHandler,
+        kill_switch: Receiver<()>,
+    ) -> Result<(Broadcaster, thread::JoinHandle<()>), String> {
+        // A socket file left behind by a previous run would make bind() fail.
+        // Anything else at that path is not ours to remove:
+        if self.file.exists() {
+            let metadata = fs::metadata(&self.file).map_err(|e| e.to_string())?;
+            if !metadata.file_type().is_socket() {
+                return Err(format!(
+                    "{} exists and is not a socket",
+                    self.file.display()
+                ));
+            }
+            fs::remove_file(&self.file).map_err(|e| e.to_string())?;
+        }
+        let listener = UnixListener::bind(&self.file).map_err(|e| e.to_string())?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let clients: Arc<Mutex<Vec<DomainSocketClient>>> = Arc::new(Mutex::new(Vec::new()));
+        let broadcaster = Broadcaster::DomainSocket(clients.clone());
+        let handler = Arc::new(handler);
+        let file = self.file.clone();
+        let mut logger = self.logger.clone();
+
+        let handle = thread::spawn(move || {
+            loop {
+                match kill_switch.try_recv() {
+                    Err(TryRecvError::Empty) => (),
+                    _ => break,
+                }
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(err) = serve_client(stream, handler.clone(), clients.clone()) {
+                            logger.log(format!(
+                                "err/interface: domain socket client failed: {}",
+                                err
+                            ));
+                        }
+                    }
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL)
+                    }
+                    Err(err) => logger.log(format!(
+                        "err/interface: domain socket accept failed: {}",
+                        err
+                    )),
+                }
+            }
+            let _ = fs::remove_file(&file);
+        });
+        Ok((broadcaster, handle))
+    }
+}
+
+/// Registers the client for broadcasts and spawns a thread that answers each
+/// line it receives as one JSON-RPC request.
+fn serve_client(
+    stream: UnixStream,
+    handler: Arc<IoHandler>,
+    clients: Arc<Mutex<Vec<DomainSocketClient>>>,
+) -> io::Result<()> {
+    // Accepted streams may inherit the non-blocking mode of the listener
+    stream.set_nonblocking(false)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    let client = Arc::new(Mutex::new(stream));
+    clients.lock().unwrap().push(client.clone());
+
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let request = match line {
+                Ok(request) => request,
+                Err(_) => break,
+            };
+            if request.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = handler.handle_request_sync(&request) {
+                if write_line(&client, &response).is_err() {
+                    break;
+                }
+            }
+        }
+        clients
+            .lock()
+            .unwrap()
+            .retain(|other| !Arc::ptr_eq(other, &client));
+    });
+    Ok(())
+}
+
+/// Writes one newline terminated message to a connected client
+pub fn write_line(client: &DomainSocketClient, message: &str) -> io::Result<()> {
+    let mut stream = client.lock().unwrap();
+    stream.write_all(message.as_bytes())?;
+    stream.write_all(b"\n")?;
+    stream.flush()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+    use holochain_json_api::json::JsonString;
+    use jsonrpc_core::Value;
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+
+    #[test]
+    fn test_domain_socket_interface_refuses_to_replace_other_files() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("conductor.sock");
+        fs::write(&file, "not a socket").unwrap();
+
+        let (_kill_tx, kill_rx) = unbounded();
+        let (log_sender, _log_receiver) = ChannelLogger::setup();
+        let logger = ChannelLogger::new("domain-socket".into(), log_sender);
+        let interface = DomainSocketInterface::new(file.clone(), logger);
+        assert!(interface.run(IoHandler::new(), kill_rx).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "not a socket");
+    }
+
+    #[test]
+    fn test_domain_socket_interface_request_and_broadcast() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("conductor.sock");
+        let mut handler = IoHandler::new();
+        handler.add_method("ping", |_| Ok(Value::String("pong".into())));
+
+        let (kill_tx, kill_rx) = unbounded();
+        let (log_sender, _log_receiver) = ChannelLogger::setup();
+        let logger = ChannelLogger::new("domain-socket".into(), log_sender);
+        let interface = DomainSocketInterface::new(file.clone(), logger);
+        let (broadcaster, handle) = interface.run(handler, kill_rx).unwrap();
+
+        let stream = UnixStream::connect(&file).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let client = Arc::new(Mutex::new(stream));
+        write_line(&client, r#"{"jsonrpc":"2.0","method":"ping","id":1}"#).unwrap();
+
+        let mut response = String::new();
+        reader.read_line(&mut response).unwrap();
+        assert_eq!(
+            response.trim(),
+            r#"{"jsonrpc":"2.0","result":"pong","id":1}"#
+        );
+
+        broadcaster
+            .send(JsonString::from_json("\"signal\""))
+            .unwrap();
+        let mut signal = String::new();
+        reader.read_line(&mut signal).unwrap();
+        assert_eq!(signal.trim(), "\"signal\"");
+
+        kill_tx.send(()).unwrap();
+        handle.join().unwrap();
+        assert!(!file.exists());
+    }
+}
@@ -1,4 +1,8 @@
+#[cfg(unix)]
+pub mod domain_socket;
 pub mod http;
 pub mod websocket;
 
+#[cfg(unix)]
+pub use self::domain_socket::*;
 pub use self::{http::*, websocket::*};
@@ -29,6 +29,7 @@ use std::{
     path::PathBuf,
     sync::{Arc, Mutex},
 };
+use storage::encrypted::STORAGE_KEY_SIZE;
 
 const PCHECK_HEADER_SIZE: usize = 8;
 const PCHECK_HEADER: [u8; 8] = *b"PHCCHECK";
@@ -38,6 +39,8 @@ const KEYBUNDLE_SIGNKEY_SUFFIX: &str = ":sign_key";
 const KEYBUNDLE_ENCKEY_SUFFIX: &str = ":enc_key";
 pub const PRIMARY_KEYBUNDLE_ID: &str = "primary_keybundle";
 pub const STANDALONE_ROOT_SEED: &str = "root_seed";
+const STORAGE_KEY_SEED_CONTEXT: [u8; 8] = *b"HCSTORAG";
+const AGENT_KEY_SEED_CONTEXT: [u8; 8] = *b"HCAGNTKY";
 
 pub enum Secret {
     SigningKey(SigningKeyPair),
@@ -50,6 +53,16 @@ pub enum KeyType {
     Encrypting,
 }
 
+/// Returns the identifier of the agent keybundle of the given generation.
+/// Generation 0 is the agent's initial keybundle, every key rotation adds one generation.
+pub fn agent_keybundle_id(generation: u64) -> String {
+    if generation == 0 {
+        PRIMARY_KEYBUNDLE_ID.to_string()
+    } else {
+        format!("{}.{}", PRIMARY_KEYBUNDLE_ID, generation)
+    }
+}
+
 /// A type for providing high-level crypto functions and managing secrets securely.
 /// Keystore can store an arbitrary number of named secrets such as key pairs and seeds.
 /// It can be serialized and deserialized with serde and stores secrets in encrypted [KeyBlob]s,
@@ -529,6 +542,77 @@ impl Keystore {
             }
         }
     }
+
+    /// derives the key for encrypting instance storage at rest from the root seed.
+    /// The derivation is deterministic so the key never needs to be stored.
+    pub fn derive_storage_key(&mut self) -> HcResult<SecBuf> {
+        let root_seed = self.get(STANDALONE_ROOT_SEED)?;
+        let mut root_seed = root_seed.lock().unwrap();
+        match *root_seed {
+            Secret::Seed(ref mut seed) => generate_derived_seed_buf(
+                seed,
+                &SeedContext::new(STORAGE_KEY_SEED_CONTEXT),
+                1,
+                STORAGE_KEY_SIZE,
+            ),
+            _ => Err(HolochainError::ErrorGeneric(
+                "source secret is not a root seed".to_string(),
+            )),
+        }
+    }
+
+    /// returns the generation of the most recent agent keybundle in the keystore
+    pub fn agent_key_generation(&self) -> u64 {
+        let mut generation = 0;
+        while self.secrets.contains_key(
+            &[
+                agent_keybundle_id(generation + 1).as_str(),
+                KEYBUNDLE_SIGNKEY_SUFFIX,
+            ]
+            .join(""),
+        ) {
+            generation += 1;
+        }
+        generation
+    }
+
+    /// derives the agent keybundle of the given generation from the root seed.
+    /// The keybundle is not added to the keystore, so a key rotation can be prepared
+    /// without touching the keystore until the new key is in use.
+    pub fn derive_agent_keybundle(&mut self, generation: u64) -> HcResult<KeyBundle> {
+        let root_seed = self.get(STANDALONE_ROOT_SEED)?;
+        let mut root_seed = root_seed.lock().unwrap();
+        match *root_seed {
+            Secret::Seed(ref mut seed) => {
+                let mut key_seed = generate_derived_seed_buf(
+                    seed,
+                    &SeedContext::new(AGENT_KEY_SEED_CONTEXT),
+                    generation,
+                    SEED_SIZE,
+                )?;
+                let sign_keys = SigningKeyPair::new_from_seed(&mut key_seed)?;
+                let enc_keys = EncryptingKeyPair::new_from_seed(&mut key_seed)?;
+                Ok(KeyBundle::new(sign_keys, enc_keys)?)
+            }
+            _ => Err(HolochainError::ErrorGeneric(
+                "source secret is not a root seed".to_string(),
+            )),
+        }
+    }
+
+    /// gets the agent keybundle of any generation that has the given public address
+    pub fn get_agent_keybundle(&mut self, public_address: &Base32) -> HcResult<KeyBundle> {
+        for generation in (0..=self.agent_key_generation()).rev() {
+            let keybundle = self.get_keybundle(&agent_keybundle_id(generation))?;
+            if keybundle.get_id() == *public_address {
+                return Ok(keybundle);
+            }
+        }
+        Err(HolochainError::ErrorGeneric(format!(
+            "no agent key with public address {}",
+            public_address
+        )))
+    }
 }
 
 pub fn test_hash_config() -> Option<PwHashConfig> {
@@ -686,6 +770,66 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_keystore_derive_storage_key() {
+        let mut keystore = new_test_keystore(random_test_passphrase());
+        assert!(keystore.derive_storage_key().is_err());
+
+        let _ = keystore.add_random_seed(STANDALONE_ROOT_SEED, SEED_SIZE);
+        let mut key = keystore.derive_storage_key().unwrap();
+        let mut same_key = keystore.derive_storage_key().unwrap();
+        assert_eq!(key.len(), STORAGE_KEY_SIZE);
+        assert_eq!(key.compare(&mut same_key), 0);
+        // Deriving the key must not add secrets that would need to be saved:
+        assert_eq!(keystore.list(), vec![STANDALONE_ROOT_SEED.to_string()]);
+
+        let mut other_keystore = new_test_keystore(random_test_passphrase());
+        let _ = other_keystore.add_random_seed(STANDALONE_ROOT_SEED, SEED_SIZE);
+        let mut other_key = other_keystore.derive_storage_key().unwrap();
+        assert_ne!(key.compare(&mut other_key), 0);
+    }
+
+    #[test]
+    fn test_keystore_rotate_agent_keybundle() {
+        let mut keystore = new_test_keystore(random_test_passphrase());
+        let _ = keystore.add_random_seed(STANDALONE_ROOT_SEED, SEED_SIZE);
+        let (initial_address, _) = keystore
+            .add_keybundle_from_seed(STANDALONE_ROOT_SEED, PRIMARY_KEYBUNDLE_ID)
+            .unwrap();
+        assert_eq!(keystore.agent_key_generation(), 0);
+
+        let mut rotated = keystore.derive_agent_keybundle(1).unwrap();
+        assert_eq!(
+            keystore.derive_agent_keybundle(1).unwrap().get_id(),
+            rotated.get_id()
+        );
+        assert_ne!(rotated.get_id(), initial_address);
+        // Deriving alone does not change the keystore:
+        assert_eq!(keystore.agent_key_generation(), 0);
+
+        keystore
+            .add_keybundle(&agent_keybundle_id(1), &mut rotated)
+            .unwrap();
+        assert_eq!(keystore.agent_key_generation(), 1);
+        assert_eq!(
+            keystore
+                .get_agent_keybundle(&rotated.get_id())
+                .unwrap()
+                .get_id(),
+            rotated.get_id()
+        );
+        assert_eq!(
+            keystore
+                .get_agent_keybundle(&initial_address)
+                .unwrap()
+                .get_id(),
+            initial_address
+        );
+        assert!(keystore
+            .get_agent_keybundle(&String::from("unknown"))
+            .is_err());
+    }
+
     #[test]
     fn test_keystore_add_signing_key_from_seed() {
         let mut keystore = new_test_keystore(random_test_passphrase());
@@ -839,5 +983,4 @@ pub mod tests {
             "VX4j1zRvIT7FojcTsqJJfu81NU1bUgiKxqWZOl/bCR4=".to_string()
         );
     }
-
 }
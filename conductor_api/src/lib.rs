@@ -132,6 +132,7 @@ extern crate json_patch;
 // #[cfg(test)]
 // extern crate reqwest;
 extern crate tokio;
+extern crate uuid;
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
@@ -151,5 +152,6 @@ pub mod keystore;
 pub mod logger;
 pub mod signal_wrapper;
 pub mod static_file_server;
+pub mod storage;
 
 pub use crate::holochain::Holochain;
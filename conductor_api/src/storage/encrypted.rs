@@ -0,0 +1,542 @@
+//! Encryption at rest for instance storage.
+//!
+//! [EncryptedStorage] and [EncryptedEavStorage] wrap any CAS or EAV storage and encrypt
+//! everything they write with a symmetric [StorageKey] (XChaCha20-Poly1305 AEAD with a
+//! random nonce per item). The conductor derives that key from the agent's root seed
+//! (see `Keystore::derive_storage_key`), so it never has to be stored anywhere.
+//!
+//! What stays in plaintext:
+//! * addresses, since they are needed for lookups (they are hashes of the content),
+//! * the kind of an EAV attribute. Only the link type and tag of link attributes carry
+//!   application data and these get encrypted.
+//!
+//! CAS ciphertexts are bound to their address so that items can't be swapped on disk.
+//!
+//! Link types and tags get encrypted deterministically, with a nonce that is a keyed
+//! hash of the plaintext. Equal attributes end up with equal cipher texts, so the
+//! wrapped EAV storage can index and match them like plaintext entities, at the cost
+//! of revealing which tuples share an attribute.
+
+use holochain_core_types::eav::{Attribute, EntityAttributeValueIndex};
+use holochain_json_api::{error::JsonResult, json::JsonString};
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    eav::{query::EaviQuery, EavFilter, EntityAttributeValueStorage, IndexFilter},
+    error::{PersistenceError, PersistenceResult},
+};
+use lib3h_sodium::{aead, hash::sha256, secbuf::SecBuf};
+use serde_json;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+};
+use uuid::Uuid;
+
+/// Size of storage keys in bytes
+pub const STORAGE_KEY_SIZE: usize = 32;
+
+/// Block size of SHA-256, used for the keyed hash
+const HASH_BLOCK_SIZE: usize = 64;
+
+/// Size of SHA-256 hashes in bytes
+const HASH_SIZE: usize = 32;
+
+/// Prepended to every plaintext before encryption.
+/// Keeps plaintexts from being empty and leaves room for format changes.
+const ENCRYPTION_FORMAT_VERSION: u8 = 1;
+
+fn to_persistence_error<E: ToString>(error: E) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!("Storage encryption error: {}", error.to_string()))
+}
+
+/// Symmetric key used to encrypt storage content
+#[derive(Clone)]
+pub struct StorageKey {
+    key: Arc<Mutex<SecBuf>>,
+}
+
+impl fmt::Debug for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StorageKey(<secret>)")
+    }
+}
+
+impl StorageKey {
+    pub fn new(key: SecBuf) -> Self {
+        StorageKey {
+            key: Arc::new(Mutex::new(key)),
+        }
+    }
+
+    /// Encrypts the given data and returns nonce and cipher text in one buffer.
+    /// The additional data is authenticated but not part of the output.
+    fn encrypt(&self, data: &[u8], additional_data: &[u8]) -> PersistenceResult<Vec<u8>> {
+        let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+        nonce.randomize();
+        self.encrypt_with_nonce(data, additional_data, nonce)
+    }
+
+    /// Like `encrypt`, but takes the nonce from a keyed hash of the data, so that the
+    /// same data always gives the same output.
+    fn encrypt_deterministic(
+        &self,
+        data: &[u8],
+        additional_data: &[u8],
+    ) -> PersistenceResult<Vec<u8>> {
+        let hash = self.keyed_hash(data)?;
+        let nonce = secbuf_from(&hash[..aead::NONCEBYTES])?;
+        self.encrypt_with_nonce(data, additional_data, nonce)
+    }
+
+    fn encrypt_with_nonce(
+        &self,
+        data: &[u8],
+        additional_data: &[u8],
+        mut nonce: SecBuf,
+    ) -> PersistenceResult<Vec<u8>> {
+        let mut message = SecBuf::with_insecure(data.len() + 1);
+        message
+            .write(0, &[ENCRYPTION_FORMAT_VERSION])
+            .map_err(to_persistence_error)?;
+        message.write(1, data).map_err(to_persistence_error)?;
+        let mut adata = secbuf_from(additional_data)?;
+        let mut cipher = SecBuf::with_insecure(message.len() + aead::ABYTES);
+
+        let mut key = self.key.lock().unwrap();
+        aead::enc(
+            &mut message,
+            &mut key,
+            Some(&mut adata),
+            &mut nonce,
+            &mut cipher,
+        )
+        .map_err(to_persistence_error)?;
+
+        let mut output = nonce.read_lock().to_vec();
+        output.extend_from_slice(&cipher.read_lock());
+        Ok(output)
+    }
+
+    fn decrypt(&self, data: &[u8], additional_data: &[u8]) -> PersistenceResult<Vec<u8>> {
+        if data.len() < aead::NONCEBYTES + aead::ABYTES + 1 {
+            return Err(to_persistence_error("cipher text is too short"));
+        }
+        let mut nonce = secbuf_from(&data[..aead::NONCEBYTES])?;
+        let mut cipher = secbuf_from(&data[aead::NONCEBYTES..])?;
+        let mut adata = secbuf_from(additional_data)?;
+        let mut message = SecBuf::with_insecure(cipher.len() - aead::ABYTES);
+
+        let mut key = self.key.lock().unwrap();
+        aead::dec(
+            &mut message,
+            &mut key,
+            Some(&mut adata),
+            &mut nonce,
+            &mut cipher,
+        )
+        .map_err(|_| to_persistence_error("could not decrypt, wrong key or corrupted data"))?;
+
+        let message = message.read_lock();
+        if message[0] != ENCRYPTION_FORMAT_VERSION {
+            return Err(to_persistence_error(format!(
+                "unsupported encryption format version {}",
+                message[0]
+            )));
+        }
+        Ok(message[1..].to_vec())
+    }
+
+    /// HMAC-SHA256 of the data with the storage key
+    fn keyed_hash(&self, data: &[u8]) -> PersistenceResult<Vec<u8>> {
+        let key = self.key.lock().unwrap();
+        let key = key.read_lock();
+        let padded_key = |pad: u8| -> Vec<u8> {
+            (0..HASH_BLOCK_SIZE)
+                .map(|i| key.get(i).cloned().unwrap_or(0) ^ pad)
+                .collect()
+        };
+        let hash = |message: &[u8]| -> PersistenceResult<Vec<u8>> {
+            let mut input = secbuf_from(message)?;
+            let mut output = SecBuf::with_insecure(HASH_SIZE);
+            sha256(&mut input, &mut output).map_err(to_persistence_error)?;
+            let output = output.read_lock().to_vec();
+            Ok(output)
+        };
+
+        let mut inner = padded_key(0x36);
+        inner.extend_from_slice(data);
+        let mut outer = padded_key(0x5c);
+        outer.extend_from_slice(&hash(&inner)?);
+        hash(&outer)
+    }
+
+    fn encrypt_string(&self, plaintext: &str) -> PersistenceResult<String> {
+        Ok(to_hex(
+            &self.encrypt_deterministic(plaintext.as_bytes(), &[])?,
+        ))
+    }
+
+    fn decrypt_string(&self, ciphertext: &str) -> PersistenceResult<String> {
+        let plaintext = self.decrypt(&from_hex(ciphertext)?, &[])?;
+        String::from_utf8(plaintext).map_err(to_persistence_error)
+    }
+}
+
+fn secbuf_from(data: &[u8]) -> PersistenceResult<SecBuf> {
+    // SecBufs can't be empty
+    let mut buf = SecBuf::with_insecure(data.len().max(1));
+    if !data.is_empty() {
+        buf.write(0, data).map_err(to_persistence_error)?;
+    }
+    Ok(buf)
+}
+
+/// Link types and tags end up in file names and in the `link__<type>__<tag>` form of
+/// attributes, so their cipher texts are hex encoded instead of base64.
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> PersistenceResult<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(to_persistence_error("invalid hex string"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(to_persistence_error))
+        .collect()
+}
+
+/// Form in which CAS content is handed to the wrapped storage
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    encrypted: String,
+}
+
+/// Encrypted content stored under the address of its plaintext
+struct EncryptedContent {
+    address: Address,
+    content: Content,
+}
+
+impl AddressableContent for EncryptedContent {
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    fn content(&self) -> Content {
+        self.content.clone()
+    }
+
+    fn try_from_content(content: &Content) -> JsonResult<Self> {
+        Ok(EncryptedContent {
+            address: content.address(),
+            content: content.clone(),
+        })
+    }
+}
+
+/// Content addressable storage that encrypts all content before it hands it to the
+/// wrapped storage
+#[derive(Clone, Debug)]
+pub struct EncryptedStorage {
+    id: Uuid,
+    inner: Arc<RwLock<ContentAddressableStorage>>,
+    key: StorageKey,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Arc<RwLock<ContentAddressableStorage>>, key: StorageKey) -> Self {
+        EncryptedStorage {
+            id: Uuid::new_v4(),
+            inner,
+            key,
+        }
+    }
+}
+
+impl ContentAddressableStorage for EncryptedStorage {
+    fn add(&mut self, content: &AddressableContent) -> PersistenceResult<()> {
+        let address = content.address();
+        let plaintext = String::from(content.content());
+        let envelope = Envelope {
+            encrypted: base64::encode(&self.key.encrypt(
+                plaintext.as_bytes(),
+                String::from(address.clone()).as_bytes(),
+            )?),
+        };
+        let json = serde_json::to_string(&envelope).map_err(to_persistence_error)?;
+        self.inner.write().unwrap().add(&EncryptedContent {
+            address,
+            content: JsonString::from_json(&json),
+        })
+    }
+
+    fn contains(&self, address: &Address) -> PersistenceResult<bool> {
+        self.inner.read().unwrap().contains(address)
+    }
+
+    fn fetch(&self, address: &Address) -> PersistenceResult<Option<Content>> {
+        let maybe_content = self.inner.read().unwrap().fetch(address)?;
+        maybe_content
+            .map(|content| {
+                let envelope: Envelope = serde_json::from_str(&String::from(content))
+                    .map_err(|_| to_persistence_error(format!("{} is not encrypted", address)))?;
+                let cipher = base64::decode(&envelope.encrypted).map_err(to_persistence_error)?;
+                let plaintext = self
+                    .key
+                    .decrypt(&cipher, String::from(address.clone()).as_bytes())?;
+                String::from_utf8(plaintext)
+                    .map(|json| JsonString::from_json(&json))
+                    .map_err(to_persistence_error)
+            })
+            .transpose()
+    }
+
+    fn get_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// EAV storage that encrypts link types and tags before it hands tuples to the wrapped
+/// storage.
+/// Queries get run on the wrapped storage first, which only decrypts attributes to
+/// check them against the query's filter. Equal attributes share a cipher text, so
+/// storages with an attribute index decrypt each attribute once.
+#[derive(Clone, Debug)]
+pub struct EncryptedEavStorage {
+    inner: Arc<RwLock<EntityAttributeValueStorage<Attribute>>>,
+    key: StorageKey,
+}
+
+impl EncryptedEavStorage {
+    pub fn new(
+        inner: Arc<RwLock<EntityAttributeValueStorage<Attribute>>>,
+        key: StorageKey,
+    ) -> Self {
+        EncryptedEavStorage { inner, key }
+    }
+
+    fn map_attribute(
+        attribute: Attribute,
+        f: &Fn(&str) -> PersistenceResult<String>,
+    ) -> PersistenceResult<Attribute> {
+        Ok(match attribute {
+            Attribute::LinkTag(link_type, tag) => Attribute::LinkTag(f(&link_type)?, f(&tag)?),
+            Attribute::RemovedLink(link_type, tag) => {
+                Attribute::RemovedLink(f(&link_type)?, f(&tag)?)
+            }
+            other => other,
+        })
+    }
+
+    fn with_attribute(
+        eavi: &EntityAttributeValueIndex,
+        attribute: Attribute,
+    ) -> PersistenceResult<EntityAttributeValueIndex> {
+        EntityAttributeValueIndex::new_with_index(
+            &eavi.entity(),
+            &attribute,
+            &eavi.value(),
+            eavi.index(),
+        )
+    }
+
+    fn encrypt_eavi(
+        &self,
+        eavi: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<EntityAttributeValueIndex> {
+        let attribute =
+            Self::map_attribute(eavi.attribute(), &|plain| self.key.encrypt_string(plain))?;
+        Self::with_attribute(eavi, attribute)
+    }
+
+    fn decrypt_attribute(&self, attribute: Attribute) -> PersistenceResult<Attribute> {
+        Self::map_attribute(attribute, &|cipher| self.key.decrypt_string(cipher))
+    }
+
+    fn decrypt_eavi(
+        &self,
+        eavi: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<EntityAttributeValueIndex> {
+        let attribute = self.decrypt_attribute(eavi.attribute())?;
+        Self::with_attribute(eavi, attribute)
+    }
+}
+
+impl EntityAttributeValueStorage<Attribute> for EncryptedEavStorage {
+    fn add_eavi(
+        &mut self,
+        eav: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex>> {
+        let encrypted = self.encrypt_eavi(eav)?;
+        let maybe_stored = self.inner.write().unwrap().add_eavi(&encrypted)?;
+        maybe_stored
+            .map(|stored| self.decrypt_eavi(&stored))
+            .transpose()
+    }
+
+    /// Finds the entities with matching tuples through the wrapped storage, then
+    /// decrypts all tuples of those entities and runs the query on them, since
+    /// tombstones are tuples of the same entity with attributes of their own.
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery<Attribute>,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        let inner = self.inner.read().unwrap();
+        let matching = inner.fetch_eavi(&EaviQuery::new(
+            EavFilter::predicate(|entity: Address| query.entity().check(entity)),
+            EavFilter::predicate(|attribute: Attribute| {
+                self.decrypt_attribute(attribute)
+                    .map(|attribute| query.attribute().check(attribute))
+                    .unwrap_or(false)
+            }),
+            EavFilter::predicate(|value: Address| query.value().check(value)),
+            IndexFilter::Range(None, None),
+            None,
+        ))?;
+        let entities: BTreeSet<Address> = matching.iter().map(|eavi| eavi.entity()).collect();
+        if entities.is_empty() {
+            return Ok(BTreeSet::new());
+        }
+
+        let candidates = inner.fetch_eavi(&EaviQuery::new(
+            EavFilter::predicate(|entity: Address| entities.contains(&entity)),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        ))?;
+        let decrypted = candidates
+            .iter()
+            .map(|eavi| self.decrypt_eavi(eavi))
+            .collect::<PersistenceResult<Vec<_>>>()?;
+        Ok(query.run(decrypted.into_iter()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::{test_entry, test_entry_a, test_entry_b};
+    use holochain_persistence_mem::{cas::memory::MemoryStorage, eav::memory::EavMemoryStorage};
+    use std::collections::HashSet;
+
+    fn test_key(byte: u8) -> StorageKey {
+        let mut key = SecBuf::with_insecure(STORAGE_KEY_SIZE);
+        key.write(0, &[byte; STORAGE_KEY_SIZE]).unwrap();
+        StorageKey::new(key)
+    }
+
+    #[test]
+    fn encrypted_cas_round_trip_stores_no_plaintext() {
+        let inner: Arc<RwLock<ContentAddressableStorage>> =
+            Arc::new(RwLock::new(MemoryStorage::new()));
+        let mut cas = EncryptedStorage::new(inner.clone(), test_key(1));
+        let entry = test_entry();
+        cas.add(&entry).unwrap();
+
+        assert_eq!(cas.contains(&entry.address()), Ok(true));
+        assert_eq!(cas.fetch(&entry.address()), Ok(Some(entry.content())));
+
+        let raw = inner
+            .read()
+            .unwrap()
+            .fetch(&entry.address())
+            .unwrap()
+            .unwrap();
+        assert!(!String::from(raw).contains(&String::from(entry.content())));
+
+        let other_key = EncryptedStorage::new(inner, test_key(2));
+        assert!(other_key.fetch(&entry.address()).is_err());
+    }
+
+    #[test]
+    fn encrypted_eav_round_trip_and_query() {
+        let inner: Arc<RwLock<EntityAttributeValueStorage<Attribute>>> =
+            Arc::new(RwLock::new(EavMemoryStorage::new()));
+        let mut eav_storage = EncryptedEavStorage::new(inner.clone(), test_key(1));
+        let attribute = Attribute::LinkTag("friends".into(), "secret tag".into());
+        let eavi = EntityAttributeValueIndex::new(
+            &test_entry_a().address(),
+            &attribute,
+            &test_entry_b().address(),
+        )
+        .unwrap();
+        let stored = eav_storage.add_eavi(&eavi).unwrap().unwrap();
+        assert_eq!(stored.attribute(), attribute);
+
+        let raw = inner
+            .read()
+            .unwrap()
+            .fetch_eavi(&EaviQuery::new(
+                None.into(),
+                None.into(),
+                None.into(),
+                IndexFilter::Range(None, None),
+                None,
+            ))
+            .unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_ne!(raw.iter().next().unwrap().attribute(), attribute);
+
+        let query = EaviQuery::new(
+            Some(test_entry_a().address()).into(),
+            Some(attribute).into(),
+            None.into(),
+            IndexFilter::LatestByAttribute,
+            None,
+        );
+        let fetched = eav_storage.fetch_eavi(&query).unwrap();
+        assert_eq!(fetched.into_iter().collect::<Vec<_>>(), vec![stored]);
+    }
+
+    #[test]
+    fn encrypted_eav_attributes_are_deterministic() {
+        let inner: Arc<RwLock<EntityAttributeValueStorage<Attribute>>> =
+            Arc::new(RwLock::new(EavMemoryStorage::new()));
+        let mut eav_storage = EncryptedEavStorage::new(inner.clone(), test_key(1));
+        let attribute = Attribute::LinkTag("friends".into(), "secret tag".into());
+        for entity in vec![test_entry_a(), test_entry_b()] {
+            let eavi = EntityAttributeValueIndex::new(
+                &entity.address(),
+                &attribute,
+                &test_entry().address(),
+            )
+            .unwrap();
+            eav_storage.add_eavi(&eavi).unwrap();
+        }
+
+        let raw_attributes = inner
+            .read()
+            .unwrap()
+            .fetch_eavi(&EaviQuery::new(
+                None.into(),
+                None.into(),
+                None.into(),
+                IndexFilter::Range(None, None),
+                None,
+            ))
+            .unwrap()
+            .iter()
+            .map(|eavi| eavi.attribute())
+            .collect::<HashSet<_>>();
+        assert_eq!(raw_attributes.len(), 1);
+        assert_ne!(raw_attributes.iter().next().unwrap(), &attribute);
+
+        let other_key = EncryptedEavStorage::new(inner, test_key(2));
+        let query = EaviQuery::new(
+            None.into(),
+            Some(attribute).into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        assert_eq!(eav_storage.fetch_eavi(&query).unwrap().len(), 2);
+        assert!(other_key.fetch_eavi(&query).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,625 @@
+//! Embedded key-value storage backend.
+//!
+//! All CAS content and EAVI tuples of an instance live in one directory with two
+//! append-only logs, `cas.log` and `eav.log`, instead of one file per item.
+//! Every item is one JSON line. Lookups go through indexes that get built when the
+//! store is opened by reading through the logs line by line: the log offsets of CAS
+//! items by address, and the EAVI tuples, which are small and held in memory, by entity
+//! and by attribute. CAS content is only read from disk when it gets fetched.
+//!
+//! Every write is synced to disk before it returns, so an item that got added survives
+//! a crash or a power loss. A line that was only partly written when the machine went
+//! down gets dropped when the store is opened again, so a crash can never leave a
+//! half-written item behind.
+//! Each item is written on its own: there are no transactions spanning several items,
+//! so after a crash a CAS item can be there without the EAVI tuples that were added
+//! right after it, just like with the other storage types.
+//! The directory carries a format version which gets checked when it is opened.
+
+use holochain_core_types::eav::{Attribute, EntityAttributeValueIndex};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    eav::{query::EaviQuery, EntityAttributeValueStorage},
+    error::{PersistenceError, PersistenceResult},
+};
+use serde_json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+/// Version of the on-disk layout written by this module.
+/// Bump this whenever the encoding of keys or values changes.
+pub const KV_FORMAT_VERSION: u32 = 1;
+
+const FORMAT_VERSION_FILE: &str = "format_version";
+const CAS_LOG: &str = "cas.log";
+const EAV_LOG: &str = "eav.log";
+
+fn to_persistence_error<E: ToString>(error: E) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!("KV storage error: {}", error.to_string()))
+}
+
+/// One append-only log file
+struct Log {
+    file: File,
+    len: u64,
+}
+
+impl Log {
+    /// Opens (or creates) the log and hands every complete line with its offset to
+    /// `on_line`, one at a time.
+    /// An incomplete last line is the remainder of a write that got interrupted and gets
+    /// cut off.
+    fn open<F>(path: PathBuf, mut on_line: F) -> PersistenceResult<Log>
+    where
+        F: FnMut(&Path, u64, &[u8]) -> PersistenceResult<()>,
+    {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(to_persistence_error)?;
+
+        let mut offset = 0;
+        let mut complete = true;
+        {
+            let mut reader = BufReader::new(&file);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                let read = reader
+                    .read_until(b'\n', &mut line)
+                    .map_err(to_persistence_error)?;
+                if read == 0 {
+                    break;
+                }
+                if line.last() != Some(&b'\n') {
+                    complete = false;
+                    break;
+                }
+                on_line(&path, offset, &line[..line.len() - 1])?;
+                offset += read as u64;
+            }
+        }
+        if !complete {
+            file.set_len(offset).map_err(to_persistence_error)?;
+            file.sync_all().map_err(to_persistence_error)?;
+        }
+
+        Ok(Log { file, len: offset })
+    }
+
+    /// Appends the line, syncs it to disk and returns its offset
+    fn append(&mut self, line: &[u8]) -> PersistenceResult<u64> {
+        let mut bytes = line.to_vec();
+        bytes.push(b'\n');
+        self.file
+            .write_all(&bytes)
+            .and_then(|_| self.file.sync_data())
+            .map_err(to_persistence_error)?;
+        let offset = self.len;
+        self.len += bytes.len() as u64;
+        Ok(offset)
+    }
+
+    fn read_line(&mut self, offset: u64, len: usize) -> PersistenceResult<Vec<u8>> {
+        let mut line = vec![0; len];
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(&mut line))
+            .map_err(to_persistence_error)?;
+        Ok(line)
+    }
+}
+
+/// Stored form of one CAS item
+#[derive(Serialize, Deserialize)]
+struct CasRecord {
+    address: Address,
+    content: String,
+}
+
+/// The part of a stored CAS item the index needs, so opening the store
+/// does not have to hold any content
+#[derive(Deserialize)]
+struct CasRecordAddress {
+    address: Address,
+}
+
+/// Stored form of one EAVI tuple
+#[derive(Serialize, Deserialize)]
+struct EaviRecord {
+    entity: Address,
+    attribute: Attribute,
+    value: Address,
+    index: i64,
+}
+
+fn decode_eavi(bytes: &[u8]) -> PersistenceResult<EntityAttributeValueIndex> {
+    let record: EaviRecord = serde_json::from_slice(bytes).map_err(to_persistence_error)?;
+    EntityAttributeValueIndex::new_with_index(
+        &record.entity,
+        &record.attribute,
+        &record.value,
+        record.index,
+    )
+}
+
+/// Both logs and their indexes
+struct Logs {
+    cas: Log,
+    /// Offset and length of the line of every CAS item
+    cas_index: HashMap<Address, (u64, usize)>,
+    eav: Log,
+    eavis: BTreeMap<i64, EntityAttributeValueIndex>,
+    /// Indices of the EAVI tuples of every entity
+    entity_index: HashMap<Address, BTreeSet<i64>>,
+    /// Indices of the EAVI tuples of every attribute
+    attribute_index: HashMap<Attribute, BTreeSet<i64>>,
+}
+
+impl Logs {
+    fn open(dir: &Path) -> PersistenceResult<Logs> {
+        let mut cas_index = HashMap::new();
+        let cas = Log::open(dir.join(CAS_LOG), |path, offset, line| {
+            let record: CasRecordAddress =
+                serde_json::from_slice(line).map_err(|error| corrupt_log(path, error))?;
+            cas_index.insert(record.address, (offset, line.len()));
+            Ok(())
+        })?;
+
+        let mut eavis = Vec::new();
+        let eav = Log::open(dir.join(EAV_LOG), |path, _, line| {
+            eavis.push(decode_eavi(line).map_err(|error| corrupt_log(path, error))?);
+            Ok(())
+        })?;
+
+        let mut logs = Logs {
+            cas,
+            cas_index,
+            eav,
+            eavis: BTreeMap::new(),
+            entity_index: HashMap::new(),
+            attribute_index: HashMap::new(),
+        };
+        for eavi in eavis {
+            logs.index_eavi(eavi);
+        }
+        Ok(logs)
+    }
+
+    fn index_eavi(&mut self, eavi: EntityAttributeValueIndex) {
+        self.entity_index
+            .entry(eavi.entity())
+            .or_insert_with(BTreeSet::new)
+            .insert(eavi.index());
+        self.attribute_index
+            .entry(eavi.attribute())
+            .or_insert_with(BTreeSet::new)
+            .insert(eavi.index());
+        self.eavis.insert(eavi.index(), eavi);
+    }
+}
+
+fn corrupt_log<E: ToString>(path: &Path, error: E) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!(
+        "KV storage log {:?} is corrupt: {}",
+        path,
+        error.to_string()
+    ))
+}
+
+/// Handle to an opened KV store.
+/// CAS and EAV storages are created from it and share the same logs.
+#[derive(Clone)]
+pub struct KvStore {
+    path: PathBuf,
+    logs: Arc<Mutex<Logs>>,
+}
+
+impl fmt::Debug for KvStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KvStore").field("path", &self.path).finish()
+    }
+}
+
+impl KvStore {
+    /// Opens (or creates) the store in the given directory.
+    /// Fails if the store was written with a different format version.
+    pub fn open<P: AsRef<Path>>(path: P) -> PersistenceResult<KvStore> {
+        let path = path.as_ref().to_path_buf();
+        fs::create_dir_all(&path).map_err(to_persistence_error)?;
+        let version_path = path.join(FORMAT_VERSION_FILE);
+        if version_path.exists() {
+            let version = read_format_version(&version_path)?;
+            if version != KV_FORMAT_VERSION {
+                return Err(PersistenceError::ErrorGeneric(format!(
+                    "KV storage has format version {} but only version {} is supported",
+                    version, KV_FORMAT_VERSION
+                )));
+            }
+        } else {
+            write_format_version(&path, KV_FORMAT_VERSION)?;
+        }
+
+        let logs = Logs::open(&path)?;
+        Ok(KvStore {
+            path,
+            logs: Arc::new(Mutex::new(logs)),
+        })
+    }
+
+    /// The format version stored in the directory
+    pub fn format_version(&self) -> PersistenceResult<u32> {
+        read_format_version(&self.path.join(FORMAT_VERSION_FILE))
+    }
+
+    fn lock(&self) -> PersistenceResult<MutexGuard<Logs>> {
+        self.logs.lock().map_err(to_persistence_error)
+    }
+}
+
+fn read_format_version(path: &Path) -> PersistenceResult<u32> {
+    fs::read_to_string(path)
+        .map_err(to_persistence_error)?
+        .trim()
+        .parse()
+        .map_err(|_| {
+            PersistenceError::ErrorGeneric("KV storage has a corrupt format version".into())
+        })
+}
+
+/// Writes to a temporary file first, so there is never a store with an empty version
+fn write_format_version(dir: &Path, version: u32) -> PersistenceResult<()> {
+    let tmp_path = dir.join(format!("{}.tmp", FORMAT_VERSION_FILE));
+    let mut file = File::create(&tmp_path).map_err(to_persistence_error)?;
+    file.write_all(version.to_string().as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&tmp_path, dir.join(FORMAT_VERSION_FILE)))
+        .map_err(to_persistence_error)
+}
+
+/// Content addressable storage in a KV store, keyed by address
+#[derive(Clone, Debug)]
+pub struct KvStorage {
+    id: Uuid,
+    store: KvStore,
+}
+
+impl KvStorage {
+    pub fn new(store: &KvStore) -> PersistenceResult<KvStorage> {
+        Ok(KvStorage {
+            id: Uuid::new_v4(),
+            store: store.clone(),
+        })
+    }
+
+    /// Number of items held
+    pub fn len(&self) -> usize {
+        self.store
+            .lock()
+            .map(|logs| logs.cas_index.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Addresses of all items held, sorted
+    pub fn addresses(&self) -> PersistenceResult<Vec<Address>> {
+        let mut addresses = self
+            .store
+            .lock()?
+            .cas_index
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        addresses.sort();
+        Ok(addresses)
+    }
+}
+
+impl ContentAddressableStorage for KvStorage {
+    fn add(&mut self, content: &AddressableContent) -> PersistenceResult<()> {
+        let address = content.address();
+        let mut logs = self.store.lock()?;
+        // Same address, same content
+        if logs.cas_index.contains_key(&address) {
+            return Ok(());
+        }
+        let record = CasRecord {
+            address: address.clone(),
+            content: String::from(content.content()),
+        };
+        let line = serde_json::to_vec(&record).map_err(to_persistence_error)?;
+        let offset = logs.cas.append(&line)?;
+        logs.cas_index.insert(address, (offset, line.len()));
+        Ok(())
+    }
+
+    fn contains(&self, address: &Address) -> PersistenceResult<bool> {
+        Ok(self.store.lock()?.cas_index.contains_key(address))
+    }
+
+    fn fetch(&self, address: &Address) -> PersistenceResult<Option<Content>> {
+        let mut logs = self.store.lock()?;
+        let (offset, len) = match logs.cas_index.get(address) {
+            Some(location) => *location,
+            None => return Ok(None),
+        };
+        let line = logs.cas.read_line(offset, len)?;
+        let record: CasRecord = serde_json::from_slice(&line).map_err(to_persistence_error)?;
+        Ok(Some(JsonString::from_json(&record.content)))
+    }
+
+    fn get_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// EAV storage in a KV store.
+/// Like the memory implementation, an added EAVI whose index is already taken
+/// gets its index incremented until it is unique.
+#[derive(Clone, Debug)]
+pub struct EavKvStorage {
+    store: KvStore,
+}
+
+impl EavKvStorage {
+    pub fn new(store: &KvStore) -> PersistenceResult<EavKvStorage> {
+        Ok(EavKvStorage {
+            store: store.clone(),
+        })
+    }
+
+    /// Number of EAVI tuples held
+    pub fn len(&self) -> usize {
+        self.store.lock().map(|logs| logs.eavis.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl EntityAttributeValueStorage<Attribute> for EavKvStorage {
+    fn add_eavi(
+        &mut self,
+        eav: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex>> {
+        let mut logs = self.store.lock()?;
+        let mut new_eav = eav.clone();
+        while logs.eavis.contains_key(&new_eav.index()) {
+            let index = new_eav.index() + 1;
+            new_eav.set_index(index);
+        }
+        let record = EaviRecord {
+            entity: new_eav.entity(),
+            attribute: new_eav.attribute(),
+            value: new_eav.value(),
+            index: new_eav.index(),
+        };
+        let line = serde_json::to_vec(&record).map_err(to_persistence_error)?;
+        logs.eav.append(&line)?;
+        logs.index_eavi(new_eav.clone());
+        Ok(Some(new_eav))
+    }
+
+    /// Only looks at the tuples of entities that pass the entity filter and have
+    /// a tuple passing the attribute filter.
+    /// All tuples of those entities get handed to the query, since tombstones are
+    /// tuples of the same entity with attributes of their own.
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery<Attribute>,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        let logs = self.store.lock()?;
+        let with_attribute = logs
+            .attribute_index
+            .iter()
+            .filter(|(attribute, _)| query.attribute().check((*attribute).clone()))
+            .flat_map(|(_, indices)| indices.iter().cloned())
+            .collect::<BTreeSet<_>>();
+        let candidates = logs
+            .entity_index
+            .iter()
+            .filter(|(entity, indices)| {
+                !indices.is_disjoint(&with_attribute) && query.entity().check((*entity).clone())
+            })
+            .flat_map(|(_, indices)| indices.iter())
+            .filter_map(|index| logs.eavis.get(index))
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(query.run(candidates.into_iter()))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use super::*;
+    use holochain_core_types::{
+        eav::Attribute,
+        entry::{test_entry, test_entry_a, test_entry_b},
+    };
+    use holochain_persistence_api::eav::IndexFilter;
+
+    fn query_all() -> EaviQuery<'static, Attribute> {
+        EaviQuery::new(
+            None.into(),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        )
+    }
+
+    #[test]
+    fn kv_cas_round_trip_and_reopen() {
+        let dir = tempdir().unwrap();
+        let entry = test_entry();
+        {
+            let store = KvStore::open(dir.path()).unwrap();
+            let mut cas = KvStorage::new(&store).unwrap();
+            assert_eq!(cas.contains(&entry.address()), Ok(false));
+            assert_eq!(cas.fetch(&entry.address()), Ok(None));
+            cas.add(&entry).unwrap();
+            cas.add(&entry).unwrap();
+            assert_eq!(cas.contains(&entry.address()), Ok(true));
+        }
+
+        let store = KvStore::open(dir.path()).unwrap();
+        let cas = KvStorage::new(&store).unwrap();
+        assert_eq!(cas.len(), 1);
+        assert_eq!(cas.addresses(), Ok(vec![entry.address()]));
+        assert_eq!(cas.fetch(&entry.address()), Ok(Some(entry.content())));
+        assert_eq!(store.format_version(), Ok(KV_FORMAT_VERSION));
+    }
+
+    #[test]
+    fn kv_eav_round_trip_with_index_collision() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut eav_storage = EavKvStorage::new(&store).unwrap();
+
+        let entity = test_entry_a().address();
+        let value = test_entry_b().address();
+        let eav = EntityAttributeValueIndex::new_with_index(
+            &entity,
+            &Attribute::LinkTag("foo".into(), "bar".into()),
+            &value,
+            42,
+        )
+        .unwrap();
+        let first = eav_storage.add_eavi(&eav).unwrap().unwrap();
+        let second = eav_storage.add_eavi(&eav).unwrap().unwrap();
+        assert_eq!(first.index(), 42);
+        assert_eq!(second.index(), 43);
+
+        let query = EaviQuery::new(
+            Some(entity).into(),
+            None.into(),
+            Some(value).into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        let fetched = eav_storage.fetch_eavi(&query).unwrap();
+        assert_eq!(fetched.len(), 2);
+        assert!(fetched.contains(&first));
+        assert!(fetched.contains(&second));
+
+        drop(eav_storage);
+        drop(store);
+        let reopened = EavKvStorage::new(&KvStore::open(dir.path()).unwrap()).unwrap();
+        assert_eq!(reopened.fetch_eavi(&query), Ok(fetched));
+    }
+
+    #[test]
+    fn kv_eav_queries_by_entity_and_attribute() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut eav_storage = EavKvStorage::new(&store).unwrap();
+
+        let entity_a = test_entry_a().address();
+        let entity_b = test_entry_b().address();
+        let link = Attribute::LinkTag("foo".into(), "bar".into());
+        let removed = Attribute::RemovedLink("foo".into(), "bar".into());
+        let link_a = eav_storage
+            .add_eavi(&EntityAttributeValueIndex::new(&entity_a, &link, &entity_b).unwrap())
+            .unwrap()
+            .unwrap();
+        let removed_a = eav_storage
+            .add_eavi(&EntityAttributeValueIndex::new(&entity_a, &removed, &entity_b).unwrap())
+            .unwrap()
+            .unwrap();
+        let link_b = eav_storage
+            .add_eavi(&EntityAttributeValueIndex::new(&entity_b, &link, &entity_a).unwrap())
+            .unwrap()
+            .unwrap();
+
+        let by_attribute = EaviQuery::new(
+            None.into(),
+            Some(link.clone()).into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        assert_eq!(
+            eav_storage.fetch_eavi(&by_attribute),
+            Ok(vec![link_a.clone(), link_b.clone()].into_iter().collect())
+        );
+
+        let by_entity = EaviQuery::new(
+            Some(entity_a.clone()).into(),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        assert_eq!(
+            eav_storage.fetch_eavi(&by_entity),
+            Ok(vec![link_a, removed_a.clone()].into_iter().collect())
+        );
+
+        // The tombstone has a different attribute than the ones queried for
+        let with_tombstone = EaviQuery::new(
+            Some(entity_a).into(),
+            Some(link).into(),
+            None.into(),
+            IndexFilter::LatestByAttribute,
+            Some(removed).into(),
+        );
+        assert!(eav_storage
+            .fetch_eavi(&with_tombstone)
+            .unwrap()
+            .contains(&removed_a));
+        assert_eq!(eav_storage.fetch_eavi(&query_all()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn kv_drops_partly_written_lines() {
+        let dir = tempdir().unwrap();
+        let entry = test_entry();
+        {
+            let store = KvStore::open(dir.path()).unwrap();
+            KvStorage::new(&store).unwrap().add(&entry).unwrap();
+        }
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(CAS_LOG))
+            .unwrap();
+        log.write_all(br#"{"address":"Qm"#).unwrap();
+        drop(log);
+
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut cas = KvStorage::new(&store).unwrap();
+        assert_eq!(cas.addresses(), Ok(vec![entry.address()]));
+        cas.add(&test_entry_a()).unwrap();
+        drop(cas);
+        drop(store);
+
+        let cas = KvStorage::new(&KvStore::open(dir.path()).unwrap()).unwrap();
+        assert_eq!(cas.len(), 2);
+        assert_eq!(cas.fetch(&entry.address()), Ok(Some(entry.content())));
+    }
+
+    #[test]
+    fn kv_rejects_unknown_format_version() {
+        let dir = tempdir().unwrap();
+        write_format_version(dir.path(), 99).unwrap();
+        assert!(KvStore::open(dir.path()).is_err());
+    }
+}
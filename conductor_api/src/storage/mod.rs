@@ -0,0 +1,5 @@
+//! Storage backends for instances that are provided by the conductor itself,
+//! in addition to the ones from the `holochain_persistence_*` crates,
+//! and wrappers that add features on top of any backend.
+pub mod encrypted;
+pub mod kv;
@@ -2,7 +2,7 @@ use crate::{
     agent::state::AgentState,
     network::{
         direct_message::DirectMessage, entry_aspect::EntryAspect,
-        entry_with_header::EntryWithHeader, state::NetworkState,
+        entry_with_header::EntryWithHeader, state::NetworkState, warrant::Warrant,
     },
     nucleus::{
         actions::{call_zome_function::ExecuteZomeFnResponse, initialize::Initialization},
@@ -22,6 +22,7 @@ use holochain_core_types::{
     error::HolochainError,
     link::link_data::LinkData,
     signature::Provenance,
+    time::Iso8601,
     validation::ValidationPackage,
 };
 use holochain_net::{
@@ -32,11 +33,16 @@ use holochain_net::{
     p2p_config::P2pConfig,
 };
 use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::{
+    bundle::StartBundleArgs,
+    get_links::{GetLinksOptions, LinksStatusRequestKind},
+};
 use snowflake;
 use std::{
     hash::{Hash, Hasher},
     vec::Vec,
 };
+use uuid::Uuid;
 
 /// Wrapper for actions that provides a unique ID
 /// The unique ID is needed for state tracking to ensure that we can differentiate between two
@@ -44,20 +50,23 @@ use std::{
 /// The standard approach is to drop the ActionWrapper into the key of a state history HashMap and
 /// use the convenience unwrap_to! macro to extract the action data in a reducer.
 /// All reducer functions must accept an ActionWrapper so all dispatchers take an ActionWrapper.
+///
+/// IDs are random (v4) UUIDs so they stay unique across restarts and state that got
+/// persisted in one process can't collide with actions created in the next one.
 #[derive(Clone, Debug, Serialize)]
 pub struct ActionWrapper {
     action: Action,
-    id: snowflake::ProcessUniqueId,
+    id: Uuid,
 }
 
 impl ActionWrapper {
     /// constructor from &Action
-    /// internal snowflake ID is automatically set
+    /// internal UUID is automatically set
     pub fn new(a: Action) -> Self {
         ActionWrapper {
             action: a,
             // auto generate id
-            id: snowflake::ProcessUniqueId::new(),
+            id: Uuid::new_v4(),
         }
     }
 
@@ -67,7 +76,7 @@ impl ActionWrapper {
     }
 
     /// read only access to id
-    pub fn id(&self) -> &snowflake::ProcessUniqueId {
+    pub fn id(&self) -> &Uuid {
         &self.id
     }
 }
@@ -81,9 +90,6 @@ impl PartialEq for ActionWrapper {
 impl Eq for ActionWrapper {}
 
 impl Hash for ActionWrapper {
-    /// @TODO dangerous when persisted!
-    /// snowflake only guarantees uniqueness per process
-    /// @see https://github.com/holochain/holochain-rust/issues/203
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
     }
@@ -100,6 +106,28 @@ pub enum Action {
     /// Does not validate, assumes entry is valid.
     Commit((Entry, Option<Address>, Vec<Provenance>)),
 
+    /// Opens a bundle for the zome call. Until it gets closed, commits of the call
+    /// get staged in the agent state instead of being appended to the source chain.
+    StartBundle((ZomeFnCall, StartBundleArgs)),
+
+    /// Stages a commit in the bundle of the zome call.
+    StageCommit((ZomeFnCall, Entry, Option<Address>, Vec<Provenance>)),
+
+    /// Stops the bundle of the zome call from taking commits, so its staged
+    /// commits can get validated.
+    SealBundle(ZomeFnCall),
+
+    /// Appends the commits of the zome call's bundle to the source chain at once,
+    /// with the headers they got validated with.
+    /// Does not validate, assumes all staged entries are valid.
+    CommitBundle((ZomeFnCall, Vec<(Entry, ChainHeader)>)),
+
+    /// Drops the bundle of the zome call together with all its staged commits.
+    DiscardBundle(ZomeFnCall),
+
+    /// Drops the staged commits of all bundles that timed out.
+    ExpireBundles,
+
     // -------------
     // DHT actions:
     // -------------
@@ -108,8 +136,9 @@ pub enum Action {
     Hold(EntryWithHeader),
 
     /// Adds a link to the local DHT shard's meta/EAV storage
+    /// together with the timestamp of the header of its LinkAdd entry.
     /// Does not validate, assumes link is valid.
-    AddLink(LinkData),
+    AddLink((LinkData, Iso8601)),
 
     //action for updating crudstatus
     CrudStatus((EntryWithHeader, CrudStatus)),
@@ -117,6 +146,10 @@ pub enum Action {
     //Removes a link for the local DHT
     RemoveLink(Entry),
 
+    /// Adds a warrant to the local DHT shard.
+    /// Does not check the warrant, assumes it is valid.
+    HoldWarrant(Warrant),
+
     // ----------------
     // Network actions:
     // ----------------
@@ -129,6 +162,10 @@ pub enum Action {
     /// (only publish for AppEntryType, publish and publish_meta for links etc)
     Publish(Address),
 
+    /// Makes the network PUT the given warrant to the DHT
+    /// as a meta item of every warranted agent's address.
+    PublishWarrant(Warrant),
+
     /// Get an Entry on the network by address
     GetEntry(GetEntryKey),
 
@@ -155,11 +192,28 @@ pub enum Action {
 
     /// get links from entry address and link_type name
     /// Last string is the stringified process unique id of this `hdk::get_links` call.
-    GetLinks(GetLinksKey),
+    /// The options get passed on to the peers so they only send back the requested page.
+    GetLinks((GetLinksKey, GetLinksOptions)),
     GetLinksTimeout(GetLinksKey),
-    RespondGetLinks((QueryEntryData, Vec<(Address, CrudStatus)>, String, String)),
+    RespondGetLinks(
+        (
+            QueryEntryData,
+            Vec<(Address, CrudStatus)>,
+            String,
+            String,
+            GetLinksOptions,
+        ),
+    ),
     HandleGetLinksResult((Vec<(Address, CrudStatus)>, GetLinksKey)),
 
+    /// count links from entry address and link_type name without fetching them
+    GetLinksCount((GetLinksKey, LinksStatusRequestKind)),
+    GetLinksCountTimeout(GetLinksKey),
+    RespondGetLinksCount((QueryEntryData, usize, String, String)),
+    HandleGetLinksCountResult((usize, GetLinksKey)),
+    /// Drops the result of a get_links_count process once its future resolved
+    ClearGetLinksCountResult(GetLinksKey),
+
     /// Makes the network module send a direct (node-to-node) message
     /// to the address given in [DirectMessageData](struct.DirectMessageData.html)
     SendDirectMessage(DirectMessageData),
@@ -313,6 +367,7 @@ pub mod tests {
     };
     use holochain_core_types::entry::{expected_entry_address, test_entry};
     use test_utils::calculate_hash;
+    use uuid::Uuid;
 
     /// dummy action
     pub fn test_action() -> Action {
@@ -360,7 +415,7 @@ pub mod tests {
         let aw1 = test_action_wrapper();
         let aw2 = test_action_wrapper();
 
-        // snowflake enforces uniqueness
+        // the UUID enforces uniqueness
         assert_eq!(aw1, aw1);
         assert_ne!(aw1, aw2);
     }
@@ -386,6 +441,16 @@ pub mod tests {
         assert_ne!(aw1.id(), aw2.id());
     }
 
+    #[test]
+    /// tests that the serialized id is a UUID, so persisted action wrappers
+    /// don't depend on process local state
+    fn action_wrapper_id_serializes_as_uuid() {
+        let aw = test_action_wrapper();
+        let json = serde_json::to_value(&aw).unwrap();
+        let id = json["id"].as_str().unwrap();
+        assert_eq!(Uuid::parse_str(id).unwrap(), *aw.id());
+    }
+
     #[test]
     /// tests that action wrapper hashes are unique
     fn action_wrapper_hash() {
@@ -394,5 +459,4 @@ pub mod tests {
 
         assert_ne!(calculate_hash(&aw1), calculate_hash(&aw2));
     }
-
 }
@@ -0,0 +1,102 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::state::ActionResponse,
+    context::Context,
+    instance::dispatch_action_and_wait,
+    nucleus::ZomeFnCall,
+};
+use holochain_core_types::{
+    chain_header::ChainHeader, entry::Entry, error::HolochainError, signature::Provenance,
+};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::bundle::StartBundleArgs;
+use std::sync::Arc;
+
+/// StartBundle Action Creator
+/// Opens a bundle for the zome call. Fails if the call already started one.
+pub fn start_bundle(
+    zome_call: ZomeFnCall,
+    args: StartBundleArgs,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::StartBundle((zome_call, args)));
+    dispatch_action_and_wait(context.clone(), action_wrapper.clone());
+    match get_response(&action_wrapper, context)? {
+        ActionResponse::StartBundle(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+/// StageCommit Action Creator
+/// Stages the commit in the bundle of the zome call.
+/// Returns the address of the staged entry.
+pub fn stage_commit(
+    zome_call: ZomeFnCall,
+    entry: Entry,
+    maybe_link_update_delete: Option<Address>,
+    provenances: Vec<Provenance>,
+    context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::StageCommit((
+        zome_call,
+        entry,
+        maybe_link_update_delete,
+        provenances,
+    )));
+    dispatch_action_and_wait(context.clone(), action_wrapper.clone());
+    match get_response(&action_wrapper, context)? {
+        ActionResponse::Commit(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+/// SealBundle Action Creator
+/// Stops the bundle of the zome call from taking further commits.
+pub fn seal_bundle(zome_call: ZomeFnCall, context: &Arc<Context>) -> Result<(), HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::SealBundle(zome_call));
+    dispatch_action_and_wait(context.clone(), action_wrapper.clone());
+    match get_response(&action_wrapper, context)? {
+        ActionResponse::SealBundle(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+/// CommitBundle Action Creator
+/// Appends the entries of the zome call's bundle to the chain with the given headers
+/// and closes the bundle.
+/// Returns the committed entries together with their headers.
+pub fn commit_bundle(
+    zome_call: ZomeFnCall,
+    bundle_headers: Vec<(Entry, ChainHeader)>,
+    context: &Arc<Context>,
+) -> Result<Vec<(Entry, ChainHeader)>, HolochainError> {
+    let action_wrapper = ActionWrapper::new(Action::CommitBundle((zome_call, bundle_headers)));
+    dispatch_action_and_wait(context.clone(), action_wrapper.clone());
+    match get_response(&action_wrapper, context)? {
+        ActionResponse::CommitBundle(result) => result,
+        _ => unreachable!(),
+    }
+}
+
+/// DiscardBundle Action Creator
+/// Drops the bundle of the zome call and all its staged commits.
+pub fn discard_bundle(zome_call: ZomeFnCall, context: &Arc<Context>) {
+    dispatch_action_and_wait(
+        context.clone(),
+        ActionWrapper::new(Action::DiscardBundle(zome_call)),
+    );
+}
+
+fn get_response(
+    action_wrapper: &ActionWrapper,
+    context: &Arc<Context>,
+) -> Result<ActionResponse, HolochainError> {
+    context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Could not get state".to_string()))?
+        .agent()
+        .actions()
+        .get(action_wrapper)
+        .cloned()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Bundle action was not reduced".to_string()))
+}
@@ -8,7 +8,7 @@ use futures::{
     future::Future,
     task::{LocalWaker, Poll},
 };
-use holochain_core_types::{entry::Entry, error::HolochainError};
+use holochain_core_types::{entry::Entry, error::HolochainError, signature::Provenance};
 use holochain_persistence_api::cas::content::Address;
 use std::{pin::Pin, sync::Arc};
 
@@ -21,11 +21,27 @@ pub async fn commit_entry(
     entry: Entry,
     maybe_link_update_delete: Option<Address>,
     context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    await!(commit_entry_with_provenances(
+        entry,
+        maybe_link_update_delete,
+        vec![],
+        context
+    ))
+}
+
+/// Same as commit_entry but with additional provenances that get added to the
+/// header next to the agent's own signature.
+pub async fn commit_entry_with_provenances(
+    entry: Entry,
+    maybe_link_update_delete: Option<Address>,
+    provenances: Vec<Provenance>,
+    context: &Arc<Context>,
 ) -> Result<Address, HolochainError> {
     let action_wrapper = ActionWrapper::new(Action::Commit((
         entry.clone(),
         maybe_link_update_delete,
-        vec![],
+        provenances,
     )));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     await!(CommitFuture {
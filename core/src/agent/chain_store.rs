@@ -3,11 +3,13 @@ use holochain_core_types::{
     chain_header::ChainHeader,
     entry::entry_type::EntryType,
     error::RibosomeErrorCode::{self, *},
+    time::Iso8601,
 };
 use holochain_persistence_api::cas::{
     content::{Address, AddressableContent},
     storage::ContentAddressableStorage,
 };
+use holochain_wasm_utils::api_serialization::QueryOrder;
 
 use std::{
     str::FromStr,
@@ -35,6 +37,13 @@ pub struct ChainStoreQueryOptions {
     pub start: usize,
     pub limit: usize,
     pub headers: bool,
+    /// Only headers with a timestamp at or after this one
+    pub since: Option<Iso8601>,
+    /// Only headers with a timestamp at or before this one
+    pub until: Option<Iso8601>,
+    pub order: QueryOrder,
+    /// Only headers that come after the header with this address in the given order
+    pub cursor: Option<Address>,
 }
 
 #[derive(Debug)]
@@ -78,6 +87,19 @@ impl ChainStore {
         entry_type_names: &[&str],
         options: ChainStoreQueryOptions,
     ) -> Result<ChainStoreQueryResult, RibosomeErrorCode> {
+        self.query_page(start_chain_header, entry_type_names, options)
+            .map(|(result, _cursor)| result)
+    }
+
+    /// Like query, but also returns the cursor to get the next page of results with.
+    /// The cursor is the address of the last returned header and only set if more headers match.
+    /// A cursor that is not on the chain yields no results.
+    pub fn query_page(
+        &self,
+        start_chain_header: &Option<ChainHeader>,
+        entry_type_names: &[&str],
+        options: ChainStoreQueryOptions,
+    ) -> Result<(ChainStoreQueryResult, Option<Address>), RibosomeErrorCode> {
         // Get entry_type name(s), if any.  If empty/blank, returns the complete source chain.  A
         // single matching entry type name with no glob pattern matching will use the single
         // entry_type optimization.  Otherwise, we'll construct a GlobSet match and scan the list to
@@ -96,29 +118,12 @@ impl ChainStore {
         } else {
             options.limit
         };
-        let headers = options.headers;
 
-        let vector = match entry_type_names {
-            // Vec<Address> or Vec<ChainHeader>
+        // Headers of the matching EntryTypes, latest first
+        let matching: Box<dyn Iterator<Item = ChainHeader>> = match entry_type_names {
             [] | [""] | ["**"] => {
                 // No filtering desired; uses bare .iter()
-                if headers {
-                    ChainStoreQueryResult::Headers(
-                        self.iter(start_chain_header)
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.to_owned())
-                            .collect(),
-                    )
-                } else {
-                    ChainStoreQueryResult::Addresses(
-                        self.iter(start_chain_header)
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.entry_address().to_owned())
-                            .collect(),
-                    )
-                }
+                Box::new(self.iter(start_chain_header))
             }
             [one] if !is_glob_str(one) => {
                 // Single EntryType without "glob" pattern; uses .iter_type()
@@ -126,23 +131,7 @@ impl ChainStore {
                     Ok(inner) => inner,
                     Err(..) => return Err(UnknownEntryType),
                 };
-                if headers {
-                    ChainStoreQueryResult::Headers(
-                        self.iter_type(start_chain_header, &entry_type)
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.to_owned())
-                            .collect(),
-                    )
-                } else {
-                    ChainStoreQueryResult::Addresses(
-                        self.iter_type(start_chain_header, &entry_type)
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.entry_address().to_owned())
-                            .collect(),
-                    )
-                }
+                Box::new(self.iter_type(start_chain_header, &entry_type))
             }
             rest => {
                 // 1 or more EntryTypes, may or may not include glob wildcards.  Create a
@@ -160,33 +149,68 @@ impl ChainStore {
                     );
                 }
                 let globset = builder.build().map_err(|_| UnknownEntryType)?;
-                if headers {
-                    ChainStoreQueryResult::Headers(
-                        self.iter(start_chain_header)
-                            .filter(|header| {
-                                globset.matches(header.entry_type().to_string()).len() > 0
-                            })
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.to_owned())
-                            .collect(),
-                    )
-                } else {
-                    ChainStoreQueryResult::Addresses(
-                        self.iter(start_chain_header)
-                            .filter(|header| {
-                                globset.matches(header.entry_type().to_string()).len() > 0
-                            })
-                            .skip(start)
-                            .take(limit)
-                            .map(|header| header.entry_address().to_owned())
-                            .collect(),
-                    )
-                }
+                Box::new(self.iter(start_chain_header).filter(move |header| {
+                    globset.matches(header.entry_type().to_string()).len() > 0
+                }))
             }
         };
 
-        Ok(vector)
+        // Timestamps come from the clock of the authoring agent, which can go backwards,
+        // so the whole chain has to be checked.
+        let since = options.since;
+        let until = options.until;
+        let in_range = matching
+            .filter(move |header| match since {
+                Some(ref since) => header.timestamp() >= since,
+                None => true,
+            })
+            .filter(move |header| match until {
+                Some(ref until) => header.timestamp() <= until,
+                None => true,
+            });
+
+        let ordered: Box<dyn Iterator<Item = ChainHeader>> = match options.order {
+            QueryOrder::LatestFirst => Box::new(in_range),
+            QueryOrder::OldestFirst => {
+                // The chain only links backwards, so all matching headers have to be collected
+                let mut headers: Vec<ChainHeader> = in_range.collect();
+                headers.reverse();
+                Box::new(headers.into_iter())
+            }
+        };
+
+        let after_cursor: Box<dyn Iterator<Item = ChainHeader>> = match options.cursor {
+            None => ordered,
+            Some(cursor) => Box::new(
+                ordered
+                    .skip_while(move |header| header.address() != cursor)
+                    .skip(1),
+            ),
+        };
+
+        // Take one more than the limit to find out whether there is a next page
+        let mut page: Vec<ChainHeader> = after_cursor
+            .skip(start)
+            .take(limit.saturating_add(1))
+            .collect();
+        let cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().map(|header| header.address())
+        } else {
+            None
+        };
+
+        let result = if options.headers {
+            ChainStoreQueryResult::Headers(page)
+        } else {
+            ChainStoreQueryResult::Addresses(
+                page.iter()
+                    .map(|header| header.entry_address().to_owned())
+                    .collect(),
+            )
+        };
+
+        Ok((result, cursor))
     }
 }
 
@@ -307,11 +331,12 @@ pub mod tests {
             entry_type::{test_entry_type_b, AppEntryType},
             test_entry, test_entry_b, test_entry_c, Entry,
         },
-        time::test_iso_8601,
+        time::{test_iso_8601, Iso8601},
     };
     use holochain_json_api::json::{JsonString, RawString};
     use holochain_persistence_api::cas::content::AddressableContent;
     use holochain_persistence_file::cas::file::FilesystemStorage;
+    use holochain_wasm_utils::api_serialization::QueryOrder;
     use tempfile;
 
     pub fn test_chain_store() -> ChainStore {
@@ -533,6 +558,7 @@ pub mod tests {
                     start: 0,
                     limit: 1,
                     headers: false,
+                    ..Default::default()
                 },
             )
             .unwrap()
@@ -791,6 +817,121 @@ pub mod tests {
         }
     }
 
+    #[test]
+    /// show query() time range, ordering and cursor
+    fn query_time_range_order_and_cursor_test() {
+        let chain_store = test_chain_store();
+        let storage = chain_store.content_storage.clone();
+        let entry = test_entry();
+        let mut headers: Vec<ChainHeader> = Vec::new();
+        for seconds in vec![10u64, 20, 30, 40] {
+            let header = ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &test_provenances("sig"),
+                &headers.last().map(|header| header.address()),
+                &None,
+                &None,
+                &Iso8601::from(seconds),
+            );
+            (*storage.write().unwrap())
+                .add(&header)
+                .expect("could not add header to cas");
+            headers.push(header);
+        }
+        let top = Some(headers[3].clone());
+        let query_headers = |options: ChainStoreQueryOptions| match chain_store
+            .query_page(
+                &top,
+                &[],
+                ChainStoreQueryOptions {
+                    headers: true,
+                    ..options
+                },
+            )
+            .unwrap()
+        {
+            (ChainStoreQueryResult::Headers(headers), cursor) => (headers, cursor),
+            other => panic!("Unexpected query value {:?}", other),
+        };
+
+        // Time range, both ends inclusive
+        let (found, cursor) = query_headers(ChainStoreQueryOptions {
+            since: Some(Iso8601::from(20u64)),
+            until: Some(Iso8601::from(30u64)),
+            ..Default::default()
+        });
+        assert_eq!(found, vec![headers[2].clone(), headers[1].clone()]);
+        assert_eq!(cursor, None);
+
+        // Oldest first, one page at a time
+        let (found, cursor) = query_headers(ChainStoreQueryOptions {
+            order: QueryOrder::OldestFirst,
+            limit: 3,
+            ..Default::default()
+        });
+        assert_eq!(found, headers[0..3].to_vec());
+        assert_eq!(cursor, Some(headers[2].address()));
+
+        let (found, cursor) = query_headers(ChainStoreQueryOptions {
+            order: QueryOrder::OldestFirst,
+            limit: 3,
+            cursor,
+            ..Default::default()
+        });
+        assert_eq!(found, vec![headers[3].clone()]);
+        assert_eq!(cursor, None);
+
+        // Cursor in chain order
+        let (found, _) = query_headers(ChainStoreQueryOptions {
+            cursor: Some(headers[2].address()),
+            limit: 1,
+            ..Default::default()
+        });
+        assert_eq!(found, vec![headers[1].clone()]);
+    }
+
+    #[test]
+    /// show that query() checks `since` for all headers, even if the clock went backwards
+    fn query_since_with_clock_going_backwards_test() {
+        let chain_store = test_chain_store();
+        let storage = chain_store.content_storage.clone();
+        let entry = test_entry();
+        let mut headers: Vec<ChainHeader> = Vec::new();
+        for seconds in vec![30u64, 10, 20] {
+            let header = ChainHeader::new(
+                &entry.entry_type(),
+                &entry.address(),
+                &test_provenances("sig"),
+                &headers.last().map(|header| header.address()),
+                &None,
+                &None,
+                &Iso8601::from(seconds),
+            );
+            (*storage.write().unwrap())
+                .add(&header)
+                .expect("could not add header to cas");
+            headers.push(header);
+        }
+
+        let found = match chain_store
+            .query(
+                &Some(headers[2].clone()),
+                &[],
+                ChainStoreQueryOptions {
+                    headers: true,
+                    since: Some(Iso8601::from(20u64)),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        {
+            ChainStoreQueryResult::Headers(headers) => headers,
+            other => panic!("Unexpected query value {:?}", other),
+        };
+        assert_eq!(found, vec![headers[2].clone(), headers[0].clone()]);
+    }
+
     use globset::{Glob, GlobBuilder, GlobSetBuilder};
 
     #[test]
@@ -875,5 +1016,4 @@ pub mod tests {
         assert_eq!(set.matches("src/bar/baz/foo.rs"), vec![2, 3]); // *.rs no longer matches, due to '/' separators
         assert_eq!(set.matches("foo.rs"), vec![0, 3]); // but, any number of leading '/' are matched by a '**/...'
     }
-
 }
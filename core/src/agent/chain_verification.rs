@@ -0,0 +1,263 @@
+//! End to end integrity check of a stored source chain.
+//!
+//! Walks the chain from the top header back to genesis, like [ChainStore::iter] does,
+//! but reports problems instead of panicking on them. Checked for every header:
+//! * the header stored under the `link` of the next header hashes to that address,
+//! * its entry is stored, hashes to `entry_address` and has the type stated in the header,
+//! * all provenance signatures verify against the entry address (or the countersigned
+//!   payload of the header) and
+//! * its timestamp is not later than the one of the next header.
+use crate::{agent::chain_store::ChainStore, workflows::countersign_entry::signs_header};
+use holochain_core_types::{chain_header::ChainHeader, entry::Entry, error::HolochainError};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::{
+    content::{Address, AddressableContent},
+    storage::ContentAddressableStorage,
+};
+use std::fmt;
+
+/// One problem found while verifying a chain
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum ChainProblem {
+    /// A header links to a previous header that is not stored.
+    /// The chain can't be walked any further.
+    MissingHeader { header: Address, link: Address },
+    /// The content stored under a header address is not a header or does not
+    /// hash to that address. The chain can't be walked any further.
+    CorruptHeader { header: Address, reason: String },
+    /// The entry referenced by a header is not stored
+    MissingEntry { header: Address, entry: Address },
+    /// The content stored under an entry address is not an entry, does not hash
+    /// to that address or is not of the type stated in the header
+    CorruptEntry {
+        header: Address,
+        entry: Address,
+        reason: String,
+    },
+    /// A provenance signature of the entry address does not verify
+    InvalidSignature { header: Address, source: Address },
+    /// A header has a later timestamp than the header that comes after it
+    TimestampNotMonotonic {
+        header: Address,
+        timestamp: String,
+        next_timestamp: String,
+    },
+}
+
+impl fmt::Display for ChainProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainProblem::MissingHeader { header, link } => write!(
+                f,
+                "broken link: header {} links to header {} which is missing",
+                header, link
+            ),
+            ChainProblem::CorruptHeader { header, reason } => {
+                write!(f, "corrupt header {}: {}", header, reason)
+            }
+            ChainProblem::MissingEntry { header, entry } => write!(
+                f,
+                "missing entry: entry {} of header {} is not stored",
+                entry, header
+            ),
+            ChainProblem::CorruptEntry {
+                header,
+                entry,
+                reason,
+            } => write!(
+                f,
+                "corrupt entry {} of header {}: {}",
+                entry, header, reason
+            ),
+            ChainProblem::InvalidSignature { header, source } => write!(
+                f,
+                "invalid signature: provenance of {} in header {} does not verify",
+                source, header
+            ),
+            ChainProblem::TimestampNotMonotonic {
+                header,
+                timestamp,
+                next_timestamp,
+            } => write!(
+                f,
+                "timestamp out of order: header {} has timestamp {} which is later than {} of the next header",
+                header, timestamp, next_timestamp
+            ),
+        }
+    }
+}
+
+/// Result of verifying a chain
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, DefaultJson)]
+pub struct ChainVerificationReport {
+    pub top_header: Option<Address>,
+    pub headers_checked: usize,
+    pub problems: Vec<ChainProblem>,
+}
+
+impl ChainVerificationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Verifies the chain that ends in `top_chain_header`.
+/// Returns an error only if the storage can't be read, problems of the chain
+/// itself end up in the report.
+pub fn verify_chain(
+    chain_store: &ChainStore,
+    top_chain_header: &Option<ChainHeader>,
+) -> Result<ChainVerificationReport, HolochainError> {
+    let storage_lock = chain_store.content_storage();
+    let storage = storage_lock.read()?;
+    let mut report = ChainVerificationReport {
+        top_header: top_chain_header.as_ref().map(|header| header.address()),
+        ..Default::default()
+    };
+
+    let mut current = top_chain_header.clone();
+    let mut next: Option<ChainHeader> = None;
+    while let Some(header) = current {
+        report.headers_checked += 1;
+        let header_address = header.address();
+
+        verify_entry(&*storage, &header, &mut report)?;
+
+        for provenance in header.provenances() {
+            if !signs_header(provenance, &header) {
+                report.problems.push(ChainProblem::InvalidSignature {
+                    header: header_address.clone(),
+                    source: provenance.source(),
+                });
+            }
+        }
+
+        if let Some(ref next) = next {
+            if header.timestamp() > next.timestamp() {
+                report.problems.push(ChainProblem::TimestampNotMonotonic {
+                    header: header_address.clone(),
+                    timestamp: header.timestamp().to_string(),
+                    next_timestamp: next.timestamp().to_string(),
+                });
+            }
+        }
+
+        current = match header.link() {
+            None => None,
+            Some(link) => match storage.fetch(&link)? {
+                None => {
+                    report.problems.push(ChainProblem::MissingHeader {
+                        header: header_address.clone(),
+                        link,
+                    });
+                    None
+                }
+                Some(content) => match ChainHeader::try_from_content(&content) {
+                    Ok(ref previous) if previous.address() == link => Some(previous.clone()),
+                    Ok(_) => {
+                        report.problems.push(ChainProblem::CorruptHeader {
+                            header: link,
+                            reason: "content does not match its address".to_string(),
+                        });
+                        None
+                    }
+                    Err(error) => {
+                        report.problems.push(ChainProblem::CorruptHeader {
+                            header: link,
+                            reason: error.to_string(),
+                        });
+                        None
+                    }
+                },
+            },
+        };
+        next = Some(header);
+    }
+
+    Ok(report)
+}
+
+fn verify_entry(
+    storage: &dyn ContentAddressableStorage,
+    header: &ChainHeader,
+    report: &mut ChainVerificationReport,
+) -> Result<(), HolochainError> {
+    let entry_address = header.entry_address();
+    let corrupt = |reason: String| ChainProblem::CorruptEntry {
+        header: header.address(),
+        entry: entry_address.clone(),
+        reason,
+    };
+    let problem = match storage.fetch(entry_address)? {
+        None => Some(ChainProblem::MissingEntry {
+            header: header.address(),
+            entry: entry_address.clone(),
+        }),
+        Some(content) => match Entry::try_from_content(&content) {
+            Err(error) => Some(corrupt(error.to_string())),
+            Ok(ref entry) if entry.address() != *entry_address => {
+                Some(corrupt("content does not match its address".to_string()))
+            }
+            Ok(ref entry) if entry.entry_type() != *header.entry_type() => Some(corrupt(format!(
+                "entry is of type {} but the header states {}",
+                entry.entry_type(),
+                header.entry_type()
+            ))),
+            Ok(_) => None,
+        },
+    };
+    if let Some(problem) = problem {
+        report.problems.push(problem);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::agent::chain_store::tests::test_chain_store;
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry, time::Iso8601};
+
+    #[test]
+    fn verify_chain_reports_missing_entry_and_header() {
+        let chain_store = test_chain_store();
+        let header = test_chain_header();
+        chain_store
+            .content_storage()
+            .write()
+            .unwrap()
+            .add(&header)
+            .unwrap();
+
+        let top = ChainHeader::new(
+            &test_entry().entry_type(),
+            &test_entry().address(),
+            &header.provenances().clone(),
+            &Some(Address::from("missing_header")),
+            &None,
+            &None,
+            &Iso8601::from(0u64),
+        );
+        let report = verify_chain(&chain_store, &Some(top.clone())).unwrap();
+
+        assert_eq!(report.top_header, Some(top.address()));
+        assert_eq!(report.headers_checked, 1);
+        assert!(!report.is_valid());
+        assert!(report.problems.contains(&ChainProblem::MissingEntry {
+            header: top.address(),
+            entry: test_entry().address(),
+        }));
+        assert!(report.problems.contains(&ChainProblem::MissingHeader {
+            header: top.address(),
+            link: Address::from("missing_header"),
+        }));
+    }
+
+    #[test]
+    fn verify_empty_chain() {
+        let report = verify_chain(&test_chain_store(), &None).unwrap();
+        assert_eq!(report.headers_checked, 0);
+        assert!(report.is_valid());
+    }
+}
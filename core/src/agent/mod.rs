@@ -2,6 +2,7 @@
 ///
 pub mod actions;
 pub mod chain_store;
+pub mod chain_verification;
 pub mod state;
 
 use crate::state::State;
@@ -1,6 +1,7 @@
 use crate::{
     action::{Action, ActionWrapper, AgentReduceFn},
     agent::chain_store::{ChainStore, ChainStoreIterator},
+    nucleus::ZomeFnCall,
     state::State,
 };
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
@@ -8,6 +9,7 @@ use holochain_persistence_api::cas::content::{Address, AddressableContent, Conte
 use holochain_core_types::{
     agent::AgentId,
     chain_header::ChainHeader,
+    chain_migrate::{ChainMigrate, MigrationDirection},
     entry::{entry_type::EntryType, Entry},
     error::{HcResult, HolochainError},
     signature::{Provenance, Signature},
@@ -22,7 +24,7 @@ use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
     sync::Arc,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 /// The state-slice for the Agent.
@@ -36,6 +38,10 @@ pub struct AgentState {
     chain_store: ChainStore,
     top_chain_header: Option<ChainHeader>,
     initial_agent_address: Address,
+    bundles: HashMap<ZomeFnCall, Bundle>,
+    /// the migration that closed the chain, kept here so that commits don't have
+    /// to walk the chain to find out whether it is still open
+    closing_migration: Option<ChainMigrate>,
 }
 
 impl AgentState {
@@ -46,6 +52,8 @@ impl AgentState {
             chain_store,
             top_chain_header: None,
             initial_agent_address,
+            bundles: HashMap::new(),
+            closing_migration: None,
         }
     }
 
@@ -59,9 +67,24 @@ impl AgentState {
             chain_store,
             top_chain_header: chain_header,
             initial_agent_address,
+            bundles: HashMap::new(),
+            closing_migration: None,
         }
     }
 
+    /// builds an AgentState for a chain that already exists, e.g. from a snapshot,
+    /// and looks up whether that chain got closed by a migration
+    pub fn restore(
+        chain_store: ChainStore,
+        chain_header: Option<ChainHeader>,
+        initial_agent_address: Address,
+    ) -> HcResult<AgentState> {
+        let mut agent_state =
+            AgentState::new_with_top_chain_header(chain_store, chain_header, initial_agent_address);
+        agent_state.closing_migration = agent_state.find_closing_migration()?;
+        Ok(agent_state)
+    }
+
     /// getter for a copy of self.actions
     /// uniquely maps action executions to the result of the action
     pub fn actions(&self) -> HashMap<ActionWrapper, ActionResponse> {
@@ -76,6 +99,29 @@ impl AgentState {
         self.top_chain_header.clone()
     }
 
+    /// The bundle the zome call started, if any.
+    /// Commits of that call get staged in it instead of being appended to the chain.
+    pub fn bundle(&self, zome_call: &ZomeFnCall) -> Option<Bundle> {
+        self.bundles.get(zome_call).cloned()
+    }
+
+    /// Looks up an entry staged in a bundle that is being closed, so that entries
+    /// of the same bundle can reference each other during validation.
+    pub fn get_closing_bundle_entry(&self, address: &Address) -> Option<Entry> {
+        self.bundles
+            .values()
+            .filter(|bundle| bundle.status == BundleStatus::Closing)
+            .filter_map(|bundle| bundle.get_entry(address))
+            .nth(0)
+    }
+
+    /// true if a bundle timed out but its commits did not get dropped yet
+    pub fn has_expired_bundles(&self) -> bool {
+        self.bundles
+            .values()
+            .any(|bundle| bundle.status != BundleStatus::TimedOut && bundle.is_expired())
+    }
+
     pub fn iter_chain(&self) -> ChainStoreIterator {
         self.chain_store.iter(&self.top_chain_header)
     }
@@ -109,6 +155,49 @@ impl AgentState {
         }
     }
 
+    /// Returns the migration entry that closed this chain, if there is one.
+    /// A closed chain does not take any further commits.
+    pub fn closing_migration(&self) -> Option<ChainMigrate> {
+        self.closing_migration.clone()
+    }
+
+    /// Walks the chain for the latest migration entry and returns it if it closed the chain.
+    fn find_closing_migration(&self) -> HcResult<Option<ChainMigrate>> {
+        let maybe_header = self
+            .chain_store()
+            .iter_type(&self.top_chain_header, &EntryType::ChainMigrate)
+            .nth(0);
+        let header = match maybe_header {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let migration_entry_json = self
+            .chain_store()
+            .content_storage()
+            .read()?
+            .fetch(header.entry_address())?
+            .ok_or(HolochainError::ErrorGeneric(
+                "ChainMigrate entry not found".to_string(),
+            ))?;
+        match migration_entry_json.try_into()? {
+            Entry::ChainMigrate(ref migration)
+                if migration.direction() == MigrationDirection::Close =>
+            {
+                Ok(Some(migration.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Remembers the migration if the committed entry closed the chain.
+    fn note_closing_migration(&mut self, entry: &Entry) {
+        if let Entry::ChainMigrate(migration) = entry {
+            if migration.direction() == MigrationDirection::Close {
+                self.closing_migration = Some(migration.clone());
+            }
+        }
+    }
+
     pub fn get_most_recent_header_for_entry(&self, entry: &Entry) -> Option<ChainHeader> {
         self.chain_store()
             .iter_type(&self.top_chain_header(), &entry.entry_type())
@@ -116,6 +205,69 @@ impl AgentState {
     }
 }
 
+/// Commits that a zome call made between `start_bundle` and `close_bundle`.
+/// They are kept out of the chain until the bundle gets committed, which appends
+/// all of them at once, or dropped if the bundle gets discarded or times out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bundle {
+    commits: Vec<(Entry, Option<Address>, Vec<Provenance>)>,
+    user_param: JsonString,
+    expires_at: SystemTime,
+    status: BundleStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BundleStatus {
+    /// Takes commits
+    Open,
+    /// Its commits get validated, so it does not take any further commits
+    Closing,
+    /// Its commits got dropped. It is kept until the zome call ends,
+    /// so that further commits of the call fail instead of going to the chain.
+    TimedOut,
+}
+
+impl Bundle {
+    pub fn new(user_param: JsonString, timeout: Duration) -> Self {
+        Bundle {
+            commits: Vec::new(),
+            user_param,
+            expires_at: SystemTime::now() + timeout,
+            status: BundleStatus::Open,
+        }
+    }
+
+    /// Staged commits in the order they were made
+    pub fn commits(&self) -> &Vec<(Entry, Option<Address>, Vec<Provenance>)> {
+        &self.commits
+    }
+
+    pub fn user_param(&self) -> &JsonString {
+        &self.user_param
+    }
+
+    pub fn status(&self) -> BundleStatus {
+        self.status
+    }
+
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now() > self.expires_at
+    }
+
+    fn time_out(&mut self) {
+        self.commits.clear();
+        self.status = BundleStatus::TimedOut;
+    }
+
+    fn get_entry(&self, address: &Address) -> Option<Entry> {
+        self.commits
+            .iter()
+            .map(|(entry, _, _)| entry)
+            .find(|entry| entry.address() == *address)
+            .cloned()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, DefaultJson)]
 pub struct AgentStateSnapshot {
     top_chain_header: Option<ChainHeader>,
@@ -166,6 +318,9 @@ impl AddressableContent for AgentStateSnapshot {
 // @see https://github.com/holochain/holochain-rust/issues/196
 pub enum ActionResponse {
     Commit(Result<Address, HolochainError>),
+    StartBundle(Result<(), HolochainError>),
+    SealBundle(Result<(), HolochainError>),
+    CommitBundle(Result<Vec<(Entry, ChainHeader)>, HolochainError>),
     FetchEntry(Option<Entry>),
     GetLinks(Result<Vec<Address>, HolochainError>),
     LinkEntries(Result<Entry, HolochainError>),
@@ -178,33 +333,104 @@ pub fn create_new_chain_header(
     crud_link: &Option<Address>,
     provenances: &Vec<Provenance>,
 ) -> Result<ChainHeader, HolochainError> {
+    let previous_header_of_type = agent_state
+        .chain_store()
+        .iter_type(&agent_state.top_chain_header, &entry.entry_type())
+        .nth(0)
+        .map(|chain_header| chain_header.address());
+    create_chain_header_on(
+        entry,
+        agent_state,
+        root_state,
+        &agent_state.top_chain_header,
+        &previous_header_of_type,
+        crud_link,
+        provenances,
+    )
+}
+
+/// Creates the headers for all commits staged in the bundle, each one on top of the
+/// one before and the first one on top of the current top chain header.
+/// These are the headers the entries get validated with and committed with.
+pub fn create_bundle_headers(
+    bundle: &Bundle,
+    agent_state: &AgentState,
+    root_state: &State,
+) -> Result<Vec<(Entry, ChainHeader)>, HolochainError> {
+    let mut top_chain_header = agent_state.top_chain_header.clone();
+    let mut bundle_headers: Vec<(Entry, ChainHeader)> = Vec::new();
+    for (entry, maybe_link_update_delete, provenances) in bundle.commits().iter() {
+        let previous_header_of_type = bundle_headers
+            .iter()
+            .rev()
+            .map(|(_, chain_header)| chain_header)
+            .find(|chain_header| *chain_header.entry_type() == entry.entry_type())
+            .map(|chain_header| chain_header.address())
+            .or_else(|| {
+                agent_state
+                    .chain_store()
+                    .iter_type(&agent_state.top_chain_header, &entry.entry_type())
+                    .nth(0)
+                    .map(|chain_header| chain_header.address())
+            });
+        let chain_header = create_chain_header_on(
+            entry,
+            agent_state,
+            root_state,
+            &top_chain_header,
+            &previous_header_of_type,
+            maybe_link_update_delete,
+            provenances,
+        )?;
+        top_chain_header = Some(chain_header.clone());
+        bundle_headers.push((entry.clone(), chain_header));
+    }
+    Ok(bundle_headers)
+}
+
+fn create_chain_header_on(
+    entry: &Entry,
+    agent_state: &AgentState,
+    root_state: &State,
+    top_chain_header: &Option<ChainHeader>,
+    previous_header_of_type: &Option<Address>,
+    crud_link: &Option<Address>,
+    provenances: &Vec<Provenance>,
+) -> Result<ChainHeader, HolochainError> {
+    if let Some(migration) = agent_state.closing_migration() {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Source chain was closed by a migration to DNA {}",
+            migration.new_dna_address()
+        )));
+    }
     let agent_address = agent_state.get_agent_address()?;
-    let signature = Signature::from(
-        root_state.conductor_api.sign(entry.address().to_string())?,
-        // Temporarily replaced by error handling for Holo hack signing.
-        // TODO: pull in the expect below after removing the Holo signing hack again
-        //.expect("Must be able to create signatures!"),
-    );
     let duration_since_epoch = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .expect("System time must not be before UNIX EPOCH");
 
     let mut provenances: Vec<Provenance> = provenances.to_vec();
-    provenances.push(Provenance::new(agent_address, signature));
+    // Countersigned entries come with the agent's signature of the countersigned payload
+    if !provenances
+        .iter()
+        .any(|provenance| provenance.source() == agent_address)
+    {
+        let signature = Signature::from(
+            root_state.conductor_api.sign(entry.address().to_string())?,
+            // Temporarily replaced by error handling for Holo hack signing.
+            // TODO: pull in the expect below after removing the Holo signing hack again
+            //.expect("Must be able to create signatures!"),
+        );
+        provenances.push(Provenance::new(agent_address, signature));
+    }
 
     Ok(ChainHeader::new(
         &entry.entry_type(),
         &entry.address(),
         &provenances,
-        &agent_state
-            .top_chain_header
+        &top_chain_header
             .clone()
             .and_then(|chain_header| Some(chain_header.address())),
-        &agent_state
-            .chain_store()
-            .iter_type(&agent_state.top_chain_header, &entry.entry_type())
-            .nth(0)
-            .and_then(|chain_header| Some(chain_header.address())),
+        previous_header_of_type,
         crud_link,
         &Iso8601::from(duration_since_epoch.as_secs()),
     ))
@@ -231,12 +457,13 @@ fn reduce_commit_entry(
     )
     .and_then(|chain_header| {
         let storage = &agent_state.chain_store.content_storage().clone();
-        storage.write().unwrap().add(entry)?;
-        storage.write().unwrap().add(&chain_header)?;
+        storage.write()?.add(entry)?;
+        storage.write()?.add(&chain_header)?;
         Ok((chain_header, entry.address()))
     })
     .and_then(|(chain_header, address)| {
         agent_state.top_chain_header = Some(chain_header);
+        agent_state.note_closing_migration(entry);
         Ok(address)
     });
 
@@ -245,10 +472,193 @@ fn reduce_commit_entry(
         .insert(action_wrapper.clone(), ActionResponse::Commit(result));
 }
 
+fn bundle_timed_out_error() -> HolochainError {
+    HolochainError::ErrorGeneric("Bundle timed out and its commits were dropped".to_string())
+}
+
+fn no_bundle_error() -> HolochainError {
+    HolochainError::ErrorGeneric("No bundle is open".to_string())
+}
+
+/// Opens a bundle for the zome call, unless it already started one.
+fn reduce_start_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (zome_call, args) = unwrap_to!(action => Action::StartBundle);
+
+    let result = if agent_state.bundles.contains_key(zome_call) {
+        Err(HolochainError::ErrorGeneric(
+            "A bundle is already open".to_string(),
+        ))
+    } else {
+        agent_state.bundles.insert(
+            zome_call.clone(),
+            Bundle::new(args.user_param.clone(), Duration::from_millis(args.timeout)),
+        );
+        Ok(())
+    };
+
+    agent_state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::StartBundle(result));
+}
+
+/// Stages a commit in the bundle of the zome call instead of appending it to the chain.
+fn reduce_stage_commit(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (zome_call, entry, maybe_link_update_delete, provenances) =
+        unwrap_to!(action => Action::StageCommit);
+
+    let result = match agent_state.bundles.get_mut(zome_call) {
+        None => Err(no_bundle_error()),
+        Some(bundle) => {
+            if bundle.is_expired() {
+                bundle.time_out();
+            }
+            match bundle.status {
+                BundleStatus::TimedOut => Err(bundle_timed_out_error()),
+                BundleStatus::Closing => Err(HolochainError::ErrorGeneric(
+                    "Bundle is being closed".to_string(),
+                )),
+                BundleStatus::Open => {
+                    bundle.commits.push((
+                        entry.clone(),
+                        maybe_link_update_delete.clone(),
+                        provenances.clone(),
+                    ));
+                    Ok(entry.address())
+                }
+            }
+        }
+    };
+
+    agent_state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::Commit(result));
+}
+
+/// Stops the bundle of the zome call from taking further commits,
+/// so that its staged commits can get validated.
+fn reduce_seal_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let zome_call = unwrap_to!(action => Action::SealBundle);
+
+    let result = match agent_state.bundles.get_mut(zome_call) {
+        None => Err(no_bundle_error()),
+        Some(ref bundle) if bundle.status != BundleStatus::Open || bundle.is_expired() => {
+            Err(bundle_timed_out_error())
+        }
+        Some(bundle) => {
+            bundle.status = BundleStatus::Closing;
+            Ok(())
+        }
+    };
+
+    agent_state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::SealBundle(result));
+}
+
+/// Appends the validated commits of the zome call's bundle to the chain, with the
+/// headers they got validated with, and removes the bundle.
+/// Fails if the chain moved on since the headers got created, so either the
+/// whole bundle ends up on the chain or nothing of it.
+fn reduce_commit_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (zome_call, bundle_headers) = unwrap_to!(action => Action::CommitBundle);
+
+    let top_chain_header_address = agent_state
+        .top_chain_header
+        .as_ref()
+        .map(|chain_header| chain_header.address());
+    let result = match agent_state.bundles.remove(zome_call) {
+        None => Err(no_bundle_error()),
+        Some(ref bundle) if bundle.status != BundleStatus::Closing || bundle.is_expired() => {
+            Err(bundle_timed_out_error())
+        }
+        Some(_) => match bundle_headers.first() {
+            Some((_, chain_header)) if chain_header.link() != top_chain_header_address => {
+                Err(HolochainError::ErrorGeneric(
+                    "Source chain moved on while the bundle got validated".to_string(),
+                ))
+            }
+            _ => {
+                let storage = &agent_state.chain_store.content_storage().clone();
+                bundle_headers
+                    .iter()
+                    .map(|(entry, chain_header)| {
+                        storage.write()?.add(entry)?;
+                        storage.write()?.add(chain_header)?;
+                        Ok(())
+                    })
+                    .collect::<Result<Vec<_>, HolochainError>>()
+                    .map(|_| {
+                        if let Some((_, chain_header)) = bundle_headers.last() {
+                            agent_state.top_chain_header = Some(chain_header.clone());
+                        }
+                        for (entry, _) in bundle_headers.iter() {
+                            agent_state.note_closing_migration(entry);
+                        }
+                        bundle_headers.clone()
+                    })
+            }
+        },
+    };
+
+    agent_state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::CommitBundle(result));
+}
+
+/// Drops the bundle of the zome call with all its staged commits.
+fn reduce_discard_bundle(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let zome_call = unwrap_to!(action => Action::DiscardBundle);
+    agent_state.bundles.remove(zome_call);
+}
+
+/// Drops the commits of all bundles that timed out.
+fn reduce_expire_bundles(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    _action_wrapper: &ActionWrapper,
+) {
+    agent_state
+        .bundles
+        .values_mut()
+        .filter(|bundle| bundle.status != BundleStatus::TimedOut && bundle.is_expired())
+        .for_each(|bundle| bundle.time_out());
+}
+
 /// maps incoming action to the correct handler
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<AgentReduceFn> {
     match action_wrapper.action() {
         Action::Commit(_) => Some(reduce_commit_entry),
+        Action::StartBundle(_) => Some(reduce_start_bundle),
+        Action::StageCommit(_) => Some(reduce_stage_commit),
+        Action::SealBundle(_) => Some(reduce_seal_bundle),
+        Action::CommitBundle(_) => Some(reduce_commit_bundle),
+        Action::DiscardBundle(_) => Some(reduce_discard_bundle),
+        Action::ExpireBundles => Some(reduce_expire_bundles),
         _ => None,
     }
 }
@@ -275,7 +685,7 @@ pub mod tests {
     use super::*;
     use crate::{
         action::tests::test_action_wrapper_commit, agent::chain_store::tests::test_chain_store,
-        instance::tests::test_context, state::State,
+        instance::tests::test_context, nucleus::tests::test_zome_call, state::State,
     };
     use holochain_core_types::{
         chain_header::{test_chain_header, ChainHeader},
@@ -285,6 +695,7 @@ pub mod tests {
     };
     use holochain_json_api::json::JsonString;
     use holochain_persistence_api::cas::content::AddressableContent;
+    use holochain_wasm_utils::api_serialization::bundle::StartBundleArgs;
     use serde_json;
     use std::collections::HashMap;
     use test_utils::mock_signing::mock_signer;
@@ -321,7 +732,7 @@ pub mod tests {
     fn test_reduce_commit_entry() {
         let netname = Some("test_reduce_commit_entry");
         let context = test_context("bob", netname);
-        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
         let state = State::new_with_agent(context, agent_state.clone());
         let action_wrapper = test_action_wrapper_commit();
 
@@ -333,6 +744,257 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test that a chain closed by a migration refuses further commits
+    fn test_reduce_commit_entry_after_closing_migration() {
+        let netname = Some("test_reduce_commit_entry_after_closing_migration");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
+        let state = State::new_with_agent(context.clone(), agent_state.clone());
+
+        let migration = ChainMigrate::close(
+            Address::from("old_dna"),
+            Address::from("new_dna"),
+            context.agent_id().address(),
+        );
+        let close_action = ActionWrapper::new(Action::Commit((
+            Entry::ChainMigrate(migration.clone()),
+            None,
+            vec![],
+        )));
+        reduce_commit_entry(&mut agent_state, &state, &close_action);
+        assert_eq!(agent_state.closing_migration(), Some(migration));
+
+        let action_wrapper = test_action_wrapper_commit();
+        reduce_commit_entry(&mut agent_state, &state, &action_wrapper);
+        assert_eq!(
+            agent_state.actions().get(&action_wrapper),
+            Some(&ActionResponse::Commit(Err(HolochainError::ErrorGeneric(
+                "Source chain was closed by a migration to DNA new_dna".to_string()
+            )))),
+        );
+    }
+
+    #[test]
+    /// test that restoring the agent state from its chain finds the closing migration
+    fn test_restore_closed_chain() {
+        let netname = Some("test_restore_closed_chain");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
+        let state = State::new_with_agent(context.clone(), agent_state.clone());
+
+        let migration = ChainMigrate::close(
+            Address::from("old_dna"),
+            Address::from("new_dna"),
+            context.agent_id().address(),
+        );
+        let close_action = ActionWrapper::new(Action::Commit((
+            Entry::ChainMigrate(migration.clone()),
+            None,
+            vec![],
+        )));
+        reduce_commit_entry(&mut agent_state, &state, &close_action);
+
+        let restored = AgentState::restore(
+            agent_state.chain_store(),
+            agent_state.top_chain_header(),
+            context.agent_id().address(),
+        )
+        .unwrap();
+        assert_eq!(restored.closing_migration(), Some(migration));
+    }
+
+    fn test_action_wrapper_start_bundle(zome_call: &ZomeFnCall, timeout: u64) -> ActionWrapper {
+        ActionWrapper::new(Action::StartBundle((
+            zome_call.clone(),
+            StartBundleArgs {
+                timeout,
+                user_param: JsonString::empty_object(),
+            },
+        )))
+    }
+
+    fn test_action_wrapper_stage_commit(zome_call: &ZomeFnCall) -> ActionWrapper {
+        ActionWrapper::new(Action::StageCommit((
+            zome_call.clone(),
+            test_entry(),
+            None,
+            vec![],
+        )))
+    }
+
+    #[test]
+    /// test that staged commits only get appended once the bundle is committed,
+    /// with the headers created for them
+    fn test_reduce_commit_bundle() {
+        let netname = Some("test_reduce_commit_bundle");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let zome_call = test_zome_call();
+
+        let start_action = test_action_wrapper_start_bundle(&zome_call, 60000);
+        reduce_start_bundle(&mut agent_state, &state, &start_action);
+        assert_eq!(
+            agent_state.actions().get(&start_action),
+            Some(&ActionResponse::StartBundle(Ok(()))),
+        );
+
+        let stage_action = test_action_wrapper_stage_commit(&zome_call);
+        reduce_stage_commit(&mut agent_state, &state, &stage_action);
+        assert_eq!(
+            agent_state.actions().get(&stage_action),
+            Some(&test_action_response_commit()),
+        );
+        assert_eq!(agent_state.top_chain_header(), None);
+        let bundle = agent_state.bundle(&zome_call).unwrap();
+        assert_eq!(bundle.commits().len(), 1);
+        // Bundles of other zome calls don't see it
+        assert_eq!(agent_state.bundle(&test_zome_call()), None);
+        assert_eq!(
+            agent_state.get_closing_bundle_entry(&test_entry().address()),
+            None
+        );
+
+        reduce_seal_bundle(
+            &mut agent_state,
+            &state,
+            &ActionWrapper::new(Action::SealBundle(zome_call.clone())),
+        );
+        assert_eq!(
+            agent_state.get_closing_bundle_entry(&test_entry().address()),
+            Some(test_entry()),
+        );
+
+        let bundle_headers = create_bundle_headers(
+            &agent_state.bundle(&zome_call).unwrap(),
+            &agent_state,
+            &state,
+        )
+        .unwrap();
+        let commit_bundle_action = ActionWrapper::new(Action::CommitBundle((
+            zome_call.clone(),
+            bundle_headers.clone(),
+        )));
+        reduce_commit_bundle(&mut agent_state, &state, &commit_bundle_action);
+        assert_eq!(
+            agent_state.actions().get(&commit_bundle_action),
+            Some(&ActionResponse::CommitBundle(Ok(bundle_headers.clone()))),
+        );
+        assert_eq!(agent_state.bundle(&zome_call), None);
+        assert_eq!(
+            agent_state.top_chain_header(),
+            Some(bundle_headers[0].1.clone())
+        );
+    }
+
+    #[test]
+    /// test that a bundle does not get committed on top of a chain that moved on
+    fn test_reduce_commit_bundle_after_chain_moved() {
+        let netname = Some("test_reduce_commit_bundle_after_chain_moved");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let zome_call = test_zome_call();
+
+        reduce_start_bundle(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_start_bundle(&zome_call, 60000),
+        );
+        reduce_stage_commit(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_stage_commit(&zome_call),
+        );
+        reduce_seal_bundle(
+            &mut agent_state,
+            &state,
+            &ActionWrapper::new(Action::SealBundle(zome_call.clone())),
+        );
+        let bundle_headers = create_bundle_headers(
+            &agent_state.bundle(&zome_call).unwrap(),
+            &agent_state,
+            &state,
+        )
+        .unwrap();
+
+        reduce_commit_entry(&mut agent_state, &state, &test_action_wrapper_commit());
+        let top_chain_header = agent_state.top_chain_header();
+
+        let commit_bundle_action =
+            ActionWrapper::new(Action::CommitBundle((zome_call.clone(), bundle_headers)));
+        reduce_commit_bundle(&mut agent_state, &state, &commit_bundle_action);
+        assert_eq!(
+            agent_state.actions().get(&commit_bundle_action),
+            Some(&ActionResponse::CommitBundle(Err(
+                HolochainError::ErrorGeneric(
+                    "Source chain moved on while the bundle got validated".to_string()
+                )
+            ))),
+        );
+        assert_eq!(agent_state.top_chain_header(), top_chain_header);
+    }
+
+    #[test]
+    /// test that discarded and timed out bundles drop their commits
+    fn test_reduce_discard_bundle() {
+        let netname = Some("test_reduce_discard_bundle");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id().address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+        let zome_call = test_zome_call();
+
+        reduce_start_bundle(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_start_bundle(&zome_call, 60000),
+        );
+        reduce_stage_commit(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_stage_commit(&zome_call),
+        );
+        reduce_discard_bundle(
+            &mut agent_state,
+            &state,
+            &ActionWrapper::new(Action::DiscardBundle(zome_call.clone())),
+        );
+        assert_eq!(agent_state.bundle(&zome_call), None);
+        assert_eq!(agent_state.top_chain_header(), None);
+
+        reduce_start_bundle(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_start_bundle(&zome_call, 0),
+        );
+        reduce_stage_commit(
+            &mut agent_state,
+            &state,
+            &test_action_wrapper_stage_commit(&zome_call),
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(agent_state.has_expired_bundles());
+        reduce_expire_bundles(
+            &mut agent_state,
+            &state,
+            &ActionWrapper::new(Action::ExpireBundles),
+        );
+        assert!(!agent_state.has_expired_bundles());
+        let bundle = agent_state.bundle(&zome_call).unwrap();
+        assert_eq!(bundle.status(), BundleStatus::TimedOut);
+        assert!(bundle.commits().is_empty());
+
+        // Later commits of the zome call fail instead of going to the chain
+        let stage_action = test_action_wrapper_stage_commit(&zome_call);
+        reduce_stage_commit(&mut agent_state, &state, &stage_action);
+        assert_eq!(
+            agent_state.actions().get(&stage_action),
+            Some(&ActionResponse::Commit(Err(bundle_timed_out_error()))),
+        );
+        assert_eq!(agent_state.top_chain_header(), None);
+    }
+
     #[test]
     /// test response to json
     fn test_commit_response_to_json() {
@@ -413,12 +1075,12 @@ pub mod tests {
     fn test_create_new_chain_header() {
         let netname = Some("test_create_new_chain_header");
         let context = test_context("bob", netname);
-        let agent_state = test_agent_state(Some(context.agent_id.address()));
+        let agent_state = test_agent_state(Some(context.agent_id().address()));
         let state = State::new_with_agent(context.clone(), agent_state.clone());
 
         let header =
             create_new_chain_header(&test_entry(), &agent_state, &state, &None, &vec![]).unwrap();
-        let agent_id = context.agent_id.clone();
+        let agent_id = context.agent_id();
         assert_eq!(
             header,
             ChainHeader::new(
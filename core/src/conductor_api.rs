@@ -1,4 +1,5 @@
 use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::cas::content::Address;
 use jsonrpc_core::IoHandler;
 use jsonrpc_lite::JsonRpc;
 use snowflake::ProcessUniqueId;
@@ -16,24 +17,94 @@ impl ConductorApi {
     }
 
     pub fn sign(&self, payload: String) -> Result<String, HolochainError> {
+        let result = self.call("agent/sign", format!(r#"{{"payload": "{}"}}"#, payload))?;
+        Ok(String::from(result["signature"].as_str()?))
+    }
+
+    /// Returns the public encrypting key of the agent.
+    pub fn encryption_key(&self) -> Result<String, HolochainError> {
+        let result = self.call("agent/encryption_key", String::from("{}"))?;
+        Ok(String::from(result["pub_key"].as_str()?))
+    }
+
+    /// Decrypts data that `sender` encrypted for the agent's encrypting key.
+    pub fn decrypt(&self, sender: &str, cipher: &[u8]) -> Result<Vec<u8>, HolochainError> {
+        let result = self.call(
+            "agent/decrypt",
+            format!(
+                r#"{{"sender": "{}", "payload": "{}"}}"#,
+                sender,
+                base64::encode(cipher)
+            ),
+        )?;
+        base64::decode(result["payload"].as_str()?)
+            .map_err(|_| HolochainError::ErrorGeneric("Decrypted payload is not base64".into()))
+    }
+
+    /// Asks the conductor to derive the next signing key of the agent.
+    /// The conductor stores the key right away, so it can't get lost once the chain refers
+    /// to it, but keeps signing with the current key until `activate_rotated_key` gets called.
+    /// Returns the public key.
+    pub fn rotate_agent_key(&self) -> Result<String, HolochainError> {
+        let result = self.call("agent/rotate_key/new", String::from("{}"))?;
+        Ok(String::from(result["pub_key"].as_str()?))
+    }
+
+    /// Signs the payload with the pending key created by `rotate_agent_key`.
+    pub fn sign_with_rotated_key(&self, payload: String) -> Result<String, HolochainError> {
+        let result = self.call(
+            "agent/rotate_key/sign",
+            format!(r#"{{"payload": "{}"}}"#, payload),
+        )?;
+        Ok(String::from(result["signature"].as_str()?))
+    }
+
+    /// Makes the pending key created by `rotate_agent_key` the one used for `sign`.
+    pub fn activate_rotated_key(&self) -> Result<(), HolochainError> {
+        self.call("agent/rotate_key/activate", String::from("{}"))?;
+        Ok(())
+    }
+
+    /// Puts the agent on the conductor's blocklist of the instance's DNA.
+    /// Direct messages of blocked agents get refused by all instances of that DNA.
+    pub fn block_agent(&self, agent_id: &Address) -> Result<(), HolochainError> {
+        self.call(
+            "blocklist/add",
+            format!(r#"{{"agent_id": "{}"}}"#, agent_id),
+        )?;
+        Ok(())
+    }
+
+    /// Returns true if the agent is on the conductor's blocklist of the instance's DNA.
+    pub fn is_blocked(&self, agent_id: &Address) -> Result<bool, HolochainError> {
+        let result = self.call(
+            "blocklist/contains",
+            format!(r#"{{"agent_id": "{}"}}"#, agent_id),
+        )?;
+        Ok(result["blocked"].as_bool()?)
+    }
+
+    fn call(&self, method: &str, params: String) -> Result<serde_json::Value, HolochainError> {
         let handler = self.0.write().unwrap();
         let request = format!(
-            r#"{{"jsonrpc": "2.0", "method": "agent/sign", "params": {{"payload": "{}"}}, "id": "{}"}}"#,
-            payload, ProcessUniqueId::new(),
+            r#"{{"jsonrpc": "2.0", "method": "{}", "params": {}, "id": "{}"}}"#,
+            method,
+            params,
+            ProcessUniqueId::new(),
         );
 
         let response = handler
             .handle_request_sync(&request)
-            .ok_or("Conductor sign call failed".to_string())?;
+            .ok_or(format!("Conductor call {} failed", method))?;
 
         let response = JsonRpc::parse(&response)?;
 
         match response {
-            JsonRpc::Success(_) => Ok(String::from(response.get_result()?["signature"].as_str()?)),
+            JsonRpc::Success(_) => Ok(response.get_result()?.to_owned()),
             JsonRpc::Error(_) => Err(HolochainError::ErrorGeneric(serde_json::to_string(
                 &response.get_error()?,
             )?)),
-            _ => Err(HolochainError::ErrorGeneric("Signing failed".to_string())),
+            _ => Err(HolochainError::ErrorGeneric(format!("{} failed", method))),
         }
     }
 
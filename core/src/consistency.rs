@@ -86,36 +86,13 @@ impl ConsistencyModel {
         use ConsistencyGroup::*;
         match action {
             Action::Commit((entry, crud_link, _)) => {
-                // XXX: Since can_publish relies on a properly initialized Context, there are a few ways
-                // can_publish can fail. If we hit the possiblity of failure, just add the commit to the cache
-                // anyway. The only reason to check is to avoid filling up the cache unnecessarily with
-                // commits that will never be published.
-                let do_cache = self.context.state().is_none()
-                    || self.context.get_dna().is_none()
-                    || entry.entry_type().can_publish(&self.context);
-
-                // If entry is publishable, construct the ConsistencySignal that should be emitted
-                // when the entry is finally published, and save it for later
-                if do_cache {
-                    let address = entry.address();
-                    let hold = Hold(address.clone());
-                    let meta = crud_link.clone().and_then(|crud| match entry {
-                        Entry::App(_, _) => Some(UpdateEntry(crud, address.clone())),
-                        Entry::Deletion(_) => Some(RemoveEntry(crud, address.clone())),
-                        Entry::LinkAdd(link_data) => Some(AddLink(link_data.clone())),
-                        Entry::LinkRemove(_) => Some(RemoveLink(entry.clone())),
-                        // Question: Why does Entry::LinkAdd take LinkData instead of Link?
-                        // as of now, link data contains more information than just the link
-                        _ => None,
-                    });
-                    let mut pending = vec![hold];
-                    meta.map(|m| pending.push(m));
-                    let signal = ConsistencySignal::new_pending(
-                        Publish(address.clone()),
-                        Validators,
-                        pending,
-                    );
-                    self.commit_cache.insert(address, signal);
+                self.cache_commit(entry, crud_link);
+                None
+            }
+            // Entries of a bundle only get published once the whole bundle got committed
+            Action::CommitBundle((_, bundle_headers)) => {
+                for (entry, chain_header) in bundle_headers.iter() {
+                    self.cache_commit(entry, &chain_header.link_update_delete());
                 }
                 None
             }
@@ -139,7 +116,7 @@ impl ConsistencyModel {
             Action::RemoveEntry((old, new)) => Some(ConsistencySignal::new_terminal(
                 ConsistencyEvent::RemoveEntry(old.clone(), new.clone()),
             )),
-            Action::AddLink(link) => Some(ConsistencySignal::new_terminal(
+            Action::AddLink((link, _)) => Some(ConsistencySignal::new_terminal(
                 ConsistencyEvent::AddLink(link.clone()),
             )),
             Action::RemoveLink(entry) => Some(ConsistencySignal::new_terminal(
@@ -169,4 +146,37 @@ impl ConsistencyModel {
             _ => None,
         }
     }
+
+    fn cache_commit(&mut self, entry: &Entry, crud_link: &Option<Address>) {
+        use ConsistencyEvent::*;
+        use ConsistencyGroup::*;
+        // XXX: Since can_publish relies on a properly initialized Context, there are a few ways
+        // can_publish can fail. If we hit the possiblity of failure, just add the commit to the cache
+        // anyway. The only reason to check is to avoid filling up the cache unnecessarily with
+        // commits that will never be published.
+        let do_cache = self.context.state().is_none()
+            || self.context.get_dna().is_none()
+            || entry.entry_type().can_publish(&self.context);
+
+        // If entry is publishable, construct the ConsistencySignal that should be emitted
+        // when the entry is finally published, and save it for later
+        if do_cache {
+            let address = entry.address();
+            let hold = Hold(address.clone());
+            let meta = crud_link.clone().and_then(|crud| match entry {
+                Entry::App(_, _) => Some(UpdateEntry(crud, address.clone())),
+                Entry::Deletion(_) => Some(RemoveEntry(crud, address.clone())),
+                Entry::LinkAdd(link_data) => Some(AddLink(link_data.clone())),
+                Entry::LinkRemove(_) => Some(RemoveLink(entry.clone())),
+                // Question: Why does Entry::LinkAdd take LinkData instead of Link?
+                // as of now, link data contains more information than just the link
+                _ => None,
+            });
+            let mut pending = vec![hold];
+            meta.map(|m| pending.push(m));
+            let signal =
+                ConsistencySignal::new_pending(Publish(address.clone()), Validators, pending);
+            self.commit_cache.insert(address, signal);
+        }
+    }
 }
@@ -51,7 +51,7 @@ use test_utils::mock_signing::mock_conductor_api;
 /// to inner components/reducers.
 #[derive(Clone)]
 pub struct Context {
-    pub agent_id: AgentId,
+    agent_id: Arc<RwLock<AgentId>>,
     pub logger: Arc<Mutex<Logger>>,
     pub persister: Arc<Mutex<Persister>>,
     state: Option<Arc<RwLock<State>>>,
@@ -106,7 +106,7 @@ impl Context {
         signal_tx: Option<SignalSender>,
     ) -> Self {
         Context {
-            agent_id: agent_id.clone(),
+            agent_id: Arc::new(RwLock::new(agent_id.clone())),
             logger,
             persister,
             state: None,
@@ -136,7 +136,7 @@ impl Context {
         p2p_config: P2pConfig,
     ) -> Result<Context, HolochainError> {
         Ok(Context {
-            agent_id: agent_id.clone(),
+            agent_id: Arc::new(RwLock::new(agent_id.clone())),
             logger,
             persister,
             state: None,
@@ -161,6 +161,16 @@ impl Context {
         logger.log(msg.into());
     }
 
+    /// The agent this instance runs as.
+    /// Shared by all clones of this context so that a key rotation is seen everywhere.
+    pub fn agent_id(&self) -> AgentId {
+        self.agent_id.read().unwrap().clone()
+    }
+
+    pub(crate) fn set_agent_id(&self, agent_id: AgentId) {
+        *self.agent_id.write().unwrap() = agent_id;
+    }
+
     pub fn set_state(&mut self, state: Arc<RwLock<State>>) {
         self.state = Some(state);
     }
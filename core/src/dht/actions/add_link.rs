@@ -7,19 +7,20 @@ use futures::{
     future::Future,
     task::{LocalWaker, Poll},
 };
-use holochain_core_types::{error::HolochainError, link::link_data::LinkData};
+use holochain_core_types::{error::HolochainError, link::link_data::LinkData, time::Iso8601};
 use std::{pin::Pin, sync::Arc};
 
 /// AddLink Action Creator
 /// This action creator dispatches an AddLink action which is consumed by the DHT reducer.
+/// The given timestamp of the LinkAdd entry's header gets stored with the link.
 /// Note that this function does not include any validation checks for the link.
 /// The DHT reducer does make sure that it only adds links to a base that it has in its
 /// local storage and will return an error that the AddLinkFuture resolves to
 /// if that is not the case.
 ///
 /// Returns a future that resolves to an Ok(()) or an Err(HolochainError).
-pub fn add_link(link: &LinkData, context: &Arc<Context>) -> AddLinkFuture {
-    let action_wrapper = ActionWrapper::new(Action::AddLink(link.clone()));
+pub fn add_link(link: &LinkData, timestamp: &Iso8601, context: &Arc<Context>) -> AddLinkFuture {
+    let action_wrapper = ActionWrapper::new(Action::AddLink((link.clone(), timestamp.clone())));
     dispatch_action(context.action_channel(), action_wrapper.clone());
 
     AddLinkFuture {
@@ -86,7 +87,11 @@ mod tests {
             test_chain_header(),
             test_agent_id(),
         );
-        let result = context.block_on(add_link(&link_data, &context.clone()));
+        let result = context.block_on(add_link(
+            &link_data,
+            test_chain_header().timestamp(),
+            &context.clone(),
+        ));
 
         assert!(result.is_ok(), "result = {:?}", result);
     }
@@ -104,7 +109,11 @@ mod tests {
             test_chain_header(),
             test_agent_id(),
         );
-        let result = context.block_on(add_link(&link_data, &context.clone()));
+        let result = context.block_on(add_link(
+            &link_data,
+            test_chain_header().timestamp(),
+            &context.clone(),
+        ));
 
         assert!(result.is_err());
         assert_eq!(
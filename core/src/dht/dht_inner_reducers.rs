@@ -10,10 +10,11 @@
 use crate::dht::dht_store::DhtStore;
 use holochain_core_types::{
     crud_status::{create_crud_link_eav, create_crud_status_eav, CrudStatus},
-    eav::{Attribute, EaviQuery, EntityAttributeValueIndex},
+    eav::{Attribute, EaviQuery, EntityAttributeValueIndex, Index},
     entry::Entry,
     error::{HcResult, HolochainError},
     link::Link,
+    time::Iso8601,
 };
 
 use holochain_persistence_api::{
@@ -21,13 +22,23 @@ use holochain_persistence_api::{
     eav::IndexFilter,
 };
 
+use chrono::{DateTime, FixedOffset};
 use std::{collections::BTreeSet, convert::TryInto, str::FromStr};
 
 pub(crate) enum LinkModification {
-    Add,
+    /// Adds a link with the timestamp of its LinkAdd entry's header
+    Add(Iso8601),
     Remove,
 }
 
+/// Nanoseconds of the given timestamp, saturating for timestamps out of the i64 range
+fn link_index(timestamp: &Iso8601) -> Index {
+    let time = DateTime::<FixedOffset>::from(timestamp);
+    time.timestamp()
+        .saturating_mul(1_000_000_000)
+        .saturating_add(i64::from(time.timestamp_subsec_nanos()))
+}
+
 /// Used as the inner function for both commit and hold reducers
 pub(crate) fn reduce_store_entry_inner(store: &mut DhtStore, entry: &Entry) -> HcResult<()> {
     match (*store.content_storage().write()?).add(entry) {
@@ -50,15 +61,20 @@ pub(crate) fn reduce_add_remove_link_inner(
     link_modification: LinkModification,
 ) -> HcResult<Address> {
     if (*store.content_storage().read()?).contains(link.base())? {
-        let attr = match link_modification {
-            LinkModification::Add => {
-                Attribute::LinkTag(link.link_type().to_string(), link.tag().to_string())
-            }
-            LinkModification::Remove => {
-                Attribute::RemovedLink(link.link_type().to_string(), link.tag().to_string())
-            }
+        let eav = match link_modification {
+            // The index of a link is its timestamp so get_links can sort by it
+            LinkModification::Add(timestamp) => EntityAttributeValueIndex::new_with_index(
+                link.base(),
+                &Attribute::LinkTag(link.link_type().to_string(), link.tag().to_string()),
+                address,
+                link_index(&timestamp),
+            )?,
+            LinkModification::Remove => EntityAttributeValueIndex::new(
+                link.base(),
+                &Attribute::RemovedLink(link.link_type().to_string(), link.tag().to_string()),
+                address,
+            )?,
         };
-        let eav = EntityAttributeValueIndex::new(link.base(), &attr, address)?;
         store.meta_storage().write()?.add_eavi(&eav)?;
         Ok(link.base().clone())
     } else {
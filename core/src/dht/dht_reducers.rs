@@ -13,7 +13,7 @@ use super::dht_inner_reducers::{
 };
 
 use holochain_core_types::entry::Entry;
-use holochain_persistence_api::cas::content::AddressableContent;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
 
 // A function that might return a mutated DhtStore
 type DhtReducer = fn(&DhtStore, &ActionWrapper) -> Option<DhtStore>;
@@ -44,10 +44,22 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<DhtReducer> {
         Action::RemoveEntry(_) => Some(reduce_remove_entry),
         Action::AddLink(_) => Some(reduce_add_link),
         Action::RemoveLink(_) => Some(reduce_remove_link),
+        Action::HoldWarrant(_) => Some(reduce_hold_warrant),
         _ => None,
     }
 }
 
+/// Addresses of the entries and headers that reducing the action adds to the DHT.
+/// Pending validations waiting on any of them get woken up after the action got reduced.
+pub(crate) fn held_addresses(action: &Action) -> Vec<Address> {
+    match action {
+        Action::Commit((entry, _, _)) => vec![entry.address()],
+        Action::Hold(EntryWithHeader { entry, header }) => vec![entry.address(), header.address()],
+        Action::AddLink((link_data, _)) => vec![Entry::LinkAdd(link_data.clone()).address()],
+        _ => Vec::new(),
+    }
+}
+
 pub(crate) fn reduce_commit_entry(
     old_store: &DhtStore,
     action_wrapper: &ActionWrapper,
@@ -85,14 +97,14 @@ pub(crate) fn reduce_add_link(
     old_store: &DhtStore,
     action_wrapper: &ActionWrapper,
 ) -> Option<DhtStore> {
-    let link_data = unwrap_to!(action_wrapper.action() => Action::AddLink);
+    let (link_data, timestamp) = unwrap_to!(action_wrapper.action() => Action::AddLink);
     let mut new_store = (*old_store).clone();
     let entry = Entry::LinkAdd(link_data.clone());
     let res = reduce_add_remove_link_inner(
         &mut new_store,
         link_data.link(),
         &entry.address(),
-        LinkModification::Add,
+        LinkModification::Add(timestamp.clone()),
     );
     new_store.actions_mut().insert(action_wrapper.clone(), res);
     Some(new_store)
@@ -144,6 +156,16 @@ pub(crate) fn reduce_remove_entry(
     Some(new_store)
 }
 
+pub(crate) fn reduce_hold_warrant(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let warrant = unwrap_to!(action_wrapper.action() => Action::HoldWarrant);
+    let mut new_store = (*old_store).clone();
+    new_store.add_warrant(warrant);
+    Some(new_store)
+}
+
 #[allow(dead_code)]
 pub(crate) fn reduce_get_links(
     _old_store: &DhtStore,
@@ -159,7 +181,7 @@ pub mod tests {
     use crate::{
         action::{Action, ActionWrapper},
         dht::{
-            dht_reducers::{reduce, reduce_hold_entry},
+            dht_reducers::{held_addresses, reduce, reduce_hold_entry},
             dht_store::create_get_links_eavi_query,
         },
         instance::tests::test_context,
@@ -234,7 +256,10 @@ pub mod tests {
             test_chain_header(),
             test_agent_id(),
         );
-        let action = ActionWrapper::new(Action::AddLink(link_data.clone()));
+        let action = ActionWrapper::new(Action::AddLink((
+            link_data.clone(),
+            test_chain_header().timestamp().clone(),
+        )));
         let link_entry = Entry::LinkAdd(link_data.clone());
 
         let new_dht_store = (*reduce(store.dht(), &action)).clone();
@@ -279,7 +304,10 @@ pub mod tests {
 
         //add link to dht
         let entry_link_add = Entry::LinkAdd(link_data.clone());
-        let action_link_add = ActionWrapper::new(Action::AddLink(link_data.clone()));
+        let action_link_add = ActionWrapper::new(Action::AddLink((
+            link_data.clone(),
+            test_chain_header().timestamp().clone(),
+        )));
         let new_dht_store = reduce(store.dht(), &action_link_add);
 
         let link_remove_data = LinkData::from_link(
@@ -317,7 +345,10 @@ pub mod tests {
         );
 
         //add new link with same chain header
-        let action_link_add = ActionWrapper::new(Action::AddLink(link_data));
+        let action_link_add = ActionWrapper::new(Action::AddLink((
+            link_data,
+            test_chain_header().timestamp().clone(),
+        )));
         let new_dht_store = reduce(store.dht(), &action_link_add);
 
         //fetch from dht after link with same chain header is added
@@ -348,7 +379,10 @@ pub mod tests {
             test_agent_id_with_name("new_agent"),
         );
         let entry_link_add = Entry::LinkAdd(link_data.clone());
-        let action_link_add = ActionWrapper::new(Action::AddLink(link_data));
+        let action_link_add = ActionWrapper::new(Action::AddLink((
+            link_data,
+            test_chain_header().timestamp().clone(),
+        )));
         let _new_dht_store = reduce(store.dht(), &action_link_add);
 
         //after new link has been added return from fetch and make sure tombstone and new link is added
@@ -390,7 +424,10 @@ pub mod tests {
             test_chain_header(),
             test_agent_id(),
         );
-        let action = ActionWrapper::new(Action::AddLink(link_data));
+        let action = ActionWrapper::new(Action::AddLink((
+            link_data,
+            test_chain_header().timestamp().clone(),
+        )));
 
         let new_dht_store = reduce(store.dht(), &action);
 
@@ -432,4 +469,20 @@ pub mod tests {
         assert_eq!(&entry, &result_entry,);
     }
 
+    #[test]
+    fn held_addresses_test() {
+        let entry_wh = EntryWithHeader {
+            entry: test_entry(),
+            header: test_chain_header(),
+        };
+        assert_eq!(
+            held_addresses(&Action::Hold(entry_wh.clone())),
+            vec![entry_wh.entry.address(), entry_wh.header.address()]
+        );
+        assert!(held_addresses(&Action::UpdateEntry((
+            entry_wh.entry.address(),
+            entry_wh.header.address()
+        )))
+        .is_empty());
+    }
 }
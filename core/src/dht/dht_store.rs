@@ -1,4 +1,4 @@
-use crate::action::ActionWrapper;
+use crate::{action::ActionWrapper, network::warrant::Warrant};
 use holochain_core_types::{
     chain_header::ChainHeader,
     crud_status::CrudStatus,
@@ -13,6 +13,9 @@ use holochain_persistence_api::{
     },
     eav::{EavFilter, EntityAttributeValueStorage, IndexFilter},
 };
+use holochain_wasm_utils::api_serialization::get_links::{
+    GetLinksOptions, LinksSortOrder, LinksStatusRequestKind,
+};
 use regex::Regex;
 
 use std::{
@@ -29,6 +32,9 @@ pub struct DhtStore {
     meta_storage: Arc<RwLock<EntityAttributeValueStorage<Attribute>>>,
 
     actions: HashMap<ActionWrapper, Result<Address, HolochainError>>,
+
+    // Checked warrants by the address of the warranted agent
+    warrants: HashMap<Address, Vec<Warrant>>,
 }
 
 impl PartialEq for DhtStore {
@@ -39,6 +45,7 @@ impl PartialEq for DhtStore {
         let other_meta = &other.meta_storage.clone();
 
         self.actions == other.actions
+            && self.warrants == other.warrants
             && (*content.read().unwrap()).get_id() == (*other_content.read().unwrap()).get_id()
             && *meta.read().unwrap() == *other_meta.read().unwrap()
     }
@@ -71,6 +78,66 @@ pub fn create_get_links_eavi_query<'a>(
     ))
 }
 
+fn link_status(eavi: &EntityAttributeValueIndex) -> CrudStatus {
+    match eavi.attribute() {
+        Attribute::LinkTag(_, _) => CrudStatus::Live,
+        _ => CrudStatus::Deleted,
+    }
+}
+
+fn status_requested(status: &CrudStatus, status_request: &LinksStatusRequestKind) -> bool {
+    match status_request {
+        LinksStatusRequestKind::All => true,
+        LinksStatusRequestKind::Live => *status == CrudStatus::Live,
+        LinksStatusRequestKind::Deleted => *status == CrudStatus::Deleted,
+    }
+}
+
+fn link_tag(eavi: &EntityAttributeValueIndex) -> String {
+    match eavi.attribute() {
+        Attribute::LinkTag(_, tag) | Attribute::RemovedLink(_, tag) => tag,
+        _ => String::new(),
+    }
+}
+
+/// Links get stored with the timestamp of their LinkAdd entry's header as index,
+/// so sorting them by time doesn't need to look up any headers.
+fn sort_links(
+    mut links: Vec<(EntityAttributeValueIndex, CrudStatus)>,
+    sort: &Option<LinksSortOrder>,
+) -> Vec<(EntityAttributeValueIndex, CrudStatus)> {
+    match sort {
+        None => (),
+        Some(LinksSortOrder::Tag) => links.sort_by(|a, b| link_tag(&a.0).cmp(&link_tag(&b.0))),
+        Some(LinksSortOrder::OldestFirst) => links.sort_by_key(|link| link.0.index()),
+        Some(LinksSortOrder::LatestFirst) => links.sort_by(|a, b| b.0.index().cmp(&a.0.index())),
+    }
+    links
+}
+
+/// Skips all links up to and including the one whose LinkAdd entry address is the cursor
+/// and cuts the rest off at the limit. A cursor that isn't among the links yields an empty
+/// page, so a client paging through links that changed in between can't loop forever.
+fn page_links(
+    links: Vec<(EntityAttributeValueIndex, CrudStatus)>,
+    cursor: &Option<Address>,
+    limit: Option<usize>,
+) -> Vec<(EntityAttributeValueIndex, CrudStatus)> {
+    let start = match cursor {
+        None => 0,
+        Some(cursor) => links
+            .iter()
+            .position(|(eavi, _)| eavi.value() == *cursor)
+            .map(|position| position + 1)
+            .unwrap_or_else(|| links.len()),
+    };
+    links
+        .into_iter()
+        .skip(start)
+        .take(limit.unwrap_or(usize::max_value()))
+        .collect()
+}
+
 impl DhtStore {
     // LifeCycle
     // =========
@@ -82,27 +149,53 @@ impl DhtStore {
             content_storage,
             meta_storage,
             actions: HashMap::new(),
+            warrants: HashMap::new(),
         }
     }
     ///This algorithmn works by querying the EAVI Query for entries that match the address given, the link _type given, the tag given and a tombstone query set of RemovedLink(link_type,tag)
     ///this means no matter how many links are added after one is removed, we will always say that the link has been removed.
     ///One thing to remember is that LinkAdd entries occupy the "Value" aspect of our EAVI link stores.
     ///When that set is obtained, we filter based on the LinkTag and RemovedLink attributes to evaluate if they are "live" or "deleted". A reminder that links cannot be modified
+    ///The links are then filtered by the requested status, sorted and paginated as given in the options,
+    ///so only the requested page has to be shipped to whoever asked for the links.
     pub fn get_links(
         &self,
         address: Address,
         link_type: String,
         tag: String,
-    ) -> Result<BTreeSet<(EntityAttributeValueIndex, CrudStatus)>, HolochainError> {
+        options: &GetLinksOptions,
+    ) -> Result<Vec<(EntityAttributeValueIndex, CrudStatus)>, HolochainError> {
         let get_links_query = create_get_links_eavi_query(address, link_type, tag)?;
         let filtered = self.meta_storage.read()?.fetch_eavi(&get_links_query)?;
-        Ok(filtered
+        let links = filtered
             .into_iter()
-            .map(|s| match s.attribute() {
-                Attribute::LinkTag(_, _) => (s, CrudStatus::Live),
-                _ => (s, CrudStatus::Deleted),
+            .map(|eavi| {
+                let status = link_status(&eavi);
+                (eavi, status)
             })
-            .collect())
+            .filter(|(_, status)| status_requested(status, &options.status_request))
+            .collect::<Vec<_>>();
+        let links = sort_links(links, &options.sort);
+        Ok(page_links(links, &options.cursor, options.limit))
+    }
+
+    /// Number of the links get_links would return without sorting or paging,
+    /// counted without collecting them.
+    pub fn get_links_count(
+        &self,
+        address: Address,
+        link_type: String,
+        tag: String,
+        status_request: &LinksStatusRequestKind,
+    ) -> Result<usize, HolochainError> {
+        let get_links_query = create_get_links_eavi_query(address, link_type, tag)?;
+        Ok(self
+            .meta_storage
+            .read()?
+            .fetch_eavi(&get_links_query)?
+            .iter()
+            .filter(|eavi| status_requested(&link_status(eavi), status_request))
+            .count())
     }
 
     pub fn get_all_metas(
@@ -175,6 +268,21 @@ impl DhtStore {
         Ok(())
     }
 
+    /// Adds the warrant to the warrants of all agents it warrants
+    pub(crate) fn add_warrant(&mut self, warrant: &Warrant) {
+        for agent in warrant.warranted_agents() {
+            let warrants = self.warrants.entry(agent).or_insert_with(Vec::new);
+            if !warrants.contains(warrant) {
+                warrants.push(warrant.clone());
+            }
+        }
+    }
+
+    /// All held warrants against the given agent
+    pub fn warrants_for(&self, agent: &Address) -> Vec<Warrant> {
+        self.warrants.get(agent).cloned().unwrap_or_default()
+    }
+
     // Getters (for reducers)
     // =======
     pub(crate) fn content_storage(&self) -> Arc<RwLock<ContentAddressableStorage>> {
@@ -196,7 +304,13 @@ impl DhtStore {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use holochain_core_types::{chain_header::test_chain_header_with_sig, entry::test_entry};
+    use crate::dht::dht_inner_reducers::{reduce_add_remove_link_inner, LinkModification};
+    use holochain_core_types::{
+        chain_header::test_chain_header_with_sig,
+        entry::{test_entry, test_entry_with_value},
+        link::Link,
+        time::Iso8601,
+    };
 
     use holochain_persistence_api::{
         cas::storage::ExampleContentAddressableStorage, eav::ExampleEntityAttributeValueStorage,
@@ -218,4 +332,130 @@ pub mod tests {
         let headers = store.get_headers(entry.address()).unwrap();
         assert_eq!(headers, vec![header1, header2]);
     }
+
+    #[test]
+    fn get_links_sorted_and_paginated() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let base = test_entry();
+        let link_adds = vec![
+            (test_entry_with_value("\"first\""), "c", 1),
+            (test_entry_with_value("\"second\""), "a", 3),
+            (test_entry_with_value("\"third\""), "b", 2),
+        ];
+        store.content_storage().write().unwrap().add(&base).unwrap();
+        for (link_add, tag, secs) in link_adds.iter() {
+            let link = Link::new(&base.address(), &link_add.address(), "link-type", tag);
+            reduce_add_remove_link_inner(
+                &mut store,
+                &link,
+                &link_add.address(),
+                LinkModification::Add(Iso8601::from(*secs)),
+            )
+            .unwrap();
+        }
+        let get_links = |options: GetLinksOptions| {
+            store
+                .get_links(base.address(), "link-type".into(), "".into(), &options)
+                .unwrap()
+                .into_iter()
+                .map(|(eavi, _)| eavi.value())
+                .collect::<Vec<_>>()
+        };
+        let (first, second, third) = (
+            link_adds[0].0.address(),
+            link_adds[1].0.address(),
+            link_adds[2].0.address(),
+        );
+
+        let oldest_first = GetLinksOptions {
+            sort: Some(LinksSortOrder::OldestFirst),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_links(oldest_first.clone()),
+            vec![first.clone(), third.clone(), second.clone()]
+        );
+        let latest_first = GetLinksOptions {
+            sort: Some(LinksSortOrder::LatestFirst),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_links(latest_first),
+            vec![second.clone(), third.clone(), first.clone()]
+        );
+        let by_tag = GetLinksOptions {
+            sort: Some(LinksSortOrder::Tag),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_links(by_tag),
+            vec![second.clone(), third.clone(), first.clone()]
+        );
+
+        let first_page = GetLinksOptions {
+            limit: Some(2),
+            ..oldest_first.clone()
+        };
+        assert_eq!(get_links(first_page), vec![first, third.clone()]);
+        let second_page = GetLinksOptions {
+            limit: Some(2),
+            cursor: Some(third),
+            ..oldest_first
+        };
+        assert_eq!(get_links(second_page), vec![second]);
+    }
+
+    #[test]
+    fn get_links_count_by_status() {
+        let mut store = DhtStore::new(
+            Arc::new(RwLock::new(
+                ExampleContentAddressableStorage::new().unwrap(),
+            )),
+            Arc::new(RwLock::new(ExampleEntityAttributeValueStorage::new())),
+        );
+        let base = test_entry();
+        store.content_storage().write().unwrap().add(&base).unwrap();
+        let link_adds = vec![
+            test_entry_with_value("\"first\""),
+            test_entry_with_value("\"second\""),
+            test_entry_with_value("\"third\""),
+        ];
+        for link_add in link_adds.iter() {
+            let link = Link::new(&base.address(), &link_add.address(), "link-type", "tag");
+            reduce_add_remove_link_inner(
+                &mut store,
+                &link,
+                &link_add.address(),
+                LinkModification::Add(Iso8601::from(1)),
+            )
+            .unwrap();
+        }
+        let removed_link = Link::new(&base.address(), &link_adds[2].address(), "link-type", "tag");
+        reduce_add_remove_link_inner(
+            &mut store,
+            &removed_link,
+            &link_adds[2].address(),
+            LinkModification::Remove,
+        )
+        .unwrap();
+
+        let count = |status_request: LinksStatusRequestKind| {
+            store
+                .get_links_count(
+                    base.address(),
+                    "link-type".into(),
+                    "tag".into(),
+                    &status_request,
+                )
+                .unwrap()
+        };
+        assert_eq!(count(LinksStatusRequestKind::Live), 2);
+        assert_eq!(count(LinksStatusRequestKind::Deleted), 1);
+        assert_eq!(count(LinksStatusRequestKind::All), 3);
+    }
 }
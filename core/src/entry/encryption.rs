@@ -0,0 +1,396 @@
+//! Encryption of entries of `encrypted` entry types.
+//!
+//! Entries of those types get committed, stored and published as an [EncryptedEntry] value of
+//! the same entry type, so headers, provenances and the DHT only ever see the cipher text and
+//! its address. The value is encrypted with a random content key that is sealed for every
+//! recipient and the author. Decryption only happens where entries get handed to the zome:
+//! `get_entry`, `query` and the validation callbacks.
+use crate::context::Context;
+use holochain_core_types::{
+    agent::Base32,
+    dna::entry_types::Sharing,
+    entry::{
+        encrypted_entry::{EncryptedEntry, SealedKey},
+        entry_type::EntryType,
+        Entry,
+    },
+    error::HolochainError,
+    validation::EntryValidationData,
+};
+use holochain_dpki::keypair::generate_random_enc_keypair;
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::AddressableContent;
+use holochain_wasm_utils::api_serialization::get_entry::{GetEntryResult, GetEntryResultType};
+use lib3h_sodium::{aead, kx, secbuf::SecBuf};
+use std::{convert::TryFrom, sync::Arc};
+
+/// Returns true if the DNA defines the entry type as an app entry type with `encrypted` sharing
+pub fn is_encrypted_type(entry_type: &EntryType, context: &Arc<Context>) -> bool {
+    match entry_type {
+        EntryType::App(app_entry_type) => context
+            .get_dna()
+            .and_then(|dna| {
+                dna.get_entry_type_def(&String::from(app_entry_type.clone()))
+                    .map(|def| def.sharing == Sharing::Encrypted)
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Checks that app entries hold cipher text exactly if their entry type is encrypted.
+/// Without this, an author could give an entry of any type the shape of an [EncryptedEntry],
+/// which validators that are not recipients accept without running the app validation.
+pub fn check_encryption(entry: &Entry, context: &Arc<Context>) -> Result<(), String> {
+    let value = match entry {
+        Entry::App(_, value) => value,
+        _ => return Ok(()),
+    };
+    let is_encrypted_value = EncryptedEntry::try_from(value.clone()).is_ok();
+    match (
+        is_encrypted_type(&entry.entry_type(), context),
+        is_encrypted_value,
+    ) {
+        (true, false) => Err("Entry of an encrypted entry type is not encrypted".to_string()),
+        (false, true) => Err("Entry of a not encrypted entry type holds cipher text".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Encrypts the entry for the given recipients if it is of an encrypted entry type.
+/// The agent itself is always added as a recipient so it can read its own entries.
+/// Entries of all other types are returned unchanged.
+pub fn encrypt_entry(
+    entry: &Entry,
+    recipients: &[Base32],
+    context: &Arc<Context>,
+) -> Result<Entry, HolochainError> {
+    let (app_entry_type, value) = match entry {
+        Entry::App(app_entry_type, value) if is_encrypted_type(&entry.entry_type(), context) => {
+            (app_entry_type, value)
+        }
+        _ => return Ok(entry.clone()),
+    };
+    if EncryptedEntry::try_from(value.clone()).is_ok() {
+        return Ok(entry.clone());
+    }
+
+    let mut all_recipients = vec![context.conductor_api.encryption_key()?];
+    for recipient in recipients {
+        if !all_recipients.contains(recipient) {
+            all_recipients.push(recipient.clone());
+        }
+    }
+
+    let mut content_key = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+    content_key.randomize();
+    let mut plain = SecBuf::with_insecure(value.to_string().len());
+    plain.from_array(value.to_string().as_bytes())?;
+    let mut adata = entry_type_adata(app_entry_type.clone())?;
+    let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+    nonce.randomize();
+    let mut cipher = SecBuf::with_insecure(plain.len() + aead::ABYTES);
+    aead::enc(
+        &mut plain,
+        &mut content_key,
+        Some(&mut adata),
+        &mut nonce,
+        &mut cipher,
+    )?;
+    let mut cipher_bytes = nonce.read_lock().to_vec();
+    cipher_bytes.extend_from_slice(&cipher.read_lock());
+
+    let mut one_time_keys = generate_random_enc_keypair()?;
+    let keys = all_recipients
+        .into_iter()
+        .map(|recipient| {
+            let sealed = one_time_keys.encrypt_for(&recipient, &mut content_key)?;
+            Ok(SealedKey {
+                recipient,
+                key: base64::encode(&sealed),
+            })
+        })
+        .collect::<Result<Vec<_>, HolochainError>>()?;
+
+    let encrypted = EncryptedEntry {
+        sender: one_time_keys.public(),
+        keys,
+        cipher: base64::encode(&cipher_bytes),
+    };
+    Ok(Entry::App(app_entry_type.clone(), encrypted.into()))
+}
+
+/// Decrypts the entry if it is an encrypted app entry the agent is a recipient of.
+/// Returns None for entries the agent can't read and for entries that are not encrypted.
+pub fn decrypt_entry(
+    entry: &Entry,
+    context: &Arc<Context>,
+) -> Result<Option<Entry>, HolochainError> {
+    let (app_entry_type, encrypted) = match entry {
+        Entry::App(app_entry_type, value) => match EncryptedEntry::try_from(value.clone()) {
+            Ok(encrypted) => (app_entry_type, encrypted),
+            Err(_) => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let own_key = context.conductor_api.encryption_key()?;
+    let sealed_key = match encrypted.key_for(&own_key) {
+        Some(sealed_key) => sealed_key,
+        None => return Ok(None),
+    };
+
+    let key_bytes = context
+        .conductor_api
+        .decrypt(&encrypted.sender, &base64::decode(&sealed_key.key)?)?;
+    let mut content_key = SecBuf::with_secure(key_bytes.len());
+    content_key.from_array(&key_bytes)?;
+
+    let cipher_bytes = base64::decode(&encrypted.cipher)?;
+    if cipher_bytes.len() < aead::NONCEBYTES + aead::ABYTES {
+        return Err(HolochainError::ErrorGeneric(
+            "Cipher text of encrypted entry is too short".to_string(),
+        ));
+    }
+    let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+    nonce.from_array(&cipher_bytes[..aead::NONCEBYTES])?;
+    let mut cipher = SecBuf::with_insecure(cipher_bytes.len() - aead::NONCEBYTES);
+    cipher.from_array(&cipher_bytes[aead::NONCEBYTES..])?;
+    let mut adata = entry_type_adata(app_entry_type.clone())?;
+    let mut plain = SecBuf::with_insecure(cipher.len() - aead::ABYTES);
+    aead::dec(
+        &mut plain,
+        &mut content_key,
+        Some(&mut adata),
+        &mut nonce,
+        &mut cipher,
+    )
+    .map_err(|_| HolochainError::ErrorGeneric("Could not decrypt entry".to_string()))?;
+
+    let value = String::from_utf8(plain.read_lock().to_vec())
+        .map_err(|_| HolochainError::ErrorGeneric("Decrypted entry is not UTF-8".to_string()))?;
+    Ok(Some(Entry::App(
+        app_entry_type.clone(),
+        JsonString::from_json(&value),
+    )))
+}
+
+/// The entry as the agent can read it: decrypted if the agent is a recipient, as stored otherwise
+pub fn readable_entry(entry: Entry, context: &Arc<Context>) -> Entry {
+    match decrypt_entry(&entry, context) {
+        Ok(Some(decrypted)) => decrypted,
+        Ok(None) => entry,
+        Err(error) => {
+            context.log(format!(
+                "warn/entry/encryption: could not decrypt entry {}: {:?}",
+                entry.address(),
+                error
+            ));
+            entry
+        }
+    }
+}
+
+/// Public encrypting keys of all recipients of an encrypted entry, empty for any other entry
+pub fn recipients_of(entry: &Entry) -> Vec<Base32> {
+    match entry {
+        Entry::App(_, value) => EncryptedEntry::try_from(value.clone())
+            .map(|encrypted| encrypted.recipients())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Replaces all entries of a get result with their readable version
+pub fn readable_entry_result(mut result: GetEntryResult, context: &Arc<Context>) -> GetEntryResult {
+    match result.result {
+        GetEntryResultType::Single(ref mut item) => {
+            item.entry = item
+                .entry
+                .take()
+                .map(|entry| readable_entry(entry, context));
+        }
+        GetEntryResultType::All(ref mut history) => {
+            for item in history.items.iter_mut() {
+                item.entry = item
+                    .entry
+                    .take()
+                    .map(|entry| readable_entry(entry, context));
+            }
+        }
+    }
+    result
+}
+
+/// Replaces the entries handed to a validation callback with their readable version,
+/// so recipients validate the plain entry and all other validators the cipher text
+pub fn readable_validation_data(
+    validation_data: EntryValidationData<Entry>,
+    context: &Arc<Context>,
+) -> EntryValidationData<Entry> {
+    match validation_data {
+        EntryValidationData::Create {
+            entry,
+            validation_data,
+        } => EntryValidationData::Create {
+            entry: readable_entry(entry, context),
+            validation_data,
+        },
+        EntryValidationData::Modify {
+            new_entry,
+            old_entry,
+            old_entry_header,
+            validation_data,
+        } => EntryValidationData::Modify {
+            new_entry: readable_entry(new_entry, context),
+            old_entry: readable_entry(old_entry, context),
+            old_entry_header,
+            validation_data,
+        },
+        EntryValidationData::Delete {
+            old_entry,
+            old_entry_header,
+            validation_data,
+        } => EntryValidationData::Delete {
+            old_entry: readable_entry(old_entry, context),
+            old_entry_header,
+            validation_data,
+        },
+    }
+}
+
+/// The entry type name is authenticated with the cipher text so the value can't be
+/// moved to an entry of another type.
+fn entry_type_adata(app_entry_type: impl Into<String>) -> Result<SecBuf, HolochainError> {
+    let name: String = app_entry_type.into();
+    let mut adata = SecBuf::with_insecure(name.len());
+    adata.from_array(name.as_bytes())?;
+    Ok(adata)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::{test_context, test_instance_and_context_by_name},
+        network::{actions::get_entry::get_entry, test_utils::test_wat_always_valid},
+        nucleus::validation::{validate_entry, ValidationError},
+        workflows::author_entry::author_entry,
+    };
+    use holochain_core_types::{
+        chain_header::test_chain_header,
+        dna::Dna,
+        entry::{entry_type::test_app_entry_type, test_entry},
+        validation::{EntryLifecycle, ValidationData, ValidationPackage},
+    };
+    use test_utils::create_test_dna_with_wat;
+
+    /// test DNA with an always valid zome, optionally with an encrypted `testEntryType`
+    fn test_dna(uuid: &str, encrypted: bool) -> Dna {
+        let mut dna = create_test_dna_with_wat("test_zome", Some(&test_wat_always_valid()));
+        dna.uuid = uuid.to_string();
+        if encrypted {
+            let zome = dna.zomes.get_mut("test_zome").unwrap();
+            let entry_type_def = zome
+                .entry_types
+                .get_mut(&EntryType::App(test_app_entry_type()))
+                .unwrap();
+            entry_type_def.sharing = Sharing::Encrypted;
+        }
+        dna
+    }
+
+    #[test]
+    fn cipher_text_of_a_not_encrypted_type_fails_validation() {
+        let dna = test_dna("cipher_text_of_a_not_encrypted_type", false);
+        let (_instance, context) = test_instance_and_context_by_name(dna, "alice", None).unwrap();
+        let cipher_text = EncryptedEntry {
+            sender: "HcKsender".to_string(),
+            keys: vec![SealedKey {
+                recipient: "HcKsender".to_string(),
+                key: "a2V5".to_string(),
+            }],
+            cipher: "Y2lwaGVy".to_string(),
+        };
+        let entry = Entry::App(test_app_entry_type(), cipher_text.into());
+
+        assert!(check_encryption(&entry, &context).is_err());
+        let validation_data = ValidationData {
+            package: ValidationPackage::only_header(test_chain_header()),
+            lifecycle: EntryLifecycle::Chain,
+        };
+        let result = context.block_on(validate_entry(entry, None, validation_data, &context));
+        match result {
+            Err(ValidationError::Fail(_)) => (),
+            other => panic!("expected validation to fail, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn plain_text_of_an_encrypted_type_fails_validation() {
+        let dna = test_dna("plain_text_of_an_encrypted_type", true);
+        let (_instance, context) = test_instance_and_context_by_name(dna, "alice", None).unwrap();
+        let entry = Entry::App(
+            test_app_entry_type(),
+            JsonString::from_json("{\"content\":\"secret\"}"),
+        );
+        assert!(check_encryption(&entry, &context).is_err());
+        let encrypted = encrypt_entry(&entry, &[], &context).unwrap();
+        assert!(check_encryption(&encrypted, &context).is_ok());
+    }
+
+    #[test]
+    fn encrypted_entries_round_trip_to_their_recipients() {
+        let netname = Some("encrypted_entries_round_trip");
+        let dna = test_dna(netname.unwrap(), true);
+        let (_alice_instance, alice) =
+            test_instance_and_context_by_name(dna.clone(), "alice", netname).unwrap();
+        let (_bob_instance, bob) =
+            test_instance_and_context_by_name(dna.clone(), "bob", netname).unwrap();
+        let (_eve_instance, eve) = test_instance_and_context_by_name(dna, "eve", netname).unwrap();
+
+        let plain = Entry::App(
+            test_app_entry_type(),
+            JsonString::from_json("{\"content\":\"secret\"}"),
+        );
+        let bob_key = bob.conductor_api.encryption_key().unwrap();
+        let encrypted = encrypt_entry(&plain, &[bob_key], &alice).unwrap();
+        assert_ne!(encrypted, plain);
+        alice
+            .block_on(author_entry(&encrypted, None, &alice, &vec![]))
+            .unwrap();
+
+        // publish returns before the entry is held, so bob polls for it
+        let mut maybe_entry = None;
+        for _ in 0..50 {
+            maybe_entry = bob
+                .block_on(get_entry(
+                    bob.clone(),
+                    encrypted.address(),
+                    Default::default(),
+                ))
+                .unwrap()
+                .map(|entry_with_meta| entry_with_meta.entry_with_meta.entry);
+            if maybe_entry.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        let fetched = maybe_entry.expect("bob should get the published entry");
+        assert_eq!(fetched, encrypted);
+
+        assert_eq!(readable_entry(fetched.clone(), &bob), plain);
+        assert_eq!(readable_entry(fetched.clone(), &alice), plain);
+        assert_eq!(decrypt_entry(&fetched, &eve).unwrap(), None);
+        assert_eq!(readable_entry(fetched, &eve), encrypted);
+    }
+
+    #[test]
+    fn entries_of_other_types_are_not_encrypted() {
+        let context = test_context("alice", None);
+        let entry = test_entry();
+        assert_eq!(encrypt_entry(&entry, &[], &context).unwrap(), entry);
+        assert_eq!(decrypt_entry(&entry, &context).unwrap(), None);
+        assert_eq!(readable_entry(entry.clone(), &context), entry);
+        assert!(recipients_of(&entry).is_empty());
+    }
+}
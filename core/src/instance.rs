@@ -1,6 +1,13 @@
 use crate::{
-    action::ActionWrapper, consistency::ConsistencyModel, context::Context, persister::Persister,
-    scheduled_jobs, signal::Signal, state::State, workflows::application,
+    action::ActionWrapper,
+    consistency::ConsistencyModel,
+    context::Context,
+    dht::dht_reducers::held_addresses,
+    persister::Persister,
+    scheduled_jobs::{self, pending_validations::PendingValidationsScheduler},
+    signal::Signal,
+    state::State,
+    workflows::application,
 };
 #[cfg(test)]
 use crate::{
@@ -34,6 +41,7 @@ pub struct Instance {
     action_channel: Option<SyncSender<ActionWrapper>>,
     observer_channel: Option<SyncSender<Observer>>,
     scheduler_handle: Option<Arc<ScheduleHandle>>,
+    pending_validations: Option<Arc<Mutex<PendingValidationsScheduler>>>,
     persister: Option<Arc<Mutex<Persister>>>,
     consistency_model: ConsistencyModel,
 }
@@ -53,10 +61,17 @@ impl Instance {
     pub(in crate::instance) fn inner_setup(&mut self, context: Arc<Context>) -> Arc<Context> {
         let (rx_action, rx_observer) = self.initialize_channels();
         let context = self.initialize_context(context);
+        let pending_validations = Arc::new(Mutex::new(PendingValidationsScheduler::new(
+            context.clone(),
+        )));
+        self.pending_validations = Some(pending_validations.clone());
         let mut scheduler = Scheduler::new();
         scheduler
             .every(10.seconds())
-            .run(scheduled_jobs::create_callback(context.clone()));
+            .run(scheduled_jobs::create_callback(
+                context.clone(),
+                pending_validations,
+            ));
         self.scheduler_handle = Some(Arc::new(
             scheduler.watch_thread(Duration::from_millis(1000)),
         ));
@@ -168,26 +183,37 @@ impl Instance {
         thread::spawn(move || {
             let mut state_observers: Vec<Observer> = Vec::new();
             for action_wrapper in rx_action {
-                state_observers = sync_self.process_action(
+                state_observers = match sync_self.process_action(
                     &action_wrapper,
                     state_observers,
                     &rx_observer,
                     &sub_context,
-                );
+                ) {
+                    Ok(state_observers) => state_observers,
+                    Err(e) => {
+                        sub_context.log(format!(
+                            "err/instance: stopping action loop since the state can not be persisted: {:?}",
+                            e
+                        ));
+                        break;
+                    }
+                };
                 sync_self.emit_signals(&sub_context, &action_wrapper);
             }
         });
     }
 
     /// Calls the reducers for an action and calls the observers with the new state
-    /// returns the new vector of observers
+    /// returns the new vector of observers.
+    /// Fails if the action could not be saved with a persister that needs every action,
+    /// since the persisted state would diverge from the state from then on.
     pub(crate) fn process_action(
         &self,
         action_wrapper: &ActionWrapper,
         mut state_observers: Vec<Observer>,
         rx_observer: &Receiver<Observer>,
         context: &Arc<Context>,
-    ) -> Vec<Observer> {
+    ) -> HcResult<Vec<Observer>> {
         // Mutate state
         {
             let new_state: State;
@@ -213,11 +239,22 @@ impl Instance {
             *state = new_state;
         }
 
-        if let Err(e) = self.save() {
+        // Don't wait for the next scheduled run with validations whose dependencies just arrived
+        if let Some(pending_validations) = &self.pending_validations {
+            let held = held_addresses(action_wrapper.action());
+            if !held.is_empty() {
+                pending_validations.lock().unwrap().wake_up(&held);
+            }
+        }
+
+        if let Err(e) = self.save_action(action_wrapper) {
             context.log(format!(
                 "err/instance/process_action: could not save state: {:?}",
                 e
             ));
+            if self.persister_needs_every_action() {
+                return Err(e);
+            }
         } else {
             context.log(format!(
                 "trace/reduce/process_actions: reducing {:?}",
@@ -228,10 +265,10 @@ impl Instance {
         // Add new observers
         state_observers.extend(rx_observer.try_iter());
         // Tick all observers and remove those that have lost their receiving part
-        state_observers
+        Ok(state_observers
             .into_iter()
             .filter(|observer| observer.ticker.send(()).is_ok())
-            .collect()
+            .collect())
     }
 
     pub(crate) fn emit_signals(&mut self, context: &Context, action_wrapper: &ActionWrapper) {
@@ -266,6 +303,7 @@ impl Instance {
             action_channel: None,
             observer_channel: None,
             scheduler_handle: None,
+            pending_validations: None,
             persister: None,
             consistency_model: ConsistencyModel::new(context.clone()),
         }
@@ -277,6 +315,7 @@ impl Instance {
             action_channel: None,
             observer_channel: None,
             scheduler_handle: None,
+            pending_validations: None,
             persister: None,
             consistency_model: ConsistencyModel::new(context.clone()),
         }
@@ -288,6 +327,30 @@ impl Instance {
             .expect("owners of the state RwLock shouldn't panic")
     }
 
+    /// Hands the action that was just reduced to the persister together with the new state.
+    /// Waits for the persister if it is busy, so no action gets skipped.
+    pub fn save_action(&self, action_wrapper: &ActionWrapper) -> HcResult<()> {
+        self.persister
+            .as_ref()
+            .ok_or(HolochainError::new(
+                "Instance::save_action() called without persister set.",
+            ))?
+            .lock()?
+            .save_action(action_wrapper, &self.state())
+    }
+
+    fn persister_needs_every_action(&self) -> bool {
+        self.persister
+            .as_ref()
+            .map(|persister| {
+                persister
+                    .lock()
+                    .map(|persister| persister.needs_every_action())
+                    .unwrap_or(true)
+            })
+            .unwrap_or(false)
+    }
+
     pub fn save(&self) -> HcResult<()> {
         self.persister
             .as_ref()
@@ -486,7 +549,7 @@ pub mod tests {
         let agent_state = AgentState::new_with_top_chain_header(
             chain_store,
             Some(chain_header),
-            context.agent_id.address(),
+            context.agent_id().address(),
         );
         let state = State::new_with_agent(Arc::new(context.clone()), agent_state);
         let global_state = Arc::new(RwLock::new(state));
@@ -609,12 +672,14 @@ pub mod tests {
         let (rx_action, rx_observer) = instance.initialize_channels();
 
         let action_wrapper = test_action_wrapper_commit();
-        let new_observers = instance.process_action(
-            &action_wrapper,
-            Vec::new(), // start with no observers
-            &rx_observer,
-            &context,
-        );
+        let new_observers = instance
+            .process_action(
+                &action_wrapper,
+                Vec::new(), // start with no observers
+                &rx_observer,
+                &context,
+            )
+            .unwrap();
 
         // test that the get action added no observers or actions
         assert!(new_observers.is_empty());
@@ -761,7 +826,9 @@ pub mod tests {
         let context = instance.initialize_context(context);
         let state_observers: Vec<Observer> = Vec::new();
         let (_, rx_observer) = channel::<Observer>();
-        instance.process_action(&commit_action, state_observers, &rx_observer, &context);
+        instance
+            .process_action(&commit_action, state_observers, &rx_observer, &context)
+            .unwrap();
 
         // Check if AgentIdEntry is found
         assert_eq!(1, instance.state().history.iter().count());
@@ -785,7 +852,7 @@ pub mod tests {
         let netname = Some("can_commit_agent");
         // Create Context, Agent and Commit AgentIdEntry Action
         let context = test_context("alex", netname);
-        let agent_entry = Entry::AgentId(context.agent_id.clone());
+        let agent_entry = Entry::AgentId(context.agent_id());
         let commit_agent_action =
             ActionWrapper::new(Action::Commit((agent_entry.clone(), None, vec![])));
 
@@ -794,12 +861,14 @@ pub mod tests {
         let state_observers: Vec<Observer> = Vec::new();
         let (_, rx_observer) = channel::<Observer>();
         let context = instance.initialize_context(context);
-        instance.process_action(
-            &commit_agent_action,
-            state_observers,
-            &rx_observer,
-            &context,
-        );
+        instance
+            .process_action(
+                &commit_agent_action,
+                state_observers,
+                &rx_observer,
+                &context,
+            )
+            .unwrap();
 
         // Check if AgentIdEntry is found
         assert_eq!(1, instance.state().history.iter().count());
@@ -816,4 +885,92 @@ pub mod tests {
                 _ => false,
             });
     }
+
+    /// Persister that needs every action like a journal and records the actions it gets
+    struct RecordingPersister {
+        actions: Vec<ActionWrapper>,
+        fail: bool,
+    }
+
+    impl Persister for RecordingPersister {
+        fn save(&mut self, _state: &State) -> HcResult<()> {
+            Ok(())
+        }
+
+        fn load(&self, _context: Arc<Context>) -> HcResult<Option<State>> {
+            Ok(None)
+        }
+
+        fn save_action(&mut self, action_wrapper: &ActionWrapper, _state: &State) -> HcResult<()> {
+            if self.fail {
+                return Err(HolochainError::new("Could not append to journal"));
+            }
+            self.actions.push(action_wrapper.clone());
+            Ok(())
+        }
+
+        fn needs_every_action(&self) -> bool {
+            true
+        }
+    }
+
+    fn instance_with_recording_persister(
+        netname: Option<&str>,
+        fail: bool,
+    ) -> (Instance, Arc<Context>, Arc<Mutex<RecordingPersister>>) {
+        let context = test_context("jason", netname);
+        let mut instance = Instance::new(context.clone());
+        let persister = Arc::new(Mutex::new(RecordingPersister {
+            actions: Vec::new(),
+            fail,
+        }));
+        instance.persister = Some(persister.clone());
+        let context = instance.initialize_context(context);
+        (instance, context, persister)
+    }
+
+    #[test]
+    fn process_action_waits_for_busy_persister() {
+        let (instance, context, persister) =
+            instance_with_recording_persister(Some("process_action_waits_for_busy_persister"), false);
+
+        let (locked_tx, locked_rx) = channel();
+        let busy_persister = persister.clone();
+        let busy = thread::spawn(move || {
+            let _guard = busy_persister.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            sleep(Duration::from_millis(100));
+        });
+        locked_rx.recv().unwrap();
+
+        let action_wrapper = test_action_wrapper_commit();
+        let (_, rx_observer) = channel::<Observer>();
+        instance
+            .process_action(&action_wrapper, Vec::new(), &rx_observer, &context)
+            .unwrap();
+        busy.join().unwrap();
+
+        assert_eq!(persister.lock().unwrap().actions, vec![action_wrapper]);
+    }
+
+    #[test]
+    fn process_action_fails_if_action_can_not_be_saved() {
+        let (instance, context, _) = instance_with_recording_persister(
+            Some("process_action_fails_if_action_can_not_be_saved"),
+            true,
+        );
+
+        let (_, rx_observer) = channel::<Observer>();
+        let result = instance.process_action(
+            &test_action_wrapper_commit(),
+            Vec::new(),
+            &rx_observer,
+            &context,
+        );
+
+        assert_eq!(
+            result.err(),
+            Some(HolochainError::new("Could not append to journal"))
+        );
+    }
 }
@@ -62,7 +62,9 @@ pub mod tests {
         let state_observers: Vec<Observer> = Vec::new();
         let (_, rx_observer) = channel::<Observer>();
         let context = instance.initialize_context(context);
-        instance.process_action(&commit_action, state_observers, &rx_observer, &context);
+        instance
+            .process_action(&commit_action, state_observers, &rx_observer, &context)
+            .unwrap();
         // Check if LinkEntry is found
         assert_eq!(1, instance.state().history.iter().count());
         instance
@@ -98,7 +100,9 @@ pub mod tests {
         let state_observers: Vec<Observer> = Vec::new();
         let (_, rx_observer) = channel::<Observer>();
         let context = instance.initialize_context(context);
-        instance.process_action(&commit_action, state_observers, &rx_observer, &context);
+        instance
+            .process_action(&commit_action, state_observers, &rx_observer, &context)
+            .unwrap();
         // Check if LinkEntry is found
         assert_eq!(1, instance.state().history.iter().count());
         instance
@@ -52,6 +52,12 @@ pub struct SendResponseFuture {
     id: String,
 }
 
+impl SendResponseFuture {
+    pub fn new(context: Arc<Context>, id: String) -> Self {
+        SendResponseFuture { context, id }
+    }
+}
+
 impl Future for SendResponseFuture {
     type Output = Result<String, HolochainError>;
 
@@ -7,20 +7,22 @@ use futures::{
     future::Future,
     task::{LocalWaker, Poll},
 };
-use holochain_core_types::{crud_status::CrudStatus, error::HcResult, time::Timeout};
+use holochain_core_types::{crud_status::CrudStatus, error::HcResult};
 use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::get_links::GetLinksOptions;
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc, thread};
 
 /// GetLinks Action Creator
 /// This is the network version of get_links that makes the network module start
 /// a look-up process.
+/// Sort order, limit and cursor of the options are applied by the peers answering.
 pub async fn get_links(
     context: Arc<Context>,
     address: Address,
     link_type: String,
     tag: String,
-    timeout: Timeout,
+    options: GetLinksOptions,
 ) -> HcResult<Vec<(Address, CrudStatus)>> {
     let key = GetLinksKey {
         base_address: address.clone(),
@@ -28,13 +30,13 @@ pub async fn get_links(
         tag: tag.clone(),
         id: ProcessUniqueId::new().to_string(),
     };
-    let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
+    let action_wrapper = ActionWrapper::new(Action::GetLinks((key.clone(), options.clone())));
     dispatch_action(context.action_channel(), action_wrapper.clone());
 
     let key_inner = key.clone();
     let context_inner = context.clone();
     let _ = thread::spawn(move || {
-        thread::sleep(timeout.into());
+        thread::sleep(options.timeout.into());
         let action_wrapper = ActionWrapper::new(Action::GetLinksTimeout(key_inner));
         dispatch_action(context_inner.action_channel(), action_wrapper.clone());
     });
@@ -0,0 +1,152 @@
+use crate::{
+    action::{Action, ActionWrapper, GetLinksKey},
+    context::Context,
+    instance::dispatch_action,
+};
+use futures::{
+    future::Future,
+    task::{LocalWaker, Poll},
+};
+use holochain_core_types::{error::HcResult, time::Timeout};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::get_links::LinksStatusRequestKind;
+use snowflake::ProcessUniqueId;
+use std::{pin::Pin, sync::Arc, thread};
+
+/// GetLinksCount Action Creator
+/// This is the network version of get_links_count that makes the network module start
+/// a look-up process. The peer holding the links counts them, so no links get sent.
+pub async fn get_links_count(
+    context: Arc<Context>,
+    address: Address,
+    link_type: String,
+    tag: String,
+    status_request: LinksStatusRequestKind,
+    timeout: Timeout,
+) -> HcResult<usize> {
+    let key = GetLinksKey {
+        base_address: address.clone(),
+        link_type: link_type.clone(),
+        tag: tag.clone(),
+        id: ProcessUniqueId::new().to_string(),
+    };
+    let action_wrapper = ActionWrapper::new(Action::GetLinksCount((key.clone(), status_request)));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+
+    let key_inner = key.clone();
+    let context_inner = context.clone();
+    let _ = thread::spawn(move || {
+        thread::sleep(timeout.into());
+        let action_wrapper = ActionWrapper::new(Action::GetLinksCountTimeout(key_inner));
+        dispatch_action(context_inner.action_channel(), action_wrapper.clone());
+    });
+
+    await!(GetLinksCountFuture {
+        context: context.clone(),
+        key
+    })
+}
+
+/// GetLinksCountFuture resolves to a HcResult<usize>.
+/// Tracks the state of the network module and clears the result from it once resolved.
+pub struct GetLinksCountFuture {
+    context: Arc<Context>,
+    key: GetLinksKey,
+}
+
+impl Future for GetLinksCountFuture {
+    type Output = HcResult<usize>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let state = self.context.state().unwrap().network();
+        let result = match state.get_links_count_results.get(&self.key) {
+            Some(Some(result)) => result.clone(),
+            _ => {
+                if let Err(error) = state.initialized() {
+                    return Poll::Ready(Err(error));
+                }
+                //
+                // TODO: connect the waker to state updates for performance reasons
+                // See: https://github.com/holochain/holochain-rust/issues/314
+                //
+                lw.wake();
+                return Poll::Pending;
+            }
+        };
+        let action_wrapper = ActionWrapper::new(Action::ClearGetLinksCountResult(self.key.clone()));
+        dispatch_action(self.context.action_channel(), action_wrapper);
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instance::tests::test_context_with_channels, state::State};
+    use holochain_core_types::entry::test_entry;
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use std::sync::{mpsc::sync_channel, RwLock};
+
+    #[test]
+    fn get_links_count_future_resolves_to_count_and_clears_result() {
+        let (action_sender, action_receiver) = sync_channel(10);
+        let (observer_sender, _observer_receiver) = sync_channel(10);
+        let mut context =
+            test_context_with_channels("alice", &action_sender, &observer_sender, None);
+        let state = State::new(Arc::new((*context).clone()));
+        let store = Arc::new(RwLock::new(state));
+        Arc::get_mut(&mut context).unwrap().set_state(store.clone());
+        let reduce = |action_wrapper: ActionWrapper| {
+            let mut state = store.write().unwrap();
+            *state = state.reduce(action_wrapper);
+        };
+
+        let key = GetLinksKey {
+            base_address: test_entry().address(),
+            link_type: "test-link".to_string(),
+            tag: "link-tag".to_string(),
+            id: ProcessUniqueId::new().to_string(),
+        };
+        // Without a network the process ends right away with an error,
+        // but a response still replaces it since the key is known
+        reduce(ActionWrapper::new(Action::GetLinksCount((
+            key.clone(),
+            LinksStatusRequestKind::Live,
+        ))));
+        reduce(ActionWrapper::new(Action::HandleGetLinksCountResult((
+            2,
+            key.clone(),
+        ))));
+
+        let result = context.block_on(GetLinksCountFuture {
+            context: context.clone(),
+            key: key.clone(),
+        });
+        assert_eq!(result, Ok(2));
+
+        let clear = action_receiver.try_recv().unwrap();
+        assert_eq!(
+            clear.action(),
+            &Action::ClearGetLinksCountResult(key.clone())
+        );
+        reduce(clear);
+        assert!(!store
+            .read()
+            .unwrap()
+            .network()
+            .get_links_count_results
+            .contains_key(&key));
+
+        // Late responses of other peers don't bring the result back
+        reduce(ActionWrapper::new(Action::HandleGetLinksCountResult((
+            3,
+            key.clone(),
+        ))));
+        assert!(!store
+            .read()
+            .unwrap()
+            .network()
+            .get_links_count_results
+            .contains_key(&key));
+    }
+}
@@ -1,9 +1,12 @@
 pub mod custom_send;
 pub mod get_entry;
 pub mod get_links;
+pub mod get_links_count;
 pub mod get_validation_package;
 pub mod initialize_network;
 pub mod publish;
+pub mod publish_warrant;
+pub mod request_countersignature;
 
 use holochain_core_types::error::HcResult;
 use holochain_persistence_api::cas::content::Address;
@@ -11,7 +14,9 @@ use holochain_persistence_api::cas::content::Address;
 #[derive(Clone, Debug)]
 pub enum ActionResponse {
     Publish(HcResult<Address>),
+    PublishWarrant(HcResult<Address>),
     RespondGet(HcResult<()>),
     RespondFetch(HcResult<()>),
     RespondGetLinks(HcResult<()>),
+    RespondGetLinksCount(HcResult<()>),
 }
@@ -0,0 +1,56 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+    network::{actions::ActionResponse, warrant::Warrant},
+};
+use futures::{
+    future::Future,
+    task::{LocalWaker, Poll},
+};
+use holochain_core_types::error::HcResult;
+use holochain_persistence_api::cas::content::Address;
+use std::{pin::Pin, sync::Arc};
+
+/// PublishWarrant Action Creator
+/// Publishes the warrant to the neighbourhoods of the warranted agents.
+///
+/// Returns a future that resolves to the address of the warrant.
+pub async fn publish_warrant(warrant: Warrant, context: &Arc<Context>) -> HcResult<Address> {
+    let action_wrapper = ActionWrapper::new(Action::PublishWarrant(warrant));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    await!(PublishWarrantFuture {
+        context: context.clone(),
+        action: action_wrapper,
+    })
+}
+
+/// PublishWarrantFuture resolves to ActionResponse
+/// Tracks the state for a response to its ActionWrapper
+pub struct PublishWarrantFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+}
+
+impl Future for PublishWarrantFuture {
+    type Output = HcResult<Address>;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<Self::Output> {
+        let state = self.context.state().unwrap().network();
+        if let Err(error) = state.initialized() {
+            return Poll::Ready(Err(error));
+        }
+        //
+        // TODO: connect the waker to state updates for performance reasons
+        // See: https://github.com/holochain/holochain-rust/issues/314
+        //
+        lw.wake();
+        match state.actions().get(&self.action) {
+            Some(ActionResponse::PublishWarrant(result)) => match result {
+                Ok(address) => Poll::Ready(Ok(address.to_owned())),
+                Err(error) => Poll::Ready(Err(error.clone())),
+            },
+            _ => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    action::{Action, ActionWrapper, DirectMessageData},
+    context::Context,
+    instance::dispatch_action,
+    network::{
+        actions::custom_send::SendResponseFuture,
+        direct_message::{CountersignatureRequest, CountersignedEntry, DirectMessage},
+    },
+};
+use holochain_core_types::{error::HolochainError, signature::Provenance, time::Timeout};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::commit_entry::CommitEntryResult;
+use std::{convert::TryFrom, sync::Arc, thread};
+use uuid::Uuid;
+
+/// SendDirectMessage Action Creator for countersignature requests.
+/// Asks the given agent to countersign the entry of the request and waits for its
+/// signature. The response gets stored with the replies of custom direct messages,
+/// so it is subject to the same timeout handling.
+pub async fn request_countersignature(
+    to_agent: Address,
+    request: CountersignatureRequest,
+    timeout: Timeout,
+    context: Arc<Context>,
+) -> Result<Provenance, HolochainError> {
+    let response = await!(send_and_wait(
+        to_agent,
+        DirectMessage::RequestCountersignature(request),
+        timeout,
+        context
+    ))?;
+    Ok(Provenance::try_from(JsonString::from_json(&response))?)
+}
+
+/// SendDirectMessage Action Creator for countersigned entries we committed.
+/// Asks the countersigning agent to commit the entry as well and waits for the address
+/// it committed.
+pub async fn send_countersigned_entry(
+    to_agent: Address,
+    countersigned_entry: CountersignedEntry,
+    timeout: Timeout,
+    context: Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let response = await!(send_and_wait(
+        to_agent,
+        DirectMessage::CommitCountersignedEntry(countersigned_entry),
+        timeout,
+        context
+    ))?;
+    Ok(CommitEntryResult::try_from(JsonString::from_json(&response))?.address())
+}
+
+async fn send_and_wait(
+    to_agent: Address,
+    message: DirectMessage,
+    timeout: Timeout,
+    context: Arc<Context>,
+) -> Result<String, HolochainError> {
+    let id = Uuid::new_v4().to_string();
+    let direct_message_data = DirectMessageData {
+        address: to_agent,
+        message,
+        msg_id: id.clone(),
+        is_response: false,
+    };
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage(direct_message_data));
+    dispatch_action(context.action_channel(), action_wrapper);
+    let context_inner = context.clone();
+    let id_inner = id.clone();
+    let _ = thread::spawn(move || {
+        thread::sleep(timeout.into());
+        let action_wrapper = ActionWrapper::new(Action::SendDirectMessageTimeout(id_inner));
+        dispatch_action(context_inner.action_channel(), action_wrapper.clone());
+    });
+
+    await!(SendResponseFuture::new(context.clone(), id))
+}
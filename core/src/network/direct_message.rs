@@ -2,7 +2,7 @@ use holochain_persistence_api::cas::content::Address;
 
 use holochain_json_api::{error::JsonError, json::JsonString};
 
-use holochain_core_types::validation::ValidationPackage;
+use holochain_core_types::{entry::Entry, signature::Provenance, validation::ValidationPackage};
 
 /// This is direct message that got created by the zome code through hdk::send().
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -16,6 +16,29 @@ pub struct CustomDirectMessage {
     pub payload: Result<String, String>,
 }
 
+/// This is a request to countersign an entry that got created through
+/// hdk::commit_countersigned_entry().
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
+pub struct CountersignatureRequest {
+    /// The entry both agents commit
+    pub entry: Entry,
+
+    /// The signature of the countersigned payload by the agent that proposes the entry,
+    /// which names both agents (see ChainHeader::countersigned_payload())
+    pub provenance: Provenance,
+}
+
+/// A countersigned entry that the proposing agent committed, sent to the counterparty
+/// so it commits the entry as well.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
+pub struct CountersignedEntry {
+    /// The entry both agents commit
+    pub entry: Entry,
+
+    /// The signatures of both agents, as found in the header of the proposing agent
+    pub provenances: Vec<Provenance>,
+}
+
 /// These are the different kinds of (low-level, i.e. non-app)
 /// node-to-node messages that can be send between Holochain nodes.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -34,4 +57,23 @@ pub enum DirectMessage {
     /// Option<> since there has to be a way to respond saying
     /// "I can't"
     ValidationPackage(Option<ValidationPackage>),
+
+    /// This message is used to ask another node to countersign an entry.
+    /// If the app accepts the entry and it is valid, the receiving node answers with
+    /// a Countersignature message but does not commit the entry yet.
+    RequestCountersignature(CountersignatureRequest),
+
+    /// With this message the countersigning node is responding to a
+    /// RequestCountersignature message with its signature of the countersigned payload
+    /// or the reason it did not countersign.
+    Countersignature(Result<Provenance, String>),
+
+    /// This message is sent by the proposing node once it committed the countersigned entry,
+    /// so the countersigning node commits it as well.
+    CommitCountersignedEntry(CountersignedEntry),
+
+    /// With this message the countersigning node is responding to a
+    /// CommitCountersignedEntry message with the address of the committed entry
+    /// or the reason it did not commit it.
+    CountersignedEntryCommitted(Result<Address, String>),
 }
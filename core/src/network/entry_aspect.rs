@@ -1,3 +1,4 @@
+use crate::network::warrant::Warrant;
 use chrono::{offset::FixedOffset, DateTime};
 use holochain_core_types::{chain_header::ChainHeader, entry::Entry, link::link_data::LinkData};
 use holochain_json_api::{error::JsonError, json::JsonString};
@@ -69,6 +70,12 @@ pub enum EntryAspect {
     // `Entry::Deletion(address)`.
     // Deletion(Address, ChainHeader),
     Deletion(ChainHeader),
+
+    // Meta item of an agent's address stating that
+    // data the agent authored is invalid.
+    // Gets published to the neighbourhood of the agent
+    // by the node that found the invalid data.
+    Warrant(Warrant),
 }
 
 impl EntryAspect {
@@ -80,6 +87,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, _) => String::from("link_remove"),
             EntryAspect::Update(_, _) => String::from("update"),
             EntryAspect::Deletion(_) => String::from("deletion"),
+            EntryAspect::Warrant(_) => String::from("warrant"),
         }
     }
     pub fn header(&self) -> ChainHeader {
@@ -90,6 +98,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, header) => header.clone(),
             EntryAspect::Update(_, header) => header.clone(),
             EntryAspect::Deletion(header) => header.clone(),
+            EntryAspect::Warrant(warrant) => warrant.entry_with_header.header.clone(),
         }
     }
 }
@@ -155,6 +164,13 @@ impl fmt::Debug for EntryAspect {
             EntryAspect::Deletion(header) => {
                 write!(f, "EntryAspect::Deletion({})", format_header(header))
             }
+            EntryAspect::Warrant(warrant) => write!(
+                f,
+                "EntryAspect::Warrant({} by {}, {})",
+                warrant.workflow,
+                warrant.warrantor.source(),
+                format_header(&warrant.entry_with_header.header)
+            ),
         }
     }
 }
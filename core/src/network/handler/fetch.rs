@@ -42,6 +42,17 @@ pub fn handle_fetch_entry(get_dht_data: FetchEntryData, context: Arc<Context>) {
         }
     }
 
+    // Warrants are held for agent addresses, whether we hold the agent's entry or not
+    aspects.extend(
+        context
+            .state()
+            .expect("Could not get state for handle_fetch_entry")
+            .dht()
+            .warrants_for(&address)
+            .into_iter()
+            .map(EntryAspect::Warrant),
+    );
+
     let action_wrapper = ActionWrapper::new(Action::RespondFetch((get_dht_data, aspects)));
     dispatch_action(context.action_channel(), action_wrapper.clone());
 }
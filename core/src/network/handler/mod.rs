@@ -28,7 +28,7 @@ fn is_my_dna(my_dna_address: &String, dna_address: &String) -> bool {
 // FIXME: Temporary hack to ignore messages incorrectly sent to us by the networking
 // module that aren't really meant for us
 fn is_my_id(context: &Arc<Context>, agent_id: &str) -> bool {
-    if agent_id != "" && context.agent_id.pub_sign_key != agent_id {
+    if agent_id != "" && context.agent_id().pub_sign_key != agent_id {
         context.log("debug/net/handle: ignoring, same id");
         return false;
     }
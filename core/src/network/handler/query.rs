@@ -10,25 +10,43 @@ use holochain_core_types::{crud_status::CrudStatus, entry::EntryWithMetaAndHeade
 use holochain_json_api::json::JsonString;
 use holochain_net::connection::json_protocol::{QueryEntryData, QueryEntryResultData};
 use holochain_persistence_api::cas::content::Address;
-use std::{collections::BTreeSet, convert::TryInto, sync::Arc};
+use holochain_wasm_utils::api_serialization::get_links::{GetLinksOptions, LinksStatusRequestKind};
+use std::{convert::TryInto, sync::Arc};
 
 fn get_links(
     context: &Arc<Context>,
     base: Address,
     link_type: String,
     tag: String,
+    options: &GetLinksOptions,
 ) -> Vec<(Address, CrudStatus)> {
     context
         .state()
         .unwrap()
         .dht()
-        .get_links(base, link_type, tag)
-        .unwrap_or(BTreeSet::new())
+        .get_links(base, link_type, tag, options)
+        .unwrap_or_default()
         .into_iter()
         .map(|eav_crud| (eav_crud.0.value(), eav_crud.1))
         .collect::<Vec<_>>()
 }
 
+/// Counts the links we hold instead of collecting them, so only the number has to be sent back
+fn get_links_count(
+    context: &Arc<Context>,
+    base: Address,
+    link_type: String,
+    tag: String,
+    status_request: LinksStatusRequestKind,
+) -> usize {
+    context
+        .state()
+        .unwrap()
+        .dht()
+        .get_links_count(base, link_type, tag, &status_request)
+        .unwrap_or(0)
+}
+
 fn get_entry(context: &Arc<Context>, address: Address) -> Option<EntryWithMetaAndHeader> {
     nucleus::actions::get_entry::get_entry_with_meta(&context, address.clone())
         .map(|entry_with_meta_opt| {
@@ -64,23 +82,51 @@ fn get_entry(context: &Arc<Context>, address: Address) -> Option<EntryWithMetaAn
         .unwrap_or(None)
 }
 
+fn respond_get_links(
+    context: &Arc<Context>,
+    query_data: QueryEntryData,
+    link_type: String,
+    tag: String,
+    options: GetLinksOptions,
+) -> ActionWrapper {
+    let links = get_links(
+        context,
+        query_data.entry_address.clone(),
+        link_type.clone(),
+        tag.clone(),
+        &options,
+    );
+    ActionWrapper::new(Action::RespondGetLinks((
+        query_data, links, link_type, tag, options,
+    )))
+}
+
 /// The network has sent us a query for entry data, so we need to examine
 /// the query and create appropriate actions for the different variants
 pub fn handle_query_entry_data(query_data: QueryEntryData, context: Arc<Context>) {
     let query_json = JsonString::from_json(&String::from_utf8(query_data.query.clone()).unwrap());
     let action_wrapper = match query_json.clone().try_into() {
         Ok(NetworkQuery::GetLinks(link_type, tag)) => {
-            let links = get_links(
+            // Plain requests get all links since the requester filters them by status itself
+            let options = GetLinksOptions {
+                status_request: LinksStatusRequestKind::All,
+                ..Default::default()
+            };
+            respond_get_links(&context, query_data, link_type, tag, options)
+        }
+        Ok(NetworkQuery::GetLinksWithOptions(link_type, tag, options)) => {
+            respond_get_links(&context, query_data, link_type, tag, options)
+        }
+        Ok(NetworkQuery::GetLinksCount(link_type, tag, status_request)) => {
+            let count = get_links_count(
                 &context,
                 query_data.entry_address.clone(),
                 link_type.clone(),
                 tag.clone(),
+                status_request,
             );
-            ActionWrapper::new(Action::RespondGetLinks((
-                query_data,
-                links,
-                link_type.clone(),
-                tag.clone(),
+            ActionWrapper::new(Action::RespondGetLinksCount((
+                query_data, count, link_type, tag,
             )))
         }
         Ok(NetworkQuery::GetEntry) => {
@@ -124,6 +170,17 @@ pub fn handle_query_entry_result(query_result_data: QueryEntryResultData, contex
                 },
             )))
         }
+        Ok(NetworkQueryResult::LinksCount(count, link_type, tag)) => {
+            ActionWrapper::new(Action::HandleGetLinksCountResult((
+                count,
+                GetLinksKey {
+                    base_address: query_result_data.entry_address.clone(),
+                    link_type,
+                    tag,
+                    id: query_result_data.request_id.clone(),
+                },
+            )))
+        }
         err => {
             context.log(format!(
                 "err/net: Error ({:?}) deserializing QueryResult {:?}",
@@ -134,3 +191,79 @@ pub fn handle_query_entry_result(query_result_data: QueryEntryResultData, contex
     };
     dispatch_action(context.action_channel(), action_wrapper.clone());
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{instance::tests::test_context_with_channels, state::State};
+    use holochain_core_types::{
+        eav::{Attribute, EntityAttributeValueIndex},
+        entry::{test_entry, test_entry_with_value},
+    };
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use std::sync::{mpsc::sync_channel, RwLock};
+
+    #[test]
+    fn get_links_count_query_gets_answered_with_the_held_count() {
+        let (action_sender, action_receiver) = sync_channel(10);
+        let (observer_sender, _observer_receiver) = sync_channel(10);
+        let mut context =
+            test_context_with_channels("alice", &action_sender, &observer_sender, None);
+        let state = State::new(Arc::new((*context).clone()));
+        Arc::get_mut(&mut context)
+            .unwrap()
+            .set_state(Arc::new(RwLock::new(state)));
+
+        // Two live links and a removed one
+        let base = test_entry().address();
+        let link_tag = Attribute::LinkTag("test-link".to_string(), "test-tag".to_string());
+        let removed_link = Attribute::RemovedLink("test-link".to_string(), "test-tag".to_string());
+        let removed = test_entry_with_value("\"removed\"").address();
+        let eavis = vec![
+            (
+                link_tag.clone(),
+                test_entry_with_value("\"first\"").address(),
+            ),
+            (
+                link_tag.clone(),
+                test_entry_with_value("\"second\"").address(),
+            ),
+            (link_tag, removed.clone()),
+            (removed_link, removed),
+        ];
+        {
+            let meta_storage = context.state().unwrap().dht().meta_storage();
+            let mut meta_storage = meta_storage.write().unwrap();
+            for (attribute, value) in eavis {
+                let eavi = EntityAttributeValueIndex::new(&base, &attribute, &value).unwrap();
+                meta_storage.add_eavi(&eavi).unwrap();
+            }
+        }
+
+        let query: JsonString = NetworkQuery::GetLinksCount(
+            "test-link".to_string(),
+            "test-tag".to_string(),
+            LinksStatusRequestKind::Live,
+        )
+        .into();
+        let query_data = QueryEntryData {
+            dna_address: "test-dna".into(),
+            entry_address: base,
+            request_id: "test-request".to_string(),
+            requester_agent_id: "bob".into(),
+            query: query.to_string().into_bytes(),
+        };
+        handle_query_entry_data(query_data.clone(), context.clone());
+
+        let response = action_receiver.try_recv().unwrap();
+        assert_eq!(
+            response.action(),
+            &Action::RespondGetLinksCount((
+                query_data,
+                2,
+                "test-link".to_string(),
+                "test-tag".to_string()
+            ))
+        );
+    }
+}
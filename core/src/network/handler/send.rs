@@ -5,6 +5,9 @@ use crate::{
     network::direct_message::DirectMessage,
     workflows::{
         handle_custom_direct_message::handle_custom_direct_message,
+        respond_countersignature_request::{
+            respond_commit_countersigned_entry, respond_countersignature_request,
+        },
         respond_validation_package_request::respond_validation_package_request,
     },
 };
@@ -13,6 +16,7 @@ use std::{sync::Arc, thread};
 
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_net::connection::json_protocol::MessageData;
+use holochain_wasm_utils::api_serialization::commit_entry::CommitEntryResult;
 use std::convert::TryFrom;
 
 fn parse_direct_message(content: Vec<u8>) -> Result<DirectMessage, JsonError> {
@@ -22,9 +26,36 @@ fn parse_direct_message(content: Vec<u8>) -> Result<DirectMessage, JsonError> {
     ))
 }
 
+/// Agents on the conductor's blocklist got warranted, so we don't talk to them anymore
+fn is_from_blocked_agent(message_data: &MessageData, context: &Arc<Context>) -> bool {
+    let sender = Address::from(message_data.from_agent_id.clone());
+    match context.conductor_api.is_blocked(&sender) {
+        Ok(blocked) => {
+            if blocked {
+                context.log(format!(
+                    "info/net/handle_send_message: Refusing direct message from blocked agent {}",
+                    sender
+                ));
+            }
+            blocked
+        }
+        Err(error) => {
+            context.log(format!(
+                "warn/net/handle_send_message: Could not check blocklist: {:?}",
+                error
+            ));
+            false
+        }
+    }
+}
+
 /// We got a ProtocolWrapper::SendMessage, this means somebody initiates message roundtrip
 /// -> we are being called
 pub fn handle_send_message(message_data: MessageData, context: Arc<Context>) {
+    if is_from_blocked_agent(&message_data, &context) {
+        return;
+    }
+
     let message = match parse_direct_message(message_data.content.clone()) {
         Ok(message) => message,
         Err(error) => {
@@ -67,12 +98,42 @@ pub fn handle_send_message(message_data: MessageData, context: Arc<Context>) {
         DirectMessage::ValidationPackage(_) => context.log(
             "err/net: Got DirectMessage::ValidationPackage as initial message. This should not happen.",
         ),
+        DirectMessage::RequestCountersignature(request) => {
+            thread::spawn(move || {
+                context.block_on(respond_countersignature_request(
+                    Address::from(message_data.from_agent_id),
+                    message_data.request_id,
+                    request,
+                    context.clone(),
+                ));
+            });
+        }
+        DirectMessage::Countersignature(_) => context.log(
+            "err/net: Got DirectMessage::Countersignature as initial message. This should not happen.",
+        ),
+        DirectMessage::CommitCountersignedEntry(countersigned_entry) => {
+            thread::spawn(move || {
+                context.block_on(respond_commit_countersigned_entry(
+                    Address::from(message_data.from_agent_id),
+                    message_data.request_id,
+                    countersigned_entry,
+                    context.clone(),
+                ));
+            });
+        }
+        DirectMessage::CountersignedEntryCommitted(_) => context.log(
+            "err/net: Got DirectMessage::CountersignedEntryCommitted as initial message. This should not happen.",
+        ),
     };
 }
 
 /// We got a JsonProtocol::HandleSendMessageResult.
 /// This means somebody has responded to our message that we called and this is the answer
 pub fn handle_send_message_result(message_data: MessageData, context: Arc<Context>) {
+    if is_from_blocked_agent(&message_data, &context) {
+        return;
+    }
+
     let response = match parse_direct_message(message_data.content.clone()) {
         Ok(message) => message,
         Err(error) => {
@@ -128,6 +189,46 @@ pub fn handle_send_message_result(message_data: MessageData, context: Arc<Contex
             )));
             dispatch_action(context.action_channel(), action_wrapper.clone());
 
+            let action_wrapper =
+                ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+        }
+        DirectMessage::RequestCountersignature(_) => context.log(
+            "err/net: Got DirectMessage::RequestCountersignature as a response. This should not happen.",
+        ),
+        DirectMessage::Countersignature(maybe_provenance) => {
+            if initial_message.is_none() {
+                context.log("err/net: Received a countersignature but could not find message ID in history. Not able to process.");
+                return;
+            }
+
+            // Countersignatures are waited for like responses to custom direct messages
+            let action_wrapper = ActionWrapper::new(Action::HandleCustomSendResponse((
+                message_data.request_id.clone(),
+                maybe_provenance.map(|provenance| JsonString::from(provenance).to_string()),
+            )));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+
+            let action_wrapper =
+                ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+        }
+        DirectMessage::CommitCountersignedEntry(_) => context.log(
+            "err/net: Got DirectMessage::CommitCountersignedEntry as a response. This should not happen.",
+        ),
+        DirectMessage::CountersignedEntryCommitted(maybe_address) => {
+            if initial_message.is_none() {
+                context.log("err/net: Received a countersigned entry commit but could not find message ID in history. Not able to process.");
+                return;
+            }
+
+            let action_wrapper = ActionWrapper::new(Action::HandleCustomSendResponse((
+                message_data.request_id.clone(),
+                maybe_address
+                    .map(|address| JsonString::from(CommitEntryResult::new(address)).to_string()),
+            )));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+
             let action_wrapper =
                 ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
             dispatch_action(context.action_channel(), action_wrapper.clone());
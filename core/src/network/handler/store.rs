@@ -4,7 +4,7 @@ use crate::{
     workflows::{
         hold_entry::hold_entry_workflow, hold_entry_remove::hold_remove_workflow,
         hold_entry_update::hold_update_workflow, hold_link::hold_link_workflow,
-        remove_link::remove_link_workflow,
+        remove_link::remove_link_workflow, warrant::hold_warrant_workflow,
     },
 };
 use holochain_core_types::entry::{deletion_entry::DeletionEntry, Entry};
@@ -100,6 +100,17 @@ pub fn handle_store(dht_data: StoreEntryAspectData, context: Arc<Context>) {
                     }
                 });
             }
+            EntryAspect::Warrant(warrant) => {
+                context
+                    .log("debug/net/handle: handle_store: Got EntryAspect::Warrant. processing...");
+                thread::spawn(move || {
+                    if let Err(error) =
+                        context.block_on(hold_warrant_workflow(warrant, context.clone()))
+                    {
+                        context.log(format!("warn/net/handle_store: {}", error))
+                    }
+                });
+            }
         }
     } else {
         context.log(format!(
@@ -8,6 +8,7 @@ pub mod reducers;
 pub mod state;
 #[cfg(test)]
 pub mod test_utils;
+pub mod warrant;
 
 #[cfg(test)]
 pub mod tests {
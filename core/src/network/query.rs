@@ -1,15 +1,21 @@
 use holochain_core_types::{crud_status::CrudStatus, entry::EntryWithMetaAndHeader};
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::get_links::{GetLinksOptions, LinksStatusRequestKind};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, DefaultJson, Clone)]
 pub enum NetworkQuery {
     GetEntry,
     GetLinks(String, String),
+    /// Only sent if the options sort, limit or page the links, which the answering peer does.
+    /// Plain requests keep using GetLinks and get filtered by the requester.
+    GetLinksWithOptions(String, String, GetLinksOptions),
+    GetLinksCount(String, String, LinksStatusRequestKind),
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, DefaultJson, Clone)]
 pub enum NetworkQueryResult {
     Entry(Option<EntryWithMetaAndHeader>),
     Links(Vec<(Address, CrudStatus)>, String, String),
+    LinksCount(usize, String, String),
 }
@@ -8,14 +8,21 @@ use holochain_core_types::error::HolochainError;
 use holochain_json_api::json::JsonString;
 use holochain_net::connection::json_protocol::{JsonProtocol, QueryEntryData};
 use holochain_persistence_api::hash::HashString;
+use holochain_wasm_utils::api_serialization::get_links::GetLinksOptions;
 
 fn reduce_get_links_inner(
     network_state: &mut NetworkState,
     key: &GetLinksKey,
+    options: &GetLinksOptions,
 ) -> Result<(), HolochainError> {
     network_state.initialized()?;
-    let query_json: JsonString =
-        NetworkQuery::GetLinks(key.link_type.clone(), key.tag.clone()).into();
+    let (link_type, tag) = (key.link_type.clone(), key.tag.clone());
+    let query = if options.sort.is_none() && options.limit.is_none() && options.cursor.is_none() {
+        NetworkQuery::GetLinks(link_type, tag)
+    } else {
+        NetworkQuery::GetLinksWithOptions(link_type, tag, options.clone())
+    };
+    let query_json: JsonString = query.into();
     send(
         network_state,
         JsonProtocol::QueryEntry(QueryEntryData {
@@ -34,9 +41,9 @@ pub fn reduce_get_links(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let key = unwrap_to!(action => crate::action::Action::GetLinks);
+    let (key, options) = unwrap_to!(action => crate::action::Action::GetLinks);
 
-    let result = match reduce_get_links_inner(network_state, &key) {
+    let result = match reduce_get_links_inner(network_state, &key, &options) {
         Ok(()) => None,
         Err(err) => Some(Err(err)),
     };
@@ -87,7 +94,8 @@ mod tests {
             tag: "link-tag".to_string(),
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
-        let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
+        let action_wrapper =
+            ActionWrapper::new(Action::GetLinks((key.clone(), Default::default())));
 
         let store = store.reduce(action_wrapper);
         let maybe_get_links_result = store
@@ -130,7 +138,8 @@ mod tests {
             link_type: link_type.clone(),
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
-        let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
+        let action_wrapper =
+            ActionWrapper::new(Action::GetLinks((key.clone(), Default::default())));
 
         let store = store.reduce(action_wrapper);
         let maybe_get_entry_result = store.network().get_links_results.get(&key).cloned();
@@ -169,7 +178,8 @@ mod tests {
             link_type: link_type.clone(),
             id: snowflake::ProcessUniqueId::new().to_string(),
         };
-        let action_wrapper = ActionWrapper::new(Action::GetLinks(key.clone()));
+        let action_wrapper =
+            ActionWrapper::new(Action::GetLinks((key.clone(), Default::default())));
 
         {
             let mut new_store = store.write().unwrap();
@@ -0,0 +1,118 @@
+use crate::{
+    action::{ActionWrapper, GetLinksKey},
+    network::{query::NetworkQuery, reducers::send, state::NetworkState},
+    state::State,
+};
+
+use holochain_core_types::error::HolochainError;
+use holochain_json_api::json::JsonString;
+use holochain_net::connection::json_protocol::{JsonProtocol, QueryEntryData};
+use holochain_persistence_api::hash::HashString;
+use holochain_wasm_utils::api_serialization::get_links::LinksStatusRequestKind;
+
+fn reduce_get_links_count_inner(
+    network_state: &mut NetworkState,
+    key: &GetLinksKey,
+    status_request: &LinksStatusRequestKind,
+) -> Result<(), HolochainError> {
+    network_state.initialized()?;
+    let query_json: JsonString = NetworkQuery::GetLinksCount(
+        key.link_type.clone(),
+        key.tag.clone(),
+        status_request.clone(),
+    )
+    .into();
+    send(
+        network_state,
+        JsonProtocol::QueryEntry(QueryEntryData {
+            requester_agent_id: network_state.agent_id.clone().unwrap().into(),
+            request_id: key.id.clone(),
+            dna_address: network_state.dna_address.clone().unwrap(),
+            entry_address: HashString::from(key.base_address.clone()),
+            query: query_json.to_string().into_bytes(),
+        }),
+    )
+}
+
+pub fn reduce_get_links_count(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (key, status_request) = unwrap_to!(action => crate::action::Action::GetLinksCount);
+
+    let result = match reduce_get_links_count_inner(network_state, &key, &status_request) {
+        Ok(()) => None,
+        Err(err) => Some(Err(err)),
+    };
+
+    network_state
+        .get_links_count_results
+        .insert(key.clone(), result);
+}
+
+pub fn reduce_get_links_count_timeout(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let key = unwrap_to!(action => crate::action::Action::GetLinksCountTimeout);
+
+    if let Some(None) = network_state.get_links_count_results.get(key) {
+        network_state
+            .get_links_count_results
+            .insert(key.clone(), Some(Err(HolochainError::Timeout)));
+    }
+}
+
+pub fn reduce_clear_get_links_count_result(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let key = unwrap_to!(action => crate::action::Action::ClearGetLinksCountResult);
+    network_state.get_links_count_results.remove(key);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        action::{Action, ActionWrapper, GetLinksKey},
+        instance::tests::test_context,
+        state::test_store,
+    };
+    use holochain_core_types::{entry::test_entry, error::HolochainError};
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use holochain_wasm_utils::api_serialization::get_links::LinksStatusRequestKind;
+
+    #[test]
+    pub fn reduce_get_links_count_without_network_initialized() {
+        let context = test_context("alice", None);
+        let store = test_store(context.clone());
+
+        let key = GetLinksKey {
+            base_address: test_entry().address(),
+            link_type: "test-link".to_string(),
+            tag: "link-tag".to_string(),
+            id: snowflake::ProcessUniqueId::new().to_string(),
+        };
+        let action_wrapper = ActionWrapper::new(Action::GetLinksCount((
+            key.clone(),
+            LinksStatusRequestKind::Live,
+        )));
+
+        let store = store.reduce(action_wrapper);
+        let maybe_get_links_count_result =
+            store.network().get_links_count_results.get(&key).cloned();
+        assert_eq!(
+            maybe_get_links_count_result,
+            Some(Some(Err(HolochainError::ErrorGeneric(
+                "Network not initialized".to_string()
+            ))))
+        );
+    }
+}
@@ -0,0 +1,14 @@
+use crate::{action::ActionWrapper, network::state::NetworkState, state::State};
+
+pub fn reduce_handle_get_links_count_result(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (count, key) = unwrap_to!(action => crate::action::Action::HandleGetLinksCountResult);
+    // Results of processes that already got cleared (e.g. further responses) are dropped
+    if let Some(result) = network_state.get_links_count_results.get_mut(key) {
+        *result = Some(Ok(*count));
+    }
+}
@@ -90,8 +90,8 @@ pub mod test {
     #[test]
     pub fn should_wait_for_protocol_p2p_ready() {
         let context: Arc<Context> = test_context();
-        let dna_address: Address = context.agent_id.address();
-        let agent_id = context.agent_id.content().to_string();
+        let dna_address: Address = context.agent_id().address();
+        let agent_id = context.agent_id().content().to_string();
         let handler = NetHandler::new(Box::new(|_| Ok(())));
         let network_settings = crate::action::NetworkSettings {
             p2p_config: context.p2p_config.clone(),
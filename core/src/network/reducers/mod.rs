@@ -1,16 +1,20 @@
 pub mod get_entry;
 pub mod get_links;
+pub mod get_links_count;
 pub mod get_validation_package;
 pub mod handle_custom_send_response;
+pub mod handle_get_links_count_result;
 pub mod handle_get_links_result;
 pub mod handle_get_result;
 pub mod handle_get_validation_package;
 pub mod init;
 pub mod publish;
+pub mod publish_warrant;
 pub mod resolve_direct_connection;
 pub mod respond_fetch;
 pub mod respond_get;
 pub mod respond_get_links;
+pub mod respond_get_links_count;
 pub mod send_direct_message;
 
 use crate::{
@@ -20,17 +24,24 @@ use crate::{
         reducers::{
             get_entry::{reduce_get_entry, reduce_get_entry_timeout},
             get_links::{reduce_get_links, reduce_get_links_timeout},
+            get_links_count::{
+                reduce_clear_get_links_count_result, reduce_get_links_count,
+                reduce_get_links_count_timeout,
+            },
             get_validation_package::reduce_get_validation_package,
             handle_custom_send_response::reduce_handle_custom_send_response,
+            handle_get_links_count_result::reduce_handle_get_links_count_result,
             handle_get_links_result::reduce_handle_get_links_result,
             handle_get_result::reduce_handle_get_result,
             handle_get_validation_package::reduce_handle_get_validation_package,
             init::reduce_init,
             publish::reduce_publish,
+            publish_warrant::reduce_publish_warrant,
             resolve_direct_connection::reduce_resolve_direct_connection,
             respond_fetch::reduce_respond_fetch_data,
             respond_get::reduce_respond_get,
             respond_get_links::reduce_respond_get_links,
+            respond_get_links_count::reduce_respond_get_links_count,
             send_direct_message::{reduce_send_direct_message, reduce_send_direct_message_timeout},
         },
         state::NetworkState,
@@ -54,17 +65,23 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NetworkReduceFn> {
         Action::GetEntryTimeout(_) => Some(reduce_get_entry_timeout),
         Action::GetLinks(_) => Some(reduce_get_links),
         Action::GetLinksTimeout(_) => Some(reduce_get_links_timeout),
+        Action::GetLinksCount(_) => Some(reduce_get_links_count),
+        Action::GetLinksCountTimeout(_) => Some(reduce_get_links_count_timeout),
+        Action::ClearGetLinksCountResult(_) => Some(reduce_clear_get_links_count_result),
         Action::GetValidationPackage(_) => Some(reduce_get_validation_package),
         Action::HandleCustomSendResponse(_) => Some(reduce_handle_custom_send_response),
         Action::HandleGetResult(_) => Some(reduce_handle_get_result),
         Action::HandleGetLinksResult(_) => Some(reduce_handle_get_links_result),
+        Action::HandleGetLinksCountResult(_) => Some(reduce_handle_get_links_count_result),
         Action::HandleGetValidationPackage(_) => Some(reduce_handle_get_validation_package),
         Action::InitNetwork(_) => Some(reduce_init),
         Action::Publish(_) => Some(reduce_publish),
+        Action::PublishWarrant(_) => Some(reduce_publish_warrant),
         Action::ResolveDirectConnection(_) => Some(reduce_resolve_direct_connection),
         Action::RespondFetch(_) => Some(reduce_respond_fetch_data),
         Action::RespondGet(_) => Some(reduce_respond_get),
         Action::RespondGetLinks(_) => Some(reduce_respond_get_links),
+        Action::RespondGetLinksCount(_) => Some(reduce_respond_get_links_count),
         Action::SendDirectMessage(_) => Some(reduce_send_direct_message),
         Action::SendDirectMessageTimeout(_) => Some(reduce_send_direct_message_timeout),
         _ => None,
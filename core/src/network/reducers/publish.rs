@@ -124,7 +124,19 @@ fn reduce_publish_inner(
 
     let entry_with_header = fetch_entry_with_header(&address, root_state)?;
     match entry_with_header.entry.entry_type() {
-        EntryType::AgentId => publish_entry(network_state, &entry_with_header),
+        EntryType::AgentId => publish_entry(network_state, &entry_with_header).and_then(|_| {
+            // An AgentId entry that updates another one rotates the agent's key
+            match entry_with_header.header.link_update_delete() {
+                Some(old_agent) => publish_update_delete_meta(
+                    network_state,
+                    old_agent,
+                    CrudStatus::Modified,
+                    &entry_with_header.clone(),
+                ),
+                None => Ok(()),
+            }
+        }),
+        EntryType::ChainMigrate => publish_entry(network_state, &entry_with_header),
         EntryType::App(_) => publish_entry(network_state, &entry_with_header).and_then(|_| {
             match entry_with_header.header.link_update_delete() {
                 Some(modified_entry) => publish_update_delete_meta(
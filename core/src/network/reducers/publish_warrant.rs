@@ -0,0 +1,53 @@
+use crate::{
+    action::ActionWrapper,
+    network::{
+        actions::ActionResponse, entry_aspect::EntryAspect, reducers::send, state::NetworkState,
+        warrant::Warrant,
+    },
+    state::State,
+};
+use holochain_core_types::error::HolochainError;
+use holochain_net::connection::json_protocol::{EntryData, JsonProtocol, ProvidedEntryData};
+use holochain_persistence_api::cas::content::AddressableContent;
+
+/// Send to network a PublishEntry message for every warranted agent,
+/// with the warrant as meta item of the agent's address.
+/// That way the warrant ends up with the nodes holding the agent's neighbourhood.
+fn reduce_publish_warrant_inner(
+    network_state: &mut NetworkState,
+    warrant: &Warrant,
+) -> Result<(), HolochainError> {
+    network_state.initialized()?;
+    for agent in warrant.warranted_agents() {
+        send(
+            network_state,
+            JsonProtocol::PublishEntry(ProvidedEntryData {
+                dna_address: network_state.dna_address.clone().unwrap(),
+                provider_agent_id: network_state.agent_id.clone().unwrap().into(),
+                entry: EntryData {
+                    entry_address: agent,
+                    aspect_list: vec![EntryAspect::Warrant(warrant.clone()).into()],
+                },
+            }),
+        )?;
+    }
+    Ok(())
+}
+
+pub fn reduce_publish_warrant(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let warrant = unwrap_to!(action => crate::action::Action::PublishWarrant);
+
+    let result = reduce_publish_warrant_inner(network_state, warrant);
+    network_state.actions.insert(
+        action_wrapper.clone(),
+        ActionResponse::PublishWarrant(match result {
+            Ok(_) => Ok(warrant.address()),
+            Err(e) => Err(HolochainError::ErrorGeneric(e.to_string())),
+        }),
+    );
+}
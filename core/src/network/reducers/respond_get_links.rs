@@ -11,19 +11,26 @@ use holochain_net::connection::json_protocol::{
     JsonProtocol, QueryEntryData, QueryEntryResultData,
 };
 use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::get_links::GetLinksOptions;
 
 /// Send back to network a HandleQueryEntryResult, no matter what.
 /// Will return an empty content field if it actually doesn't have the data.
+/// Never sends more links than the requester asked for with the limit of its options.
 fn reduce_respond_get_links_inner(
     network_state: &mut NetworkState,
     query_data: &QueryEntryData,
     links: &Vec<(Address, CrudStatus)>,
     link_type: String,
     tag: String,
+    options: &GetLinksOptions,
 ) -> Result<(), HolochainError> {
     network_state.initialized()?;
-    let query_result_json: JsonString =
-        NetworkQueryResult::Links(links.clone(), link_type, tag).into();
+    let links = links
+        .iter()
+        .take(options.limit.unwrap_or(usize::max_value()))
+        .cloned()
+        .collect();
+    let query_result_json: JsonString = NetworkQueryResult::Links(links, link_type, tag).into();
     send(
         network_state,
         JsonProtocol::HandleQueryEntryResult(QueryEntryResultData {
@@ -43,7 +50,7 @@ pub fn reduce_respond_get_links(
     action_wrapper: &ActionWrapper,
 ) {
     let action = action_wrapper.action();
-    let (query_data, links, link_type, tag) =
+    let (query_data, links, link_type, tag, options) =
         unwrap_to!(action => crate::action::Action::RespondGetLinks);
     let result = reduce_respond_get_links_inner(
         network_state,
@@ -51,6 +58,7 @@ pub fn reduce_respond_get_links(
         links,
         link_type.clone(),
         tag.clone(),
+        options,
     );
 
     network_state.actions.insert(
@@ -30,6 +30,12 @@ type GetEntryWithMetaResult = Option<Result<Option<EntryWithMetaAndHeader>, Holo
 /// Some(Ok(_)): we got the list of links
 type GetLinksResult = Option<Result<Vec<(Address, CrudStatus)>, HolochainError>>;
 
+/// This represents the state of a get_links_count network process:
+/// None: process started, but no response yet from the network
+/// Some(Err(_)): there was a problem at some point
+/// Some(Ok(_)): we got the number of links
+type GetLinksCountResult = Option<Result<usize, HolochainError>>;
+
 /// This represents the state of a get_validation_package network process:
 /// None: process started, but no response yet from the network
 /// Some(Err(_)): there was a problem at some point
@@ -59,6 +65,10 @@ pub struct NetworkState {
     /// None means that we are still waiting for a result from the network.
     pub get_links_results: HashMap<GetLinksKey, GetLinksResult>,
 
+    /// Here we store the results of get_links_count processes, keyed like get_links_results.
+    /// None means that we are still waiting for a result from the network.
+    pub get_links_count_results: HashMap<GetLinksKey, GetLinksCountResult>,
+
     /// Here we store the results of get validation package processes.
     /// None means that we are still waiting for a result from the network.
     pub get_validation_package_results: HashMap<Address, GetValidationPackageResult>,
@@ -88,6 +98,7 @@ impl NetworkState {
 
             get_entry_with_meta_results: HashMap::new(),
             get_links_results: HashMap::new(),
+            get_links_count_results: HashMap::new(),
             get_validation_package_results: HashMap::new(),
             direct_message_connections: HashMap::new(),
             custom_direct_message_replys: HashMap::new(),
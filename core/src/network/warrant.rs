@@ -0,0 +1,173 @@
+//! Warrants are signed statements of a node that data it was asked to hold failed validation.
+//! They get published as an aspect of the warranted agents' addresses, which puts them into
+//! the neighbourhood of those agents, so their peers learn about the misbehaviour.
+use crate::{
+    context::Context,
+    network::entry_with_header::EntryWithHeader,
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
+    workflows::countersign_entry::{agent_address, signs_header},
+};
+use holochain_core_types::{
+    error::HolochainError,
+    signature::{Provenance, Signature},
+};
+use holochain_dpki::utils::Verify;
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
+use std::{convert::TryFrom, sync::Arc};
+
+/// Signed statement that the given entry and header failed the validation of the given workflow
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DefaultJson)]
+pub struct Warrant {
+    /// The invalid data. The provenances of its header name the warranted agents.
+    pub entry_with_header: EntryWithHeader,
+
+    /// The workflow whose validation failed
+    pub workflow: ValidatingWorkflow,
+
+    /// The validation error
+    pub reason: String,
+
+    /// The agent that issued the warrant and its signature of all the above
+    pub warrantor: Provenance,
+}
+
+impl AddressableContent for Warrant {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> Result<Self, JsonError> {
+        Self::try_from(content.to_owned())
+    }
+}
+
+impl Warrant {
+    /// Creates a warrant signed by the agent of the given context.
+    /// The warrantor is the agent's current address, which is the key the context signs with,
+    /// also after key rotations.
+    pub fn new(
+        entry_with_header: EntryWithHeader,
+        workflow: ValidatingWorkflow,
+        reason: String,
+        context: &Arc<Context>,
+    ) -> Result<Self, HolochainError> {
+        let warrantor = agent_address(context)?;
+        let signature = context.sign(signed_payload(&entry_with_header, &workflow, &reason))?;
+        Ok(Warrant {
+            entry_with_header,
+            workflow,
+            reason,
+            warrantor: Provenance::new(warrantor, Signature::from(signature)),
+        })
+    }
+
+    /// The agents that signed the invalid header
+    pub fn warranted_agents(&self) -> Vec<Address> {
+        self.entry_with_header
+            .header
+            .provenances()
+            .iter()
+            .map(|provenance| provenance.source())
+            .collect()
+    }
+
+    /// Checks the signature of the warrantor and the signatures of the warranted agents.
+    /// Without the latter, anybody could get warranted for data somebody else made up.
+    pub fn verify_signatures(&self) -> Result<(), HolochainError> {
+        let payload = signed_payload(&self.entry_with_header, &self.workflow, &self.reason);
+        if !self.warrantor.verify(payload)? {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Invalid warrant signature of {}",
+                self.warrantor.source()
+            )));
+        }
+
+        let header = &self.entry_with_header.header;
+        if self.entry_with_header.entry.address() != *header.entry_address() {
+            return Err(HolochainError::ErrorGeneric(
+                "Warranted header does not belong to the warranted entry".to_string(),
+            ));
+        }
+        if header.provenances().is_empty() {
+            return Err(HolochainError::ErrorGeneric(
+                "Warranted header has no provenances".to_string(),
+            ));
+        }
+        for provenance in header.provenances() {
+            if !signs_header(provenance, header) {
+                return Err(HolochainError::ErrorGeneric(format!(
+                    "Warranted header is not signed by {}",
+                    provenance.source()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the warrantor signs. The reason is free text, hence base64 encoded.
+fn signed_payload(
+    entry_with_header: &EntryWithHeader,
+    workflow: &ValidatingWorkflow,
+    reason: &str,
+) -> String {
+    format!(
+        "{}:{}:{}",
+        entry_with_header.header.address(),
+        workflow,
+        base64::encode(reason)
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        action::{Action, ActionWrapper},
+        agent::actions::commit::commit_entry,
+        instance::tests::test_instance_and_context,
+        network::entry_with_header::fetch_entry_with_header,
+        nucleus::actions::tests::*,
+    };
+    use holochain_core_types::entry::test_entry;
+
+    #[test]
+    fn warrants_verify_only_unchanged_and_get_held() {
+        let dna = test_dna();
+        let (_instance, context) = test_instance_and_context(dna, None).unwrap();
+        let address = context
+            .block_on(commit_entry(test_entry(), None, &context))
+            .unwrap();
+        let entry_with_header =
+            fetch_entry_with_header(&address, &context.state().unwrap()).unwrap();
+
+        let warrant = Warrant::new(
+            entry_with_header,
+            ValidatingWorkflow::HoldEntry,
+            "invalid \"value\"".to_string(),
+            &context,
+        )
+        .unwrap();
+        assert_eq!(
+            warrant.warranted_agents(),
+            vec![context.agent_id().address()]
+        );
+        assert!(warrant.verify_signatures().is_ok());
+
+        let tampered = Warrant {
+            reason: "something else".to_string(),
+            ..warrant.clone()
+        };
+        assert!(tampered.verify_signatures().is_err());
+
+        let state = context
+            .state()
+            .unwrap()
+            .reduce(ActionWrapper::new(Action::HoldWarrant(warrant.clone())));
+        assert_eq!(
+            state.dht().warrants_for(&context.agent_id().address()),
+            vec![warrant]
+        );
+    }
+}
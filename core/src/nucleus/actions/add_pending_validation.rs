@@ -17,11 +17,7 @@ pub fn add_pending_validation(
     dispatch_action(
         context.action_channel(),
         ActionWrapper::new(Action::AddPendingValidation(Arc::new(
-            PendingValidationStruct {
-                entry_with_header,
-                dependencies,
-                workflow,
-            },
+            PendingValidationStruct::new(entry_with_header, dependencies, workflow),
         ))),
     );
 }
@@ -26,8 +26,61 @@ pub async fn build_validation_package<'a>(
     context: Arc<Context>,
     provenances: &'a Vec<Provenance>,
 ) -> Result<ValidationPackage, HolochainError> {
-    let id = snowflake::ProcessUniqueId::new();
+    check_entry_type(entry, &context)?;
+
+    let maybe_entry_header = find_chain_header(&entry.clone(), &context.state().unwrap());
+    let entry_header = if maybe_entry_header.is_none() {
+        // TODO: make sure that we don't run into race conditions with respect to the chain
+        // We need the source chain header as part of the validation package.
+        // For an already committed entry (when asked to deliver the validation package to
+        // a DHT node) we should have gotten one from chain_header() above.
+        // But when we commit an entry, there is no header for it in the chain yet.
+        // That is why we have to create a pre-flight header here.
+        // If there is another zome function call that also calls commit before this commit
+        // is done, we might create two pre-flight chain headers linking to the same
+        // previous header. Since these pre-flight headers are not written to the chain
+        // and just used for the validation, I don't see why it would be a problem.
+        // If it was a problem, we would have to make sure that the whole commit process
+        // (including validtion) is atomic.
+        let state = &context.state()?;
+        agent::state::create_new_chain_header(
+            &entry,
+            &state.agent(),
+            &*state,
+            &None,
+            provenances,
+        )?
+    } else {
+        maybe_entry_header.unwrap()
+    };
+
+    await!(build_validation_package_with_header(
+        entry,
+        entry_header,
+        Vec::new(),
+        context
+    ))
+}
+
+/// Builds the validation package of an entry staged in a bundle, with the header it
+/// gets committed with. `staged` are the entries of the bundle committed before it,
+/// which the package includes as if they were on the chain already.
+pub async fn build_bundled_validation_package<'a>(
+    entry: &'a Entry,
+    entry_header: ChainHeader,
+    staged: Vec<(Entry, ChainHeader)>,
+    context: Arc<Context>,
+) -> Result<ValidationPackage, HolochainError> {
+    check_entry_type(entry, &context)?;
+    await!(build_validation_package_with_header(
+        entry,
+        entry_header,
+        staged,
+        context
+    ))
+}
 
+fn check_entry_type(entry: &Entry, context: &Arc<Context>) -> Result<(), HolochainError> {
     match entry.entry_type() {
         EntryType::App(app_entry_type) => {
             if context
@@ -65,6 +118,10 @@ pub async fn build_validation_package<'a>(
         EntryType::AgentId => {
             // FIXME
         }
+
+        EntryType::ChainMigrate => {
+            // ChainMigrate entries are validated against the DNA and the chain headers
+        }
         _ => {
             return Err(HolochainError::ValidationFailed(format!(
                 "Attempted to validate system entry type {:?}",
@@ -72,36 +129,21 @@ pub async fn build_validation_package<'a>(
             )));
         }
     };
+    Ok(())
+}
+
+async fn build_validation_package_with_header<'a>(
+    entry: &'a Entry,
+    entry_header: ChainHeader,
+    staged: Vec<(Entry, ChainHeader)>,
+    context: Arc<Context>,
+) -> Result<ValidationPackage, HolochainError> {
+    let id = snowflake::ProcessUniqueId::new();
 
     {
         let id = id.clone();
         let entry = entry.clone();
         let context = context.clone();
-        let maybe_entry_header = find_chain_header(&entry.clone(), &context.state().unwrap());
-        let entry_header = if maybe_entry_header.is_none() {
-            // TODO: make sure that we don't run into race conditions with respect to the chain
-            // We need the source chain header as part of the validation package.
-            // For an already committed entry (when asked to deliver the validation package to
-            // a DHT node) we should have gotten one from chain_header() above.
-            // But when we commit an entry, there is no header for it in the chain yet.
-            // That is why we have to create a pre-flight header here.
-            // If there is another zome function call that also calls commit before this commit
-            // is done, we might create two pre-flight chain headers linking to the same
-            // previous header. Since these pre-flight headers are not written to the chain
-            // and just used for the validation, I don't see why it would be a problem.
-            // If it was a problem, we would have to make sure that the whole commit process
-            // (including validtion) is atomic.
-            let state = &context.state()?;
-            agent::state::create_new_chain_header(
-                &entry,
-                &state.agent(),
-                &*state,
-                &None,
-                provenances,
-            )?
-        } else {
-            maybe_entry_header.unwrap()
-        };
 
         thread::spawn(move || {
             let maybe_callback_result = get_validation_package_definition(&entry, context.clone());
@@ -125,18 +167,22 @@ pub async fn build_validation_package<'a>(
                         Entry => ValidationPackage::only_header(entry_header),
                         ChainEntries => {
                             let mut package = ValidationPackage::only_header(entry_header);
-                            package.source_chain_entries = Some(all_public_chain_entries(&context));
+                            package.source_chain_entries =
+                                Some(all_public_chain_entries(&staged, &context));
                             package
                         }
                         ChainHeaders => {
                             let mut package = ValidationPackage::only_header(entry_header);
-                            package.source_chain_headers = Some(all_chain_headers(&context));
+                            package.source_chain_headers =
+                                Some(all_chain_headers(&staged, &context));
                             package
                         }
                         ChainFull => {
                             let mut package = ValidationPackage::only_header(entry_header);
-                            package.source_chain_entries = Some(all_public_chain_entries(&context));
-                            package.source_chain_headers = Some(all_chain_headers(&context));
+                            package.source_chain_entries =
+                                Some(all_public_chain_entries(&staged, &context));
+                            package.source_chain_headers =
+                                Some(all_chain_headers(&staged, &context));
                             package
                         }
                         Custom(string) => {
@@ -164,10 +210,19 @@ pub async fn build_validation_package<'a>(
     })
 }
 
-fn all_public_chain_entries(context: &Arc<Context>) -> Vec<Entry> {
+/// The public entries of the chain, newest first, after the staged entries
+fn all_public_chain_entries(
+    staged: &[(Entry, ChainHeader)],
+    context: &Arc<Context>,
+) -> Vec<Entry> {
     let chain = context.state().unwrap().agent().chain_store();
     let top_header = context.state().unwrap().agent().top_chain_header();
-    chain
+    let staged_entries = staged
+        .iter()
+        .rev()
+        .filter(|(entry, _)| entry.entry_type().can_publish(context))
+        .map(|(entry, _)| entry.clone());
+    let chain_entries = chain
         .iter(&top_header)
         .filter(|ref chain_header| chain_header.entry_type().can_publish(context))
         .map(|chain_header| {
@@ -178,14 +233,23 @@ fn all_public_chain_entries(context: &Arc<Context>) -> Vec<Entry> {
             json.expect("Could not find CAS for existing chain header")
                 .try_into()
                 .expect("Could not convert to serialized entry")
-        })
-        .collect::<Vec<_>>()
+        });
+    staged_entries.chain(chain_entries).collect::<Vec<_>>()
 }
 
-fn all_chain_headers(context: &Arc<Context>) -> Vec<ChainHeader> {
+/// The headers of the chain, newest first, after the headers of the staged entries
+fn all_chain_headers(
+    staged: &[(Entry, ChainHeader)],
+    context: &Arc<Context>,
+) -> Vec<ChainHeader> {
     let chain = context.state().unwrap().agent().chain_store();
     let top_header = context.state().unwrap().agent().top_chain_header();
-    chain.iter(&top_header).collect()
+    staged
+        .iter()
+        .rev()
+        .map(|(_, chain_header)| chain_header.clone())
+        .chain(chain.iter(&top_header))
+        .collect()
 }
 
 /// ValidationPackageFuture resolves to the ValidationPackage or a HolochainError.
@@ -1,5 +1,6 @@
 use crate::{
     action::{Action, ActionWrapper},
+    agent::actions::bundle::discard_bundle,
     context::Context,
     nucleus::{
         actions::get_entry::get_entry_from_agent_chain,
@@ -101,6 +102,15 @@ pub async fn call_zome_function(
             WasmCallData::new_zome_call(context_clone.clone(), zome_call_clone.clone()),
         );
         context_clone.log("debug/actions/call_zome_fn: got call_result from ribosome::run_dna.");
+        // A bundle the call did not close gets dropped with the call:
+        let has_bundle = context_clone
+            .state()
+            .map(|state| state.agent().bundle(&zome_call_clone).is_some())
+            .unwrap_or(false);
+        if has_bundle {
+            context_clone.log("debug/actions/call_zome_fn: discarding bundle left open.");
+            discard_bundle(zome_call_clone.clone(), &context_clone);
+        }
         // Construct response
         let response = ExecuteZomeFnResponse::new(zome_call_clone, call_result);
         // Send ReturnZomeFunctionResult Action
@@ -165,7 +175,7 @@ pub fn validate_call(
 }
 
 fn is_token_the_agent(context: Arc<Context>, request: &CapabilityRequest) -> bool {
-    context.agent_id.pub_sign_key == request.cap_token.to_string()
+    context.agent_id().pub_sign_key == request.cap_token.to_string()
 }
 
 fn get_grant(context: &Arc<Context>, address: &Address) -> Option<CapTokenGrant> {
@@ -221,7 +231,7 @@ pub fn make_cap_request_for_call<J: Into<JsonString>>(
 ) -> CapabilityRequest {
     CapabilityRequest::new(
         cap_token,
-        callers_context.agent_id.address(),
+        callers_context.agent_id().address(),
         make_call_sig(callers_context, function, parameters),
     )
 }
@@ -334,7 +344,7 @@ pub mod tests {
     #[test]
     fn test_agent_as_token() {
         let context = test_context("alice", None);
-        let agent_token = context.agent_id.address();
+        let agent_token = context.agent_id().address();
         let cap_request =
             make_cap_request_for_call(context.clone(), agent_token.clone(), "test", "{}");
         assert!(is_token_the_agent(context.clone(), &cap_request));
@@ -355,12 +365,12 @@ pub mod tests {
 
         // only exact same call signed by the same person should verify
         let call_sig1 = make_call_sig(context1.clone(), "func", "{}");
-        let provenance1 = Provenance::new(context1.agent_id.address(), call_sig1.clone());
+        let provenance1 = Provenance::new(context1.agent_id().address(), call_sig1.clone());
         assert!(verify_call_sig(&provenance1, "func", "{}"));
         assert!(!verify_call_sig(&provenance1, "func1", "{}"));
         assert!(!verify_call_sig(&provenance1, "func", "{\"x\":1}"));
 
-        let bad_provenance = Provenance::new(context2.agent_id.address(), call_sig1);
+        let bad_provenance = Provenance::new(context2.agent_id().address(), call_sig1);
 
         assert!(!verify_call_sig(&bad_provenance, "func", "{}"));
     }
@@ -373,7 +383,7 @@ pub mod tests {
         assert_eq!(cap_request.cap_token, dummy_capability_token());
         assert_eq!(
             cap_request.provenance.source().to_string(),
-            context.agent_id.pub_sign_key
+            context.agent_id().pub_sign_key
         );
         assert_eq!(
             cap_request.provenance.signature(),
@@ -404,7 +414,7 @@ pub mod tests {
     fn test_verify_grant() {
         let context = test_context("alice", None);
         let context2 = test_context("bob", None);
-        let test_address1 = context.agent_id.address();
+        let test_address1 = context.agent_id().address();
 
         fn zome_call_valid(context: Arc<Context>, token: &Address) -> ZomeFnCall {
             ZomeFnCall::new(
@@ -178,7 +178,7 @@ pub mod tests {
     ) -> CapabilityRequest {
         make_cap_request_for_call(
             context.clone(),
-            Address::from(context.agent_id.address()),
+            Address::from(context.agent_id().address()),
             function,
             parameters,
         )
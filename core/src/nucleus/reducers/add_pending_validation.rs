@@ -1,13 +1,18 @@
 use crate::{
     action::{Action, ActionWrapper},
     nucleus::state::{NucleusState, PendingValidationKey},
+    scheduled_jobs::pending_validations::PendingValidationStruct,
     state::State,
 };
 use holochain_persistence_api::cas::content::AddressableContent;
+use std::sync::Arc;
 
 /// Reduce AddPendingValidation Action.
 /// Inserts boxed EntryWithHeader and dependencies into state, referenced with
-/// the entry's address.
+/// the entry's address, and indexed under its dependencies so it gets woken up
+/// as soon as one of them gets held.
+/// If the validation was pending already, this means another attempt failed
+/// and the attempts get counted up.
 #[allow(unknown_lints)]
 #[allow(needless_pass_by_value)]
 pub fn reduce_add_pending_validation(
@@ -17,12 +22,18 @@ pub fn reduce_add_pending_validation(
 ) {
     let action = action_wrapper.action();
     let pending = unwrap_to!(action => Action::AddPendingValidation);
-    let address = pending.entry_with_header.entry.address();
-    let workflow = pending.workflow.clone();
-    state.pending_validations.insert(
-        PendingValidationKey::new(address, workflow),
-        pending.clone(),
+    let key = PendingValidationKey::new(
+        pending.entry_with_header.entry.address(),
+        pending.workflow.clone(),
     );
+    let pending = match state.pending_validations.get(&key) {
+        Some(previous) => Arc::new(PendingValidationStruct {
+            attempts: previous.attempts + 1,
+            ..(**pending).clone()
+        }),
+        None => pending.clone(),
+    };
+    state.add_pending_validation(pending);
 }
 
 #[cfg(test)]
@@ -32,12 +43,11 @@ pub mod tests {
         instance::tests::test_context,
         network::entry_with_header::EntryWithHeader,
         nucleus::state::{tests::test_nucleus_state, PendingValidationKey},
-        scheduled_jobs::pending_validations::{PendingValidationStruct, ValidatingWorkflow},
+        scheduled_jobs::pending_validations::ValidatingWorkflow,
         state::test_store,
     };
     use holochain_core_types::{chain_header::test_chain_header, entry::Entry};
     use holochain_json_api::json::RawString;
-    use std::sync::Arc;
 
     #[test]
     fn test_reduce_add_pending_validation() {
@@ -52,20 +62,21 @@ pub mod tests {
         };
 
         let action_wrapper = ActionWrapper::new(Action::AddPendingValidation(Arc::new(
-            PendingValidationStruct {
+            PendingValidationStruct::new(
                 entry_with_header,
-                dependencies: Vec::new(),
-                workflow: ValidatingWorkflow::HoldEntry,
-            },
+                Vec::new(),
+                ValidatingWorkflow::HoldEntry,
+            ),
         )));
 
         reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
 
-        assert!(state
-            .pending_validations
-            .contains_key(&PendingValidationKey::new(
-                entry.address(),
-                ValidatingWorkflow::HoldEntry
-            )));
+        let key = PendingValidationKey::new(entry.address(), ValidatingWorkflow::HoldEntry);
+        assert_eq!(state.pending_validations.get(&key).unwrap().attempts, 0);
+
+        // Adding it again means another attempt failed
+        reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
+        reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
+        assert_eq!(state.pending_validations.get(&key).unwrap().attempts, 2);
     }
 }
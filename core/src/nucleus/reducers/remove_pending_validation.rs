@@ -17,9 +17,7 @@ pub fn reduce_remove_pending_validation(
 ) {
     let action = action_wrapper.action();
     let (address, workflow) = unwrap_to!(action => Action::RemovePendingValidation).clone();
-    state
-        .pending_validations
-        .remove(&PendingValidationKey::new(address, workflow));
+    state.remove_pending_validation(&PendingValidationKey::new(address, workflow));
 }
 
 #[cfg(test)]
@@ -53,11 +51,11 @@ pub mod tests {
         };
 
         let action_wrapper = ActionWrapper::new(Action::AddPendingValidation(Arc::new(
-            PendingValidationStruct {
+            PendingValidationStruct::new(
                 entry_with_header,
-                dependencies: Vec::new(),
-                workflow: ValidatingWorkflow::HoldEntry,
-            },
+                Vec::new(),
+                ValidatingWorkflow::HoldEntry,
+            ),
         )));
 
         reduce_add_pending_validation(&mut nucleus_state, &state, &action_wrapper);
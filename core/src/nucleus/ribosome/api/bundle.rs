@@ -0,0 +1,70 @@
+use crate::{
+    agent::actions::bundle::start_bundle,
+    nucleus::ribosome::{api::ZomeApiResult, Runtime},
+    workflows::close_bundle::close_bundle_workflow,
+};
+use holochain_core_types::error::HolochainError;
+use holochain_wasm_utils::api_serialization::bundle::{CloseBundleArgs, StartBundleArgs};
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::StartBundle function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: StartBundleArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_start_bundle(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let args = match StartBundleArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(..) => {
+            context.log(format!(
+                "err/zome: invoke_start_bundle failed to deserialize: {:?}",
+                args_str
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result = match runtime.zome_call() {
+        Some(zome_call) => start_bundle(zome_call, args, &context),
+        None => Err(not_in_zome_call_error()),
+    };
+    runtime.store_result(result)
+}
+
+/// ZomeApiFunction::CloseBundle function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CloseBundleArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_close_bundle(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let args = match CloseBundleArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(..) => {
+            context.log(format!(
+                "err/zome: invoke_close_bundle failed to deserialize: {:?}",
+                args_str
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result: Result<(), HolochainError> = match runtime.zome_call() {
+        Some(zome_call) => context
+            .block_on(close_bundle_workflow(zome_call, args.action, &context))
+            .map(|_| ()),
+        None => Err(not_in_zome_call_error()),
+    };
+    if let Err(ref err) = result {
+        context.log(format!("err/zome: close_bundle_workflow failed: {:?}", err));
+    }
+    runtime.store_result(result)
+}
+
+fn not_in_zome_call_error() -> HolochainError {
+    HolochainError::ErrorGeneric("Bundles can only be used in zome calls".to_string())
+}
@@ -378,7 +378,7 @@ pub mod tests {
         test_reduce_call(&test_setup, cap_request, expected_failure.clone());
 
         // test assigned capability where the caller is the agent
-        let agent_token_str = test_setup.context.agent_id.address();
+        let agent_token_str = test_setup.context.agent_id().address();
         let cap_request = make_cap_request_for_call(
             test_setup.context.clone(),
             Address::from(agent_token_str.clone()),
@@ -389,7 +389,7 @@ pub mod tests {
 
         // test assigned capability where the caller is someone else
         let other_agent_context = test_context("other agent", None);
-        let someone = other_agent_context.agent_id.address();
+        let someone = other_agent_context.agent_id().address();
         let mut cap_functions = CapFunctions::new();
         cap_functions.insert("test_zome".to_string(), vec![String::from("test")]);
         let grant = CapTokenGrant::create(
@@ -467,8 +467,8 @@ pub mod tests {
             "test_zome",
             make_cap_request_for_call(
                 context.clone(),
-                Address::from(context.agent_id.address()), // <- agent token
-                "foo_function",                            //<- not the function in the zome_call!
+                Address::from(context.agent_id().address()), // <- agent token
+                "foo_function",                              //<- not the function in the zome_call!
                 "{}",
             ),
             "test",
@@ -483,7 +483,7 @@ pub mod tests {
             "test_zome",
             make_cap_request_for_call(
                 context.clone(),
-                Address::from(context.agent_id.address()), // <- agent token
+                Address::from(context.agent_id().address()), // <- agent token
                 "test",
                 "{}",
             ),
@@ -0,0 +1,72 @@
+use crate::{
+    nucleus::ribosome::{api::ZomeApiResult, Runtime},
+    workflows::author_entry::author_entry,
+};
+use holochain_core_types::{
+    chain_migrate::{ChainMigrate, MigrationDirection},
+    entry::Entry,
+    error::HolochainError,
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+
+use holochain_wasm_utils::api_serialization::chain_migrate::CommitChainMigrateArgs;
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::CommitChainMigrate function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CommitChainMigrateArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_commit_chain_migrate(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let args = match CommitChainMigrateArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(..) => {
+            context.log(format!(
+                "err/zome: invoke_commit_chain_migrate failed to deserialize: {:?}",
+                args_str
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let dna_address = match context.get_dna() {
+        Some(dna) => dna.address(),
+        None => return ribosome_error_code!(WorkflowFailed),
+    };
+    let agent_address = match context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Context has no state".into()))
+        .and_then(|state| state.agent().get_agent_address())
+    {
+        Ok(address) => address,
+        Err(err) => {
+            context.log(format!(
+                "err/zome: invoke_commit_chain_migrate could not get agent address: {:?}",
+                err
+            ));
+            return ribosome_error_code!(WorkflowFailed);
+        }
+    };
+    let migration = match args.direction {
+        MigrationDirection::Close => {
+            ChainMigrate::close(dna_address, args.dna_address, agent_address)
+        }
+        MigrationDirection::Open => {
+            ChainMigrate::open(args.dna_address, dna_address, agent_address)
+        }
+    };
+
+    let res: Result<Address, HolochainError> = context
+        .block_on(author_entry(
+            &Entry::ChainMigrate(migration),
+            None,
+            &context.clone(),
+            &vec![],
+        ))
+        .map(|result| result.address());
+
+    runtime.store_result(res)
+}
@@ -1,10 +1,13 @@
 use crate::{
+    entry::encryption::{encrypt_entry, is_encrypted_type},
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
-    workflows::author_entry::author_entry,
+    workflows::{author_entry::author_entry_in_zome_call, countersign_entry::countersign_entry},
 };
 use holochain_core_types::error::HolochainError;
 
-use holochain_wasm_utils::api_serialization::commit_entry::{CommitEntryArgs, CommitEntryResult};
+use holochain_wasm_utils::api_serialization::commit_entry::{
+    CommitCountersignedEntryArgs, CommitEntryArgs, CommitEntryResult,
+};
 
 use std::convert::TryFrom;
 use wasmi::{RuntimeArgs, RuntimeValue};
@@ -29,13 +32,72 @@ pub fn invoke_commit_app_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> Zom
             return ribosome_error_code!(ArgumentDeserializationFailed);
         }
     };
-    // Wait for future to be resolved
-    let task_result: Result<CommitEntryResult, HolochainError> = context.block_on(author_entry(
+    // Entries of encrypted types get committed as cipher text. Their address is only known
+    // after encrypting, so nobody else can have signed it yet.
+    let provenances = commit_entry_arg.options().provenance();
+    if !provenances.is_empty()
+        && is_encrypted_type(&commit_entry_arg.entry().entry_type(), &context)
+    {
+        context
+            .log("err/zome: invoke_commit_app_entry can't add provenances to an encrypted entry");
+        return ribosome_error_code!(ArgumentDeserializationFailed);
+    }
+    let entry = match encrypt_entry(
         &commit_entry_arg.entry(),
-        None,
+        &commit_entry_arg.options().recipients(),
         &context,
-        &commit_entry_arg.options().provenance(),
-    ));
+    ) {
+        Ok(entry) => entry,
+        Err(error) => {
+            context.log(format!(
+                "err/zome: invoke_commit_app_entry failed to encrypt entry: {:?}",
+                error
+            ));
+            return ribosome_error_code!(Unspecified);
+        }
+    };
+    // Wait for future to be resolved
+    let task_result: Result<CommitEntryResult, HolochainError> = context.block_on(
+        author_entry_in_zome_call(&entry, None, &context, &provenances, runtime.zome_call()),
+    );
+
+    runtime.store_result(task_result)
+}
+
+/// ZomeApiFunction::CommitCountersignedEntry function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CommitCountersignedEntryArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_commit_countersigned_entry(
+    runtime: &mut Runtime,
+    args: &RuntimeArgs,
+) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let args = match CommitCountersignedEntryArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(error) => {
+            context.log(format!(
+                "err/zome: invoke_commit_countersigned_entry failed to \
+                 deserialize Entry: {:?} with error {:?}",
+                args_str, error
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+    // The counterparty commits the entry as it is, so it can't get encrypted
+    if is_encrypted_type(&args.entry.entry_type(), &context) {
+        context.log(
+            "err/zome: invoke_commit_countersigned_entry can't countersign an encrypted entry",
+        );
+        return ribosome_error_code!(ArgumentDeserializationFailed);
+    }
+
+    // Wait for the countersignature and both commits
+    let task_result: Result<CommitEntryResult, HolochainError> = context.block_on(
+        countersign_entry(&args.entry, args.counterparty, args.timeout, &context),
+    );
 
     runtime.store_result(task_result)
 }
@@ -96,5 +158,4 @@ pub mod tests {
             ),
         );
     }
-
 }
@@ -1,4 +1,5 @@
 use crate::{
+    entry::encryption::readable_entry_result,
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
     workflows::get_entry_result::get_entry_result_workflow,
 };
@@ -26,7 +27,9 @@ pub fn invoke_get_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiRes
         }
     };
     // Create workflow future and block on it
-    let result = context.block_on(get_entry_result_workflow(&context, &input));
+    let result = context
+        .block_on(get_entry_result_workflow(&context, &input))
+        .map(|result| readable_entry_result(result, &context));
     // Store result in wasm memory
     runtime.store_result(result)
 }
@@ -116,6 +116,7 @@ pub mod tests {
             assert!(initialized_context
                 .block_on(add_link(
                     &LinkData::add_from_link(&link, test_chain_header(), test_agent_id()),
+                    test_chain_header().timestamp(),
                     &initialized_context
                 ))
                 .is_ok());
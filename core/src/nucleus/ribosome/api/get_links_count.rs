@@ -0,0 +1,40 @@
+use crate::{
+    network::actions::get_links_count::get_links_count,
+    nucleus::ribosome::{api::ZomeApiResult, Runtime},
+};
+use holochain_wasm_utils::api_serialization::get_links::{GetLinksCountArgs, GetLinksCountResult};
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::GetLinksCount function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: GetLinksCountArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_get_links_count(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let input = match GetLinksCountArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(_) => {
+            context.log(format!(
+                "err/zome: invoke_get_links_count failed to deserialize GetLinksCountArgs: {:?}",
+                args_str
+            ));
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    let result = context
+        .block_on(get_links_count(
+            context.clone(),
+            input.entry_address,
+            input.link_type,
+            input.tag,
+            input.status_request,
+            input.timeout,
+        ))
+        .map(|count| GetLinksCountResult { count });
+
+    runtime.store_result(result)
+}
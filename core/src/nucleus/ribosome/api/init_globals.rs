@@ -19,12 +19,16 @@ pub fn invoke_init_globals(runtime: &mut Runtime, _args: &RuntimeArgs) -> ZomeAp
     let call_data = runtime.call_data()?;
     let dna = runtime.context()?.get_dna().unwrap();
     let dna_name = dna.name.clone();
+    let agent_encryption_key = match call_data.context.conductor_api.encryption_key() {
+        Ok(key) => key,
+        Err(error) => return runtime.store_result::<ZomeApiGlobals>(Err(error)),
+    };
     // Create the ZomeApiGlobals struct with some default values
     let mut globals = ZomeApiGlobals {
         dna_name,
         dna_address: Address::from(""),
-        agent_id_str: JsonString::from(call_data.context.agent_id.clone()).to_string(),
-        agent_address: Address::from(call_data.context.agent_id.address()),
+        agent_id_str: JsonString::from(call_data.context.agent_id()).to_string(),
+        agent_address: Address::from(call_data.context.agent_id().address()),
         agent_initial_hash: HashString::from(""),
         agent_latest_hash: HashString::from(""),
         public_token: Address::from(""),
@@ -33,6 +37,7 @@ pub fn invoke_init_globals(runtime: &mut Runtime, _args: &RuntimeArgs) -> ZomeAp
             .map(|zome_call_data| Some(zome_call_data.call.cap.clone()))
             .unwrap_or_else(|_| None),
         properties: JsonString::from(dna.properties),
+        agent_encryption_key,
     };
 
     // Update fields
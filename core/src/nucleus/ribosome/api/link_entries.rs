@@ -1,6 +1,6 @@
 use crate::{
     nucleus::ribosome::{api::ZomeApiResult, runtime::Runtime},
-    workflows::author_entry::author_entry,
+    workflows::author_entry::author_entry_in_zome_call,
 };
 use holochain_core_types::{
     entry::Entry,
@@ -49,14 +49,20 @@ pub fn invoke_link_entries(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
         &link,
         LinkActionKind::ADD,
         top_chain_header,
-        context.agent_id.clone(),
+        context.agent_id(),
     );
     let entry = Entry::LinkAdd(link_add);
 
     // Wait for future to be resolved
     // This is where the link entry actually gets created.
     let result: Result<Address, HolochainError> = context
-        .block_on(author_entry(&entry, None, &context, &vec![]))
+        .block_on(author_entry_in_zome_call(
+            &entry,
+            None,
+            &context,
+            &vec![],
+            runtime.zome_call(),
+        ))
         .map(|_| entry.address().clone());
 
     runtime.store_result(result)
@@ -237,5 +243,4 @@ pub mod tests {
 
         assert_ne!(result1, result2);
     }
-
 }
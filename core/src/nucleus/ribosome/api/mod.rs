@@ -1,6 +1,7 @@
 //! Module for ZomeApiFunctions
 //! ZomeApiFunctions are the functions provided by the ribosome that are callable by Zomes.
 
+pub mod bundle;
 pub mod call;
 pub mod commit;
 pub mod debug;
@@ -8,11 +9,13 @@ pub mod emit_signal;
 pub mod entry_address;
 pub mod get_entry;
 pub mod get_links;
+pub mod get_links_count;
 pub mod init_globals;
 pub mod link_entries;
 #[macro_use]
 mod macros;
 pub mod capabilities;
+pub mod chain_migrate;
 pub mod keystore;
 pub mod query;
 pub mod remove_entry;
@@ -20,19 +23,23 @@ pub mod remove_link;
 pub mod send;
 pub mod sign;
 pub mod sleep;
+pub mod update_agent;
 pub mod update_entry;
 pub mod verify_signature;
 
 use crate::nucleus::ribosome::{
     api::{
+        bundle::{invoke_close_bundle, invoke_start_bundle},
         call::invoke_call,
         capabilities::{invoke_commit_capability_claim, invoke_commit_capability_grant},
-        commit::invoke_commit_app_entry,
+        chain_migrate::invoke_commit_chain_migrate,
+        commit::{invoke_commit_app_entry, invoke_commit_countersigned_entry},
         debug::invoke_debug,
         emit_signal::invoke_emit_signal,
         entry_address::invoke_entry_address,
         get_entry::invoke_get_entry,
         get_links::invoke_get_links,
+        get_links_count::invoke_get_links_count,
         init_globals::invoke_init_globals,
         keystore::{
             invoke_keystore_derive_key, invoke_keystore_derive_seed,
@@ -40,12 +47,13 @@ use crate::nucleus::ribosome::{
             invoke_keystore_sign,
         },
         link_entries::invoke_link_entries,
-        query::invoke_query,
+        query::{invoke_query, invoke_query_page},
         remove_entry::invoke_remove_entry,
         remove_link::invoke_remove_link,
         send::invoke_send,
         sign::{invoke_sign, invoke_sign_one_time},
         sleep::invoke_sleep,
+        update_agent::invoke_update_agent,
         update_entry::invoke_update_entry,
         verify_signature::invoke_verify_signature,
     },
@@ -78,6 +86,10 @@ link_zome_api! {
     "hc_update_entry", UpdateEntry, invoke_update_entry;
     "hc_remove_entry", RemoveEntry, invoke_remove_entry;
 
+    /// Rotate the agent's key by committing a new AgentId entry that updates the current one
+    /// update_agent() -> Address
+    "hc_update_agent", UpdateAgent, invoke_update_agent;
+
     /// Init Zome API Globals
     /// hc_init_globals() -> InitGlobalsOutput
     "hc_init_globals", InitGlobals, invoke_init_globals;
@@ -144,6 +156,24 @@ link_zome_api! {
 
     /// Send a DNA defined signal to UIs and other listeners
     "hc_emit_signal", EmitSignal, invoke_emit_signal;
+
+    /// Commit a chain migration entry that closes or opens the source chain
+    "hc_commit_chain_migrate", CommitChainMigrate, invoke_commit_chain_migrate;
+
+    /// Stage all following commits until the bundle gets closed
+    "hc_start_bundle", StartBundle, invoke_start_bundle;
+
+    /// Commit or discard all commits staged since start_bundle
+    "hc_close_bundle", CloseBundle, invoke_close_bundle;
+
+    /// Commit an entry of a countersigned entry type together with another agent
+    "hc_commit_countersigned_entry", CommitCountersignedEntry, invoke_commit_countersigned_entry;
+
+    /// Count the links of a base without fetching them
+    "hc_get_links_count", GetLinksCount, invoke_get_links_count;
+
+    /// Query the local chain for entries, returning a page with a cursor to the next one
+    "hc_query_page", QueryPage, invoke_query_page;
 }
 
 #[cfg(test)]
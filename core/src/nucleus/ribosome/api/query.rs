@@ -1,6 +1,7 @@
 use crate::{
     agent::chain_store::{ChainStoreQueryOptions, ChainStoreQueryResult},
     context::Context,
+    entry::encryption::readable_entry,
     nucleus::{
         actions::get_entry::get_entry_from_agent,
         ribosome::{api::ZomeApiResult, Runtime},
@@ -14,7 +15,7 @@ use holochain_core_types::{
     entry::{entry_type::EntryType, Entry},
     error::HolochainError,
 };
-use holochain_wasm_utils::api_serialization::{QueryArgs, QueryArgsNames, QueryResult};
+use holochain_wasm_utils::api_serialization::{QueryArgs, QueryArgsNames, QueryPage, QueryResult};
 use std::{convert::TryFrom, sync::Arc};
 use wasmi::{RuntimeArgs, RuntimeValue};
 
@@ -70,94 +71,110 @@ pub fn invoke_query(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult
         Err(..) => return ribosome_error_code!(ArgumentDeserializationFailed),
     };
 
-    // Perform query
+    match query_page(&context, query) {
+        Some(page) => runtime.store_result(Ok::<QueryResult, HolochainError>(page.result)),
+        None => ribosome_error_code!(UnknownEntryType),
+    }
+}
+
+/// ZomeApiFunction::query_page function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: QueryArgs
+/// Returns an HcApiReturnCode as I64
+///
+/// Same as hc_query, but returns a QueryPage holding the result together with the cursor to
+/// pass in QueryArgsOptions::cursor to get the next page, if more entries match.
+pub fn invoke_query_page(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args.
+    let args_str = runtime.load_json_string_from_args(&args);
+    let query = match QueryArgs::try_from(args_str) {
+        Ok(input) => input,
+        Err(..) => return ribosome_error_code!(ArgumentDeserializationFailed),
+    };
+
+    match query_page(&context, query) {
+        Some(page) => runtime.store_result(Ok::<QueryPage, HolochainError>(page)),
+        None => ribosome_error_code!(UnknownEntryType),
+    }
+}
+
+/// Runs the query against the local chain.
+/// Returns None for unknown entry types or entries that could not be read.
+fn query_page(context: &Arc<Context>, query: QueryArgs) -> Option<QueryPage> {
     let agent = context.state().unwrap().agent();
     let top = agent
         .top_chain_header()
         .expect("Should have genesis entries.");
-    let maybe_result = match query.entry_type_names {
-        // Result<ChainStoreQueryResult,...>
-        QueryArgsNames::QueryList(pats) => {
-            let refs: Vec<&str> = pats.iter().map(AsRef::as_ref).collect(); // Vec<String> -> Vec<&str>
-            agent.chain_store().query(
-                &Some(top),
-                refs.as_slice(), // Vec<&str> -> Vec[&str]
-                ChainStoreQueryOptions {
-                    start: query.options.start,
-                    limit: query.options.limit,
-                    headers: query.options.headers,
-                },
-            )
-        }
-        QueryArgsNames::QueryName(name) => {
-            let refs: Vec<&str> = vec![&name]; // String -> Vec<&str>
-            agent.chain_store().query(
-                &Some(top),
-                refs.as_slice(), // Vec<&str> -> &[&str]
-                ChainStoreQueryOptions {
-                    start: query.options.start,
-                    limit: query.options.limit,
-                    headers: query.options.headers,
-                },
-            )
-        }
+    let names: Vec<String> = match query.entry_type_names {
+        QueryArgsNames::QueryList(pats) => pats,
+        QueryArgsNames::QueryName(name) => vec![name],
     };
-    let result = match maybe_result {
-        // TODO #793: the Err(_code) is the RibosomeErrorCode, but we can't import that type here.
-        // Perhaps return chain_store().query should return Some(result)/None instead, and the fixed
-        // UnknownEntryType code here, rather than trying to return a specific error code.
-        Ok(result) => Ok(match (query.options.entries, result) {
-            (false, ChainStoreQueryResult::Addresses(addresses)) => {
-                QueryResult::Addresses(addresses)
-            }
-            (false, ChainStoreQueryResult::Headers(headers)) => QueryResult::Headers(headers),
-            (true, ChainStoreQueryResult::Addresses(addresses)) => {
-                let maybe_entries: Result<Vec<(Address, Entry)>, HolochainError> = addresses
-                    .iter()
-                    .map(|address| // -> Result<Entry, HolochainError>
-                         Ok((address.to_owned(), get_entry_from_chain(&context, address)?)))
-                    .filter(|maybe_entry_address_pair| match maybe_entry_address_pair {
-                        // Don't include DNA entries since we are storing the result in WASM memory
-                        // and DNA entries are usually quite big (several MBs).
-                        Ok((_, entry)) => entry.entry_type() != EntryType::Dna,
-                        Err(_) => true,
-                    })
-                    .collect();
+    let refs: Vec<&str> = names.iter().map(AsRef::as_ref).collect(); // Vec<String> -> Vec<&str>
 
-                match maybe_entries {
-                    Ok(entries) => QueryResult::Entries(entries),
-                    Err(_e) => return ribosome_error_code!(UnknownEntryType), // TODO: return actual error?
-                }
-            }
-            (true, ChainStoreQueryResult::Headers(headers)) => {
-                let maybe_headers_with_entries: Result<Vec<(ChainHeader,Entry)>,HolochainError> = headers
+    // TODO #793: the Err(_code) is the RibosomeErrorCode, but we can't import that type here.
+    // Perhaps return chain_store().query should return Some(result)/None instead, and the fixed
+    // UnknownEntryType code here, rather than trying to return a specific error code.
+    let (result, cursor) = agent
+        .chain_store()
+        .query_page(
+            &Some(top),
+            refs.as_slice(), // Vec<&str> -> &[&str]
+            ChainStoreQueryOptions {
+                start: query.options.start,
+                limit: query.options.limit,
+                headers: query.options.headers,
+                since: query.options.since,
+                until: query.options.until,
+                order: query.options.order,
+                cursor: query.options.cursor,
+            },
+        )
+        .ok()?;
+    let result = match (query.options.entries, result) {
+        (false, ChainStoreQueryResult::Addresses(addresses)) => QueryResult::Addresses(addresses),
+        (false, ChainStoreQueryResult::Headers(headers)) => QueryResult::Headers(headers),
+        (true, ChainStoreQueryResult::Addresses(addresses)) => {
+            let maybe_entries: Result<Vec<(Address, Entry)>, HolochainError> = addresses
+                .iter()
+                .map(|address| // -> Result<Entry, HolochainError>
+                     Ok((address.to_owned(), get_entry_from_chain(&context, address)?)))
+                .filter(|maybe_entry_address_pair| match maybe_entry_address_pair {
+                    // Don't include DNA entries since we are storing the result in WASM memory
+                    // and DNA entries are usually quite big (several MBs).
+                    Ok((_, entry)) => entry.entry_type() != EntryType::Dna,
+                    Err(_) => true,
+                })
+                .collect();
+            // TODO: return actual error?
+            QueryResult::Entries(maybe_entries.ok()?)
+        }
+        (true, ChainStoreQueryResult::Headers(headers)) => {
+            let maybe_headers_with_entries: Result<Vec<(ChainHeader, Entry)>, HolochainError> =
+                headers
                     .iter()
                     // Don't include DNA entries since we are storing the result in WASM memory
                     // and DNA entries are usually quite big (several MBs).
                     .filter(|header| *header.entry_type() != EntryType::Dna)
                     .map(|header| // -> Result<Entry, HolochainError>
-                         Ok((header.to_owned(), get_entry_from_chain(&context,header.entry_address())?)))
+                         Ok((header.to_owned(), get_entry_from_chain(&context, header.entry_address())?)))
                     .collect();
-                match maybe_headers_with_entries {
-                    Ok(headers_with_entries) => {
-                        QueryResult::HeadersWithEntries(headers_with_entries)
-                    }
-                    Err(_e) => return ribosome_error_code!(UnknownEntryType), // TODO: return actual error?
-                }
-            }
-        }),
-        Err(_code) => return ribosome_error_code!(UnknownEntryType),
+            // TODO: return actual error?
+            QueryResult::HeadersWithEntries(maybe_headers_with_entries.ok()?)
+        }
     };
-
-    runtime.store_result(result)
+    Some(QueryPage { result, cursor })
 }
 
-/// Get an local-chain Entry via the provided context, returning Entry or HolochainError on failure
+/// Get an local-chain Entry via the provided context, returning Entry or HolochainError on failure.
+/// Entries of encrypted types are returned decrypted.
 fn get_entry_from_chain(
     context: &Arc<Context>,
     address: &Address,
 ) -> Result<Entry, HolochainError> {
-    get_entry_from_agent(context, address)?.ok_or_else(|| {
-        HolochainError::ErrorGeneric(format!("Failed to obtain Entry for Address {}", address))
-    })
+    get_entry_from_agent(context, address)?
+        .map(|entry| readable_entry(entry, context))
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("Failed to obtain Entry for Address {}", address))
+        })
 }
@@ -1,6 +1,8 @@
 use crate::{
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
-    workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
+    workflows::{
+        author_entry::author_entry_in_zome_call, get_entry_result::get_entry_result_workflow,
+    },
 };
 use holochain_core_types::{
     entry::{deletion_entry::DeletionEntry, Entry},
@@ -61,11 +63,12 @@ pub fn invoke_remove_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
     let deletion_entry = Entry::Deletion(DeletionEntry::new(deleted_entry_address.clone()));
 
     let res: Result<Address, HolochainError> = context
-        .block_on(author_entry(
+        .block_on(author_entry_in_zome_call(
             &deletion_entry.clone(),
             Some(deleted_entry_address.clone()),
             &context.clone(),
             &vec![],
+            runtime.zome_call(),
         ))
         .map(|_| deletion_entry.address());
 
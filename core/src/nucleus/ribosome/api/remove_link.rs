@@ -1,7 +1,9 @@
 use crate::{
     network::actions::get_links::get_links,
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
-    workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
+    workflows::{
+        author_entry::author_entry_in_zome_call, get_entry_result::get_entry_result_workflow,
+    },
 };
 
 use holochain_core_types::{
@@ -56,14 +58,14 @@ pub fn invoke_remove_link(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiR
         &link,
         LinkActionKind::REMOVE,
         top_chain_header,
-        context.agent_id.clone(),
+        context.agent_id(),
     );
     let links_result = context.block_on(get_links(
         context.clone(),
         link.base().clone(),
         link.link_type().clone(),
         link.tag().clone(),
-        GetLinksOptions::default().timeout.clone(),
+        GetLinksOptions::default(),
     ));
     if links_result.is_err() {
         context.log("err/zome : Could not get links for remove_link method");
@@ -103,7 +105,13 @@ pub fn invoke_remove_link(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiR
 
         // Wait for future to be resolved
         let result: Result<(), HolochainError> = context
-            .block_on(author_entry(&entry, None, &context, &vec![]))
+            .block_on(author_entry_in_zome_call(
+                &entry,
+                None,
+                &context,
+                &vec![],
+                runtime.zome_call(),
+            ))
             .map(|_| ());
 
         runtime.store_result(result)
@@ -35,7 +35,9 @@ pub fn invoke_sign(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
 
     context.log(format!(
         "debug/zome: signature of data:{:?} by:{:?} is:{:?}",
-        sign_args.payload, context.agent_id, signature
+        sign_args.payload,
+        context.agent_id(),
+        signature
     ));
 
     runtime.store_result(signature)
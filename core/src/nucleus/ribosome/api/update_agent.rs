@@ -0,0 +1,17 @@
+use crate::{
+    nucleus::ribosome::{api::ZomeApiResult, Runtime},
+    workflows::update_agent::update_agent_workflow,
+};
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::UpdateAgent function code
+/// args: none
+/// Returns the address of the new AgentId entry as an HcApiReturnCode as I64
+pub fn invoke_update_agent(runtime: &mut Runtime, _args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    let result = context.block_on(update_agent_workflow(&context));
+    if let Err(ref err) = result {
+        context.log(format!("err/zome: update_agent_workflow failed: {:?}", err));
+    }
+    runtime.store_result(result)
+}
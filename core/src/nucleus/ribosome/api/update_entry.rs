@@ -1,6 +1,9 @@
 use crate::{
+    entry::encryption::{encrypt_entry, recipients_of},
     nucleus::ribosome::{api::ZomeApiResult, Runtime},
-    workflows::{author_entry::author_entry, get_entry_result::get_entry_result_workflow},
+    workflows::{
+        author_entry::author_entry_in_zome_call, get_entry_result::get_entry_result_workflow,
+    },
 };
 use holochain_core_types::{entry::Entry, error::HolochainError};
 
@@ -49,15 +52,29 @@ pub fn invoke_update_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApi
     }
     let latest_entry = entry_result.latest().unwrap();
 
-    // Create Chain Entry
-    let entry = Entry::from(entry_args.new_entry.clone());
+    // Create Chain Entry, encrypted for the recipients of the old one if its type is encrypted
+    let entry = match encrypt_entry(
+        &Entry::from(entry_args.new_entry.clone()),
+        &recipients_of(&latest_entry),
+        &context,
+    ) {
+        Ok(entry) => entry,
+        Err(error) => {
+            context.log(format!(
+                "err/zome: invoke_update_entry failed to encrypt entry: {:?}",
+                error
+            ));
+            return ribosome_error_code!(Unspecified);
+        }
+    };
 
     let res: Result<Address, HolochainError> = context
-        .block_on(author_entry(
+        .block_on(author_entry_in_zome_call(
             &entry,
             Some(latest_entry.clone().address()),
             &context.clone(),
             &vec![], // TODO should provenance be a parameter?
+            runtime.zome_call(),
         ))
         .map(|result| result.address())
         .map_err(|validation_error| HolochainError::from(validation_error));
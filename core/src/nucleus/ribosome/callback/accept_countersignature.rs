@@ -0,0 +1,101 @@
+use crate::{
+    context::Context,
+    nucleus::{
+        ribosome::{
+            self,
+            callback::{Callback, CallbackParams, CallbackResult},
+            runtime::WasmCallData,
+            Defn,
+        },
+        CallbackFnCall,
+    },
+};
+
+use holochain_json_api::json::JsonString;
+use std::sync::Arc;
+
+/// Asks the zome if the agent countersigns the proposed entry.
+/// Returns Pass if the zome accepts it, Fail with the reason if it rejects it and
+/// NotImplemented if the zome has no accept_countersignature callback.
+pub fn accept_countersignature(
+    context: Arc<Context>,
+    zome: &str,
+    parameters: &CallbackParams,
+) -> CallbackResult {
+    let params = match parameters {
+        CallbackParams::AcceptCountersignature(params) => params,
+        _ => return CallbackResult::NotImplemented("accept_countersignature/1".into()),
+    };
+
+    let call = CallbackFnCall::new(
+        zome,
+        &Callback::AcceptCountersignature.as_str().to_string(),
+        JsonString::from(params),
+    );
+
+    match ribosome::run_dna(
+        Some(call.clone().parameters.to_bytes()),
+        WasmCallData::new_callback_call(context, call),
+    ) {
+        Ok(call_result) => {
+            if call_result.is_null() {
+                CallbackResult::Pass
+            } else {
+                CallbackResult::Fail(call_result.to_string())
+            }
+        }
+        Err(_) => CallbackResult::NotImplemented("accept_countersignature/2".into()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::accept_countersignature;
+    use crate::{
+        instance::tests::test_context,
+        nucleus::ribosome::{
+            callback::{tests::test_callback_instance, Callback, CallbackParams, CallbackResult},
+            Defn,
+        },
+    };
+    use holochain_core_types::entry::test_entry;
+    use holochain_persistence_api::cas::content::Address;
+    use holochain_wasm_utils::api_serialization::commit_entry::AcceptCountersignatureParams;
+
+    fn params() -> CallbackParams {
+        CallbackParams::AcceptCountersignature(AcceptCountersignatureParams {
+            from: Address::from("proposer"),
+            entry: test_entry(),
+        })
+    }
+
+    #[test]
+    fn not_implemented() {
+        let zome = "test_zome";
+        let netname = Some("accept_countersignature not_implemented");
+        let instance = test_callback_instance(zome, Callback::Receive.as_str(), 0, netname)
+            .expect("Test callback instance could not be initialized");
+        let context = instance.initialize_context(test_context("test", netname));
+
+        match accept_countersignature(context, zome, &params()) {
+            CallbackResult::NotImplemented(_) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn implemented_with_null() {
+        let zome = "test_zome";
+        let netname = Some("accept_countersignature implemented_with_null");
+        let instance =
+            test_callback_instance(zome, Callback::AcceptCountersignature.as_str(), 0, netname)
+                .expect("Test callback instance could not be initialized");
+        let context = instance.initialize_context(test_context("test", netname));
+
+        assert_eq!(
+            CallbackResult::Pass,
+            accept_countersignature(context, zome, &params())
+        );
+    }
+}
@@ -4,44 +4,43 @@ use holochain_core_types::{
     error::HolochainError,
     link::Link,
 };
+use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::{get_entry::*, validation::LinkDirection};
 use std::sync::Arc;
 
 /// Retrieves the base and target entries of the link and returns both.
+/// Entries staged in a bundle that is being closed are used as well, since links of a
+/// bundle get validated before any of its entries are on the DHT.
 pub fn get_link_entries(
     link: &Link,
     context: &Arc<Context>,
 ) -> Result<(Entry, Entry), HolochainError> {
-    let base_address = link.base();
-    let target_address = link.target();
-    let entry_args = &GetEntryArgs {
-        address: base_address.clone(),
-        options: Default::default(),
-    };
-    let base_entry_get_result =
-        context.block_on(get_entry_result_workflow(&context, entry_args))?;
-    if !base_entry_get_result.found() {
-        return Err(HolochainError::ErrorGeneric(String::from(
-            "Base for link not found",
-        )));
+    let base_entry = get_link_entry(link.base(), context)?
+        .ok_or_else(|| HolochainError::ErrorGeneric(String::from("Base for link not found")))?;
+    let target_entry = get_link_entry(link.target(), context)?
+        .ok_or_else(|| HolochainError::ErrorGeneric(String::from("Target for link not found")))?;
+    Ok((base_entry, target_entry))
+}
+
+fn get_link_entry(
+    address: &Address,
+    context: &Arc<Context>,
+) -> Result<Option<Entry>, HolochainError> {
+    let bundled_entry = context
+        .state()
+        .and_then(|state| state.agent().get_closing_bundle_entry(address));
+    if bundled_entry.is_some() {
+        return Ok(bundled_entry);
     }
-    let base_entry = base_entry_get_result.latest().unwrap();
     let entry_args = &GetEntryArgs {
-        address: target_address.clone(),
+        address: address.clone(),
         options: Default::default(),
     };
-    let target_entry_get_result =
-        context.block_on(get_entry_result_workflow(&context, entry_args))?;
-    if !target_entry_get_result.found() {
-        return Err(HolochainError::ErrorGeneric(String::from(
-            "Target for link not found",
-        )));
+    let get_result = context.block_on(get_entry_result_workflow(&context, entry_args))?;
+    if !get_result.found() {
+        return Ok(None);
     }
-
-    Ok((
-        base_entry.clone(),
-        target_entry_get_result.latest().unwrap(),
-    ))
+    Ok(get_result.latest())
 }
 
 /// This is a "path" in the DNA tree.
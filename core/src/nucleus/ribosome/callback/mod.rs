@@ -1,6 +1,7 @@
 //! Module for ZomeCallbacks
 //! ZomeCallbacks are functions in a Zome that are callable by the ribosome.
 
+pub mod accept_countersignature;
 pub mod genesis;
 pub mod links_utils;
 pub mod receive;
@@ -11,7 +12,10 @@ use crate::{
     nucleus::{
         ribosome::{
             self,
-            callback::{genesis::genesis, receive::receive},
+            callback::{
+                accept_countersignature::accept_countersignature, genesis::genesis,
+                receive::receive,
+            },
             runtime::WasmCallData,
             Defn,
         },
@@ -28,7 +32,8 @@ use holochain_json_api::{
 };
 
 use holochain_wasm_utils::{
-    api_serialization::receive::ReceiveParams, memory::allocation::WasmAllocation,
+    api_serialization::{commit_entry::AcceptCountersignatureParams, receive::ReceiveParams},
+    memory::allocation::WasmAllocation,
 };
 use num_traits::FromPrimitive;
 use serde_json;
@@ -49,6 +54,9 @@ pub enum Callback {
 
     /// receive(from: Address, message: String) -> String
     Receive,
+
+    /// accept_countersignature(from: Address, entry: Entry) -> Result<(), String>
+    AcceptCountersignature,
 }
 
 impl FromStr for Callback {
@@ -57,6 +65,7 @@ impl FromStr for Callback {
         match s {
             "genesis" => Ok(Callback::Genesis),
             "receive" => Ok(Callback::Receive),
+            "accept_countersignature" => Ok(Callback::AcceptCountersignature),
             other if other.is_empty() => Ok(Callback::MissingNo),
             _ => Err("Cannot convert string to Callback"),
         }
@@ -79,6 +88,7 @@ impl Callback {
             // @TODO call this from somewhere
             // @see https://github.com/holochain/holochain-rust/issues/201
             Callback::Receive => receive,
+            Callback::AcceptCountersignature => accept_countersignature,
         }
     }
 }
@@ -89,6 +99,7 @@ impl Defn for Callback {
             Callback::MissingNo => "",
             Callback::Genesis => "genesis",
             Callback::Receive => "receive",
+            Callback::AcceptCountersignature => "accept_countersignature",
         }
     }
 
@@ -112,6 +123,7 @@ pub enum CallbackParams {
     Genesis,
     ValidateCommit(Entry),
     Receive(ReceiveParams),
+    AcceptCountersignature(AcceptCountersignatureParams),
 }
 
 impl ToString for CallbackParams {
@@ -122,6 +134,9 @@ impl ToString for CallbackParams {
                 String::from(JsonString::from(serialized_entry.to_owned()))
             }
             CallbackParams::Receive(params) => JsonString::from(params).to_string(),
+            CallbackParams::AcceptCountersignature(params) => {
+                JsonString::from(params).to_string()
+            }
         }
     }
 }
@@ -327,12 +342,18 @@ pub mod tests {
             (Callback::MissingNo, ""),
             (Callback::Genesis, "genesis"),
             (Callback::Receive, "receive"),
+            (Callback::AcceptCountersignature, "accept_countersignature"),
         ] {
             assert_eq!(output, input.as_str());
         }
 
         // str_to_index()
-        for (input, output) in vec![("", 0), ("genesis", 1), ("receive", 2)] {
+        for (input, output) in vec![
+            ("", 0),
+            ("genesis", 1),
+            ("receive", 2),
+            ("accept_countersignature", 3),
+        ] {
             assert_eq!(output, Callback::str_to_index(input));
         }
 
@@ -341,6 +362,7 @@ pub mod tests {
             (0, Callback::MissingNo),
             (1, Callback::Genesis),
             (2, Callback::Receive),
+            (3, Callback::AcceptCountersignature),
         ] {
             assert_eq!(output, Callback::from_index(input));
         }
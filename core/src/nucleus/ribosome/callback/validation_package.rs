@@ -116,7 +116,8 @@ pub fn get_validation_package_definition(
         }
         EntryType::Deletion => JsonString::from(ValidationPackageDefinition::ChainFull),
         EntryType::CapTokenGrant => JsonString::from(ValidationPackageDefinition::Entry),
-        EntryType::AgentId => JsonString::from(ValidationPackageDefinition::Entry),
+        EntryType::AgentId => JsonString::from(ValidationPackageDefinition::ChainHeaders),
+        EntryType::ChainMigrate => JsonString::from(ValidationPackageDefinition::ChainHeaders),
         _ => Err(HolochainError::NotImplemented(
             "get_validation_package_definition/3".into(),
         ))?,
@@ -122,6 +122,14 @@ impl Runtime {
         }
     }
 
+    /// The zome call this runtime executes, None for callbacks
+    pub fn zome_call(&self) -> Option<ZomeFnCall> {
+        match &self.data {
+            WasmCallData::ZomeCall(ref data) => Some(data.call.clone()),
+            _ => None,
+        }
+    }
+
     pub fn callback_call_data(&self) -> Result<CallbackCallData, Trap> {
         match &self.data {
             WasmCallData::CallbackCall(ref data) => Ok(data.clone()),
@@ -11,7 +11,10 @@ use holochain_json_api::{
 };
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
 use snowflake;
-use std::{collections::HashMap, convert::TryFrom};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, DefaultJson)]
 pub enum NucleusStatus {
@@ -47,6 +50,11 @@ pub struct NucleusState {
     pub dna: Option<Dna>, //DNA is transient here because it is stored in the chain and gets
     //read from there when loading an instance/chain.
 
+    /// Keys of the pending validations waiting on each dependency address.
+    /// Derived from pending_validations, which is why it only gets changed together with it
+    /// through add_pending_validation() and remove_pending_validation().
+    pub pending_validations_by_dependency: HashMap<Address, HashSet<PendingValidationKey>>,
+
     // @TODO eventually drop stale calls
     // @see https://github.com/holochain/holochain-rust/issues/166
     // @TODO should this use the standard ActionWrapper/ActionResponse format?
@@ -66,7 +74,56 @@ impl NucleusState {
             validation_results: HashMap::new(),
             validation_packages: HashMap::new(),
             pending_validations: HashMap::new(),
+            pending_validations_by_dependency: HashMap::new(),
+        }
+    }
+
+    /// Adds the pending validation, replacing an older one with the same key,
+    /// and indexes it under all its dependencies
+    pub fn add_pending_validation(&mut self, pending: PendingValidation) {
+        let key = pending.key();
+        self.remove_pending_validation(&key);
+        for dependency in pending.dependencies.iter() {
+            self.pending_validations_by_dependency
+                .entry(dependency.clone())
+                .or_insert_with(HashSet::new)
+                .insert(key.clone());
+        }
+        self.pending_validations.insert(key, pending);
+    }
+
+    /// Removes the pending validation together with its dependency index entries
+    pub fn remove_pending_validation(
+        &mut self,
+        key: &PendingValidationKey,
+    ) -> Option<PendingValidation> {
+        let pending = self.pending_validations.remove(key)?;
+        for dependency in pending.dependencies.iter() {
+            let now_empty = self
+                .pending_validations_by_dependency
+                .get_mut(dependency)
+                .map(|keys| {
+                    keys.remove(key);
+                    keys.is_empty()
+                })
+                .unwrap_or(false);
+            if now_empty {
+                self.pending_validations_by_dependency.remove(dependency);
+            }
         }
+        Some(pending)
+    }
+
+    /// All pending validations that have the given address as one of their dependencies
+    pub fn pending_validations_waiting_on(&self, address: &Address) -> Vec<PendingValidation> {
+        self.pending_validations_by_dependency
+            .get(address)
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|key| self.pending_validations.get(key).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
     pub fn zome_call_result(
@@ -125,14 +182,23 @@ impl From<&State> for NucleusStateSnapshot {
 
 impl From<NucleusStateSnapshot> for NucleusState {
     fn from(snapshot: NucleusStateSnapshot) -> Self {
-        NucleusState {
+        let mut state = NucleusState {
             dna: None,
             status: snapshot.status,
             zome_calls: HashMap::new(),
             validation_results: HashMap::new(),
             validation_packages: HashMap::new(),
-            pending_validations: snapshot.pending_validations,
+            pending_validations: HashMap::new(),
+            pending_validations_by_dependency: HashMap::new(),
+        };
+        for pending in snapshot
+            .pending_validations
+            .into_iter()
+            .map(|(_, pending)| pending)
+        {
+            state.add_pending_validation(pending);
         }
+        state
     }
 }
 
@@ -155,10 +221,47 @@ impl AddressableContent for NucleusStateSnapshot {
 pub mod tests {
 
     use super::NucleusState;
+    use crate::{
+        network::entry_with_header::EntryWithHeader,
+        scheduled_jobs::pending_validations::{PendingValidationStruct, ValidatingWorkflow},
+    };
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry};
+    use holochain_persistence_api::cas::content::Address;
+    use std::sync::Arc;
 
     /// dummy nucleus state
     pub fn test_nucleus_state() -> NucleusState {
         NucleusState::new()
     }
 
+    #[test]
+    fn pending_validations_are_indexed_by_dependency() {
+        let mut state = test_nucleus_state();
+        let dependency = Address::from("dependency");
+        let pending = Arc::new(PendingValidationStruct::new(
+            EntryWithHeader {
+                entry: test_entry(),
+                header: test_chain_header(),
+            },
+            vec![dependency.clone()],
+            ValidatingWorkflow::HoldEntry,
+        ));
+
+        state.add_pending_validation(pending.clone());
+        assert_eq!(
+            state.pending_validations_waiting_on(&dependency),
+            vec![pending.clone()]
+        );
+        assert!(state
+            .pending_validations_waiting_on(&Address::from("other"))
+            .is_empty());
+
+        assert_eq!(
+            state.remove_pending_validation(&pending.key()),
+            Some(pending)
+        );
+        assert!(state.pending_validations_waiting_on(&dependency).is_empty());
+        assert!(state.pending_validations_by_dependency.is_empty());
+    }
+
 }
@@ -0,0 +1,59 @@
+use crate::nucleus::validation::{previous_headers, ValidationError, ValidationResult};
+use holochain_core_types::{
+    entry::{entry_type::EntryType, Entry},
+    validation::ValidationData,
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+
+/// Validates an AgentId entry.
+///
+/// An AgentId entry that updates another AgentId entry rotates the agent's key.
+/// Since the address of an AgentId entry is the agent's public key, the rotation is valid
+/// if it replaces the agent's current key, which is the latest AgentId entry before it
+/// on the chain, and if it is signed by both that key and the new key.
+/// The signatures themselves have already been verified by the provenances check.
+pub fn validate_agent_entry(
+    entry: Entry,
+    link: Option<Address>,
+    validation_data: &ValidationData,
+) -> ValidationResult {
+    let agent = unwrap_to!(entry=>Entry::AgentId);
+    let header = &validation_data.package.chain_header;
+    let old_agent_address = match link.or_else(|| header.link_update_delete()) {
+        Some(address) => address,
+        None => return Ok(()),
+    };
+    let new_agent_address = agent.address();
+
+    if old_agent_address == new_agent_address {
+        return Err(ValidationError::Fail(
+            "Key rotation has to change the agent's key".to_string(),
+        ));
+    }
+
+    let current_agent_address = previous_headers(validation_data)?
+        .into_iter()
+        .find(|header| *header.entry_type() == EntryType::AgentId)
+        .map(|header| header.entry_address().clone());
+    if current_agent_address != Some(old_agent_address.clone()) {
+        return Err(ValidationError::Fail(format!(
+            "Key rotation from {} does not replace the agent's current key",
+            old_agent_address
+        )));
+    }
+
+    let signers: Vec<Address> = header
+        .provenances()
+        .iter()
+        .map(|provenance| provenance.source())
+        .collect();
+    for key in [&old_agent_address, &new_agent_address].iter() {
+        if !signers.contains(key) {
+            return Err(ValidationError::Fail(format!(
+                "Key rotation from {} to {} is not signed by {}",
+                old_agent_address, new_agent_address, key
+            )));
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::{
     context::Context,
+    entry::encryption::{check_encryption, readable_validation_data},
     nucleus::{
         actions::{
             get_entry::get_entry_from_dht, run_validation_callback::run_validation_callback,
@@ -25,6 +26,7 @@ pub async fn validate_app_entry(
     validation_data: ValidationData,
 ) -> ValidationResult {
     let dna = context.get_dna().expect("Callback called without DNA set!");
+    check_encryption(&entry, context).map_err(ValidationError::Fail)?;
 
     let zome_name = dna
         .get_zome_name_for_app_entry_type(&app_entry_type)
@@ -35,11 +37,11 @@ pub async fn validate_app_entry(
         })?;
     };
 
+    let entry_validation_data =
+        entry_to_validation_data(context.clone(), &entry, link, validation_data)?;
+    // Validators that are recipients of an encrypted entry get to see the plain entry
     let params = EntryValidationArgs {
-        validation_data: entry_to_validation_data(context.clone(), &entry, link, validation_data)
-            .map_err(|_| {
-            ValidationError::Fail("Could not get entry validation".to_string())
-        })?,
+        validation_data: readable_validation_data(entry_validation_data, context),
     };
     let call = CallbackFnCall::new(&zome_name, "__hdk_validate_app_entry", params);
 
@@ -0,0 +1,78 @@
+use crate::{
+    context::Context,
+    nucleus::validation::{previous_headers, ValidationError, ValidationResult},
+};
+use holochain_core_types::{
+    chain_migrate::MigrationDirection,
+    entry::{entry_type::EntryType, Entry},
+    validation::ValidationData,
+};
+use holochain_persistence_api::cas::content::AddressableContent;
+use std::sync::Arc;
+
+/// Validates a ChainMigrate entry against the DNA of the chain it got committed to.
+///
+/// Both ends of a migration carry the addresses of the old and the new DNA.
+/// A `Close` entry has to sit on the chain of the old DNA and point to the new one,
+/// an `Open` entry has to sit on the chain of the new DNA and point back to the old one.
+/// Together this makes sure the closed and the opened chain reference each other.
+/// The agent named in the entry has to be the one who authored it.
+/// An `Open` entry has to be the first entry after genesis, so that the opened chain
+/// does not carry anything from before the migration.
+pub fn validate_chain_migrate(
+    entry: Entry,
+    validation_data: &ValidationData,
+    context: &Arc<Context>,
+) -> ValidationResult {
+    let migration = unwrap_to!(entry=>Entry::ChainMigrate);
+    let dna = context
+        .get_dna()
+        .ok_or_else(|| ValidationError::Error("No DNA set in context".to_string()))?;
+
+    if migration.old_dna_address() == migration.new_dna_address() {
+        return Err(ValidationError::Fail(
+            "Chain migration must move to a different DNA".to_string(),
+        ));
+    }
+
+    if *migration.own_dna_address() != dna.address() {
+        let expected = match migration.direction() {
+            MigrationDirection::Close => "closing migration must reference this DNA as old DNA",
+            MigrationDirection::Open => "opening migration must reference this DNA as new DNA",
+        };
+        return Err(ValidationError::Fail(format!(
+            "Invalid chain migration: {} ({}), found {}",
+            expected,
+            dna.address(),
+            migration.own_dna_address()
+        )));
+    }
+
+    let authored_by_agent = validation_data
+        .package
+        .chain_header
+        .provenances()
+        .iter()
+        .any(|provenance| provenance.source() == *migration.agent());
+    if !authored_by_agent {
+        return Err(ValidationError::Fail(format!(
+            "Chain migration of agent {} was not authored by that agent",
+            migration.agent()
+        )));
+    }
+
+    if migration.direction() == MigrationDirection::Open {
+        let previous = previous_headers(validation_data)?;
+        let after_genesis = previous.iter().all(|header| match header.entry_type() {
+            EntryType::Dna | EntryType::AgentId => true,
+            _ => false,
+        });
+        if !after_genesis {
+            return Err(ValidationError::Fail(
+                "Opening chain migration must be the first entry after genesis".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -6,11 +6,13 @@ use holochain_core_types::{
     time::Timeout,
     validation::{EntryValidationData, ValidationData},
 };
-use holochain_persistence_api::cas::content::Address;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+mod agent_entry;
 mod app_entry;
+mod chain_migrate;
 mod header_address;
 mod link_entry;
 mod provenances;
@@ -22,6 +24,8 @@ pub enum ValidationError {
     /// `Fail` means the validation function did run successfully and recognized the entry
     /// as invalid. The String parameter holds the non-zero return value of the app validation
     /// function.
+    /// Authors get warranted for entries that fail validation, so missing data or other
+    /// errors that could go away must never be reported as `Fail`.
     Fail(String),
 
     /// The entry could not get validated because known dependencies (like base and target
@@ -65,7 +69,8 @@ impl From<ValidationError> for HolochainError {
 /// 1. Checks if the entry's address matches the address in given header provided by
 ///    the validation package.
 /// 2. Validates provenances given in the header by verifying the cryptographic signatures
-///    against the source agent addresses.
+///    against the source agent addresses and checks that entries of countersigned types
+///    are signed by their author and one counterparty.
 /// 3. Finally spawns a thread to run the type specific validation callback in a Ribosome.
 ///
 /// All of this actually happens in the functions of the sub modules. This function is the
@@ -78,7 +83,8 @@ pub async fn validate_entry(
 ) -> ValidationResult {
     //check_entry_type(entry.entry_type(), context)?;
     header_address::validate_header_address(&entry, &validation_data.package.chain_header)?;
-    provenances::validate_provenances(&validation_data)?;
+    provenances::validate_provenances(&entry, &validation_data, context)?;
+    provenances::validate_countersignatures(&entry, &validation_data, context)?;
 
     match entry.entry_type() {
         // DNA entries are not validated currently and always valid
@@ -122,33 +128,44 @@ pub async fn validate_entry(
         //                      validation_data,
         //                      context,
         //                    )?
-        EntryType::AgentId => Ok(()),
+        // For now only key rotations get checked.
+        EntryType::AgentId => {
+            agent_entry::validate_agent_entry(entry.clone(), link, &validation_data)
+        }
+
+        EntryType::ChainMigrate => {
+            chain_migrate::validate_chain_migrate(entry.clone(), &validation_data, context)
+        }
 
         _ => Err(ValidationError::NotImplemented),
     }
 }
 
+/// Builds the validation data the app validation callbacks get for the entry.
+/// Entries that update or delete another entry need that entry from the DHT.
+/// If it can't be fetched (yet), the entry can't be validated (yet), which is an unresolved
+/// dependency and not a validation failure.
 pub fn entry_to_validation_data(
     context: Arc<Context>,
     entry: &Entry,
     maybe_link_update_delete: Option<Address>,
     validation_data: ValidationData,
-) -> Result<EntryValidationData<Entry>, HolochainError> {
+) -> Result<EntryValidationData<Entry>, ValidationError> {
+    let get_dependency = |address: &Address| {
+        get_entry_with_header(context.clone(), address)
+            .map_err(|_| ValidationError::UnresolvedDependencies(vec![address.clone()]))
+    };
     match entry {
         Entry::App(_, _) => maybe_link_update_delete
             .map(|link_update| {
-                get_entry_with_header(context.clone(), &link_update)
-                    .map(|entry_with_header| {
-                        Ok(EntryValidationData::Modify {
-                            old_entry: entry_with_header.0.entry.clone(),
-                            new_entry: entry.clone(),
-                            old_entry_header: entry_with_header.1.clone(),
-                            validation_data: validation_data.clone(),
-                        })
-                    })
-                    .unwrap_or(Err(HolochainError::ErrorGeneric(
-                        "Could not find Entry".to_string(),
-                    )))
+                get_dependency(&link_update).map(|entry_with_header| {
+                    EntryValidationData::Modify {
+                        old_entry: entry_with_header.0.entry.clone(),
+                        new_entry: entry.clone(),
+                        old_entry_header: entry_with_header.1.clone(),
+                        validation_data: validation_data.clone(),
+                    }
+                })
             })
             .unwrap_or(Ok(EntryValidationData::Create {
                 entry: entry.clone(),
@@ -156,25 +173,19 @@ pub fn entry_to_validation_data(
             })),
         Entry::Deletion(deletion_entry) => {
             let deletion_address = deletion_entry.clone().deleted_entry_address();
-            get_entry_with_header(context.clone(), &deletion_address)
-                .map(|entry_with_header| {
-                    Ok(EntryValidationData::Delete {
-                        old_entry: entry_with_header.0.entry.clone(),
-                        old_entry_header: entry_with_header.1.clone(),
-                        validation_data: validation_data.clone(),
-                    })
-                })
-                .unwrap_or(Err(HolochainError::ErrorGeneric(
-                    "Could not find Entry".to_string(),
-                )))
+            get_dependency(&deletion_address).map(|entry_with_header| {
+                EntryValidationData::Delete {
+                    old_entry: entry_with_header.0.entry.clone(),
+                    old_entry_header: entry_with_header.1.clone(),
+                    validation_data: validation_data.clone(),
+                }
+            })
         }
-        Entry::CapTokenGrant(_) => Ok(EntryValidationData::Create {
+        Entry::CapTokenGrant(_) | Entry::ChainMigrate(_) => Ok(EntryValidationData::Create {
             entry: entry.clone(),
             validation_data,
         }),
-        _ => Err(HolochainError::NotImplemented(
-            "Not implemented".to_string(),
-        )),
+        _ => Err(ValidationError::NotImplemented),
     }
 }
 
@@ -198,3 +209,30 @@ fn get_entry_with_header(
         ))?;
     Ok((entry_with_meta.entry_with_meta, latest_header.clone()))
 }
+
+/// The headers that precede the entry's header on its chain, newest first.
+/// The package might hold headers that got added after the entry as well, so this
+/// follows the links from the entry's header instead of taking all of them.
+fn previous_headers(validation_data: &ValidationData) -> Result<Vec<ChainHeader>, ValidationError> {
+    let chain_headers: HashMap<Address, &ChainHeader> = validation_data
+        .package
+        .source_chain_headers
+        .as_ref()
+        .ok_or_else(|| {
+            ValidationError::Error("Validation package holds no chain headers".to_string())
+        })?
+        .iter()
+        .map(|header| (header.address(), header))
+        .collect();
+
+    let mut previous = Vec::new();
+    let mut maybe_link = validation_data.package.chain_header.link();
+    while let Some(link) = maybe_link {
+        let header = chain_headers
+            .get(&link)
+            .ok_or_else(|| ValidationError::UnresolvedDependencies(vec![link.clone()]))?;
+        previous.push((*header).clone());
+        maybe_link = header.link();
+    }
+    Ok(previous)
+}
@@ -1,15 +1,35 @@
-use crate::nucleus::validation::{ValidationError, ValidationResult};
+use crate::{
+    context::Context,
+    nucleus::validation::{ValidationError, ValidationResult},
+    workflows::countersign_entry::is_countersigned_type,
+};
 use boolinator::Boolinator;
-use holochain_core_types::validation::ValidationData;
+use holochain_core_types::{
+    entry::Entry,
+    validation::{EntryLifecycle, ValidationData},
+};
 use holochain_dpki::utils::Verify;
+use std::{collections::HashSet, sync::Arc};
 
-pub fn validate_provenances(validation_data: &ValidationData) -> ValidationResult {
+/// Verifies the signatures of all provenances of the header.
+/// They sign the entry address, or the countersigned payload of the header
+/// for entries of countersigned entry types.
+pub fn validate_provenances(
+    entry: &Entry,
+    validation_data: &ValidationData,
+    context: &Arc<Context>,
+) -> ValidationResult {
     let header = &validation_data.package.chain_header;
+    let payload = if is_countersigned_type(&entry.entry_type(), context) {
+        header.countersigned_payload()
+    } else {
+        header.entry_address().to_string()
+    };
     header
         .provenances()
         .iter()
         .map(|provenance| {
-            let maybe_has_authored = provenance.verify(header.entry_address().to_string());
+            let maybe_has_authored = provenance.verify(payload.clone());
             match maybe_has_authored {
                 Err(_) => {
                     Err(ValidationError::Fail(format!(
@@ -30,3 +50,48 @@ pub fn validate_provenances(validation_data: &ValidationData) -> ValidationResul
         .collect::<Result<Vec<()>, ValidationError>>()?;
     Ok(())
 }
+
+/// Entries of countersigned entry types need the signatures of exactly two distinct agents,
+/// the author and its counterparty. On the chain they get committed to, the agent of that
+/// chain has to be one of them.
+/// The signatures themselves get verified by `validate_provenances`.
+pub fn validate_countersignatures(
+    entry: &Entry,
+    validation_data: &ValidationData,
+    context: &Arc<Context>,
+) -> ValidationResult {
+    if !is_countersigned_type(&entry.entry_type(), context) {
+        return Ok(());
+    }
+
+    let header = &validation_data.package.chain_header;
+    let signers = header
+        .provenances()
+        .iter()
+        .map(|provenance| provenance.source())
+        .collect::<HashSet<_>>();
+    (signers.len() == 2 && header.provenances().len() == 2).ok_or(ValidationError::Fail(
+        format!(
+            "Countersigned entry {} needs the signatures of two agents but has {}",
+            header.entry_address(),
+            header.provenances().len(),
+        ),
+    ))?;
+
+    if let EntryLifecycle::Chain = validation_data.lifecycle {
+        let author = context
+            .state()
+            .ok_or_else(|| ValidationError::Error("State not initialized".to_string()))?
+            .agent()
+            .get_agent_address()
+            .map_err(|error| ValidationError::Error(error.to_string()))?;
+        signers
+            .contains(&author)
+            .ok_or(ValidationError::Fail(format!(
+                "Countersigned entry {} is not signed by the author {}",
+                header.entry_address(),
+                author,
+            )))?;
+    }
+    Ok(())
+}
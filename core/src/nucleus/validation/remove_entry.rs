@@ -23,9 +23,7 @@ pub async fn validate_remove_entry(
     let deletion_address = deletion_entry.clone().deleted_entry_address();
     let entry_to_delete = get_entry_from_dht(&context.clone(), &deletion_address)
         .map_err(|_| ValidationError::UnresolvedDependencies(vec![deletion_address.clone()]))?
-        .ok_or(ValidationError::Fail(
-            "Could not obtain entry for link_update_delte".to_string(),
-        ))?;
+        .ok_or_else(|| ValidationError::UnresolvedDependencies(vec![deletion_address.clone()]))?;
     let app_entry_type = match entry_to_delete.clone() {
         Entry::App(app_entry_type, _) => Ok(app_entry_type),
         _ => Err(ValidationError::Fail(
@@ -38,10 +36,7 @@ pub async fn validate_remove_entry(
         .ok_or(ValidationError::NotImplemented)?;
 
     let params = EntryValidationArgs {
-        validation_data: entry_to_validation_data(context.clone(), &entry, None, validation_data)
-            .map_err(|_| {
-            ValidationError::Fail("Could not get entry validation".to_string())
-        })?,
+        validation_data: entry_to_validation_data(context.clone(), &entry, None, validation_data)?,
     };
 
     let call = CallbackFnCall::new(&zome_name, "__hdk_validate_app_entry", params);
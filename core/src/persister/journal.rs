@@ -0,0 +1,608 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::state::{ActionResponse, AgentStateSnapshot},
+    context::Context,
+    network::{entry_with_header::EntryWithHeader, warrant::Warrant},
+    nucleus::state::NucleusStateSnapshot,
+    persister::Persister,
+    scheduled_jobs::pending_validations::{PendingValidationStruct, ValidatingWorkflow},
+    state::State,
+};
+use holochain_core_types::{
+    chain_header::ChainHeader, entry::Entry, error::HolochainError, link::link_data::LinkData,
+    time::Iso8601,
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use serde_json;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Number of journal records after which a new checkpoint is written by default
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100;
+
+const JOURNAL_FILE: &str = "journal.ndjson";
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+const CHECKPOINT_TMP_FILE: &str = "checkpoint.json.tmp";
+const STORAGE_LOG_FILE: &str = "storage.ndjson";
+
+/// The durable part of a reduced action.
+/// Only actions that change state which has to survive a restart get journaled.
+/// Commits are recorded together with the header the agent reducer created for them,
+/// so that replaying them restores the exact same chain instead of creating new headers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JournaledAction {
+    Commit((Entry, ChainHeader)),
+    CommitBundle(Vec<(Entry, ChainHeader)>),
+    Hold(EntryWithHeader),
+    AddLink((LinkData, Iso8601)),
+    RemoveLink(Entry),
+    UpdateEntry((Address, Address)),
+    RemoveEntry((Address, Address)),
+    HoldWarrant(Warrant),
+    AddPendingValidation(PendingValidationStruct),
+    RemovePendingValidation((Address, ValidatingWorkflow)),
+}
+
+impl JournaledAction {
+    /// Picks the durable part of an action out of the state it was just reduced into.
+    /// Returns None for actions that don't need to be journaled.
+    pub fn from_reduced(action_wrapper: &ActionWrapper, state: &State) -> Option<Self> {
+        match action_wrapper.action() {
+            Action::Commit((entry, _, _)) => match state.agent().actions().get(action_wrapper) {
+                Some(ActionResponse::Commit(Ok(_))) => state
+                    .agent()
+                    .top_chain_header()
+                    .map(|header| JournaledAction::Commit((entry.clone(), header))),
+                _ => None,
+            },
+            // Commits that got staged in a bundle get journaled with the bundle
+            // once it got committed:
+            Action::CommitBundle(_) => match state.agent().actions().get(action_wrapper) {
+                Some(ActionResponse::CommitBundle(Ok(committed))) => {
+                    Some(JournaledAction::CommitBundle(committed.clone()))
+                }
+                _ => None,
+            },
+            Action::Hold(entry_with_header) => {
+                Some(JournaledAction::Hold(entry_with_header.clone()))
+            }
+            Action::AddLink(link) => Some(JournaledAction::AddLink(link.clone())),
+            Action::RemoveLink(entry) => Some(JournaledAction::RemoveLink(entry.clone())),
+            Action::UpdateEntry(addresses) => Some(JournaledAction::UpdateEntry(addresses.clone())),
+            Action::RemoveEntry(addresses) => Some(JournaledAction::RemoveEntry(addresses.clone())),
+            Action::HoldWarrant(warrant) => Some(JournaledAction::HoldWarrant(warrant.clone())),
+            Action::AddPendingValidation(pending) => {
+                Some(JournaledAction::AddPendingValidation((**pending).clone()))
+            }
+            Action::RemovePendingValidation(key) => {
+                Some(JournaledAction::RemovePendingValidation(key.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Actions that end up in the CAS/EAV storages are kept through checkpoints so
+    /// that the chain and the held DHT shard can be rebuilt even on transient storage.
+    /// The same goes for held warrants, which are not part of any snapshot.
+    /// Pending validation changes are compacted into the nucleus snapshot instead.
+    fn is_storage_record(&self) -> bool {
+        match self {
+            JournaledAction::AddPendingValidation(_)
+            | JournaledAction::RemovePendingValidation(_) => false,
+            _ => true,
+        }
+    }
+
+    /// The action that gets reduced again on replay.
+    /// Commits are not reduced but restored directly (see `JournalPersister::load`).
+    fn to_action(&self) -> Option<Action> {
+        match self {
+            JournaledAction::Commit(_) | JournaledAction::CommitBundle(_) => None,
+            JournaledAction::Hold(entry_with_header) => {
+                Some(Action::Hold(entry_with_header.clone()))
+            }
+            JournaledAction::AddLink(link) => Some(Action::AddLink(link.clone())),
+            JournaledAction::RemoveLink(entry) => Some(Action::RemoveLink(entry.clone())),
+            JournaledAction::UpdateEntry(addresses) => Some(Action::UpdateEntry(addresses.clone())),
+            JournaledAction::RemoveEntry(addresses) => Some(Action::RemoveEntry(addresses.clone())),
+            JournaledAction::HoldWarrant(warrant) => Some(Action::HoldWarrant(warrant.clone())),
+            JournaledAction::AddPendingValidation(pending) => {
+                Some(Action::AddPendingValidation(Arc::new(pending.clone())))
+            }
+            JournaledAction::RemovePendingValidation(key) => {
+                Some(Action::RemovePendingValidation(key.clone()))
+            }
+        }
+    }
+}
+
+/// One line of the journal file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub seq: u64,
+    pub action: JournaledAction,
+}
+
+/// Everything up to and including record `seq`, compacted.
+/// Storage records don't get copied into the checkpoint. Each checkpoint appends the
+/// ones of its journal to the storage log and only references the first
+/// `storage_log_len` bytes of it, so writing a checkpoint does not get slower
+/// the more data the instance holds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Checkpoint {
+    seq: u64,
+    agent: AgentStateSnapshot,
+    nucleus: NucleusStateSnapshot,
+    storage_log_len: u64,
+}
+
+/// Persister that appends every durable action to a journal file as soon as it got
+/// reduced, instead of rewriting state snapshots.
+/// Every `checkpoint_interval` records, agent and nucleus state get compacted into a
+/// checkpoint file and the journal starts over.
+/// `load` rebuilds the state from the last checkpoint and replays the journal on top,
+/// which brings back the source chain, pending validations and the held DHT shard.
+pub struct JournalPersister {
+    dir: PathBuf,
+    journal: File,
+    seq: u64,
+    records_since_checkpoint: u64,
+    checkpoint_interval: u64,
+}
+
+impl JournalPersister {
+    /// Opens the journal in the given directory, creating it if needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, HolochainError> {
+        Self::with_checkpoint_interval(dir, DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    pub fn with_checkpoint_interval<P: AsRef<Path>>(
+        dir: P,
+        checkpoint_interval: u64,
+    ) -> Result<Self, HolochainError> {
+        let dir: PathBuf = dir.as_ref().into();
+        fs::create_dir_all(&dir)?;
+        repair_journal(&dir)?;
+        let checkpoint_seq = read_checkpoint(&dir)?.map(|c| c.seq).unwrap_or(0);
+        let tail = read_journal(&dir, checkpoint_seq)?;
+        let seq = tail.last().map(|r| r.seq).unwrap_or(checkpoint_seq);
+        let journal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))?;
+        Ok(JournalPersister {
+            dir,
+            journal,
+            seq,
+            records_since_checkpoint: tail.len() as u64,
+            checkpoint_interval: checkpoint_interval.max(1),
+        })
+    }
+
+    fn append(&mut self, action: JournaledAction) -> Result<(), HolochainError> {
+        let record = JournalRecord {
+            seq: self.seq + 1,
+            action,
+        };
+        let mut line = serde_json::to_string(&record)
+            .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+        line.push('\n');
+        self.journal.write_all(line.as_bytes())?;
+        self.journal.sync_data()?;
+        self.seq = record.seq;
+        self.records_since_checkpoint += 1;
+        Ok(())
+    }
+
+    /// Compacts the previous checkpoint and the journal into a new checkpoint for the
+    /// given state and starts a new, empty journal.
+    pub fn checkpoint(&mut self, state: &State) -> Result<(), HolochainError> {
+        let (previous_seq, previous_storage_log_len) = read_checkpoint(&self.dir)?
+            .map(|c| (c.seq, c.storage_log_len))
+            .unwrap_or_default();
+        let storage_records: Vec<JournaledAction> = read_journal(&self.dir, previous_seq)?
+            .into_iter()
+            .map(|record| record.action)
+            .filter(JournaledAction::is_storage_record)
+            .collect();
+        let storage_log_len =
+            append_storage_log(&self.dir, previous_storage_log_len, &storage_records)?;
+        let checkpoint = Checkpoint {
+            seq: self.seq,
+            agent: AgentStateSnapshot::from(state),
+            nucleus: NucleusStateSnapshot::from(state),
+            storage_log_len,
+        };
+
+        // Write to a temporary file first and move it into place so that a crash
+        // never leaves a half-written checkpoint behind:
+        let tmp_path = self.dir.join(CHECKPOINT_TMP_FILE);
+        {
+            let mut file = File::create(&tmp_path)?;
+            let json = serde_json::to_string(&checkpoint)
+                .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, self.dir.join(CHECKPOINT_FILE))?;
+
+        // Records up to the checkpoint's seq are skipped on load, so crashing
+        // before the journal got truncated is fine.
+        self.journal = File::create(self.dir.join(JOURNAL_FILE))?;
+        self.journal.sync_all()?;
+        self.records_since_checkpoint = 0;
+        Ok(())
+    }
+}
+
+fn read_checkpoint(dir: &Path) -> Result<Option<Checkpoint>, HolochainError> {
+    let path = dir.join(CHECKPOINT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| HolochainError::SerializationError(e.to_string()))
+}
+
+/// Reads all records after the given seq.
+/// A broken last line is what a crash in the middle of an append leaves behind,
+/// so it gets ignored. Broken lines anywhere else mean the journal is corrupt.
+fn read_journal(dir: &Path, after_seq: u64) -> Result<Vec<JournalRecord>, HolochainError> {
+    let path = dir.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let lines = BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<Result<Vec<String>, _>>()?;
+    let line_count = lines.len();
+    let mut records = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        match serde_json::from_str::<JournalRecord>(&line) {
+            Ok(record) => {
+                if record.seq > after_seq {
+                    records.push(record)
+                }
+            }
+            Err(_) if i + 1 == line_count => break,
+            Err(e) => {
+                return Err(HolochainError::SerializationError(format!(
+                    "Corrupt journal record in line {}: {}",
+                    i + 1,
+                    e
+                )))
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Appends storage records to the storage log and returns its new length.
+/// Anything after `valid_len` got appended by a checkpoint that crashed before it got
+/// written, and is dropped since those records are still in the journal.
+fn append_storage_log(
+    dir: &Path,
+    valid_len: u64,
+    records: &[JournaledAction],
+) -> Result<u64, HolochainError> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(dir.join(STORAGE_LOG_FILE))?;
+    file.set_len(valid_len)?;
+    file.seek(SeekFrom::End(0))?;
+    for record in records {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+    }
+    file.sync_all()?;
+    Ok(file.metadata()?.len())
+}
+
+/// Reads the storage records within the first `len` bytes of the storage log.
+fn read_storage_log(dir: &Path, len: u64) -> Result<Vec<JournaledAction>, HolochainError> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    let mut contents = String::new();
+    File::open(dir.join(STORAGE_LOG_FILE))?
+        .take(len)
+        .read_to_string(&mut contents)?;
+    if (contents.len() as u64) < len {
+        return Err(HolochainError::ErrorGeneric(
+            "Storage log is shorter than the checkpoint referencing it".to_string(),
+        ));
+    }
+    contents
+        .lines()
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| HolochainError::SerializationError(e.to_string()))
+        })
+        .collect()
+}
+
+/// Drops a broken last line left by a crash during an append, so that new
+/// records don't get glued onto it.
+fn repair_journal(dir: &Path) -> Result<(), HolochainError> {
+    let path = dir.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(());
+    }
+    let contents = fs::read_to_string(&path)?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let last_is_broken = lines
+        .last()
+        .map(|line| serde_json::from_str::<JournalRecord>(line).is_err())
+        .unwrap_or(false);
+    if last_is_broken || (!contents.is_empty() && !contents.ends_with('\n')) {
+        let valid_lines = if last_is_broken {
+            &lines[..lines.len() - 1]
+        } else {
+            &lines[..]
+        };
+        let mut repaired = valid_lines.join("\n");
+        if !repaired.is_empty() {
+            repaired.push('\n');
+        }
+        let tmp_path = dir.join(format!("{}.tmp", JOURNAL_FILE));
+        {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(repaired.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &path)?;
+    }
+    Ok(())
+}
+
+impl Persister for JournalPersister {
+    /// Checkpoints the whole state.
+    /// The instance calls `save_action` after each reduction; this is for explicit saves.
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
+        self.checkpoint(state)
+    }
+
+    fn save_action(
+        &mut self,
+        action_wrapper: &ActionWrapper,
+        state: &State,
+    ) -> Result<(), HolochainError> {
+        if let Some(action) = JournaledAction::from_reduced(action_wrapper, state) {
+            self.append(action)?;
+        }
+        if self.records_since_checkpoint >= self.checkpoint_interval {
+            self.checkpoint(state)?;
+        }
+        Ok(())
+    }
+
+    /// Replaying the journal restores the state, which needs all the actions.
+    fn needs_every_action(&self) -> bool {
+        true
+    }
+
+    fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError> {
+        let checkpoint = read_checkpoint(&self.dir)?;
+        let checkpoint_seq = checkpoint.as_ref().map(|c| c.seq).unwrap_or(0);
+        let tail: Vec<JournaledAction> = read_journal(&self.dir, checkpoint_seq)?
+            .into_iter()
+            .map(|record| record.action)
+            .collect();
+        if checkpoint.is_none() && tail.is_empty() {
+            return Ok(None);
+        }
+
+        let (mut top_chain_header, nucleus_snapshot, storage_records) = match checkpoint {
+            Some(checkpoint) => (
+                checkpoint.agent.top_chain_header().cloned(),
+                checkpoint.nucleus,
+                read_storage_log(&self.dir, checkpoint.storage_log_len)?,
+            ),
+            None => (
+                None,
+                NucleusStateSnapshot::from(&State::new(context.clone())),
+                Vec::new(),
+            ),
+        };
+        let records: Vec<JournaledAction> = storage_records
+            .into_iter()
+            .chain(tail.into_iter())
+            .collect();
+
+        // Restore the source chain first since building the state reads the DNA from it:
+        for record in records.iter() {
+            let commits = match record {
+                JournaledAction::Commit(commit) => vec![commit.clone()],
+                JournaledAction::CommitBundle(commits) => commits.clone(),
+                _ => continue,
+            };
+            for (entry, header) in commits {
+                context.chain_storage.write()?.add(&entry)?;
+                context.chain_storage.write()?.add(&header)?;
+                context.dht_storage.write()?.add(&entry)?;
+                top_chain_header = Some(header);
+            }
+        }
+
+        let mut state = State::try_from_snapshots(
+            context.clone(),
+            AgentStateSnapshot::new(top_chain_header),
+            nucleus_snapshot,
+        )?;
+        for action in records.iter().filter_map(JournaledAction::to_action) {
+            state = state.reduce(ActionWrapper::new(action));
+        }
+        Ok(Some(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_context_with_agent_state, network::entry_with_header::EntryWithHeader,
+    };
+    use holochain_core_types::{
+        chain_header::test_chain_header,
+        entry::{test_entry, test_entry_a},
+    };
+    use tempfile::tempdir;
+
+    fn pending_validation() -> PendingValidationStruct {
+        PendingValidationStruct::new(
+            EntryWithHeader {
+                entry: test_entry_a(),
+                header: test_chain_header(),
+            },
+            vec![test_entry().address()],
+            ValidatingWorkflow::HoldEntry,
+        )
+    }
+
+    #[test]
+    fn journal_replays_pending_validations_after_restart() {
+        let dir = tempdir().unwrap();
+        let context = test_context_with_agent_state(None);
+        let pending = pending_validation();
+        {
+            let mut persister = JournalPersister::new(dir.path()).unwrap();
+            let action_wrapper =
+                ActionWrapper::new(Action::AddPendingValidation(Arc::new(pending.clone())));
+            let state = context.state().unwrap().reduce(action_wrapper.clone());
+            persister.save_action(&action_wrapper, &state).unwrap();
+        }
+
+        let persister = JournalPersister::new(dir.path()).unwrap();
+        let loaded = persister.load(context.clone()).unwrap().unwrap();
+        let loaded_pending: Vec<PendingValidationStruct> = loaded
+            .nucleus()
+            .pending_validations
+            .values()
+            .map(|p| (**p).clone())
+            .collect();
+        assert_eq!(loaded_pending, vec![pending]);
+    }
+
+    #[test]
+    fn checkpoint_compacts_journal_and_keeps_storage_records() {
+        let dir = tempdir().unwrap();
+        let context = test_context_with_agent_state(None);
+        let mut persister = JournalPersister::with_checkpoint_interval(dir.path(), 2).unwrap();
+        let entry_with_header = EntryWithHeader {
+            entry: test_entry(),
+            header: test_chain_header(),
+        };
+        let mut state = context.state().unwrap().clone();
+        for action in vec![
+            Action::Hold(entry_with_header.clone()),
+            Action::AddPendingValidation(Arc::new(pending_validation())),
+            Action::RemovePendingValidation((
+                test_entry_a().address(),
+                ValidatingWorkflow::HoldEntry,
+            )),
+        ] {
+            let action_wrapper = ActionWrapper::new(action);
+            state = state.reduce(action_wrapper.clone());
+            persister.save_action(&action_wrapper, &state).unwrap();
+        }
+
+        let checkpoint = read_checkpoint(dir.path()).unwrap().unwrap();
+        assert_eq!(checkpoint.seq, 2);
+        assert_eq!(
+            read_storage_log(dir.path(), checkpoint.storage_log_len).unwrap(),
+            vec![JournaledAction::Hold(entry_with_header)]
+        );
+        let tail = read_journal(dir.path(), checkpoint.seq).unwrap();
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].seq, 3);
+
+        let loaded = persister.load(context.clone()).unwrap().unwrap();
+        assert!(loaded.nucleus().pending_validations.is_empty());
+        assert!(loaded
+            .dht()
+            .content_storage()
+            .read()
+            .unwrap()
+            .contains(&test_entry().address())
+            .unwrap());
+    }
+
+    #[test]
+    fn checkpoints_append_to_storage_log() {
+        let dir = tempdir().unwrap();
+        let context = test_context_with_agent_state(None);
+        let mut persister = JournalPersister::with_checkpoint_interval(dir.path(), 1).unwrap();
+        let holds: Vec<JournaledAction> = vec![test_entry(), test_entry_a()]
+            .into_iter()
+            .map(|entry| {
+                JournaledAction::Hold(EntryWithHeader {
+                    entry,
+                    header: test_chain_header(),
+                })
+            })
+            .collect();
+        let mut state = context.state().unwrap().clone();
+        for (i, hold) in holds.iter().enumerate() {
+            // Leftovers of a checkpoint that crashed before it got written:
+            if i > 0 {
+                let mut file = OpenOptions::new()
+                    .append(true)
+                    .open(dir.path().join(STORAGE_LOG_FILE))
+                    .unwrap();
+                file.write_all(b"{\"Hold\":").unwrap();
+            }
+            let action_wrapper = ActionWrapper::new(hold.to_action().unwrap());
+            state = state.reduce(action_wrapper.clone());
+            persister.save_action(&action_wrapper, &state).unwrap();
+        }
+
+        let checkpoint = read_checkpoint(dir.path()).unwrap().unwrap();
+        assert_eq!(checkpoint.seq, 2);
+        assert_eq!(
+            read_storage_log(dir.path(), checkpoint.storage_log_len).unwrap(),
+            holds
+        );
+    }
+
+    #[test]
+    fn truncated_last_record_is_ignored() {
+        let dir = tempdir().unwrap();
+        let context = test_context_with_agent_state(None);
+        {
+            let mut persister = JournalPersister::new(dir.path()).unwrap();
+            let action_wrapper =
+                ActionWrapper::new(Action::AddPendingValidation(Arc::new(pending_validation())));
+            let state = context.state().unwrap().reduce(action_wrapper.clone());
+            persister.save_action(&action_wrapper, &state).unwrap();
+        }
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(JOURNAL_FILE))
+            .unwrap();
+        file.write_all(b"{\"seq\":2,\"act").unwrap();
+
+        let records = read_journal(dir.path(), 0).unwrap();
+        assert_eq!(records.len(), 1);
+
+        let mut persister = JournalPersister::new(dir.path()).unwrap();
+        assert_eq!(persister.seq, 1);
+        persister
+            .append(JournaledAction::RemovePendingValidation((
+                test_entry_a().address(),
+                ValidatingWorkflow::HoldEntry,
+            )))
+            .unwrap();
+        let records = read_journal(dir.path(), 0).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].seq, 2);
+    }
+}
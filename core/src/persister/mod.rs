@@ -1,4 +1,5 @@
 use crate::{
+    action::ActionWrapper,
     agent::state::{AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
     context::Context,
     nucleus::state::{NucleusStateSnapshot, NUCLEUS_SNAPSHOT_ADDRESS},
@@ -13,14 +14,31 @@ use holochain_persistence_api::cas::{
 
 use std::sync::{Arc, RwLock};
 
+pub mod journal;
+
+pub use self::journal::JournalPersister;
+
 /// trait that defines the persistence functionality that holochain_core requires
 pub trait Persister: Send {
-    // @TODO how does save/load work with snowflake IDs?
-    // snowflake is only unique across a single process, not a reboot save/load round trip
-    // we'd need real UUIDs for persistant uniqueness
-    // @see https://github.com/holochain/holochain-rust/issues/203
     fn save(&mut self, state: &State) -> Result<(), HolochainError>;
     fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError>;
+
+    /// Gets called by the instance after every reduction with the action that
+    /// was just reduced and the resulting state.
+    /// Persisters that only store whole states can rely on the default which saves the state.
+    fn save_action(
+        &mut self,
+        _action_wrapper: &ActionWrapper,
+        state: &State,
+    ) -> Result<(), HolochainError> {
+        self.save(state)
+    }
+
+    /// Whether the state can only be restored if every action got saved.
+    /// Instances stop if saving an action fails with such a persister.
+    fn needs_every_action(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Clone)]
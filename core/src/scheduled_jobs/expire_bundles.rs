@@ -0,0 +1,22 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+};
+use std::sync::Arc;
+
+/// Drops the staged commits of bundles that timed out, so they don't stay in memory
+/// until their zome call closes or discards them.
+pub fn expire_bundles(context: &Arc<Context>) {
+    let has_expired_bundles = context
+        .state()
+        .map(|state| state.agent().has_expired_bundles())
+        .unwrap_or(false);
+    if has_expired_bundles {
+        context.log("debug/scheduled_jobs: dropping commits of timed out bundles");
+        dispatch_action(
+            context.action_channel(),
+            ActionWrapper::new(Action::ExpireBundles),
+        );
+    }
+}
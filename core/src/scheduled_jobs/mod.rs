@@ -1,11 +1,24 @@
+pub mod expire_bundles;
 pub mod pending_validations;
 
-use crate::context::Context;
-use std::sync::Arc;
+use crate::{
+    context::Context,
+    scheduled_jobs::{
+        expire_bundles::expire_bundles, pending_validations::PendingValidationsScheduler,
+    },
+};
+use std::sync::{Arc, Mutex};
 
-pub fn create_callback(context: Arc<Context>) -> impl 'static + FnMut() + Sync + Send {
+pub fn create_callback(
+    context: Arc<Context>,
+    pending_validations: Arc<Mutex<PendingValidationsScheduler>>,
+) -> impl 'static + FnMut() + Sync + Send {
     move || {
         context.log("debug/scheduled_jobs: tick");
-        pending_validations::run_pending_validations(context.clone());
+        pending_validations
+            .lock()
+            .unwrap()
+            .run_pending_validations();
+        expire_bundles(&context);
     }
 }
@@ -1,21 +1,49 @@
 use crate::{
     context::Context,
     network::entry_with_header::EntryWithHeader,
-    nucleus::actions::remove_pending_validation::remove_pending_validation,
-    workflows::{hold_entry::hold_entry_workflow, hold_link::hold_link_workflow},
+    nucleus::{
+        actions::remove_pending_validation::remove_pending_validation, state::PendingValidationKey,
+    },
+    workflows::{
+        hold_entry::hold_entry_workflow, hold_entry_remove::hold_remove_workflow,
+        hold_entry_update::hold_update_workflow, hold_link::hold_link_workflow,
+        remove_link::remove_link_workflow,
+    },
 };
+use crossbeam_channel::Sender;
 use holochain_core_types::error::HolochainError;
 
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
-use std::{fmt, sync::Arc, thread};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 pub type PendingValidation = Arc<PendingValidationStruct>;
 
+/// Number of failed retries after which a pending validation counts as stuck.
+/// Stuck validations are not retried by the scheduler anymore, but they stay in the state
+/// until they get cleared through the admin interface or the entry gets received again.
+pub const MAX_RETRY_ATTEMPTS: u32 = 10;
+
+/// Number of worker threads per instance that retry pending validations
+pub const RETRY_WORKERS: usize = 4;
+
+/// Delay before the first retry, doubled with every failed attempt up to MAX_RETRY_DELAY
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(10);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize, DefaultJson)]
 pub enum ValidatingWorkflow {
     HoldEntry,
     HoldLink,
+    UpdateEntry,
+    RemoveEntry,
+    RemoveLink,
 }
 
 impl fmt::Display for ValidatingWorkflow {
@@ -23,6 +51,9 @@ impl fmt::Display for ValidatingWorkflow {
         match self {
             ValidatingWorkflow::HoldEntry => write!(f, "HoldEntryWorkflow"),
             ValidatingWorkflow::HoldLink => write!(f, "HoldLinkWorkflow"),
+            ValidatingWorkflow::UpdateEntry => write!(f, "UpdateEntryWorkflow"),
+            ValidatingWorkflow::RemoveEntry => write!(f, "RemoveEntryWorkflow"),
+            ValidatingWorkflow::RemoveLink => write!(f, "RemoveLinkWorkflow"),
         }
     }
 }
@@ -32,44 +63,424 @@ pub struct PendingValidationStruct {
     pub entry_with_header: EntryWithHeader,
     pub dependencies: Vec<Address>,
     pub workflow: ValidatingWorkflow,
+    /// How often the validation ended up pending again after it was added.
+    /// Counted up by the reducer whenever the same validation gets added again.
+    #[serde(default)]
+    pub attempts: u32,
 }
 
-fn retry_validation(pending: PendingValidation, context: Arc<Context>) {
-    thread::spawn(move || {
-        let result = match pending.workflow {
-            ValidatingWorkflow::HoldLink => {
-                context.block_on(hold_link_workflow(&pending.entry_with_header, &context))
-            }
-            ValidatingWorkflow::HoldEntry => context.block_on(hold_entry_workflow(
-                &pending.entry_with_header,
+impl PendingValidationStruct {
+    pub fn new(
+        entry_with_header: EntryWithHeader,
+        dependencies: Vec<Address>,
+        workflow: ValidatingWorkflow,
+    ) -> Self {
+        PendingValidationStruct {
+            entry_with_header,
+            dependencies,
+            workflow,
+            attempts: 0,
+        }
+    }
+
+    pub fn key(&self) -> PendingValidationKey {
+        PendingValidationKey::new(
+            self.entry_with_header.entry.address(),
+            self.workflow.clone(),
+        )
+    }
+
+    pub fn is_stuck(&self) -> bool {
+        self.attempts >= MAX_RETRY_ATTEMPTS
+    }
+}
+
+/// What the admin interface shows of a pending validation
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PendingValidationInfo {
+    pub address: Address,
+    pub entry_type: String,
+    pub workflow: ValidatingWorkflow,
+    pub dependencies: Vec<Address>,
+    pub attempts: u32,
+    pub stuck: bool,
+}
+
+impl From<&PendingValidationStruct> for PendingValidationInfo {
+    fn from(pending: &PendingValidationStruct) -> Self {
+        PendingValidationInfo {
+            address: pending.entry_with_header.entry.address(),
+            entry_type: pending.entry_with_header.entry.entry_type().to_string(),
+            workflow: pending.workflow.clone(),
+            dependencies: pending.dependencies.clone(),
+            attempts: pending.attempts,
+            stuck: pending.is_stuck(),
+        }
+    }
+}
+
+/// Lists all pending validations of the instance
+pub fn list_pending_validations(context: &Arc<Context>) -> Vec<PendingValidationInfo> {
+    context
+        .state()
+        .map(|state| {
+            state
+                .nucleus()
+                .pending_validations
+                .values()
+                .map(|pending| PendingValidationInfo::from(&**pending))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Removes the stuck pending validations of the instance, or all of them if `all` is set.
+/// Returns the removed ones.
+pub fn clear_pending_validations(context: &Arc<Context>, all: bool) -> Vec<PendingValidationInfo> {
+    let cleared = list_pending_validations(context)
+        .into_iter()
+        .filter(|info| all || info.stuck)
+        .collect::<Vec<_>>();
+    for info in cleared.iter() {
+        remove_pending_validation(info.address.clone(), info.workflow.clone(), context);
+    }
+    cleared
+}
+
+/// Delay between the given number of failed attempts and the next retry
+fn retry_delay(attempts: u32) -> Duration {
+    BASE_RETRY_DELAY
+        .checked_mul(1 << attempts.min(16))
+        .map(|delay| delay.min(MAX_RETRY_DELAY))
+        .unwrap_or(MAX_RETRY_DELAY)
+}
+
+fn retry_validation(pending: &PendingValidation, context: &Arc<Context>) {
+    let result = match pending.workflow {
+        ValidatingWorkflow::HoldLink => {
+            context.block_on(hold_link_workflow(&pending.entry_with_header, context))
+        }
+        ValidatingWorkflow::HoldEntry => context.block_on(hold_entry_workflow(
+            &pending.entry_with_header,
+            context.clone(),
+        )),
+        ValidatingWorkflow::UpdateEntry => context
+            .block_on(hold_update_workflow(
+                pending.entry_with_header.clone(),
                 context.clone(),
-            )),
+            ))
+            .map(|_| ()),
+        ValidatingWorkflow::RemoveEntry => context.block_on(hold_remove_workflow(
+            pending.entry_with_header.clone(),
+            context.clone(),
+        )),
+        ValidatingWorkflow::RemoveLink => {
+            context.block_on(remove_link_workflow(&pending.entry_with_header, context))
+        }
+    };
+
+    if Err(HolochainError::ValidationPending) != result {
+        remove_pending_validation(
+            pending.entry_with_header.entry.address(),
+            pending.workflow.clone(),
+            context,
+        );
+    }
+}
+
+/// Retries the pending validations of an instance on a fixed number of worker threads.
+/// Validations get retried right away when one of their dependencies gets held (see wake_up()).
+/// The periodic run_pending_validations() is only a fallback for everything else:
+/// there, every pending validation waits an exponentially growing delay between two attempts
+/// and stuck ones are not retried at all.
+pub struct PendingValidationsScheduler {
+    context: Arc<Context>,
+    queue: Sender<PendingValidation>,
+    running: Arc<Mutex<HashSet<PendingValidationKey>>>,
+    next_retry: HashMap<PendingValidationKey, (u32, Instant)>,
+}
+
+type RetryFn = Arc<dyn Fn(&PendingValidation, &Arc<Context>) + Send + Sync>;
+
+impl PendingValidationsScheduler {
+    pub fn new(context: Arc<Context>) -> Self {
+        Self::with_retry(context, Arc::new(retry_validation))
+    }
+
+    /// Creates a scheduler whose workers run the given function for every retry
+    fn with_retry(context: Arc<Context>, retry: RetryFn) -> Self {
+        // Without capacity, sending only succeeds if a worker is idle
+        let (queue, jobs) = crossbeam_channel::bounded::<PendingValidation>(0);
+        let running = Arc::new(Mutex::new(HashSet::new()));
+        for _ in 0..RETRY_WORKERS {
+            let jobs = jobs.clone();
+            let running = running.clone();
+            let context = context.clone();
+            let retry = retry.clone();
+            // Workers end when the scheduler gets dropped together with the sending side
+            thread::spawn(move || {
+                for pending in jobs.iter() {
+                    retry(&pending, &context);
+                    running.lock().unwrap().remove(&pending.key());
+                }
+            });
+        }
+        PendingValidationsScheduler {
+            context,
+            queue,
+            running,
+            next_retry: HashMap::new(),
+        }
+    }
+
+    pub fn run_pending_validations(&mut self) {
+        let pending_validations = match self.context.state() {
+            Some(state) => state.nucleus().pending_validations.clone(),
+            None => return,
+        };
+        self.schedule(&pending_validations, Instant::now());
+    }
+
+    /// Hands the given pending validations that are due at `now` to idle workers
+    fn schedule(
+        &mut self,
+        pending_validations: &HashMap<PendingValidationKey, PendingValidation>,
+        now: Instant,
+    ) {
+        self.next_retry
+            .retain(|key, _| pending_validations.contains_key(key));
+
+        for (key, pending) in pending_validations.iter() {
+            if pending.is_stuck() || self.running.lock().unwrap().contains(key) {
+                continue;
+            }
+            let (attempts, due) = self
+                .next_retry
+                .entry(key.clone())
+                .or_insert_with(|| (pending.attempts, now + retry_delay(pending.attempts)));
+            if *attempts != pending.attempts {
+                *attempts = pending.attempts;
+                *due = now + retry_delay(pending.attempts);
+            }
+            if now < *due {
+                continue;
+            }
+
+            self.context.log(format!(
+                "debug/scheduled_jobs/run_pending_validations: retrying pending validation for {}: {} (attempt {})",
+                pending.entry_with_header.entry.entry_type(),
+                pending.entry_with_header.entry.address(),
+                pending.attempts + 1,
+            ));
+            self.running.lock().unwrap().insert(key.clone());
+            if self.queue.try_send(pending.clone()).is_err() {
+                // All workers are busy, the remaining validations wait for the next tick
+                self.running.lock().unwrap().remove(key);
+                break;
+            }
+            self.next_retry.remove(key);
+        }
+    }
+
+    /// Retries the pending validations waiting on any of the given addresses immediately,
+    /// ignoring their backoff and whether they are stuck since a dependency just arrived.
+    /// Validations that find no idle worker are due on the next periodic run, unless stuck.
+    pub fn wake_up(&mut self, held_addresses: &[Address]) {
+        let state = match self.context.state() {
+            Some(state) => state,
+            None => return,
         };
+        let mut waiting: HashMap<PendingValidationKey, PendingValidation> = HashMap::new();
+        for address in held_addresses {
+            for pending in state.nucleus().pending_validations_waiting_on(address) {
+                waiting.insert(pending.key(), pending);
+            }
+        }
+        drop(state);
 
-        if Err(HolochainError::ValidationPending) != result {
-            remove_pending_validation(
+        let now = Instant::now();
+        for (key, pending) in waiting {
+            if self.running.lock().unwrap().contains(&key) {
+                continue;
+            }
+            self.context.log(format!(
+                "debug/scheduled_jobs/wake_up: dependency of pending validation for {}: {} arrived",
+                pending.entry_with_header.entry.entry_type(),
                 pending.entry_with_header.entry.address(),
-                pending.workflow.clone(),
-                &context,
-            );
+            ));
+            self.running.lock().unwrap().insert(key.clone());
+            if self.queue.try_send(pending.clone()).is_err() {
+                self.running.lock().unwrap().remove(&key);
+                self.next_retry.insert(key, (pending.attempts, now));
+            } else {
+                self.next_retry.remove(&key);
+            }
         }
-    });
+    }
 }
 
-pub fn run_pending_validations(context: Arc<Context>) {
-    let pending_validations = context
-        .state()
-        .unwrap()
-        .nucleus()
-        .pending_validations
-        .clone();
-
-    pending_validations.iter().for_each(|(_, pending)| {
-        context.log(format!(
-            "debug/scheduled_jobs/run_pending_validations: found pending validation for {}: {}",
-            pending.entry_with_header.entry.entry_type(),
-            pending.entry_with_header.entry.address()
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::instance::tests::test_context;
+    use holochain_core_types::{
+        chain_header::test_chain_header,
+        entry::{entry_type::test_app_entry_type, Entry},
+    };
+
+    fn test_pending_validation(value: usize, attempts: u32) -> PendingValidation {
+        let entry = Entry::App(
+            test_app_entry_type(),
+            JsonString::from_json(&format!("{}", value)),
+        );
+        let mut pending = PendingValidationStruct::new(
+            EntryWithHeader::new(entry, test_chain_header()),
+            Vec::new(),
+            ValidatingWorkflow::HoldEntry,
+        );
+        pending.attempts = attempts;
+        Arc::new(pending)
+    }
+
+    fn test_pending_validations(
+        pending: Vec<PendingValidation>,
+    ) -> HashMap<PendingValidationKey, PendingValidation> {
+        pending
+            .into_iter()
+            .map(|pending| (pending.key(), pending))
+            .collect()
+    }
+
+    /// Scheduler with workers that just count the retries they get
+    fn counting_scheduler(retries: Arc<Mutex<usize>>) -> PendingValidationsScheduler {
+        let scheduler = PendingValidationsScheduler::with_retry(
+            test_context("alice", None),
+            Arc::new(move |_: &PendingValidation, _: &Arc<Context>| {
+                *retries.lock().unwrap() += 1;
+            }),
+        );
+        // Jobs only get handed to workers that are already waiting for them
+        thread::sleep(Duration::from_millis(100));
+        scheduler
+    }
+
+    fn wait_for<F: Fn() -> bool>(condition: F) -> bool {
+        for _ in 0..200 {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    fn idle(scheduler: &PendingValidationsScheduler) -> bool {
+        scheduler.running.lock().unwrap().is_empty()
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_maximum() {
+        assert_eq!(retry_delay(0), BASE_RETRY_DELAY);
+        assert_eq!(retry_delay(1), BASE_RETRY_DELAY * 2);
+        assert_eq!(retry_delay(3), BASE_RETRY_DELAY * 8);
+        assert_eq!(retry_delay(MAX_RETRY_ATTEMPTS), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::max_value()), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn retries_wait_for_growing_backoff() {
+        let retries = Arc::new(Mutex::new(0));
+        let mut scheduler = counting_scheduler(retries.clone());
+        let start = Instant::now();
+
+        let pending_validations = test_pending_validations(vec![test_pending_validation(1, 0)]);
+        scheduler.schedule(&pending_validations, start);
+        scheduler.schedule(&pending_validations, start + retry_delay(0) / 2);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*retries.lock().unwrap(), 0);
+
+        scheduler.schedule(&pending_validations, start + retry_delay(0));
+        assert!(wait_for(
+            || *retries.lock().unwrap() == 1 && idle(&scheduler)
         ));
-        retry_validation(pending.clone(), context.clone());
-    });
+
+        // The retry ended up pending again, so the next one waits twice as long
+        let failed_at = start + retry_delay(0);
+        let pending_validations = test_pending_validations(vec![test_pending_validation(1, 1)]);
+        scheduler.schedule(&pending_validations, failed_at);
+        scheduler.schedule(&pending_validations, failed_at + retry_delay(0));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*retries.lock().unwrap(), 1);
+
+        scheduler.schedule(&pending_validations, failed_at + retry_delay(1));
+        assert!(wait_for(|| *retries.lock().unwrap() == 2));
+    }
+
+    #[test]
+    fn stuck_validations_are_not_retried() {
+        let retries = Arc::new(Mutex::new(0));
+        let mut scheduler = counting_scheduler(retries.clone());
+        let start = Instant::now();
+
+        let stuck = test_pending_validation(1, MAX_RETRY_ATTEMPTS);
+        assert!(stuck.is_stuck());
+        let pending_validations = test_pending_validations(vec![
+            stuck,
+            test_pending_validation(2, MAX_RETRY_ATTEMPTS - 1),
+        ]);
+        scheduler.schedule(&pending_validations, start);
+        scheduler.schedule(&pending_validations, start + MAX_RETRY_DELAY * 2);
+        assert!(wait_for(
+            || *retries.lock().unwrap() == 1 && idle(&scheduler)
+        ));
+
+        scheduler.schedule(&pending_validations, start + MAX_RETRY_DELAY * 4);
+        scheduler.schedule(&pending_validations, start + MAX_RETRY_DELAY * 6);
+        assert!(wait_for(|| *retries.lock().unwrap() == 2));
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*retries.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn retries_never_exceed_worker_count() {
+        // (running retries, most running retries at once, finished retries)
+        let counts = Arc::new(Mutex::new((0usize, 0usize, 0usize)));
+        let worker_counts = counts.clone();
+        let mut scheduler = PendingValidationsScheduler::with_retry(
+            test_context("alice", None),
+            Arc::new(move |_: &PendingValidation, _: &Arc<Context>| {
+                {
+                    let mut counts = worker_counts.lock().unwrap();
+                    counts.0 += 1;
+                    counts.1 = counts.1.max(counts.0);
+                }
+                thread::sleep(Duration::from_millis(50));
+                let mut counts = worker_counts.lock().unwrap();
+                counts.0 -= 1;
+                counts.2 += 1;
+            }),
+        );
+        thread::sleep(Duration::from_millis(100));
+
+        let total = RETRY_WORKERS * 3;
+        let pending_validations = test_pending_validations(
+            (0..total)
+                .map(|value| test_pending_validation(value, 0))
+                .collect(),
+        );
+        let start = Instant::now();
+        scheduler.schedule(&pending_validations, start);
+        for _ in 0..200 {
+            if counts.lock().unwrap().2 == total {
+                break;
+            }
+            scheduler.schedule(&pending_validations, start + retry_delay(0));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let (_, most_running, finished) = *counts.lock().unwrap();
+        assert_eq!(finished, total);
+        assert!(most_running > 0);
+        assert!(most_running <= RETRY_WORKERS);
+    }
 }
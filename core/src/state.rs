@@ -59,7 +59,7 @@ impl State {
             nucleus: Arc::new(NucleusState::new()),
             agent: Arc::new(AgentState::new(
                 ChainStore::new(chain_cas.clone()),
-                context.agent_id.address(),
+                context.agent_id().address(),
             )),
             dht: Arc::new(DhtStore::new(dht_cas.clone(), eav)),
             network: Arc::new(NetworkState::new()),
@@ -156,11 +156,11 @@ impl State {
         agent_snapshot: AgentStateSnapshot,
         nucleus_snapshot: NucleusStateSnapshot,
     ) -> HcResult<State> {
-        let agent_state = AgentState::new_with_top_chain_header(
+        let agent_state = AgentState::restore(
             ChainStore::new(context.dht_storage.clone()),
             agent_snapshot.top_chain_header().map(|h| h.to_owned()),
-            context.agent_id.address(),
-        );
+            context.agent_id().address(),
+        )?;
         let nucleus_state = NucleusState::from(nucleus_snapshot);
         Ok(State::new_with_agent_and_nucleus(
             context.clone(),
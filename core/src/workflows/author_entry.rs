@@ -1,10 +1,11 @@
 use crate::{
-    agent::actions::commit::commit_entry,
+    agent::actions::{bundle::stage_commit, commit::commit_entry_with_provenances},
     context::Context,
     entry::CanPublish,
     network::actions::publish::publish,
     nucleus::{
         actions::build_validation_package::build_validation_package, validation::validate_entry,
+        ZomeFnCall,
     },
 };
 
@@ -33,38 +34,24 @@ pub async fn author_entry<'a>(
         address, entry
     ));
 
-    // 1. Build the context needed for validation of the entry
-    let validation_package = await!(build_validation_package(
-        &entry,
-        context.clone(),
-        provenances
-    ))?;
-    let validation_data = ValidationData {
-        package: validation_package,
-        lifecycle: EntryLifecycle::Chain,
-    };
-
-    // 2. Validate the entry
-    context.log(format!(
-        "debug/workflow/authoring_entry/{}: validating...",
-        address
-    ));
-    await!(validate_entry(
-        entry.clone(),
+    // 1. Validate the entry
+    await!(validate_authored_entry(
+        entry,
         maybe_link_update_delete.clone(),
-        validation_data,
-        &context
+        context,
+        provenances
     ))?;
     context.log(format!("Authoring entry {}: is valid!", address));
 
-    // 3. Commit the entry
+    // 2. Commit the entry
     context.log(format!(
         "debug/workflow/authoring_entry/{}: committing...",
         address
     ));
-    let addr = await!(commit_entry(
+    let addr = await!(commit_entry_with_provenances(
         entry.clone(),
         maybe_link_update_delete,
+        provenances.clone(),
         &context
     ))?;
     context.log(format!(
@@ -72,7 +59,7 @@ pub async fn author_entry<'a>(
         address
     ));
 
-    // 4. Publish the valid entry to DHT. This will call Hold to itself
+    // 3. Publish the valid entry to DHT. This will call Hold to itself
     if entry.entry_type().can_publish(context) {
         context.log(format!(
             "debug/workflow/authoring_entry/{}: publishing...",
@@ -92,6 +79,82 @@ pub async fn author_entry<'a>(
     Ok(CommitEntryResult::new(addr))
 }
 
+/// Validates an entry the agent is about to commit, as the first steps of authoring it
+/// do, without committing it.
+pub async fn validate_authored_entry<'a>(
+    entry: &'a Entry,
+    maybe_link_update_delete: Option<Address>,
+    context: &'a Arc<Context>,
+    provenances: &'a Vec<Provenance>,
+) -> Result<(), HolochainError> {
+    let address = entry.address();
+
+    // 1. Build the context needed for validation of the entry
+    let validation_package = await!(build_validation_package(
+        &entry,
+        context.clone(),
+        provenances
+    ))?;
+    let validation_data = ValidationData {
+        package: validation_package,
+        lifecycle: EntryLifecycle::Chain,
+    };
+
+    // 2. Validate the entry
+    context.log(format!(
+        "debug/workflow/authoring_entry/{}: validating...",
+        address
+    ));
+    await!(validate_entry(
+        entry.clone(),
+        maybe_link_update_delete,
+        validation_data,
+        &context
+    ))?;
+    Ok(())
+}
+
+/// Authors an entry on behalf of a zome call.
+/// If the call started a bundle, the entry only gets staged in it. Validating, appending
+/// and publishing happen for all staged entries together when the bundle gets closed.
+/// Entries of other zome calls don't get into the bundle.
+pub async fn author_entry_in_zome_call<'a>(
+    entry: &'a Entry,
+    maybe_link_update_delete: Option<Address>,
+    context: &'a Arc<Context>,
+    provenances: &'a Vec<Provenance>,
+    maybe_zome_call: Option<ZomeFnCall>,
+) -> Result<CommitEntryResult, HolochainError> {
+    let bundled_zome_call = maybe_zome_call.filter(|zome_call| {
+        context
+            .state()
+            .map(|state| state.agent().bundle(zome_call).is_some())
+            .unwrap_or(false)
+    });
+    match bundled_zome_call {
+        Some(zome_call) => {
+            let address = stage_commit(
+                zome_call,
+                entry.clone(),
+                maybe_link_update_delete,
+                provenances.clone(),
+                context,
+            )?;
+            context.log(format!(
+                "debug/workflow/authoring_entry/{}: staged in bundle",
+                address
+            ));
+            Ok(CommitEntryResult::new(address))
+        }
+        None => await!(author_entry(
+            entry,
+            maybe_link_update_delete,
+            context,
+            provenances
+        )),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::author_entry;
@@ -0,0 +1,138 @@
+use crate::{
+    agent::{
+        actions::bundle::{commit_bundle, discard_bundle, seal_bundle},
+        state::create_bundle_headers,
+    },
+    context::Context,
+    entry::CanPublish,
+    network::actions::publish::publish,
+    nucleus::{
+        actions::build_validation_package::build_bundled_validation_package,
+        validation::validate_entry, ZomeFnCall,
+    },
+};
+
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    entry::Entry,
+    error::HolochainError,
+    validation::{EntryLifecycle, ValidationData},
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::bundle::BundleOnClose;
+use std::sync::Arc;
+
+/// Closes the bundle of the zome call.
+///
+/// With `BundleOnClose::Discard` all staged commits get dropped.
+/// With `BundleOnClose::Commit` the bundle stops taking commits and the headers
+/// for all staged entries get created, each one on top of the one before.
+/// Every entry gets validated with its header, so that entries of the bundle can
+/// reference each other. If any of them is invalid, the whole bundle gets dropped
+/// and the validation error is returned. Otherwise all entries get appended to the
+/// chain at once with these headers and published.
+/// Returns the addresses of the committed entries.
+pub async fn close_bundle_workflow<'a>(
+    zome_call: ZomeFnCall,
+    on_close: BundleOnClose,
+    context: &'a Arc<Context>,
+) -> Result<Vec<Address>, HolochainError> {
+    let state = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Could not get state".to_string()))?;
+    let bundle = state
+        .agent()
+        .bundle(&zome_call)
+        .ok_or_else(|| HolochainError::ErrorGeneric("No bundle is open".to_string()))?;
+
+    if on_close == BundleOnClose::Discard {
+        discard_bundle(zome_call, context);
+        context.log(format!(
+            "debug/workflow/close_bundle: discarded bundle {} with {} commits",
+            bundle.user_param(),
+            bundle.commits().len()
+        ));
+        return Ok(Vec::new());
+    }
+
+    // 1. Stop taking commits and create the headers
+    seal_bundle(zome_call.clone(), context)?;
+    let state = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Could not get state".to_string()))?;
+    let bundle = state
+        .agent()
+        .bundle(&zome_call)
+        .ok_or_else(|| HolochainError::ErrorGeneric("No bundle is open".to_string()))?;
+    let bundle_headers = match create_bundle_headers(&bundle, &state.agent(), &state) {
+        Ok(bundle_headers) => bundle_headers,
+        Err(error) => {
+            discard_bundle(zome_call, context);
+            return Err(error);
+        }
+    };
+
+    // 2. Validate all staged entries with their headers
+    for (index, (entry, chain_header)) in bundle_headers.iter().enumerate() {
+        let validation_result = await!(validate_bundled_entry(
+            entry,
+            chain_header,
+            bundle_headers[..index].to_vec(),
+            context
+        ));
+        if let Err(error) = validation_result {
+            discard_bundle(zome_call, context);
+            context.log(format!(
+                "debug/workflow/close_bundle: entry {} is invalid, dropped bundle {}: {:?}",
+                entry.address(),
+                bundle.user_param(),
+                error
+            ));
+            return Err(error);
+        }
+    }
+
+    // 3. Append them to the chain all at once
+    let committed = commit_bundle(zome_call, bundle_headers, context)?;
+    context.log(format!(
+        "debug/workflow/close_bundle: committed bundle {} with {} entries",
+        bundle.user_param(),
+        committed.len()
+    ));
+
+    // 4. Publish the ones that are public
+    for (entry, _) in committed.iter() {
+        if entry.entry_type().can_publish(context) {
+            await!(publish(entry.address(), &context))?;
+        }
+    }
+    Ok(committed
+        .iter()
+        .map(|(entry, _)| entry.address())
+        .collect())
+}
+
+async fn validate_bundled_entry<'a>(
+    entry: &'a Entry,
+    chain_header: &'a ChainHeader,
+    staged: Vec<(Entry, ChainHeader)>,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let validation_package = await!(build_bundled_validation_package(
+        &entry,
+        chain_header.clone(),
+        staged,
+        context.clone()
+    ))?;
+    let validation_data = ValidationData {
+        package: validation_package,
+        lifecycle: EntryLifecycle::Chain,
+    };
+    await!(validate_entry(
+        entry.clone(),
+        chain_header.link_update_delete(),
+        validation_data,
+        &context
+    ))?;
+    Ok(())
+}
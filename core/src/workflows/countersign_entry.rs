@@ -0,0 +1,175 @@
+use crate::{
+    context::Context,
+    network::{
+        actions::request_countersignature::{request_countersignature, send_countersigned_entry},
+        direct_message::{CountersignatureRequest, CountersignedEntry},
+    },
+    workflows::author_entry::author_entry,
+};
+
+use holochain_core_types::{
+    chain_header::{countersigned_payload, ChainHeader},
+    entry::{entry_type::EntryType, Entry},
+    error::HolochainError,
+    signature::{Provenance, Signature},
+    time::Timeout,
+};
+use holochain_dpki::utils::Verify;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::commit_entry::CommitEntryResult;
+use std::sync::Arc;
+
+/// Returns true if the DNA defines the entry type as a countersigned app entry type
+pub fn is_countersigned_type(entry_type: &EntryType, context: &Arc<Context>) -> bool {
+    match entry_type {
+        EntryType::App(app_entry_type) => context
+            .get_dna()
+            .and_then(|dna| {
+                dna.get_entry_type_def(&String::from(app_entry_type.clone()))
+                    .map(|def| def.countersigned)
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// The agent's current address
+pub(crate) fn agent_address(context: &Arc<Context>) -> Result<Address, HolochainError> {
+    context
+        .state()
+        .ok_or("State uninitialized!")?
+        .agent()
+        .get_agent_address()
+}
+
+/// Signs the countersigned payload of the entry for the agent and the counterparty
+/// with the agent's current key
+pub(crate) fn sign_countersigned_entry(
+    entry: &Entry,
+    counterparty: &Address,
+    context: &Arc<Context>,
+) -> Result<Provenance, HolochainError> {
+    let agent_address = agent_address(context)?;
+    let payload = countersigned_payload(
+        &entry.entry_type(),
+        &entry.address(),
+        &[agent_address.clone(), counterparty.clone()],
+    );
+    let signature = Signature::from(context.sign(payload)?);
+    Ok(Provenance::new(agent_address, signature))
+}
+
+/// Checks that the provenance is a signature of `signer` of the countersigned payload
+/// of the entry for `signer` and `other_signer`
+pub(crate) fn verify_countersignature(
+    provenance: &Provenance,
+    entry: &Entry,
+    signer: &Address,
+    other_signer: &Address,
+) -> bool {
+    let payload = countersigned_payload(
+        &entry.entry_type(),
+        &entry.address(),
+        &[signer.clone(), other_signer.clone()],
+    );
+    provenance.source() == *signer && provenance.verify(payload).unwrap_or(false)
+}
+
+/// true if the provenance signs the entry of the header: its address or, for countersigned
+/// entries, the countersigned payload of the header.
+/// Meant for checks that can't look up if the entry type is countersigned.
+pub fn signs_header(provenance: &Provenance, header: &ChainHeader) -> bool {
+    provenance
+        .verify(header.entry_address().to_string())
+        .unwrap_or(false)
+        || provenance
+            .verify(header.countersigned_payload())
+            .unwrap_or(false)
+}
+
+/// Both signatures in the same order, so both headers get the same provenances
+fn sorted_provenances(mut provenances: Vec<Provenance>) -> Vec<Provenance> {
+    provenances.sort_by_key(|provenance| provenance.source().to_string());
+    provenances
+}
+
+/// Authors an entry of a countersigned entry type together with the agent `counterparty`:
+///
+/// 1. Signs the countersigned payload, which names the entry and both agents, and asks
+///    the counterparty to countersign it.
+/// 2. The counterparty asks its app if it accepts the entry, validates it and responds
+///    with its signature, without committing anything yet.
+/// 3. Commits the entry with both signatures as provenances.
+/// 4. Sends the committed entry to the counterparty, which only now commits it as well.
+///
+/// So the counterparty never commits an entry the proposing agent did not commit.
+/// If the last step fails, only the proposing agent's chain holds the entry,
+/// which the returned error says.
+pub async fn countersign_entry<'a>(
+    entry: &'a Entry,
+    counterparty: Address,
+    timeout: Timeout,
+    context: &'a Arc<Context>,
+) -> Result<CommitEntryResult, HolochainError> {
+    if !is_countersigned_type(&entry.entry_type(), context) {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Entry type {} is not countersigned",
+            entry.entry_type()
+        )));
+    }
+    let address = entry.address();
+    let agent_address = agent_address(context)?;
+    if agent_address == counterparty {
+        return Err(HolochainError::ErrorGeneric(
+            "An entry can't be countersigned by its author".to_string(),
+        ));
+    }
+    context.log(format!(
+        "debug/workflow/countersign_entry/{}: requesting countersignature from {}",
+        address, counterparty
+    ));
+
+    let provenance = sign_countersigned_entry(entry, &counterparty, context)?;
+    let request = CountersignatureRequest {
+        entry: entry.clone(),
+        provenance: provenance.clone(),
+    };
+    let countersignature = await!(request_countersignature(
+        counterparty.clone(),
+        request,
+        timeout.clone(),
+        context.clone()
+    ))?;
+
+    if !verify_countersignature(&countersignature, entry, &counterparty, &agent_address) {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Got an invalid countersignature of entry {} from {}",
+            address, counterparty
+        )));
+    }
+
+    let provenances = sorted_provenances(vec![provenance, countersignature]);
+    let result = await!(author_entry(entry, None, context, &provenances))?;
+
+    context.log(format!(
+        "debug/workflow/countersign_entry/{}: committed, asking {} to commit",
+        address, counterparty
+    ));
+    let countersigned_entry = CountersignedEntry {
+        entry: entry.clone(),
+        provenances,
+    };
+    await!(send_countersigned_entry(
+        counterparty.clone(),
+        countersigned_entry,
+        timeout,
+        context.clone()
+    ))
+    .map_err(|error| {
+        HolochainError::ErrorGeneric(format!(
+            "Committed entry {}, but {} did not commit it: {}",
+            address, counterparty, error
+        ))
+    })?;
+    Ok(result)
+}
@@ -7,6 +7,7 @@ use holochain_core_types::{
     chain_header::ChainHeader, crud_status::CrudStatus, entry::Entry, error::HolochainError,
     link::link_data::LinkData,
 };
+use holochain_persistence_api::cas::content::AddressableContent;
 use holochain_wasm_utils::api_serialization::{
     get_entry::{GetEntryArgs, GetEntryOptions, GetEntryResultType::Single},
     get_links::{GetLinksArgs, GetLinksResult, LinksResult, LinksStatusRequestKind},
@@ -18,6 +19,13 @@ pub async fn get_link_result_workflow<'a>(
     link_args: &'a GetLinksArgs,
 ) -> Result<GetLinksResult, HolochainError> {
     let links = await!(get_link_add_entries(context, link_args))?;
+    //a full page means there might be more links, so the last one is where the next page starts
+    let cursor = match link_args.options.limit {
+        Some(limit) if limit > 0 && links.len() >= limit => links
+            .last()
+            .map(|link_entry_crud| Entry::LinkAdd(link_entry_crud.0.clone()).address()),
+        _ => None,
+    };
     //get links based on status request, all for everything, deleted for deleted links and live for active links
     let link_results = links
         .into_iter()
@@ -34,7 +42,7 @@ pub async fn get_link_result_workflow<'a>(
         })
         .collect::<Vec<LinksResult>>();
 
-    Ok(GetLinksResult::new(link_results))
+    Ok(GetLinksResult::with_cursor(link_results, cursor))
 }
 
 pub async fn get_link_add_entries<'a>(
@@ -47,7 +55,7 @@ pub async fn get_link_add_entries<'a>(
         link_args.entry_address.clone(),
         link_args.link_type.clone(),
         link_args.tag.clone(),
-        link_args.options.timeout.clone()
+        link_args.options.clone()
     ))?;
 
     //iterate over link add entries
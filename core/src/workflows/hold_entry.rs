@@ -1,15 +1,15 @@
 use crate::{
-    context::Context,
-    dht::actions::hold::hold_entry,
-    network::entry_with_header::EntryWithHeader,
-    nucleus::{
-        actions::add_pending_validation::add_pending_validation, validation::validate_entry,
-    },
+    context::Context, dht::actions::hold::hold_entry, network::entry_with_header::EntryWithHeader,
+    nucleus::validation::validate_entry,
 };
 
 use crate::{
-    nucleus::validation::ValidationError, scheduled_jobs::pending_validations::ValidatingWorkflow,
-    workflows::validation_package,
+    nucleus::validation::ValidationError,
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
+    workflows::{
+        pending_on_unresolved_dependencies, validation_package_or_pending,
+        warrant::issue_warrant_workflow,
+    },
 };
 use holochain_core_types::{
     error::HolochainError,
@@ -25,31 +25,11 @@ pub async fn hold_entry_workflow<'a>(
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
     // 1. Get hold of validation package
-    let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))
-        .map_err(|err| {
-            let message = "Could not get validation package from source! -> Add to pending...";
-            context.log(format!("debug/workflow/hold_entry: {}", message));
-            context.log(format!("debug/workflow/hold_entry: Error was: {:?}", err));
-            add_pending_validation(
-                entry_with_header.to_owned(),
-                Vec::new(),
-                ValidatingWorkflow::HoldEntry,
-                &context,
-            );
-            HolochainError::ValidationPending
-        })?;
-
-    let validation_package = maybe_validation_package.ok_or_else(|| {
-        let message = "Source did respond to request but did not deliver validation package! (Empty response) This is weird! Let's try this again later -> Add to pending";
-        context.log(format!("debug/workflow/hold_entry: {}", message));
-        add_pending_validation(
-            entry_with_header.to_owned(),
-            Vec::new(),
-            ValidatingWorkflow::HoldEntry,
-            &context,
-        );
-        HolochainError::ValidationPending
-    })?;
+    let validation_package = await!(validation_package_or_pending(
+        &entry_with_header,
+        ValidatingWorkflow::HoldEntry,
+        context.clone()
+    ))?;
     context.log(format!("debug/workflow/hold_entry: got validation package"));
 
     // 2. Create validation data struct
@@ -59,34 +39,41 @@ pub async fn hold_entry_workflow<'a>(
     };
 
     // 3. Validate the entry
-    await!(validate_entry(
+    let validation_result = await!(validate_entry(
         entry_with_header.entry.clone(),
         None,
         validation_data,
         &context
-    ))
-    .map_err(|err| {
-        if let ValidationError::UnresolvedDependencies(dependencies) = &err {
+    ));
+    if let Err(ValidationError::Fail(reason)) = &validation_result {
+        // Let the neighbourhood of the author know
+        if let Err(error) = await!(issue_warrant_workflow(
+            entry_with_header,
+            ValidatingWorkflow::HoldEntry,
+            reason.clone(),
+            &context
+        )) {
             context.log(format!(
-                "debug/workflow/hold_entry: {} could not be validated due to unresolved dependencies and will be tried later. List of missing dependencies: {:?}",
-                entry_with_header.entry.address(),
-                dependencies,
+                "debug/workflow/hold_entry: Could not issue warrant: {}",
+                error
             ));
-            add_pending_validation(
-                entry_with_header.to_owned(),
-                dependencies.clone(),
-                ValidatingWorkflow::HoldEntry,
-                &context,
-            );
-            HolochainError::ValidationPending
-        } else {
+        }
+    }
+    validation_result.map_err(|err| {
+        let error = pending_on_unresolved_dependencies(
+            err,
+            entry_with_header,
+            ValidatingWorkflow::HoldEntry,
+            &context,
+        );
+        if error != HolochainError::ValidationPending {
             context.log(format!(
                 "info/workflow/hold_entry: Entry {} is NOT valid! Validation error: {:?}",
                 entry_with_header.entry.address(),
-                err,
+                error,
             ));
-            HolochainError::from(err)
         }
+        error
     })?;
 
     context.log(format!(
@@ -1,9 +1,10 @@
 use crate::{
     context::Context, dht::actions::remove_entry::remove_entry,
     network::entry_with_header::EntryWithHeader, nucleus::validation::validate_entry,
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
 };
 
-use crate::workflows::validation_package;
+use crate::workflows::{pending_on_unresolved_dependencies, validation_package_or_pending};
 use holochain_core_types::{
     entry::Entry,
     error::HolochainError,
@@ -17,9 +18,11 @@ pub async fn hold_remove_workflow<'a>(
     context: Arc<Context>,
 ) -> Result<(), HolochainError> {
     // 1. Get hold of validation package
-    let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))?;
-    let validation_package = maybe_validation_package
-        .ok_or("Could not get validation package from source".to_string())?;
+    let validation_package = await!(validation_package_or_pending(
+        &entry_with_header,
+        ValidatingWorkflow::RemoveEntry,
+        context.clone()
+    ))?;
 
     // 2. Create validation data struct
     let validation_data = ValidationData {
@@ -33,7 +36,15 @@ pub async fn hold_remove_workflow<'a>(
         None,
         validation_data,
         &context
-    ))?;
+    ))
+    .map_err(|err| {
+        pending_on_unresolved_dependencies(
+            err,
+            &entry_with_header,
+            ValidatingWorkflow::RemoveEntry,
+            &context,
+        )
+    })?;
 
     let deletion_entry = unwrap_to!(entry_with_header.entry => Entry::Deletion);
 
@@ -1,10 +1,11 @@
 use crate::{
     context::Context, dht::actions::update_entry::update_entry,
     network::entry_with_header::EntryWithHeader, nucleus::validation::validate_entry,
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
 };
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 
-use crate::workflows::validation_package;
+use crate::workflows::{pending_on_unresolved_dependencies, validation_package_or_pending};
 use holochain_core_types::{
     error::HolochainError,
     validation::{EntryLifecycle, ValidationData},
@@ -18,9 +19,11 @@ pub async fn hold_update_workflow<'a>(
     let EntryWithHeader { entry, header } = &entry_with_header;
 
     // 1. Get hold of validation package
-    let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))?;
-    let validation_package = maybe_validation_package
-        .ok_or("Could not get validation package from source".to_string())?;
+    let validation_package = await!(validation_package_or_pending(
+        &entry_with_header,
+        ValidatingWorkflow::UpdateEntry,
+        context.clone()
+    ))?;
 
     // get link from header
     let link = header
@@ -39,7 +42,15 @@ pub async fn hold_update_workflow<'a>(
         Some(link.clone()),
         validation_data,
         &context
-    ))?;
+    ))
+    .map_err(|err| {
+        pending_on_unresolved_dependencies(
+            err,
+            &entry_with_header,
+            ValidatingWorkflow::UpdateEntry,
+            &context,
+        )
+    })?;
 
     // 3. If valid store the entry in the local DHT shard
     await!(update_entry(
@@ -4,11 +4,12 @@ use crate::{
 };
 
 use crate::{
-    nucleus::{
-        actions::add_pending_validation::add_pending_validation, validation::ValidationError,
-    },
+    nucleus::validation::ValidationError,
     scheduled_jobs::pending_validations::ValidatingWorkflow,
-    workflows::validation_package,
+    workflows::{
+        pending_on_unresolved_dependencies, validation_package_or_pending,
+        warrant::issue_warrant_workflow,
+    },
 };
 use holochain_core_types::{
     entry::Entry,
@@ -34,30 +35,11 @@ pub async fn hold_link_workflow<'a>(
         "debug/workflow/hold_link: getting validation package..."
     ));
     // 1. Get hold of validation package
-    let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))
-        .map_err(|err| {
-            let message = "Could not get validation package from source! -> Add to pending...";
-            context.log(format!("debug/workflow/hold_link: {}", message));
-            context.log(format!("debug/workflow/hold_link: Error was: {:?}", err));
-            add_pending_validation(
-                entry_with_header.to_owned(),
-                Vec::new(),
-                ValidatingWorkflow::HoldLink,
-                context,
-            );
-            HolochainError::ValidationPending
-        })?;
-    let validation_package = maybe_validation_package.ok_or_else(|| {
-        let message = "Source did respond to request but did not deliver validation package! (Empty response) This is weird! Let's try this again later -> Add to pending";
-        context.log(format!("debug/workflow/hold_link: {}", message));
-        add_pending_validation(
-            entry_with_header.to_owned(),
-            Vec::new(),
-            ValidatingWorkflow::HoldLink,
-            &context,
-        );
-        HolochainError::ValidationPending
-    })?;
+    let validation_package = await!(validation_package_or_pending(
+        &entry_with_header,
+        ValidatingWorkflow::HoldLink,
+        context.clone()
+    ))?;
     context.log(format!("debug/workflow/hold_link: got validation package"));
 
     // 2. Create validation data struct
@@ -68,36 +50,49 @@ pub async fn hold_link_workflow<'a>(
 
     // 3. Validate the entry
     context.log(format!("debug/workflow/hold_link: validate..."));
-    await!(validate_entry(
+    let validation_result = await!(validate_entry(
         entry_with_header.entry.clone(),
         None,
         validation_data,
         &context
-    ))
-    .map_err(|err| {
-        if let ValidationError::UnresolvedDependencies(dependencies) = &err {
-            context.log(format!("debug/workflow/hold_link: Link could not be validated due to unresolved dependencies and will be tried later. List of missing dependencies: {:?}", dependencies));
-            add_pending_validation(
-                entry_with_header.to_owned(),
-                dependencies.clone(),
-                ValidatingWorkflow::HoldLink,
-                &context,
-            );
-            HolochainError::ValidationPending
-        } else {
+    ));
+    if let Err(ValidationError::Fail(reason)) = &validation_result {
+        // Let the neighbourhood of the author know
+        if let Err(error) = await!(issue_warrant_workflow(
+            entry_with_header,
+            ValidatingWorkflow::HoldLink,
+            reason.clone(),
+            context
+        )) {
+            context.log(format!(
+                "debug/workflow/hold_link: Could not issue warrant: {}",
+                error
+            ));
+        }
+    }
+    validation_result.map_err(|err| {
+        let error = pending_on_unresolved_dependencies(
+            err,
+            entry_with_header,
+            ValidatingWorkflow::HoldLink,
+            context,
+        );
+        if error != HolochainError::ValidationPending {
             context.log(format!(
                 "info/workflow/hold_link: Link {:?} is NOT valid! Validation error: {:?}",
-                entry_with_header.entry,
-                err,
+                entry_with_header.entry, error,
             ));
-            HolochainError::from(err)
         }
-
+        error
     })?;
     context.log(format!("debug/workflow/hold_link: is valid!"));
 
     // 3. If valid store the entry in the local DHT shard
-    await!(add_link(&link_add, &context))?;
+    await!(add_link(
+        &link_add,
+        entry_with_header.header.timestamp(),
+        &context
+    ))?;
     context.log(format!("debug/workflow/hold_link: added! {:?}", link));
     Ok(())
 }
@@ -1,5 +1,7 @@
 pub mod application;
 pub mod author_entry;
+pub mod close_bundle;
+pub mod countersign_entry;
 pub mod get_entry_result;
 pub mod get_link_result;
 pub mod handle_custom_direct_message;
@@ -8,21 +10,30 @@ pub mod hold_entry_remove;
 pub mod hold_entry_update;
 pub mod hold_link;
 pub mod remove_link;
+pub mod respond_countersignature_request;
 pub mod respond_validation_package_request;
+pub mod update_agent;
+pub mod warrant;
 
 use crate::{
     context::Context,
     network::{
         actions::get_validation_package::get_validation_package, entry_with_header::EntryWithHeader,
     },
-    nucleus::ribosome::callback::{
-        validation_package::get_validation_package_definition, CallbackResult,
+    nucleus::{
+        actions::add_pending_validation::add_pending_validation,
+        ribosome::callback::{
+            validation_package::get_validation_package_definition, CallbackResult,
+        },
+        validation::ValidationError,
     },
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
 };
 use holochain_core_types::{
     error::HolochainError,
     validation::{ValidationPackage, ValidationPackageDefinition},
 };
+use holochain_persistence_api::cas::content::AddressableContent;
 use std::sync::Arc;
 
 /// Try to create a ValidationPackage for the given entry just from/with the header.
@@ -74,3 +85,55 @@ async fn validation_package(
         ))
     }
 }
+
+/// Gets hold of the validation package for the given entry like validation_package() does.
+/// If there is none to be had, the entry gets added to the pending validations of the given
+/// workflow so it will be retried later, and ValidationPending is returned.
+async fn validation_package_or_pending(
+    entry_with_header: &EntryWithHeader,
+    workflow: ValidatingWorkflow,
+    context: Arc<Context>,
+) -> Result<ValidationPackage, HolochainError> {
+    let message = match await!(validation_package(&entry_with_header, context.clone())) {
+        Ok(Some(package)) => return Ok(package),
+        Ok(None) => String::from(
+            "Source did respond to request but did not deliver validation package!",
+        ),
+        Err(err) => format!(
+            "Could not get validation package from source! Error was: {:?}",
+            err
+        ),
+    };
+    context.log(format!(
+        "debug/workflow/validation_package: {} for {}: {} -> Add to pending...",
+        workflow,
+        entry_with_header.entry.address(),
+        message,
+    ));
+    add_pending_validation(entry_with_header.to_owned(), Vec::new(), workflow, &context);
+    Err(HolochainError::ValidationPending)
+}
+
+/// Maps the error of a failed validation to the error the given workflow ends with.
+/// If the validation only lacked dependencies, the entry gets added to the pending
+/// validations of the workflow so it will be retried once they are held.
+fn pending_on_unresolved_dependencies(
+    err: ValidationError,
+    entry_with_header: &EntryWithHeader,
+    workflow: ValidatingWorkflow,
+    context: &Arc<Context>,
+) -> HolochainError {
+    match err {
+        ValidationError::UnresolvedDependencies(dependencies) => {
+            context.log(format!(
+                "debug/workflow/validate: {} for {} could not be validated due to unresolved dependencies and will be tried later. List of missing dependencies: {:?}",
+                workflow,
+                entry_with_header.entry.address(),
+                dependencies,
+            ));
+            add_pending_validation(entry_with_header.to_owned(), dependencies, workflow, context);
+            HolochainError::ValidationPending
+        }
+        err => HolochainError::from(err),
+    }
+}
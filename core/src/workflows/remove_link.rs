@@ -1,9 +1,10 @@
 use crate::{
     context::Context, dht::actions::remove_link::remove_link,
     network::entry_with_header::EntryWithHeader, nucleus::validation::validate_entry,
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
 };
 
-use crate::workflows::validation_package;
+use crate::workflows::{pending_on_unresolved_dependencies, validation_package_or_pending};
 use holochain_core_types::{
     entry::Entry,
     error::HolochainError,
@@ -28,9 +29,11 @@ pub async fn remove_link_workflow<'a>(
     context.log(format!(
         "debug/workflow/remove_link: getting validation package..."
     ));
-    let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))?;
-    let validation_package = maybe_validation_package
-        .ok_or("Could not get validation package from source".to_string())?;
+    let validation_package = await!(validation_package_or_pending(
+        &entry_with_header,
+        ValidatingWorkflow::RemoveLink,
+        context.clone()
+    ))?;
     context.log(format!(
         "debug/workflow/remove_link: got validation package!"
     ));
@@ -50,8 +53,16 @@ pub async fn remove_link_workflow<'a>(
         &context
     ))
     .map_err(|err| {
-        context.log(format!("debug/workflow/remove_link: invalid! {:?}", err));
-        err
+        let error = pending_on_unresolved_dependencies(
+            err,
+            &entry_with_header,
+            ValidatingWorkflow::RemoveLink,
+            context,
+        );
+        if error != HolochainError::ValidationPending {
+            context.log(format!("debug/workflow/remove_link: invalid! {:?}", error));
+        }
+        error
     })?;
     context.log(format!("debug/workflow/remove_link: is valid!"));
 
@@ -0,0 +1,221 @@
+use crate::{
+    action::{Action, ActionWrapper, DirectMessageData},
+    context::Context,
+    instance::dispatch_action,
+    network::direct_message::{CountersignatureRequest, CountersignedEntry, DirectMessage},
+    nucleus::{
+        actions::build_validation_package::build_validation_package,
+        ribosome::callback::{
+            accept_countersignature::accept_countersignature, CallbackParams, CallbackResult,
+        },
+        validation::validate_entry,
+    },
+    workflows::{
+        author_entry::author_entry,
+        countersign_entry::{
+            agent_address, is_countersigned_type, sign_countersigned_entry,
+            verify_countersignature,
+        },
+    },
+};
+
+use holochain_core_types::{
+    entry::{entry_type::EntryType, Entry},
+    error::HolochainError,
+    signature::Provenance,
+    validation::{EntryLifecycle, ValidationData},
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::commit_entry::AcceptCountersignatureParams;
+use std::sync::Arc;
+
+/// Handles a countersignature request of agent `to_agent_id`.
+/// If the app accepts the proposed entry and it is valid, our signature is sent back,
+/// so the proposing agent can commit the entry. We only commit it once the proposing
+/// agent sends it back committed (see respond_commit_countersigned_entry()).
+pub async fn respond_countersignature_request(
+    to_agent_id: Address,
+    msg_id: String,
+    request: CountersignatureRequest,
+    context: Arc<Context>,
+) {
+    let address = request.entry.address();
+    let response = await!(countersign(&to_agent_id, request, &context));
+
+    match response {
+        Ok(_) => context.log(format!(
+            "debug/workflow/respond_countersignature_request: countersigned entry {} of agent {}",
+            address, to_agent_id
+        )),
+        Err(ref error) => context.log(format!(
+            "debug/workflow/respond_countersignature_request: did not countersign entry {} of agent {}: {}",
+            address, to_agent_id, error
+        )),
+    };
+
+    let direct_message =
+        DirectMessage::Countersignature(response.map_err(|error| error.to_string()));
+    respond(to_agent_id, msg_id, direct_message, &context);
+}
+
+/// Handles a countersigned entry that agent `to_agent_id` committed after we countersigned it,
+/// by committing it to our chain as well.
+pub async fn respond_commit_countersigned_entry(
+    to_agent_id: Address,
+    msg_id: String,
+    countersigned_entry: CountersignedEntry,
+    context: Arc<Context>,
+) {
+    let address = countersigned_entry.entry.address();
+    let response = await!(commit_countersigned(
+        &to_agent_id,
+        countersigned_entry,
+        &context
+    ));
+
+    match response {
+        Ok(_) => context.log(format!(
+            "debug/workflow/respond_commit_countersigned_entry: committed entry {} countersigned with agent {}",
+            address, to_agent_id
+        )),
+        Err(ref error) => context.log(format!(
+            "debug/workflow/respond_commit_countersigned_entry: did not commit entry {} countersigned with agent {}: {}",
+            address, to_agent_id, error
+        )),
+    };
+
+    let direct_message =
+        DirectMessage::CountersignedEntryCommitted(response.map_err(|error| error.to_string()));
+    respond(to_agent_id, msg_id, direct_message, &context);
+}
+
+fn respond(to_agent_id: Address, msg_id: String, message: DirectMessage, context: &Arc<Context>) {
+    let direct_message_data = DirectMessageData {
+        address: to_agent_id,
+        message,
+        msg_id,
+        is_response: true,
+    };
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage(direct_message_data));
+    dispatch_action(context.action_channel(), action_wrapper);
+}
+
+fn check_countersigned_type(entry: &Entry, context: &Arc<Context>) -> Result<(), HolochainError> {
+    if is_countersigned_type(&entry.entry_type(), context) {
+        Ok(())
+    } else {
+        Err(HolochainError::ErrorGeneric(format!(
+            "Entry type {} is not countersigned",
+            entry.entry_type()
+        )))
+    }
+}
+
+/// Runs the accept_countersignature callback of the zome defining the entry type.
+/// Entries are only accepted if the zome implements it and it passes.
+fn check_accepted(
+    from_agent_id: &Address,
+    entry: &Entry,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    let zome_name = match entry.entry_type() {
+        EntryType::App(app_entry_type) => context
+            .get_dna()
+            .and_then(|dna| dna.get_zome_name_for_app_entry_type(&app_entry_type)),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!("No zome defines entry type {}", entry.entry_type()))
+    })?;
+
+    let params = CallbackParams::AcceptCountersignature(AcceptCountersignatureParams {
+        from: from_agent_id.clone(),
+        entry: entry.clone(),
+    });
+    match accept_countersignature(context.clone(), &zome_name, &params) {
+        CallbackResult::Pass => Ok(()),
+        CallbackResult::Fail(reason) => Err(HolochainError::ErrorGeneric(format!(
+            "Countersignature rejected: {}",
+            reason
+        ))),
+        _ => Err(HolochainError::ErrorGeneric(format!(
+            "Zome {} does not accept countersignatures",
+            zome_name
+        ))),
+    }
+}
+
+async fn countersign<'a>(
+    from_agent_id: &'a Address,
+    request: CountersignatureRequest,
+    context: &'a Arc<Context>,
+) -> Result<Provenance, HolochainError> {
+    let entry = request.entry;
+    check_countersigned_type(&entry, context)?;
+
+    let agent_address = agent_address(context)?;
+    let proposer = request.provenance;
+    if !verify_countersignature(&proposer, &entry, from_agent_id, &agent_address) {
+        return Err(HolochainError::ErrorGeneric(
+            "Signature of the proposing agent is invalid".to_string(),
+        ));
+    }
+
+    check_accepted(from_agent_id, &entry, context)?;
+
+    // Validate the entry as it would get committed, without committing it yet
+    let countersignature = sign_countersigned_entry(&entry, from_agent_id, context)?;
+    let provenances = vec![proposer, countersignature.clone()];
+    let validation_package = await!(build_validation_package(
+        &entry,
+        context.clone(),
+        &provenances
+    ))?;
+    let validation_data = ValidationData {
+        package: validation_package,
+        lifecycle: EntryLifecycle::Chain,
+    };
+    await!(validate_entry(entry, None, validation_data, context))?;
+    Ok(countersignature)
+}
+
+async fn commit_countersigned<'a>(
+    from_agent_id: &'a Address,
+    countersigned_entry: CountersignedEntry,
+    context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let entry = countersigned_entry.entry;
+    check_countersigned_type(&entry, context)?;
+
+    // Our own signature in there means we countersigned the entry before
+    let agent_address = agent_address(context)?;
+    let provenances = countersigned_entry.provenances;
+    let signed_by = |signer: &Address, other_signer: &Address| {
+        provenances.iter().any(|provenance| {
+            verify_countersignature(provenance, &entry, signer, other_signer)
+        })
+    };
+    if provenances.len() != 2
+        || !signed_by(&agent_address, from_agent_id)
+        || !signed_by(from_agent_id, &agent_address)
+    {
+        return Err(HolochainError::ErrorGeneric(
+            "Entry is not countersigned by both agents".to_string(),
+        ));
+    }
+
+    // The proposing agent could send it again
+    let already_committed = context
+        .state()
+        .ok_or("State uninitialized!")?
+        .agent()
+        .iter_chain()
+        .any(|header| {
+            *header.entry_address() == entry.address() && header.provenances() == &provenances
+        });
+    if already_committed {
+        return Ok(entry.address());
+    }
+
+    Ok(await!(author_entry(&entry, None, context, &provenances))?.address())
+}
@@ -0,0 +1,59 @@
+use crate::{context::Context, workflows::author_entry::author_entry};
+use holochain_core_types::{
+    agent::AgentId,
+    entry::Entry,
+    error::HolochainError,
+    signature::{Provenance, Signature},
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::sync::Arc;
+
+/// Rotates the agent's signing key.
+///
+/// Commits a new AgentId entry with a fresh key derived by the conductor's keystore
+/// as an update of the current AgentId entry.
+/// The keystore stores the new key before the commit, so a crash after the commit
+/// can't leave the chain with a key that is nowhere to be found.
+/// The header carries two provenances: one signed with the old key (added by the commit
+/// as for every entry) and one signed with the new key, so validators can check that
+/// both keys agree on the rotation.
+/// Only after the commit succeeded the conductor switches to the new key and the
+/// context's agent gets replaced by the new one.
+/// Since the agent address of the AgentState is the address of the latest AgentId
+/// entry on the chain, it changes with the commit.
+pub async fn update_agent_workflow<'a>(
+    context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let old_agent = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Could not get state".to_string()))?
+        .agent()
+        .get_agent()?;
+
+    let new_key = context.conductor_api.rotate_agent_key()?;
+    let new_agent = AgentId::new(&old_agent.nick, new_key);
+    let new_agent_entry = Entry::AgentId(new_agent.clone());
+    let new_key_signature = context
+        .conductor_api
+        .sign_with_rotated_key(new_agent_entry.address().to_string())?;
+    let provenances = vec![Provenance::new(
+        new_agent.address(),
+        Signature::from(new_key_signature),
+    )];
+
+    let result = await!(author_entry(
+        &new_agent_entry,
+        Some(old_agent.address()),
+        context,
+        &provenances
+    ))?;
+
+    context.conductor_api.activate_rotated_key()?;
+    context.set_agent_id(new_agent.clone());
+    context.log(format!(
+        "debug/workflow/update_agent: rotated agent key from {} to {}",
+        old_agent.address(),
+        new_agent.address()
+    ));
+    Ok(result.address())
+}
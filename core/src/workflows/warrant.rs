@@ -0,0 +1,119 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+    network::{
+        actions::publish_warrant::publish_warrant, entry_with_header::EntryWithHeader,
+        warrant::Warrant,
+    },
+    nucleus::validation::{validate_entry, ValidationError},
+    scheduled_jobs::pending_validations::ValidatingWorkflow,
+    workflows::validation_package,
+};
+use holochain_core_types::{
+    error::HolochainError,
+    validation::{EntryLifecycle, ValidationData},
+};
+use std::sync::Arc;
+
+/// Issues a warrant for data that failed validation while we were asked to hold it.
+/// The warranted agents get blocked and the warrant gets published to their neighbourhoods.
+/// Data whose header signatures don't check out does not tell who authored it,
+/// so no warrant gets issued for it.
+pub async fn issue_warrant_workflow<'a>(
+    entry_with_header: &'a EntryWithHeader,
+    workflow: ValidatingWorkflow,
+    reason: String,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let warrant = Warrant::new(entry_with_header.clone(), workflow, reason, context)?;
+    warrant.verify_signatures()?;
+
+    context.log(format!(
+        "info/workflow/issue_warrant: Warranting {:?} for {}",
+        warrant.warranted_agents(),
+        entry_with_header.header.entry_address(),
+    ));
+    act_on_warrant(&warrant, context)?;
+    await!(publish_warrant(warrant, context))?;
+    Ok(())
+}
+
+/// Another node published a warrant to us.
+/// It only gets acted on if its signatures check out and validating the warranted data
+/// ourselves fails as well, so nobody can get an agent blocked with made up warrants.
+pub async fn hold_warrant_workflow(
+    warrant: Warrant,
+    context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    warrant.verify_signatures()?;
+
+    match await!(validate_warranted_data(&warrant, &context)) {
+        Err(ValidationError::Fail(_)) => (),
+        Ok(()) => {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Rejecting warrant of {}: warranted data is valid",
+                warrant.warrantor.source()
+            )));
+        }
+        Err(error) => {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Could not check warrant of {}: {:?}",
+                warrant.warrantor.source(),
+                error
+            )));
+        }
+    }
+
+    context.log(format!(
+        "info/workflow/hold_warrant: Got warrant for {:?} from {}",
+        warrant.warranted_agents(),
+        warrant.warrantor.source(),
+    ));
+    act_on_warrant(&warrant, &context)
+}
+
+/// Puts the warranted agents on the conductor's blocklist and holds the warrant
+/// so it gets gossiped with the agent's address.
+fn act_on_warrant(warrant: &Warrant, context: &Arc<Context>) -> Result<(), HolochainError> {
+    for agent in warrant.warranted_agents() {
+        context.conductor_api.block_agent(&agent)?;
+    }
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::HoldWarrant(warrant.clone())),
+    );
+    Ok(())
+}
+
+/// Runs the validation of the workflow the warrant was issued from
+async fn validate_warranted_data(
+    warrant: &Warrant,
+    context: &Arc<Context>,
+) -> Result<(), ValidationError> {
+    let lifecycle = match warrant.workflow {
+        ValidatingWorkflow::HoldEntry => EntryLifecycle::Dht,
+        ValidatingWorkflow::HoldLink => EntryLifecycle::Meta,
+        _ => {
+            return Err(ValidationError::Error(format!(
+                "Warrants for {} are not supported",
+                warrant.workflow
+            )));
+        }
+    };
+    let package = await!(validation_package(
+        &warrant.entry_with_header,
+        context.clone()
+    ))
+    .map_err(|error| ValidationError::Error(error.to_string()))?
+    .ok_or_else(|| {
+        ValidationError::Error("Author did not deliver a validation package".to_string())
+    })?;
+
+    await!(validate_entry(
+        warrant.entry_with_header.entry.clone(),
+        None,
+        ValidationData { package, lifecycle },
+        context
+    ))
+}
@@ -116,6 +116,38 @@ impl ChainHeader {
     pub fn provenances(&self) -> &Vec<Provenance> {
         &self.provenances
     }
+
+    /// What the agents of the provenances sign if the entry is countersigned
+    pub fn countersigned_payload(&self) -> String {
+        let signers = self
+            .provenances
+            .iter()
+            .map(|provenance| provenance.source())
+            .collect::<Vec<_>>();
+        countersigned_payload(&self.entry_type, &self.entry_address, &signers)
+    }
+}
+
+/// What all agents countersigning an entry sign instead of the entry address:
+/// the entry type, the entry address and the sorted addresses of all signing agents.
+/// All of it is the same in the headers of every agent committing the entry, and the
+/// signatures can't be used for another entry type or another set of agents.
+pub fn countersigned_payload(
+    entry_type: &EntryType,
+    entry_address: &Address,
+    signers: &[Address],
+) -> String {
+    let mut signers = signers
+        .iter()
+        .map(|signer| signer.to_string())
+        .collect::<Vec<_>>();
+    signers.sort();
+    format!(
+        "countersigned:{}:{}:{}",
+        entry_type,
+        entry_address,
+        signers.join(",")
+    )
 }
 
 impl AddressableContent for ChainHeader {
@@ -156,7 +188,7 @@ pub fn test_provenances(sig: &'static str) -> Vec<Provenance> {
 #[cfg(test)]
 pub mod tests {
     use crate::{
-        chain_header::{test_chain_header, test_provenances, ChainHeader},
+        chain_header::{countersigned_payload, test_chain_header, test_provenances, ChainHeader},
         entry::{
             entry_type::{test_entry_type, test_entry_type_a, test_entry_type_b},
             test_entry, test_entry_a, test_entry_b,
@@ -403,4 +435,31 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn countersigned_payload_does_not_depend_on_the_order_of_signers() {
+        let entry = test_entry();
+        let alice = Address::from("alice");
+        let bob = Address::from("bob");
+        let payload = countersigned_payload(
+            &entry.entry_type(),
+            &entry.address(),
+            &[alice.clone(), bob.clone()],
+        );
+        assert_eq!(
+            payload,
+            countersigned_payload(
+                &entry.entry_type(),
+                &entry.address(),
+                &[bob.clone(), alice.clone()]
+            )
+        );
+        assert_ne!(
+            payload,
+            countersigned_payload(&entry.entry_type(), &entry.address(), &[alice.clone()])
+        );
+        assert_ne!(
+            payload,
+            countersigned_payload(&test_entry_type_b(), &entry.address(), &[alice, bob])
+        );
+    }
 }
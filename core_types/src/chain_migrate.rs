@@ -1,4 +1,146 @@
-//! NOT YET AVAILABLE
+//! A ChainMigrate entry marks the move of an agent's source chain from one DNA to another.
+//!
+//! Migrating takes two entries: the chain on the old DNA gets closed with a `Close` entry
+//! and a linked chain on the new DNA gets opened with an `Open` entry.
+//! Both entries carry the same DNA addresses and agent, so each one references the other
+//! chain and the counterpart entry can always be reconstructed with [ChainMigrate::counterpart].
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::Address;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ChainMigrate {}
+/// Which end of a migration a ChainMigrate entry marks
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum MigrationDirection {
+    /// First entry after genesis on the chain of the new DNA
+    #[serde(rename = "open")]
+    Open,
+    /// Last entry on the chain of the old DNA
+    #[serde(rename = "close")]
+    Close,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash, DefaultJson)]
+pub struct ChainMigrate {
+    old_dna_address: Address,
+    new_dna_address: Address,
+    direction: MigrationDirection,
+    agent: Address,
+}
+
+impl ChainMigrate {
+    pub fn new(
+        old_dna_address: Address,
+        new_dna_address: Address,
+        direction: MigrationDirection,
+        agent: Address,
+    ) -> Self {
+        ChainMigrate {
+            old_dna_address,
+            new_dna_address,
+            direction,
+            agent,
+        }
+    }
+
+    /// Entry that closes the chain of `agent` on the old DNA
+    pub fn close(old_dna_address: Address, new_dna_address: Address, agent: Address) -> Self {
+        Self::new(
+            old_dna_address,
+            new_dna_address,
+            MigrationDirection::Close,
+            agent,
+        )
+    }
+
+    /// Entry that opens the chain of `agent` on the new DNA
+    pub fn open(old_dna_address: Address, new_dna_address: Address, agent: Address) -> Self {
+        Self::new(
+            old_dna_address,
+            new_dna_address,
+            MigrationDirection::Open,
+            agent,
+        )
+    }
+
+    pub fn old_dna_address(&self) -> &Address {
+        &self.old_dna_address
+    }
+
+    pub fn new_dna_address(&self) -> &Address {
+        &self.new_dna_address
+    }
+
+    pub fn direction(&self) -> MigrationDirection {
+        self.direction
+    }
+
+    pub fn agent(&self) -> &Address {
+        &self.agent
+    }
+
+    /// Address of the DNA whose chain this entry belongs to
+    pub fn own_dna_address(&self) -> &Address {
+        match self.direction {
+            MigrationDirection::Close => &self.old_dna_address,
+            MigrationDirection::Open => &self.new_dna_address,
+        }
+    }
+
+    /// Address of the DNA of the chain on the other end of the migration
+    pub fn other_dna_address(&self) -> &Address {
+        match self.direction {
+            MigrationDirection::Close => &self.new_dna_address,
+            MigrationDirection::Open => &self.old_dna_address,
+        }
+    }
+
+    /// The matching entry on the other chain of this migration
+    pub fn counterpart(&self) -> ChainMigrate {
+        let direction = match self.direction {
+            MigrationDirection::Close => MigrationDirection::Open,
+            MigrationDirection::Open => MigrationDirection::Close,
+        };
+        ChainMigrate {
+            direction,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    pub fn test_chain_migrate_close() -> ChainMigrate {
+        ChainMigrate::close(
+            Address::from("old_dna"),
+            Address::from("new_dna"),
+            Address::from("agent"),
+        )
+    }
+
+    #[test]
+    fn chain_migrate_dna_addresses_test() {
+        let close = test_chain_migrate_close();
+        assert_eq!(close.own_dna_address(), &Address::from("old_dna"));
+        assert_eq!(close.other_dna_address(), &Address::from("new_dna"));
+
+        let open = close.counterpart();
+        assert_eq!(open.direction(), MigrationDirection::Open);
+        assert_eq!(open.own_dna_address(), &Address::from("new_dna"));
+        assert_eq!(open.other_dna_address(), &Address::from("old_dna"));
+        assert_eq!(open.agent(), close.agent());
+        assert_eq!(open.counterpart(), close);
+    }
+
+    #[test]
+    fn chain_migrate_json_round_trip_test() {
+        let close = test_chain_migrate_close();
+        let json = JsonString::from(close.clone());
+        assert_eq!(
+            "{\"old_dna_address\":\"old_dna\",\"new_dna_address\":\"new_dna\",\"direction\":\"close\",\"agent\":\"agent\"}",
+            json.to_string(),
+        );
+        assert_eq!(close, ChainMigrate::try_from(json).unwrap());
+    }
+}
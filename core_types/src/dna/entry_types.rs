@@ -137,6 +137,16 @@ pub struct EntryTypeDef {
     /// An array of link definitions for links pointing to entries of this type
     #[serde(default)]
     pub linked_from: Vec<LinkedFrom>,
+
+    /// Entries of countersigned types are authored by two agents at once.
+    /// Both commit the same entry and the header provenances have to hold
+    /// the signatures of both of them.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub countersigned: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl EntryTypeDef {
@@ -195,4 +205,18 @@ mod tests {
 
         assert_eq!(fixture, entry);
     }
+
+    #[test]
+    fn countersigned_is_only_serialized_if_set() {
+        let mut entry = EntryTypeDef::new();
+        assert_eq!(
+            "{\"description\":\"\",\"sharing\":\"public\",\"links_to\":[],\"linked_from\":[]}",
+            serde_json::to_string(&entry).unwrap(),
+        );
+
+        entry.countersigned = true;
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains("\"countersigned\":true"));
+        assert_eq!(entry, serde_json::from_str(&json).unwrap());
+    }
 }
@@ -0,0 +1,86 @@
+//! The value that entries of `encrypted` entry types are committed and published with.
+//!
+//! The plain app entry value gets encrypted with a random content key. That key gets encrypted
+//! for each recipient with a one-time encrypting key pair of the author, so only agents holding
+//! the private key to one of the recipient keys can read the entry.
+use crate::agent::Base32;
+use holochain_json_api::{error::JsonError, json::JsonString};
+
+/// The content key of an encrypted entry, encrypted for one recipient
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct SealedKey {
+    /// Public encrypting key of the recipient
+    pub recipient: Base32,
+    /// Base64 encoded nonce and cipher text of the content key
+    pub key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, DefaultJson)]
+pub struct EncryptedEntry {
+    /// Public key of the one-time key pair the content keys got encrypted with
+    pub sender: Base32,
+    pub keys: Vec<SealedKey>,
+    /// Base64 encoded nonce and cipher text of the app entry value
+    pub cipher: String,
+}
+
+impl EncryptedEntry {
+    /// Public encrypting keys of all agents that can decrypt the entry
+    pub fn recipients(&self) -> Vec<Base32> {
+        self.keys.iter().map(|key| key.recipient.clone()).collect()
+    }
+
+    /// The content key encrypted for the given recipient, if it is one
+    pub fn key_for(&self, recipient: &Base32) -> Option<&SealedKey> {
+        self.keys.iter().find(|key| key.recipient == *recipient)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    pub fn test_encrypted_entry() -> EncryptedEntry {
+        EncryptedEntry {
+            sender: "HcKsender".to_string(),
+            keys: vec![
+                SealedKey {
+                    recipient: "HcKalice".to_string(),
+                    key: "a2V5X2Zvcl9hbGljZQ==".to_string(),
+                },
+                SealedKey {
+                    recipient: "HcKbob".to_string(),
+                    key: "a2V5X2Zvcl9ib2I=".to_string(),
+                },
+            ],
+            cipher: "Y2lwaGVy".to_string(),
+        }
+    }
+
+    #[test]
+    fn encrypted_entry_recipients_test() {
+        let entry = test_encrypted_entry();
+        assert_eq!(
+            entry.recipients(),
+            vec!["HcKalice".to_string(), "HcKbob".to_string()]
+        );
+        assert_eq!(
+            entry
+                .key_for(&"HcKbob".to_string())
+                .map(|key| key.key.clone()),
+            Some("a2V5X2Zvcl9ib2I=".to_string())
+        );
+        assert_eq!(entry.key_for(&"HcKeve".to_string()), None);
+    }
+
+    #[test]
+    fn encrypted_entry_from_app_value_test() {
+        let entry = test_encrypted_entry();
+        assert_eq!(
+            EncryptedEntry::try_from(JsonString::from(entry.clone())).unwrap(),
+            entry
+        );
+        assert!(EncryptedEntry::try_from(JsonString::from_json("{\"title\":\"foo\"}")).is_err());
+    }
+}
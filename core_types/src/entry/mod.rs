@@ -5,6 +5,7 @@
 
 pub mod cap_entries;
 pub mod deletion_entry;
+pub mod encrypted_entry;
 pub mod entry_type;
 
 use self::{
@@ -6,8 +6,11 @@ use crate::{
     utils, CODEC_HCK0, CODEC_HCS0, SEED_SIZE, SIGNATURE_SIZE,
 };
 use hcid::*;
-use holochain_core_types::{agent::Base32, error::HcResult};
-use lib3h_sodium::{kx, secbuf::SecBuf, sign};
+use holochain_core_types::{
+    agent::Base32,
+    error::{HcResult, HolochainError},
+};
+use lib3h_sodium::{aead, kx, secbuf::SecBuf, sign};
 use serde_json::json;
 use std::str;
 
@@ -173,7 +176,63 @@ impl EncryptingKeyPair {
         Self { public, private }
     }
 
-    // TODO: Encrypt and decrypt functions
+    /// Encrypt data for the owner of another encrypting key pair
+    /// The secret key is derived by key exchange between this key pair and the recipient's public key.
+    /// @param {Base32} recipient - public encrypting key of the recipient
+    /// @param {SecBuf} data - the data to encrypt
+    /// @return {Vec<u8>} the random nonce followed by the cipher text
+    pub fn encrypt_for(&mut self, recipient: &Base32, data: &mut SecBuf) -> HcResult<Vec<u8>> {
+        let mut recipient_pub_key = utils::decode_pub_key(recipient.clone(), Self::codec())?;
+        let mut pub_key = self.decode_pub_key_into_secbuf();
+        let mut rx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        let mut tx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        kx::client_session(
+            &mut pub_key,
+            &mut self.private,
+            &mut recipient_pub_key,
+            &mut rx,
+            &mut tx,
+        )?;
+        let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+        nonce.randomize();
+        let mut cipher = SecBuf::with_insecure(data.len() + aead::ABYTES);
+        aead::enc(data, &mut tx, None, &mut nonce, &mut cipher)?;
+
+        let mut output = nonce.read_lock().to_vec();
+        output.extend_from_slice(&cipher.read_lock());
+        Ok(output)
+    }
+
+    /// Decrypt data that was encrypted for this key pair with `encrypt_for`
+    /// @param {Base32} sender - public encrypting key of the key pair that encrypted the data
+    /// @param {[u8]} data - nonce and cipher text as returned by `encrypt_for`
+    /// @return {SecBuf} the decrypted data
+    pub fn decrypt_from(&mut self, sender: &Base32, data: &[u8]) -> HcResult<SecBuf> {
+        if data.len() < aead::NONCEBYTES + aead::ABYTES {
+            return Err(HolochainError::ErrorGeneric(
+                "Cipher text is too short".to_string(),
+            ));
+        }
+        let mut sender_pub_key = utils::decode_pub_key(sender.clone(), Self::codec())?;
+        let mut pub_key = self.decode_pub_key_into_secbuf();
+        let mut rx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        let mut tx = SecBuf::with_secure(kx::SESSIONKEYBYTES);
+        kx::server_session(
+            &mut pub_key,
+            &mut self.private,
+            &mut sender_pub_key,
+            &mut rx,
+            &mut tx,
+        )?;
+        let mut nonce = SecBuf::with_insecure(aead::NONCEBYTES);
+        nonce.from_array(&data[..aead::NONCEBYTES])?;
+        let mut cipher = SecBuf::with_insecure(data.len() - aead::NONCEBYTES);
+        cipher.from_array(&data[aead::NONCEBYTES..])?;
+        let mut message = SecBuf::with_secure(cipher.len() - aead::ABYTES);
+        aead::dec(&mut message, &mut rx, None, &mut nonce, &mut cipher)
+            .map_err(|_| HolochainError::ErrorGeneric("Could not decrypt data".to_string()))?;
+        Ok(message)
+    }
 }
 
 pub fn generate_random_sign_keypair() -> HcResult<SigningKeyPair> {
@@ -231,6 +290,26 @@ mod tests {
         assert!(keys.new_from_self().unwrap().is_same(&mut keys));
     }
 
+    #[test]
+    fn keypair_should_encrypt_for_recipient_and_decrypt() {
+        let mut sender = test_generate_random_enc_keypair();
+        let mut recipient = test_generate_random_enc_keypair();
+        let mut other = test_generate_random_enc_keypair();
+
+        let mut message = SecBuf::with_insecure(16);
+        message.randomize();
+        let cipher = sender
+            .encrypt_for(&recipient.public(), &mut message)
+            .unwrap();
+
+        let mut decrypted = recipient.decrypt_from(&sender.public(), &cipher).unwrap();
+        assert_eq!(0, decrypted.compare(&mut message));
+
+        // Nobody else can decrypt it
+        assert!(other.decrypt_from(&sender.public(), &cipher).is_err());
+        assert!(recipient.decrypt_from(&other.public(), &cipher).is_err());
+    }
+
     #[test]
     fn keypair_should_sign_message_and_verify() {
         let mut sign_keys = test_generate_random_sign_keypair();
@@ -0,0 +1,54 @@
+use crate::zome_code_def::ZomeCodeDef;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+impl ZomeCodeDef {
+    pub fn accept_countersignature_callback(&self) -> TokenStream {
+        let (accept_blocks, accept_from, accept_entry) =
+            match &self.accept_countersignature_callback {
+                None => (Vec::new(), Vec::new(), Vec::new()),
+                Some(callback) => (
+                    vec![callback.code.clone()],
+                    vec![callback.from_param.clone()],
+                    vec![callback.entry_param.clone()],
+                ),
+            };
+
+        quote! {
+            #(
+                #[no_mangle]
+                pub extern "C" fn accept_countersignature(encoded_allocation_of_input: hdk::holochain_core_types::error::RibosomeEncodingBits) -> hdk::holochain_core_types::error::RibosomeEncodingBits {
+                    let maybe_allocation = hdk::holochain_wasm_utils::memory::allocation::WasmAllocation::try_from_ribosome_encoding(encoded_allocation_of_input);
+                    let allocation = match maybe_allocation {
+                        Ok(allocation) => allocation,
+                        Err(allocation_error) => return hdk::holochain_core_types::error::RibosomeEncodedValue::from(allocation_error).into(),
+                    };
+                    let init = hdk::global_fns::init_global_memory(allocation);
+                    if init.is_err() {
+                        return hdk::holochain_wasm_utils::memory::ribosome::return_code_for_allocation_result(
+                            init
+                        ).into();
+                    }
+
+                    // Deserialize input
+                    let input = load_json!(encoded_allocation_of_input);
+
+                    fn execute(input: hdk::holochain_wasm_utils::api_serialization::commit_entry::AcceptCountersignatureParams) -> Result<(), String> {
+                        let #accept_from = input.from;
+                        let #accept_entry = input.entry;
+                        #accept_blocks
+                    }
+
+                    match execute(input) {
+                        Ok(_) => hdk::holochain_core_types::error::RibosomeEncodedValue::Success.into(),
+                        Err(e) => hdk::holochain_wasm_utils::memory::ribosome::return_code_for_allocation_result(
+                            hdk::global_fns::write_json(
+                                hdk::holochain_wasm_utils::holochain_json_api::json::RawString::from(e)
+                            )
+                        ).into(),
+                    }
+                }
+            )*
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod accept_countersignature_callback;
 pub mod genesis;
 pub mod list_functions;
 pub mod list_traits;
@@ -1,8 +1,8 @@
 extern crate proc_macro2;
 
 use crate::zome_code_def::{
-    EntryDefCallbacks, FnDeclaration, FnParameter, GenesisCallback, ReceiveCallback, ZomeCodeDef,
-    ZomeFunction, ZomeFunctions,
+    AcceptCountersignatureCallback, EntryDefCallbacks, FnDeclaration, FnParameter, GenesisCallback,
+    ReceiveCallback, ZomeCodeDef, ZomeFunction, ZomeFunctions,
 };
 
 use hdk::holochain_core_types::dna::{fn_declarations::TraitFns, zome::ZomeTraits};
@@ -12,6 +12,7 @@ static GENESIS_ATTRIBUTE: &str = "genesis";
 static ZOME_FN_ATTRIBUTE: &str = "zome_fn";
 static ENTRY_DEF_ATTRIBUTE: &str = "entry_def";
 static RECEIVE_CALLBACK_ATTRIBUTE: &str = "receive";
+static ACCEPT_COUNTERSIGNATURE_CALLBACK_ATTRIBUTE: &str = "accept_countersignature";
 
 pub trait IntoZome {
     fn extract_zome_fns(&self) -> ZomeFunctions;
@@ -19,6 +20,7 @@ pub trait IntoZome {
     fn extract_genesis(&self) -> GenesisCallback;
     fn extract_traits(&self) -> ZomeTraits;
     fn extract_receive_callback(&self) -> Option<ReceiveCallback>;
+    fn extract_accept_countersignature_callback(&self) -> Option<AcceptCountersignatureCallback>;
     fn extract_extra(&self) -> Vec<syn::Item>;
 
     fn extract_zome(&self) -> ZomeCodeDef {
@@ -27,6 +29,7 @@ pub trait IntoZome {
             entry_def_fns: self.extract_entry_defs(),
             genesis: self.extract_genesis(),
             receive_callback: self.extract_receive_callback(),
+            accept_countersignature_callback: self.extract_accept_countersignature_callback(),
             zome_fns: self.extract_zome_fns(),
             extra: self.extract_extra(),
         }
@@ -196,6 +199,7 @@ impl IntoZome for syn::ItemMod {
                                 && !is_tagged_with(GENESIS_ATTRIBUTE)(func)
                                 && !is_tagged_with(ENTRY_DEF_ATTRIBUTE)(func)
                                 && !is_tagged_with(RECEIVE_CALLBACK_ATTRIBUTE)(func)
+                                && !is_tagged_with(ACCEPT_COUNTERSIGNATURE_CALLBACK_ATTRIBUTE)(func)
                         } else {
                             true // and anything that is not a function
                         }
@@ -259,6 +263,44 @@ impl IntoZome for syn::ItemMod {
             }
         }
     }
+
+    fn extract_accept_countersignature_callback(&self) -> Option<AcceptCountersignatureCallback> {
+        // find all the functions tagged as the accept_countersignature callback
+        let callbacks: Vec<AcceptCountersignatureCallback> = funcs_iter(self)
+            .filter(is_tagged_with(ACCEPT_COUNTERSIGNATURE_CALLBACK_ATTRIBUTE))
+            .map(|func| {
+                let params = func.decl.inputs.iter().collect::<Vec<_>>();
+                match params.as_slice() {
+                    [
+                        syn::FnArg::Captured(syn::ArgCaptured{pat: syn::Pat::Ident(from_ident), ..}),
+                        syn::FnArg::Captured(syn::ArgCaptured{pat: syn::Pat::Ident(entry_ident), ..})
+                    ] => AcceptCountersignatureCallback {
+                        from_param: from_ident.ident.clone(),
+                        entry_param: entry_ident.ident.clone(),
+                        code: *func.block.clone(),
+                    },
+                    _ => {
+                        emit_error(
+                            &func.ident,
+                            "accept_countersignature callback must take two named arguments of type 'Address' and 'Entry' respectively",
+                        );
+                        panic!()
+                    }
+                }
+            })
+            .collect();
+        match callbacks.len() {
+            0 => None,
+            1 => Some(callbacks[0].clone()),
+            _ => {
+                emit_error(
+                    &self.ident,
+                    "Multiple functions tagged with accept_countersignature. Only one permitted per zome.",
+                );
+                panic!()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -505,4 +547,25 @@ mod tests {
         let zome_def = module.extract_zome();
         assert!(zome_def.receive_callback.is_some())
     }
+
+    #[test]
+    fn test_accept_countersignature_callback() {
+        let module: syn::ItemMod = parse_quote! {
+            mod zome {
+                #[genesis]
+                fn genesis() {
+                    Ok(())
+                }
+
+                #[accept_countersignature]
+                fn accept_countersignature(from: Address, entry: Entry) {
+                    Ok(())
+                }
+            }
+        };
+        let zome_def = module.extract_zome();
+        assert!(zome_def.receive_callback.is_none());
+        assert!(zome_def.accept_countersignature_callback.is_some());
+        assert_eq!(zome_def.extra.len(), 0);
+    }
 }
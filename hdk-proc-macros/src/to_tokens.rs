@@ -114,6 +114,7 @@ impl ToTokens for ZomeCodeDef {
         let entry_def_fns = self.entry_def_fns.clone();
         let extra = &self.extra;
         let receive_callback = self.receive_callback();
+        let accept_countersignature_callback = self.accept_countersignature_callback();
         let panic_handler = panic_handler();
 
         tokens.extend(quote! {
@@ -132,6 +133,8 @@ impl ToTokens for ZomeCodeDef {
 
             #receive_callback
 
+            #accept_countersignature_callback
+
             #panic_handler
 
             #(#zome_fns )*
@@ -15,6 +15,13 @@ pub struct ReceiveCallback {
     pub code: syn::Block,
 }
 
+#[derive(Clone, PartialEq, Debug)]
+pub struct AcceptCountersignatureCallback {
+    pub from_param: Ident,
+    pub entry_param: Ident,
+    pub code: syn::Block,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct FnParameter {
     pub ident: Ident,
@@ -78,6 +85,7 @@ pub struct ZomeCodeDef {
     pub entry_def_fns: Vec<syn::ItemFn>,
     pub traits: ZomeTraits,
     pub receive_callback: Option<ReceiveCallback>,
+    pub accept_countersignature_callback: Option<AcceptCountersignatureCallback>,
     pub extra: Vec<syn::Item>, // extra stuff to be added as is to the zome code
 }
 
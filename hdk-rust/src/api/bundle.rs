@@ -1,12 +1,30 @@
+use super::Dispatch;
 use api::BundleOnClose;
-use error::{ZomeApiError, ZomeApiResult};
+use error::ZomeApiResult;
+use holochain_json_api::json::JsonString;
+use holochain_wasm_utils::api_serialization::bundle::{CloseBundleArgs, StartBundleArgs};
 
-/// NOT YET AVAILABLE
-pub fn start_bundle(_timeout: usize, _user_param: serde_json::Value) -> ZomeApiResult<()> {
-    Err(ZomeApiError::FunctionNotImplemented)
+/// Opens a bundle of commits for the current zome function call.
+/// All entries the call commits (including links, updates and removals) after this call
+/// only get staged and don't show up on the source chain until the bundle is closed
+/// with [close_bundle](fn.close_bundle.html). Commits of other zome calls are not affected.
+/// If the bundle isn't closed within `timeout` milliseconds, all staged commits get dropped
+/// and further commits of the call fail. A bundle that is still open when the zome
+/// function returns gets discarded. `user_param` is kept with the bundle for debugging.
+/// Fails if the call already opened a bundle.
+pub fn start_bundle(timeout: usize, user_param: serde_json::Value) -> ZomeApiResult<()> {
+    Dispatch::StartBundle.with_input(StartBundleArgs {
+        timeout: timeout as u64,
+        user_param: JsonString::from_json(&user_param.to_string()),
+    })
 }
 
-/// NOT YET AVAILABLE
-pub fn close_bundle(_action: BundleOnClose) -> ZomeApiResult<()> {
-    Err(ZomeApiError::FunctionNotImplemented)
+/// Closes the bundle of the current zome function call.
+/// With `BundleOnClose::Commit` every staged entry gets validated with the header it
+/// gets committed with, and only if all of them are valid, they get appended to the
+/// source chain and published.
+/// If any of them is invalid, none of them get committed and the validation error is returned.
+/// With `BundleOnClose::Discard` all staged entries get dropped.
+pub fn close_bundle(action: BundleOnClose) -> ZomeApiResult<()> {
+    Dispatch::CloseBundle.with_input(CloseBundleArgs { action })
 }
@@ -0,0 +1,27 @@
+use super::Dispatch;
+use error::ZomeApiResult;
+use holochain_core_types::chain_migrate::MigrationDirection;
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::chain_migrate::CommitChainMigrateArgs;
+
+/// Closes the local source chain as part of a migration to the DNA at `new_dna_address`.
+/// Commits a `ChainMigrate` entry that references both DNAs and the current agent.
+/// After this no further entries can be committed to the chain.
+/// Returns the address of the committed `ChainMigrate` entry.
+pub fn close_chain(new_dna_address: Address) -> ZomeApiResult<Address> {
+    Dispatch::CommitChainMigrate.with_input(CommitChainMigrateArgs {
+        direction: MigrationDirection::Close,
+        dna_address: new_dna_address,
+    })
+}
+
+/// Opens the local source chain as the continuation of the agent's chain on the DNA at
+/// `old_dna_address`. This is the counterpart of [close_chain](fn.close_chain.html) and
+/// should be called on the new DNA after the old chain got closed.
+/// Returns the address of the committed `ChainMigrate` entry.
+pub fn open_chain(old_dna_address: Address) -> ZomeApiResult<Address> {
+    Dispatch::CommitChainMigrate.with_input(CommitChainMigrateArgs {
+        direction: MigrationDirection::Open,
+        dna_address: old_dna_address,
+    })
+}
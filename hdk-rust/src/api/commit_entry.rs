@@ -1,9 +1,9 @@
 use super::Dispatch;
 use error::ZomeApiResult;
-use holochain_core_types::entry::Entry;
+use holochain_core_types::{entry::Entry, time::Timeout};
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::commit_entry::{
-    CommitEntryArgs, CommitEntryOptions, CommitEntryResult,
+    CommitCountersignedEntryArgs, CommitEntryArgs, CommitEntryOptions, CommitEntryResult,
 };
 
 /// Attempts to commit an entry to the local source chain. The entry
@@ -76,3 +76,29 @@ pub fn commit_entry_result(
         options,
     })
 }
+
+/// Commits an entry of a countersigned entry type together with the agent `counterparty`.
+/// The counterparty gets asked through a direct message to countersign the entry.
+/// Its zome's `accept_countersignature` callback decides if it agrees to the entry,
+/// and requests are rejected if the zome does not define one. If it agrees and the entry
+/// is valid, it answers with its signature and the entry gets committed to your chain.
+/// Only then the counterparty commits it as well, so both chains hold the same entry
+/// whose header provenances hold the signatures of both agents.
+///
+/// Waits up to `timeout` for each answer of the counterparty. An error is returned if the
+/// entry type is not countersigned, the counterparty did not countersign in time or the
+/// entry is invalid. If the counterparty did not commit the entry after it got committed
+/// to your chain, the error says so.
+pub fn commit_countersigned_entry(
+    entry: &Entry,
+    counterparty: Address,
+    timeout: Timeout,
+) -> ZomeApiResult<Address> {
+    Dispatch::CommitCountersignedEntry
+        .with_input(CommitCountersignedEntryArgs {
+            entry: entry.clone(),
+            counterparty,
+            timeout,
+        })
+        .map(|result: CommitEntryResult| result.address())
+}
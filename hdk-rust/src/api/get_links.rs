@@ -5,7 +5,10 @@ use holochain_core_types::{entry::Entry, link::LinkMatch};
 use holochain_persistence_api::{cas::content::Address, hash::HashString};
 use holochain_wasm_utils::api_serialization::{
     get_entry::{GetEntryOptions, GetEntryResult, GetEntryResultItem, GetEntryResultType},
-    get_links::{GetLinksArgs, GetLinksOptions, GetLinksResult},
+    get_links::{
+        GetLinksArgs, GetLinksCountArgs, GetLinksCountResult, GetLinksOptions, GetLinksResult,
+        LinksStatusRequestKind,
+    },
 };
 
 /// Consumes four values; the address of an entry get get links from (the base), the type of the links
@@ -17,6 +20,9 @@ use holochain_wasm_utils::api_serialization::{
 /// Links are created using the Zome API function [link_entries](fn.link_entries.html).
 /// If you also need the content of the entry consider using one of the helper functions:
 /// [get_links_result](fn.get_links_result) or [get_links_and_load](fn._get_links_and_load)
+/// The options can also sort the links by header timestamp or tag and limit their number.
+/// If the limit cut links off, the result has a cursor that, set as the cursor of the options,
+/// returns the next page.
 /// # Examples
 /// ```rust
 /// # extern crate hdk;
@@ -62,6 +68,45 @@ pub fn get_links(
     get_links_with_options(base, link_type, tag, GetLinksOptions::default())
 }
 
+/// Returns the number of links from the base that match the link type and tag and have the
+/// requested crud status. The DHT node holding the links counts them, so unlike counting the
+/// addresses returned by [get_links](fn.get_links.html) no links get sent over the network.
+/// # Examples
+/// ```rust
+/// # extern crate hdk;
+/// # extern crate holochain_core_types;
+/// # extern crate holochain_wasm_utils;
+/// # extern crate holochain_persistence_api;
+/// # use hdk::error::ZomeApiResult;
+/// # use holochain_persistence_api::cas::content::Address;
+/// # use holochain_wasm_utils::api_serialization::get_links::LinksStatusRequestKind;
+/// # use holochain_core_types::link::LinkMatch;
+///
+/// # fn main() {
+/// pub fn handle_comment_count(post: Address) -> ZomeApiResult<usize> {
+///     hdk::get_links_count(&post, LinkMatch::Exactly("comments"), LinkMatch::Any, LinksStatusRequestKind::Live)
+/// }
+/// # }
+/// ```
+pub fn get_links_count(
+    base: &Address,
+    link_type: LinkMatch<&str>,
+    tag: LinkMatch<&str>,
+    status_request: LinksStatusRequestKind,
+) -> ZomeApiResult<usize> {
+    let type_re = link_type.to_regex_string()?;
+    let tag_re = tag.to_regex_string()?;
+
+    let result: GetLinksCountResult = Dispatch::GetLinksCount.with_input(GetLinksCountArgs {
+        entry_address: base.clone(),
+        link_type: type_re,
+        tag: tag_re,
+        status_request,
+        timeout: Default::default(),
+    })?;
+    Ok(result.count)
+}
+
 /// Retrieves data about entries linked to a base address with a given type and tag. This is the most general version of the various get_links
 /// helpers (such as get_links_and_load) and can return the linked addresses, entries, headers and sources. Also supports CRUD status_request.
 /// The data returned is configurable with the GetLinksOptions to specify links options and GetEntryOptions argument wto specify options when loading the entries.
@@ -21,6 +21,7 @@ use std::convert::{TryFrom, TryInto};
 mod bundle;
 mod call;
 mod capability;
+mod chain_migrate;
 mod commit_entry;
 mod debug;
 mod emit_signal;
@@ -41,19 +42,22 @@ pub use self::{
     bundle::{close_bundle, start_bundle},
     call::call,
     capability::{commit_capability_claim, commit_capability_grant},
-    commit_entry::{commit_entry, commit_entry_result},
+    chain_migrate::{close_chain, open_chain},
+    commit_entry::{commit_countersigned_entry, commit_entry, commit_entry_result},
     debug::debug,
     emit_signal::emit_signal,
     entry_address::entry_address,
     get_entry::{get_entry, get_entry_history, get_entry_initial, get_entry_result},
-    get_links::{get_links, get_links_and_load, get_links_result, get_links_with_options},
+    get_links::{
+        get_links, get_links_and_load, get_links_count, get_links_result, get_links_with_options,
+    },
     keystore::{
         keystore_derive_key, keystore_derive_seed, keystore_get_public_key, keystore_list,
         keystore_new_random, keystore_sign,
     },
     link_entries::link_entries,
     property::property,
-    query::{query, query_result},
+    query::{query, query_page, query_result},
     remove_link::remove_link,
     send::send,
     sign::{sign, sign_one_time, verify_signature},
@@ -124,8 +128,6 @@ macro_rules! def_api_fns {
         #[allow(dead_code)]
         extern "C" {
             pub(crate) fn hc_property(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
-            pub(crate) fn hc_start_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
-            pub(crate) fn hc_close_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits;
             $( pub(crate) fn $function_name (_: RibosomeEncodingBits) -> RibosomeEncodingBits;) *
         }
 
@@ -162,6 +164,7 @@ def_api_fns! {
     hc_entry_address, EntryAddress;
     hc_query, Query;
     hc_update_entry, UpdateEntry;
+    hc_update_agent, UpdateAgent;
     hc_remove_entry, RemoveEntry;
     hc_send, Send;
     hc_debug, Debug;
@@ -182,6 +185,12 @@ def_api_fns! {
     hc_commit_capability_grant, CommitCapabilityGrant;
     hc_commit_capability_claim, CommitCapabilityClaim;
     hc_emit_signal, EmitSignal;
+    hc_commit_chain_migrate, CommitChainMigrate;
+    hc_start_bundle, StartBundle;
+    hc_close_bundle, CloseBundle;
+    hc_commit_countersigned_entry, CommitCountersignedEntry;
+    hc_get_links_count, GetLinksCount;
+    hc_query_page, QueryPage;
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -221,6 +230,11 @@ lazy_static! {
     /// After a call to `update_agent` it will have the value of the hash of the newly committed identity entry.
     pub static ref AGENT_LATEST_HASH: &'static HashString = &GLOBALS.agent_latest_hash;
 
+    /// Your public encrypting key.
+    /// Hand it to other agents so they can add you as a recipient of entries of `encrypted` entry types.
+    /// Empty if the conductor does not provide one.
+    pub static ref AGENT_ENCRYPTION_KEY: &'static str = &GLOBALS.agent_encryption_key;
+
     /// The Address of the public token (if any)
     pub static ref PUBLIC_TOKEN: &'static Address = &GLOBALS.public_token;
 
@@ -269,6 +283,12 @@ impl From<AGENT_LATEST_HASH> for JsonString {
     }
 }
 
+impl From<AGENT_ENCRYPTION_KEY> for JsonString {
+    fn from(agent_encryption_key: AGENT_ENCRYPTION_KEY) -> JsonString {
+        JsonString::from(RawString::from(agent_encryption_key.to_string()))
+    }
+}
+
 impl From<PUBLIC_TOKEN> for JsonString {
     fn from(public_token: PUBLIC_TOKEN) -> JsonString {
         JsonString::from(Address::from(public_token.to_string()))
@@ -352,7 +372,4 @@ impl Default for GetEntryMask {
 //}
 
 /// Allowed input for close_bundle()
-pub enum BundleOnClose {
-    Commit,
-    Discard,
-}
+pub use holochain_wasm_utils::api_serialization::bundle::BundleOnClose;
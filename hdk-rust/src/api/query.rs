@@ -2,7 +2,7 @@ use super::Dispatch;
 use error::{ZomeApiError, ZomeApiResult};
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::{
-    QueryArgs, QueryArgsNames, QueryArgsOptions, QueryResult,
+    QueryArgs, QueryArgsNames, QueryArgsOptions, QueryPage, QueryResult,
 };
 
 /// Returns a list of entries from your local source chain that match a given entry type name or names.
@@ -67,6 +67,23 @@ use holochain_wasm_utils::api_serialization::{
 /// //     HeadersWithEntries(Vec<(ChainHeader, Entry)>),  // true     true
 /// // }
 /// ```
+///
+/// QueryArgsOptions can also restrict the result to a range of header timestamps with `since` and
+/// `until` (both inclusive), return the oldest entries first with `order: QueryOrder::OldestFirst`
+/// and resume after a previous page with `cursor`, e.g. for "my posts from last week, oldest first":
+///
+/// ```
+/// // pub fn get_posts_of_last_week(since: Iso8601) -> ZomeApiResult<QueryResult> {
+/// //    hdk::query_result("post".into(), QueryArgsOptions{
+/// //        since: Some(since),
+/// //        order: QueryOrder::OldestFirst,
+/// //        entries: true,
+/// //        ..Default::default()
+/// //    })
+/// // }
+/// ```
+///
+/// Use hdk::query_page to also get the cursor for the next page.
 pub fn query(
     entry_type_names: QueryArgsNames,
     start: usize,
@@ -80,6 +97,7 @@ pub fn query(
             limit,
             headers: false,
             entries: false,
+            ..Default::default()
         },
     )
     .and_then(|result| match result {
@@ -97,3 +115,29 @@ pub fn query_result(
         options,
     })
 }
+
+/// Like hdk::query_result, but also returns a cursor if more entries match than `limit` allows.
+/// Pass it as `cursor` in the QueryArgsOptions of the next call to get the following page:
+///
+/// ```
+/// // let first = hdk::query_page("post".into(), QueryArgsOptions{ limit: 10, ..Default::default()})?;
+/// // if let Some(cursor) = first.cursor {
+/// //     let second = hdk::query_page("post".into(), QueryArgsOptions{
+/// //         limit: 10,
+/// //         cursor: Some(cursor),
+/// //         ..Default::default()
+/// //     })?;
+/// // }
+/// ```
+///
+/// The cursor is the address of the last returned header and stays valid when new entries get
+/// committed.
+pub fn query_page(
+    entry_type_names: QueryArgsNames,
+    options: QueryArgsOptions,
+) -> ZomeApiResult<QueryPage> {
+    Dispatch::QueryPage.with_input(QueryArgs {
+        entry_type_names,
+        options,
+    })
+}
@@ -1,5 +1,5 @@
 use super::Dispatch;
-use error::{ZomeApiError, ZomeApiResult};
+use error::ZomeApiResult;
 use holochain_core_types::entry::Entry;
 use holochain_persistence_api::cas::content::Address;
 use holochain_wasm_utils::api_serialization::UpdateEntryArgs;
@@ -17,9 +17,14 @@ pub fn update_entry(new_entry: Entry, address: &Address) -> ZomeApiResult<Addres
     })
 }
 
-/// NOT YET AVAILABLE
+/// Rotates the agent's signing key, for instance after a device got compromised.
+/// The conductor derives a new key from the agent's keystore and a new AgentId entry
+/// with that key gets committed as an update of the current one. It is signed by both
+/// the old and the new key, and from then on the new key is used for signing.
+/// Getting the old agent address from the DHT resolves to the new AgentId entry.
+/// Returns the new agent address.
 pub fn update_agent() -> ZomeApiResult<Address> {
-    Err(ZomeApiError::FunctionNotImplemented)
+    Dispatch::UpdateAgent.without_input()
 }
 
 /// Commit a DeletionEntry to your local source chain that marks an entry as 'deleted' by setting
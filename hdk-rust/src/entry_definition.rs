@@ -3,8 +3,8 @@
 
 use crate::error::{ZomeApiError, ZomeApiResult};
 use holochain_core_types::{
-    dna::entry_types::EntryTypeDef,
-    entry::{entry_type::EntryType, AppEntryValue, Entry},
+    dna::entry_types::{EntryTypeDef, Sharing},
+    entry::{encrypted_entry::EncryptedEntry, entry_type::EntryType, AppEntryValue, Entry},
     validation::{EntryValidationData, LinkValidationData, ValidationPackageDefinition},
 };
 use holochain_wasm_utils::api_serialization::validation::LinkDirection;
@@ -69,7 +69,9 @@ pub struct ValidatingLinkDefinition {
 ///      It is what must be given as the `entry_type_name` argument when calling [commit_entry](fn.commit_entry.html) and the other data read/write functions.
 /// 2. description: `description` is something that is primarily for human readers of your code, just describe this entry type
 /// 3. sharing: `sharing` defines what distribution over the DHT, or not, occurs with entries of this type, possible values
-///      are defined in the [Sharing](../core_types/entry/dna/zome/entry_types/enum.Sharing.html) enum
+///      are defined in the [Sharing](../core_types/entry/dna/zome/entry_types/enum.Sharing.html) enum.
+///      It can optionally be followed by `countersigned: true` for entry types whose entries have to be
+///      authored by two agents at once with [commit_countersigned_entry](fn.commit_countersigned_entry.html).
 /// 4. native_type: `native_type` references a given Rust struct, which provides a clear schema for entries of this type.
 /// 5. validation_package: `validation_package` is a special identifier, which declares which data is required from peers
 ///      when attempting to validate entries of this type.
@@ -174,6 +176,7 @@ macro_rules! entry {
         name: $name:expr,
         description: $description:expr,
         sharing: $sharing:expr,
+        $(countersigned: $countersigned:expr,)*
        // $(native_type: $native_type:ty,)*
 
         validation_package: || $package_creator:expr,
@@ -192,6 +195,7 @@ macro_rules! entry {
             let mut entry_type = hdk::holochain_core_types::dna::entry_types::EntryTypeDef::new();
             entry_type.description = String::from($description);
             entry_type.sharing = $sharing;
+            $(entry_type.countersigned = $countersigned;)*
 
             $($(
                 match $link_expr.direction {
@@ -220,6 +224,9 @@ macro_rules! entry {
             });
 
             let validator = Box::new(|validation_data: hdk::holochain_wasm_utils::holochain_core_types::validation::EntryValidationData<hdk::holochain_core_types::entry::Entry>| {
+                if hdk::entry_definition::is_unreadable_encrypted_entry(&validation_data, &$sharing) {
+                    return Ok(());
+                }
                 let $validation_data = hdk::entry_definition::entry_to_native_type::<$native_type>(validation_data.clone())?;
                 use std::convert::TryFrom;
                 let e_type = hdk::holochain_core_types::entry::entry_type::EntryType::try_from(validation_data)?;
@@ -356,6 +363,29 @@ macro_rules! from {
     )
 }
 
+/// Returns true if the validated entry is the cipher text of an entry of an `encrypted`
+/// entry type. Validators get the decrypted entry only if they are one of its recipients,
+/// all others can't convert it to the native type and accept it without running the
+/// validation of the entry definition.
+/// Entries of all other entry types are always validated, whatever their value looks like.
+pub fn is_unreadable_encrypted_entry(
+    entry_validation: &EntryValidationData<Entry>,
+    sharing: &Sharing,
+) -> bool {
+    if *sharing != Sharing::Encrypted {
+        return false;
+    }
+    let entry = match entry_validation {
+        EntryValidationData::Create { entry, .. } => entry,
+        EntryValidationData::Modify { new_entry, .. } => new_entry,
+        EntryValidationData::Delete { old_entry, .. } => old_entry,
+    };
+    match entry {
+        Entry::App(_, entry_value) => EncryptedEntry::try_from(entry_value.to_owned()).is_ok(),
+        _ => false,
+    }
+}
+
 //could not turn this to try_from
 pub fn entry_to_native_type<T: TryFrom<AppEntryValue> + Clone>(
     entry_validation: EntryValidationData<Entry>,
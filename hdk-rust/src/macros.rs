@@ -48,7 +48,11 @@ macro_rules! load_string {
 /// 3. receive (optional): `receive` is a callback called by Holochain when another agent on a hApp has initiated a node-to-node direct message.
 ///     That node-to-node message is initiated via the [**send** function of the API](api/fn.send.html), which is where you can read further about use of `send` and `receive`.
 ///     `receive` is optional to include, based on whether you use `send` anywhere in the code.
-/// 4. functions:
+/// 4. accept_countersignature (optional): `accept_countersignature` is a callback called by Holochain when another agent asks
+///     this agent to countersign an entry with [commit_countersigned_entry](api/fn.commit_countersigned_entry.html).
+///     It gets the address of that agent and the proposed entry and returns `Ok` to countersign it or an `Err` with the reason not to.
+///     Without it, all countersignature requests get rejected.
+/// 5. functions:
 ///     `functions` declares all the zome's functions with their input/output signatures
 /// # Examples
 ///
@@ -133,6 +137,20 @@ macro_rules! load_string {
 /// # pub fn hc_commit_capability_claim(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
 /// # #[no_mangle]
 /// # pub fn hc_emit_signal(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_commit_chain_migrate(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_update_agent(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_start_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_close_bundle(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_commit_countersigned_entry(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_get_links_count(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
+/// # #[no_mangle]
+/// # pub fn hc_query_page(_: RibosomeEncodingBits) -> RibosomeEncodingBits { RibosomeEncodedValue::Success.into() }
 ///
 /// # fn main() {
 ///
@@ -227,6 +245,12 @@ macro_rules! define_zome {
             }
         )*
 
+        $(
+            accept_countersignature : |$accept_from:ident, $accept_entry:ident| {
+                $accept_expr:expr
+            }
+        )*
+
         functions : [
             $(
                         $zome_function_name:ident : {
@@ -315,6 +339,41 @@ macro_rules! define_zome {
             }
         )*
 
+        $(
+            #[no_mangle]
+            pub extern "C" fn accept_countersignature(encoded_allocation_of_input: hdk::holochain_core_types::error::RibosomeEncodingBits) -> hdk::holochain_core_types::error::RibosomeEncodingBits {
+                let maybe_allocation = $crate::holochain_wasm_utils::memory::allocation::WasmAllocation::try_from_ribosome_encoding(encoded_allocation_of_input);
+                let allocation = match maybe_allocation {
+                    Ok(allocation) => allocation,
+                    Err(allocation_error) => return hdk::holochain_core_types::error::RibosomeEncodedValue::from(allocation_error).into(),
+                };
+                let init = $crate::global_fns::init_global_memory(allocation);
+                if init.is_err() {
+                    return $crate::holochain_wasm_utils::memory::ribosome::return_code_for_allocation_result(
+                        init
+                    ).into();
+                }
+
+                // Deserialize input
+                let input = load_json!(encoded_allocation_of_input);
+
+                fn execute(input: $crate::holochain_wasm_utils::api_serialization::commit_entry::AcceptCountersignatureParams) -> Result<(), String> {
+                    let $accept_from = input.from;
+                    let $accept_entry = input.entry;
+                    $accept_expr
+                }
+
+                match execute(input) {
+                    Ok(_) => hdk::holochain_core_types::error::RibosomeEncodedValue::Success.into(),
+                    Err(e) => $crate::holochain_wasm_utils::memory::ribosome::return_code_for_allocation_result(
+                        $crate::global_fns::write_json(
+                            $crate::holochain_wasm_utils::holochain_json_api::json::RawString::from(e)
+                        )
+                    ).into(),
+                }
+            }
+        )*
+
         use std::collections::HashMap;
 
         #[no_mangle]
@@ -8,6 +8,7 @@ use super::protocol::Protocol;
 use failure::Error;
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::Address;
+use serde_bytes;
 use std::convert::TryFrom;
 
 //--------------------------------------------------------------------------------------------------
@@ -80,6 +81,7 @@ pub struct MessageData {
     pub to_agent_id: Address,
     pub from_agent_id: Address,
     #[serde(rename = "data")]
+    #[serde(with = "serde_bytes")]
     pub content: Vec<u8>,
 }
 
@@ -103,6 +105,7 @@ pub struct TrackDnaData {
 pub struct EntryAspectData {
     pub aspect_address: Address,
     pub type_hint: String,
+    #[serde(with = "serde_bytes")]
     pub aspect: Vec<u8>,
     pub publish_ts: u64,
 }
@@ -138,6 +141,7 @@ pub struct QueryEntryData {
     #[serde(rename = "_id")]
     pub request_id: String,
     pub requester_agent_id: Address,
+    #[serde(with = "serde_bytes")]
     pub query: Vec<u8>, // opaque query struct
 }
 
@@ -150,6 +154,7 @@ pub struct QueryEntryResultData {
     pub request_id: String,
     pub requester_agent_id: Address,
     pub responder_agent_id: Address,
+    #[serde(with = "serde_bytes")]
     pub query_result: Vec<u8>, // opaque query-result struct
 }
 
@@ -352,6 +357,7 @@ impl From<JsonProtocol> for Protocol {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::connection::wire_encoding::WireEncoding;
 
     macro_rules! hashmap {
         ($( $key: expr => $val: expr ),*) => {{
@@ -361,12 +367,17 @@ mod tests {
         }}
     }
 
+    /// convert $e to Protocol and back, and round-trip it through every wire encoding
     macro_rules! test_convert {
         ($e:expr) => {
             let orig = $e;
             let p = Protocol::from(orig.clone());
             let w = JsonProtocol::try_from(p).unwrap();
             assert_eq!(orig, w);
+            for encoding in WireEncoding::supported() {
+                let bytes = encoding.encode(&orig).unwrap();
+                assert_eq!(orig, encoding.decode(&bytes).unwrap());
+            }
         };
     }
 
@@ -386,6 +397,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_can_convert_SuccessResult() {
+        test_convert!(JsonProtocol::SuccessResult(GenericResultData {
+            dna_address: "test_dna".into(),
+            request_id: "test_id".to_string(),
+            to_agent_id: Address::from("test_to"),
+            result_info: "done".into(),
+        }));
+    }
+
+    #[test]
+    fn it_can_convert_FailureResult() {
+        test_convert!(JsonProtocol::FailureResult(GenericResultData {
+            dna_address: "test_dna".into(),
+            request_id: "test_id".to_string(),
+            to_agent_id: Address::from("test_to"),
+            result_info: "failed".into(),
+        }));
+    }
+
+    #[test]
+    fn it_can_convert_TrackDna() {
+        test_convert!(JsonProtocol::TrackDna(TrackDnaData {
+            dna_address: "test_dna".into(),
+            agent_id: Address::from("test_agent"),
+        }));
+    }
+
+    #[test]
+    fn it_can_convert_UntrackDna() {
+        test_convert!(JsonProtocol::UntrackDna(TrackDnaData {
+            dna_address: "test_dna".into(),
+            agent_id: Address::from("test_agent"),
+        }));
+    }
+
     #[test]
     fn it_can_convert_GetState() {
         test_convert!(JsonProtocol::GetState);
@@ -511,7 +558,7 @@ mod tests {
             request_id: "test_id".to_string(),
             provider_agent_id: Address::from("test_from"),
             entry_address: "Hk42".into(),
-            aspect_address_list: None,
+            aspect_address_list: Some(vec!["HkAspect".into()]),
         }));
     }
     #[test]
@@ -566,6 +613,28 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn it_can_convert_HandleQueryEntry() {
+        test_convert!(JsonProtocol::HandleQueryEntry(QueryEntryData {
+            dna_address: "test_dna".into(),
+            entry_address: "Hk42".into(),
+            request_id: "test_id".to_string(),
+            requester_agent_id: Address::from("test_from"),
+            query: vec![4, 3, 2, 1],
+        }));
+    }
+    #[test]
+    fn it_can_convert_HandleQueryEntryResult() {
+        test_convert!(JsonProtocol::HandleQueryEntryResult(QueryEntryResultData {
+            dna_address: "test_dna".into(),
+            entry_address: "Hk42".into(),
+            request_id: "test_id".to_string(),
+            requester_agent_id: Address::from("test_from"),
+            responder_agent_id: Address::from("test_to"),
+            query_result: vec![4, 3, 2, 1],
+        }));
+    }
+
     // -- Entry lists -- //
 
     #[test]
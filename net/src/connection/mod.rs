@@ -12,3 +12,4 @@ pub mod net_connection;
 pub mod net_connection_thread;
 pub mod net_relay;
 pub mod protocol;
+pub mod wire_encoding;
@@ -0,0 +1,156 @@
+//! Encodings of JsonProtocol messages for sending them over the wire.
+//! JSON is understood by every endpoint and stays the fallback.
+//! MessagePack is a compact binary alternative for endpoints that support it:
+//! endpoints offer the encodings they accept and senders pick one of them with negotiate().
+
+use super::{json_protocol::JsonProtocol, protocol::Protocol, NetResult};
+use holochain_json_api::json::JsonString;
+use std::{convert::TryFrom, str::FromStr};
+
+/// How JsonProtocol messages get serialized
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum WireEncoding {
+    /// JSON text, understood by every endpoint
+    Json,
+    /// MessagePack with named fields
+    MessagePack,
+}
+
+impl Default for WireEncoding {
+    fn default() -> Self {
+        WireEncoding::Json
+    }
+}
+
+impl FromStr for WireEncoding {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(WireEncoding::Json),
+            "messagePack" => Ok(WireEncoding::MessagePack),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<WireEncoding> for String {
+    fn from(encoding: WireEncoding) -> String {
+        String::from(match encoding {
+            WireEncoding::Json => "json",
+            WireEncoding::MessagePack => "messagePack",
+        })
+    }
+}
+
+impl WireEncoding {
+    /// All encodings this build can decode
+    pub fn supported() -> Vec<WireEncoding> {
+        vec![WireEncoding::MessagePack, WireEncoding::Json]
+    }
+
+    /// The encoding to send with: the preferred one if the other endpoint offered it, JSON otherwise
+    pub fn negotiate(preferred: WireEncoding, offered: &[WireEncoding]) -> WireEncoding {
+        if offered.contains(&preferred) {
+            preferred
+        } else {
+            WireEncoding::Json
+        }
+    }
+
+    /// One byte identifying the encoding of a message on the wire
+    pub fn tag(self) -> u8 {
+        match self {
+            WireEncoding::Json => 0,
+            WireEncoding::MessagePack => 1,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> NetResult<Self> {
+        match tag {
+            0 => Ok(WireEncoding::Json),
+            1 => Ok(WireEncoding::MessagePack),
+            _ => bail!("unknown wire encoding tag: {}", tag),
+        }
+    }
+
+    pub fn encode(self, msg: &JsonProtocol) -> NetResult<Vec<u8>> {
+        Ok(match self {
+            WireEncoding::Json => String::from(JsonString::from(msg)).into_bytes(),
+            WireEncoding::MessagePack => rmp_serde::to_vec_named(msg)?,
+        })
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> NetResult<JsonProtocol> {
+        Ok(match self {
+            WireEncoding::Json => {
+                let json = JsonString::from_json(std::str::from_utf8(bytes)?);
+                JsonProtocol::try_from(Protocol::Json(json))?
+            }
+            WireEncoding::MessagePack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::{EntryAspectData, EntryData, ProvidedEntryData};
+
+    fn example_publish() -> JsonProtocol {
+        JsonProtocol::PublishEntry(ProvidedEntryData {
+            dna_address: "test_dna".into(),
+            provider_agent_id: "test_agent".into(),
+            entry: EntryData {
+                entry_address: "HkEntry".into(),
+                aspect_list: vec![EntryAspectData {
+                    aspect_address: "HkAspect".into(),
+                    type_hint: "content".into(),
+                    aspect: br#"{"App":["post","{\"content\":\"hello\"}"]}"#.to_vec(),
+                    publish_ts: 42,
+                }],
+            },
+        })
+    }
+
+    #[test]
+    fn message_pack_is_smaller_than_json() {
+        let msg = example_publish();
+        let json = WireEncoding::Json.encode(&msg).unwrap();
+        let message_pack = WireEncoding::MessagePack.encode(&msg).unwrap();
+        assert!(message_pack.len() < json.len());
+        assert_eq!(
+            WireEncoding::MessagePack.decode(&message_pack).unwrap(),
+            msg
+        );
+        assert_eq!(WireEncoding::Json.decode(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn negotiation_falls_back_to_json() {
+        assert_eq!(
+            WireEncoding::negotiate(WireEncoding::MessagePack, &WireEncoding::supported()),
+            WireEncoding::MessagePack
+        );
+        assert_eq!(
+            WireEncoding::negotiate(WireEncoding::MessagePack, &[WireEncoding::Json]),
+            WireEncoding::Json
+        );
+        assert_eq!(
+            WireEncoding::negotiate(WireEncoding::Json, &WireEncoding::supported()),
+            WireEncoding::Json
+        );
+    }
+
+    #[test]
+    fn tags_and_names_round_trip() {
+        for encoding in WireEncoding::supported() {
+            assert_eq!(WireEncoding::from_tag(encoding.tag()).unwrap(), encoding);
+            assert_eq!(
+                WireEncoding::from_str(&String::from(encoding)),
+                Ok(encoding)
+            );
+        }
+        assert!(WireEncoding::from_tag(42).is_err());
+    }
+}
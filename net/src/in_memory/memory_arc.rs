@@ -0,0 +1,102 @@
+//! Address space model of the sharded in-memory DHT.
+//! Agents and entries get a location on a ring of u32 values.
+//! An address is held by the `redundancy` agents whose locations are closest to it,
+//! so every agent covers the arc of the ring around its own location that is closer to it
+//! than to all but `redundancy - 1` other agents. Arcs grow and shrink as agents come and go.
+
+use holochain_persistence_api::cas::content::Address;
+use sha2::Digest;
+
+/// Position on the address space ring
+pub(crate) type Location = u32;
+
+/// Location of an entry or agent address on the ring
+pub(crate) fn location(address: &Address) -> Location {
+    let mut hash = sha2::Sha256::new();
+    hash.input(address.to_string().as_bytes());
+    let hash = hash.result();
+    hash.iter()
+        .take(4)
+        .fold(0, |location, byte| (location << 8) | Location::from(*byte))
+}
+
+/// Shortest distance between two locations, going either way around the ring
+pub(crate) fn ring_distance(a: Location, b: Location) -> Location {
+    let forward = a.wrapping_sub(b);
+    let backward = b.wrapping_sub(a);
+    forward.min(backward)
+}
+
+/// The agents responsible for holding the given address, nearest first.
+/// Returns all agents if there are not more than `redundancy` of them.
+pub(crate) fn nearest_agents<'a, I>(address: &Address, agents: I, redundancy: usize) -> Vec<Address>
+where
+    I: IntoIterator<Item = &'a Address>,
+{
+    let target = location(address);
+    let mut agents = agents
+        .into_iter()
+        .map(|agent| (ring_distance(target, location(agent)), agent.clone()))
+        .collect::<Vec<_>>();
+    // Ties are broken by agent id so every call picks the same agents
+    agents.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| a.1.to_string().cmp(&b.1.to_string()))
+    });
+    agents
+        .into_iter()
+        .take(redundancy)
+        .map(|(_, agent)| agent)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agents(count: usize) -> Vec<Address> {
+        (0..count)
+            .map(|i| Address::from(format!("agent-{}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn ring_distance_wraps_around() {
+        assert_eq!(ring_distance(10, 10), 0);
+        assert_eq!(ring_distance(10, 25), 15);
+        assert_eq!(ring_distance(25, 10), 15);
+        assert_eq!(ring_distance(Location::max_value(), 1), 2);
+        assert_eq!(ring_distance(0, (1 << 31) + 1), (1 << 31) - 1);
+    }
+
+    #[test]
+    fn nearest_agents_picks_redundancy_closest() {
+        let address = Address::from("QmEntry");
+        let all = agents(10);
+        let holders = nearest_agents(&address, all.iter(), 3);
+        assert_eq!(holders.len(), 3);
+
+        let target = location(&address);
+        let furthest_holder = holders
+            .iter()
+            .map(|agent| ring_distance(target, location(agent)))
+            .max()
+            .unwrap();
+        for agent in all.iter().filter(|agent| !holders.contains(agent)) {
+            assert!(ring_distance(target, location(agent)) >= furthest_holder);
+        }
+
+        assert_eq!(nearest_agents(&address, all.iter().take(2), 3).len(), 2);
+        assert_eq!(nearest_agents(&address, all.iter().rev(), 3), holders);
+    }
+
+    #[test]
+    fn new_agent_displaces_at_most_one_holder() {
+        let address = Address::from("QmEntry");
+        let all = agents(10);
+        let before = nearest_agents(&address, all.iter().take(9), 3);
+        let after = nearest_agents(&address, all.iter(), 3);
+        let kept = before.iter().filter(|agent| after.contains(agent)).count();
+        assert!(kept >= 2);
+    }
+}
@@ -0,0 +1,270 @@
+//! Network faults the in-memory server can inject into message delivery:
+//! latency, lost, duplicated and reordered messages, and partitions that cut groups of agents
+//! off from the rest while they are active.
+//! All random decisions come from a seeded generator, so a test run can be repeated.
+
+use super::{memory_book::ChainId, memory_server::MEMORY_SERVER_MAP};
+use crate::connection::{protocol::Protocol, NetResult};
+use holochain_persistence_api::cas::content::Address;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// Longest time a reordered message waits for the next message to the same agent
+const MAX_HOLD_BACK: Duration = Duration::from_millis(100);
+
+/// What can go wrong with messages sent through an in-memory network.
+/// The default profile is a perfect network.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FaultProfile {
+    /// Delay of every message
+    pub latency_ms: u64,
+    /// Upper bound of a random delay added to every message
+    pub jitter_ms: u64,
+    /// Probability of a message getting lost
+    pub drop_rate: f64,
+    /// Probability of a message getting delivered twice
+    pub duplicate_rate: f64,
+    /// Probability of a message getting delivered after the next message to the same agent
+    pub reorder_rate: f64,
+    /// Agents per partition name. While a partition is active, its agents can only
+    /// reach each other. Partitions start inactive, see set_partition_active().
+    pub partitions: HashMap<String, Vec<Address>>,
+    /// Seed of all random decisions
+    pub seed: u64,
+}
+
+/// Turns a partition of the in-memory network with the given name on or off
+pub fn set_partition_active(server_name: &str, partition: &str, active: bool) -> NetResult<()> {
+    let server_map = MEMORY_SERVER_MAP.read().unwrap();
+    let mut server = server_map
+        .get(server_name)
+        .ok_or_else(|| format_err!("No in-memory network named {}", server_name))?
+        .lock()
+        .unwrap();
+    server.set_partition_active(partition, active)
+}
+
+/// splitmix64, which is good enough for test decisions and accepts any seed
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// true with the given probability
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// uniformly distributed in 0..=max
+    fn up_to(&mut self, max: u64) -> u64 {
+        if max == 0 {
+            0
+        } else {
+            self.next_u64() % (max + 1)
+        }
+    }
+}
+
+/// Applies a fault profile to the messages of an in-memory server.
+/// Messages get routed into a queue ordered by the time they are due,
+/// from which the server takes them whenever it delivers.
+pub(crate) struct FaultInjector {
+    profile: FaultProfile,
+    rng: SeededRng,
+    active_partitions: HashSet<String>,
+    // (due, sequence number) -> message, the sequence keeps the send order of equal due times
+    queue: BTreeMap<(Instant, u64), (ChainId, Protocol)>,
+    // messages waiting for the next message to the same chain, with the time they were due
+    held_back: BTreeMap<ChainId, Vec<(Instant, Protocol)>>,
+    sequence: u64,
+}
+
+impl FaultInjector {
+    pub fn new(profile: FaultProfile) -> Self {
+        let rng = SeededRng(profile.seed);
+        FaultInjector {
+            profile,
+            rng,
+            active_partitions: HashSet::new(),
+            queue: BTreeMap::new(),
+            held_back: BTreeMap::new(),
+            sequence: 0,
+        }
+    }
+
+    pub fn set_partition_active(&mut self, partition: &str, active: bool) -> NetResult<()> {
+        if !self.profile.partitions.contains_key(partition) {
+            return Err(format_err!("Unknown partition {}", partition));
+        }
+        if active {
+            self.active_partitions.insert(partition.to_string());
+        } else {
+            self.active_partitions.remove(partition);
+        }
+        Ok(())
+    }
+
+    /// true if an active partition separates the two agents
+    pub fn is_cut_off(&self, from_agent_id: &Address, to_agent_id: &Address) -> bool {
+        self.active_partitions.iter().any(|partition| {
+            let members = &self.profile.partitions[partition];
+            members.contains(from_agent_id) != members.contains(to_agent_id)
+        })
+    }
+
+    /// Queues a message to the given chain according to the profile, if it doesn't get lost
+    pub fn route(&mut self, chain_id: &ChainId, data: Protocol, now: Instant) {
+        if self.rng.chance(self.profile.drop_rate) {
+            return;
+        }
+        let copies = if self.rng.chance(self.profile.duplicate_rate) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let delay = self.profile.latency_ms + self.rng.up_to(self.profile.jitter_ms);
+            let due = now + Duration::from_millis(delay);
+            if self.rng.chance(self.profile.reorder_rate) {
+                self.held_back
+                    .entry(chain_id.clone())
+                    .or_insert_with(Vec::new)
+                    .push((due, data.clone()));
+            } else {
+                self.sequence += 1;
+                self.queue
+                    .insert((due, self.sequence), (chain_id.clone(), data.clone()));
+            }
+        }
+    }
+
+    /// Takes the messages that are due, in delivery order.
+    /// Held back messages follow the next due message to their chain,
+    /// or get released on their own once they waited too long.
+    pub fn take_due(&mut self, now: Instant) -> Vec<(ChainId, Protocol)> {
+        let later = self.queue.split_off(&(now, u64::max_value()));
+        let due = std::mem::replace(&mut self.queue, later);
+        let mut messages = Vec::new();
+        for (_, (chain_id, data)) in due {
+            messages.push((chain_id.clone(), data));
+            if let Some(held_back) = self.held_back.get_mut(&chain_id) {
+                while !held_back.is_empty() && held_back[0].0 <= now {
+                    messages.push((chain_id.clone(), held_back.remove(0).1));
+                }
+            }
+        }
+        for (chain_id, held_back) in self.held_back.iter_mut() {
+            while !held_back.is_empty() && held_back[0].0 + MAX_HOLD_BACK <= now {
+                messages.push((chain_id.clone(), held_back.remove(0).1));
+            }
+        }
+        self.held_back.retain(|_, held_back| !held_back.is_empty());
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::{JsonProtocol, PeerData};
+
+    fn message(i: usize) -> Protocol {
+        JsonProtocol::PeerConnected(PeerData {
+            agent_id: Address::from(format!("agent-{}", i)),
+        })
+        .into()
+    }
+
+    fn deliver_all(profile: FaultProfile, count: usize) -> Vec<Protocol> {
+        let mut faults = FaultInjector::new(profile);
+        let now = Instant::now();
+        let chain_id = "dna::agent".to_string();
+        for i in 0..count {
+            faults.route(&chain_id, message(i), now);
+        }
+        faults
+            .take_due(now + Duration::from_secs(1))
+            .into_iter()
+            .map(|(_, data)| data)
+            .collect()
+    }
+
+    #[test]
+    fn perfect_network_delivers_everything_in_order_right_away() {
+        let mut faults = FaultInjector::new(FaultProfile::default());
+        let now = Instant::now();
+        let chain_id = "dna::agent".to_string();
+        faults.route(&chain_id, message(0), now);
+        faults.route(&chain_id, message(1), now);
+        assert_eq!(
+            faults.take_due(now),
+            vec![(chain_id.clone(), message(0)), (chain_id, message(1))]
+        );
+    }
+
+    #[test]
+    fn latency_delays_delivery() {
+        let mut faults = FaultInjector::new(FaultProfile {
+            latency_ms: 50,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        faults.route(&"dna::agent".to_string(), message(0), now);
+        assert!(faults.take_due(now).is_empty());
+        assert_eq!(faults.take_due(now + Duration::from_millis(50)).len(), 1);
+    }
+
+    #[test]
+    fn faults_are_deterministic_per_seed() {
+        let profile = FaultProfile {
+            drop_rate: 0.2,
+            duplicate_rate: 0.2,
+            reorder_rate: 0.2,
+            jitter_ms: 10,
+            seed: 42,
+            ..Default::default()
+        };
+        let delivered = deliver_all(profile.clone(), 100);
+        assert_eq!(deliver_all(profile.clone(), 100), delivered);
+        assert_ne!(delivered, (0..100).map(message).collect::<Vec<_>>());
+        assert_ne!(
+            deliver_all(
+                FaultProfile {
+                    seed: 43,
+                    ..profile
+                },
+                100
+            ),
+            delivered
+        );
+    }
+
+    #[test]
+    fn active_partitions_cut_off_their_agents() {
+        let mut partitions = HashMap::new();
+        partitions.insert("split".to_string(), vec![Address::from("alex")]);
+        let mut faults = FaultInjector::new(FaultProfile {
+            partitions,
+            ..Default::default()
+        });
+        let alex = Address::from("alex");
+        let billy = Address::from("billy");
+        assert!(!faults.is_cut_off(&alex, &billy));
+        faults.set_partition_active("split", true).unwrap();
+        assert!(faults.is_cut_off(&alex, &billy));
+        assert!(faults.is_cut_off(&billy, &alex));
+        assert!(!faults.is_cut_off(&billy, &billy));
+        faults.set_partition_active("split", false).unwrap();
+        assert!(!faults.is_cut_off(&alex, &billy));
+        assert!(faults.set_partition_active("unknown", true).is_err());
+    }
+}
@@ -1,10 +1,16 @@
 //! provides in-memory p2p "server" for use in scenario testing
 //! the server connects all the memory_workers together, so there is no real gossiping going around.
-//! Could have pluggable DHT strategy. Full-sync currently hard-coded: #fullsync
+//! By default every node holds everything: #fullsync
+//! With a redundancy factor, each address is only held by the nodes nearest to it (see memory_arc).
+//! All messages go through the network faults of the server's profile (see memory_faults).
 
 #![allow(non_snake_case)]
 
-use super::memory_book::*;
+use super::{
+    memory_arc::nearest_agents,
+    memory_book::*,
+    memory_faults::{FaultInjector, FaultProfile},
+};
 use crate::{
     connection::{
         json_protocol::{
@@ -23,6 +29,7 @@ use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     convert::TryFrom,
     sync::{mpsc, Mutex, RwLock},
+    time::Instant,
 };
 
 type RequestId = String;
@@ -60,10 +67,40 @@ pub(crate) struct InMemoryServer {
     // used for making unique request ids
     request_count: usize,
 
+    // Number of nodes holding each address, None for full sync
+    redundancy: Option<usize>,
+    // Keep track of fetch requests for handing aspects off to the nodes that should hold them
+    // request_id -> agent_ids
+    handoff_book: HashMap<RequestId, Vec<Address>>,
+
+    // Network faults applied to every message sent to a node
+    faults: FaultInjector,
+    // Agent that sent the message being served, for applying partitions
+    current_origin: Option<Address>,
+
     // Logger
     log: TweetProxy,
 }
 
+/// The agent that sent a message to the network, if the message tells
+fn message_origin(msg: &JsonProtocol) -> Option<Address> {
+    match msg {
+        JsonProtocol::TrackDna(msg) | JsonProtocol::UntrackDna(msg) => Some(msg.agent_id.clone()),
+        JsonProtocol::SendMessage(msg) | JsonProtocol::HandleSendMessageResult(msg) => {
+            Some(msg.from_agent_id.clone())
+        }
+        JsonProtocol::PublishEntry(msg) => Some(msg.provider_agent_id.clone()),
+        JsonProtocol::HandleFetchEntryResult(msg) => Some(msg.provider_agent_id.clone()),
+        JsonProtocol::QueryEntry(msg) => Some(msg.requester_agent_id.clone()),
+        JsonProtocol::HandleQueryEntryResult(msg) => Some(msg.responder_agent_id.clone()),
+        JsonProtocol::HandleGetAuthoringEntryListResult(msg)
+        | JsonProtocol::HandleGetGossipingEntryListResult(msg) => {
+            Some(msg.provider_agent_id.clone())
+        }
+        _ => None,
+    }
+}
+
 /// Books handling
 impl InMemoryServer {
     /// generate a new request_id
@@ -126,12 +163,53 @@ impl InMemoryServer {
         )
         .expect("Sending HandleGetHoldingEntryList failed");
     }
+
+    /// Agents currently tracking the dna
+    fn priv_tracking_agents(&self, dna_address: &Address) -> Vec<Address> {
+        self.senders_by_dna
+            .get(dna_address)
+            .map(|senders| {
+                senders
+                    .keys()
+                    .filter(|agent_id| {
+                        self.trackdna_book
+                            .contains(&into_chain_id(dna_address, agent_id))
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Agents that should hold the entry address:
+    /// all tracking agents on full sync, the `redundancy` ones nearest to the address otherwise
+    fn priv_holders(&self, dna_address: &Address, entry_address: &Address) -> Vec<Address> {
+        let agents = self.priv_tracking_agents(dna_address);
+        match self.redundancy {
+            None => agents,
+            Some(redundancy) => nearest_agents(entry_address, agents.iter(), redundancy),
+        }
+    }
+
+    /// Agents to request the data lists from after the agents tracking a dna changed.
+    /// On full sync only the new agent has to tell us what it has.
+    /// Sharded, the arcs of all agents change, so everybody gets asked.
+    fn priv_rebalance(&mut self, dna_address: &Address, maybe_new_agent_id: Option<&Address>) {
+        let agents = match self.redundancy {
+            None => maybe_new_agent_id.cloned().into_iter().collect(),
+            Some(_) => self.priv_tracking_agents(dna_address),
+        };
+        for agent_id in agents {
+            self.priv_request_all_lists(dna_address, &agent_id);
+        }
+    }
 }
 
 /// Public API
 impl InMemoryServer {
-    /// create a new in-memory network server
-    pub fn new(name: String) -> Self {
+    /// create a new in-memory network server.
+    /// With a redundancy factor, addresses are only stored on that many nodes.
+    pub fn new(name: String, redundancy: Option<usize>, faults: FaultProfile) -> Self {
         Self {
             name,
             senders: HashMap::new(),
@@ -142,6 +220,10 @@ impl InMemoryServer {
             stored_book: HashMap::new(),
             request_count: 0,
             trackdna_book: HashSet::new(),
+            redundancy,
+            handoff_book: HashMap::new(),
+            faults: FaultInjector::new(faults),
+            current_origin: None,
             log: TweetProxy::new("memory_server"),
         }
     }
@@ -207,6 +289,30 @@ impl InMemoryServer {
         self.log.d(&format!("unregistering '{}' DONE", chain_id));
     }
 
+    /// Turns a partition of the fault profile on or off
+    pub fn set_partition_active(&mut self, partition: &str, active: bool) -> NetResult<()> {
+        self.log.i(&format!(
+            "---- '{}' partition '{}' active: {}",
+            self.name.clone(),
+            partition,
+            active
+        ));
+        self.faults.set_partition_active(partition, active)
+    }
+
+    /// send the messages whose delay is over to their nodes
+    pub fn deliver_due(&mut self) -> NetResult<()> {
+        for (chain_id, data) in self.faults.take_due(Instant::now()) {
+            // Nodes that left in the meantime miss out
+            if let Some(sender) = self.senders.get(&chain_id) {
+                self.log
+                    .d(&format!("<<<< '{}' send: {:?}", self.name.clone(), data));
+                sender.send(data)?;
+            }
+        }
+        Ok(())
+    }
+
     /// process a message sent by a node to the "network"
     pub fn serve(&mut self, data: Protocol) -> NetResult<()> {
         self.log
@@ -216,6 +322,7 @@ impl InMemoryServer {
         if maybe_json_msg.is_err() {
             return Ok(());
         };
+        self.current_origin = message_origin(maybe_json_msg.as_ref().unwrap());
         // Note: use same order as the enum
         match maybe_json_msg.as_ref().unwrap() {
             JsonProtocol::SuccessResult(msg) => {
@@ -279,7 +386,7 @@ impl InMemoryServer {
                     .into(),
                 )?;
                 // Request all data lists from this agent
-                self.priv_request_all_lists(&msg.dna_address, &msg.agent_id);
+                self.priv_rebalance(&msg.dna_address, Some(&msg.agent_id));
             }
 
             JsonProtocol::UntrackDna(msg) => {
@@ -293,6 +400,11 @@ impl InMemoryServer {
                     return Ok(());
                 }
                 self.trackdna_book.remove(&chain_id);
+                // Hand the data of the leaving agent off to the agents taking over its arc
+                if self.redundancy.is_some() {
+                    self.stored_book.remove(&chain_id);
+                    self.priv_rebalance(&msg.dna_address, None);
+                }
             }
 
             JsonProtocol::SendMessage(msg) => {
@@ -322,7 +434,7 @@ impl InMemoryServer {
 
             // Our request for the hold_list has returned
             JsonProtocol::HandleGetGossipingEntryListResult(msg) => {
-                self.priv_serve_HandleGetGossipingEntryListResult(&msg);
+                self.priv_serve_HandleGetGossipingEntryListResult(&msg)?;
             }
 
             _ => {
@@ -390,8 +502,7 @@ impl InMemoryServer {
     /// send a message to the appropriate channel based on dna_address::to_agent_id
     /// If chain_id is unknown, send back FailureResult to `maybe_sender_info`
     fn priv_send_one_with_chain_id(&mut self, chain_id: &str, data: Protocol) -> NetResult<()> {
-        let maybe_sender = self.senders.get_mut(chain_id);
-        if maybe_sender.is_none() {
+        if !self.senders.contains_key(chain_id) {
             self.log.e(&format!(
                 "#### ({}) error: No sender channel found for {}",
                 self.name.clone(),
@@ -403,11 +514,23 @@ impl InMemoryServer {
                 chain_id,
             ));
         }
-        let sender = maybe_sender.unwrap();
-        self.log
-            .d(&format!("<<<< '{}' send: {:?}", self.name.clone(), data));
-        sender.send(data)?;
-        Ok(())
+        let to_agent_id = undo_chain_id(&chain_id.to_string()).1;
+        let is_cut_off = self
+            .current_origin
+            .as_ref()
+            .map(|origin| self.faults.is_cut_off(origin, &to_agent_id))
+            .unwrap_or(false);
+        if is_cut_off {
+            self.log.d(&format!(
+                "---- '{}' partitioned, not sending: {:?}",
+                self.name.clone(),
+                data
+            ));
+            return Ok(());
+        }
+        self.faults
+            .route(&chain_id.to_string(), data, Instant::now());
+        self.deliver_due()
     }
     /// send a message to the appropriate channel based on chain_id (dna_address::to_agent_id)
     /// If chain_id is unknown, send back FailureResult to `maybe_sender_info`
@@ -430,8 +553,74 @@ impl InMemoryServer {
                 data.clone(),
                 dna_address.clone()
             ));
-            for (_k, val) in arr.iter_mut() {
-                (*val).send(data.clone())?;
+            let chain_ids = arr
+                .keys()
+                .map(|agent_id| into_chain_id(dna_address, agent_id))
+                .collect::<Vec<_>>();
+            for chain_id in chain_ids {
+                self.priv_send_one_with_chain_id(&chain_id, data.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// have the given agents store an aspect and book it as stored by them
+    fn priv_send_store(
+        &mut self,
+        agent_ids: &[Address],
+        store_msg: StoreEntryAspectData,
+    ) -> NetResult<()> {
+        for agent_id in agent_ids {
+            bookkeep(
+                &mut self.stored_book,
+                &store_msg.dna_address,
+                agent_id,
+                &store_msg.entry_address,
+                &store_msg.entry_aspect.aspect_address,
+            );
+            self.priv_send_one(
+                &store_msg.dna_address,
+                agent_id,
+                JsonProtocol::HandleStoreEntryAspect(store_msg.clone()).into(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sharded only: fetch the listed aspects that the agents that should hold them
+    /// are missing from the agent that sent the list, and hand them off to those agents
+    fn priv_hand_off_list(&mut self, chain_id: &ChainId, msg: &EntryListData) -> NetResult<()> {
+        for (entry_address, aspect_address_list) in msg.address_map.clone() {
+            let holders = self.priv_holders(&msg.dna_address, &entry_address);
+            for aspect_address in aspect_address_list {
+                let missing = holders
+                    .iter()
+                    .filter(|agent_id| {
+                        !book_has_aspect(
+                            &self.stored_book,
+                            into_chain_id(&msg.dna_address, agent_id),
+                            &entry_address,
+                            &aspect_address,
+                        )
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if missing.is_empty() {
+                    continue;
+                }
+                let request_id = self.priv_create_request_with_chain_id(chain_id);
+                self.handoff_book.insert(request_id.clone(), missing);
+                self.priv_send_one_with_chain_id(
+                    chain_id,
+                    JsonProtocol::HandleFetchEntry(FetchEntryData {
+                        dna_address: msg.dna_address.clone(),
+                        provider_agent_id: undo_chain_id(chain_id).1,
+                        request_id,
+                        entry_address: entry_address.clone(),
+                        aspect_address_list: Some(vec![aspect_address]),
+                    })
+                    .into(),
+                )?;
             }
         }
         Ok(())
@@ -497,7 +686,8 @@ impl InMemoryServer {
 
     // -- serve Publish Entry -- //
 
-    /// on publish, we send store requests to all nodes connected on this dna
+    /// on publish, we send store requests to all nodes connected on this dna,
+    /// or only to the nodes that should hold the entry address if sharded
     fn priv_serve_PublishEntry(&mut self, msg: &ProvidedEntryData) -> NetResult<()> {
         // Provider must be tracking
         let sender_info = Some((msg.provider_agent_id.clone(), None));
@@ -535,12 +725,18 @@ impl InMemoryServer {
                 entry_address: msg.entry.entry_address.clone(),
                 entry_aspect: aspect,
             };
-            // #fullsync
-            // Broadcast: have everyone store it (including self)
-            self.priv_send_all(
-                &msg.dna_address,
-                JsonProtocol::HandleStoreEntryAspect(store_msg).into(),
-            )?;
+            match self.redundancy {
+                // #fullsync
+                // Broadcast: have everyone store it (including self)
+                None => self.priv_send_all(
+                    &msg.dna_address,
+                    JsonProtocol::HandleStoreEntryAspect(store_msg).into(),
+                )?,
+                Some(_) => {
+                    let holders = self.priv_holders(&msg.dna_address, &msg.entry.entry_address);
+                    self.priv_send_store(&holders, store_msg)?;
+                }
+            }
         }
         Ok(())
     }
@@ -564,6 +760,21 @@ impl InMemoryServer {
         //            return Ok(());
         //        }
 
+        // Fetched for a hand off: only store it on the agents that are missing it
+        if let Some(agent_ids) = self.handoff_book.remove(&msg.request_id) {
+            for aspect in msg.entry.aspect_list.clone() {
+                let store_msg = StoreEntryAspectData {
+                    request_id: self.priv_generate_request_id(),
+                    dna_address: msg.dna_address.clone(),
+                    provider_agent_id: msg.provider_agent_id.clone(),
+                    entry_address: msg.entry.entry_address.clone(),
+                    entry_aspect: aspect,
+                };
+                self.priv_send_store(&agent_ids, store_msg)?;
+            }
+            return Ok(());
+        }
+
         // Should be from our own request do a publish
         if !self.request_book.contains_key(&msg.request_id) {
             // FIXME return Err instead
@@ -588,21 +799,44 @@ impl InMemoryServer {
         if !is_tracking {
             return Ok(());
         }
-        // #fullsync
-        // Have the requester respond to itself
-        match self.senders_by_dna.entry(msg.dna_address.to_owned()) {
-            Entry::Occupied(mut e) => {
-                if !e.get().is_empty() {
-                    for (k, r) in e.get_mut().iter() {
-                        if k == &msg.requester_agent_id {
-                            self.log.i(&format!("---- HandleQueryEntry {}", k));
-                            r.send(JsonProtocol::HandleQueryEntry(msg.clone()).into())?;
-                            return Ok(());
-                        }
-                    }
+        match self.redundancy {
+            // #fullsync
+            // Have the requester respond to itself
+            None => {
+                let is_registered = self
+                    .senders_by_dna
+                    .get(&msg.dna_address)
+                    .map(|senders| senders.contains_key(&msg.requester_agent_id))
+                    .expect("Requester's dna should be registered");
+                if is_registered {
+                    self.log
+                        .i(&format!("---- HandleQueryEntry {}", msg.requester_agent_id));
+                    self.priv_send_one(
+                        &msg.dna_address,
+                        &msg.requester_agent_id,
+                        JsonProtocol::HandleQueryEntry(msg.clone()).into(),
+                    )?;
+                    return Ok(());
+                }
+            }
+            // Have the requester respond if it holds the address, the nearest holder otherwise
+            Some(_) => {
+                let holders = self.priv_holders(&msg.dna_address, &msg.entry_address);
+                let maybe_responder = holders
+                    .iter()
+                    .find(|agent_id| **agent_id == msg.requester_agent_id)
+                    .or_else(|| holders.first())
+                    .cloned();
+                if let Some(responder) = maybe_responder {
+                    self.log.i(&format!("---- HandleQueryEntry {}", responder));
+                    self.priv_send_one(
+                        &msg.dna_address,
+                        &responder,
+                        JsonProtocol::HandleQueryEntry(msg.clone()).into(),
+                    )?;
+                    return Ok(());
                 }
             }
-            _ => unreachable!(),
         };
 
         // No node found, send an empty FetchEntryResultData
@@ -662,6 +896,9 @@ impl InMemoryServer {
             "---- HandleGetAuthoringEntryListResult: chain_id = '{}'",
             chain_id,
         ));
+        if self.redundancy.is_some() {
+            return self.priv_hand_off_list(&chain_id, msg);
+        }
         // Compare with already authored list
         // For each aspect not already authored, fetch it and publish it ourselves.
         for (entry_address, aspect_address_list) in msg.address_map.clone() {
@@ -692,7 +929,10 @@ impl InMemoryServer {
     }
 
     /// Received response from our request for the 'holding_list'
-    fn priv_serve_HandleGetGossipingEntryListResult(&mut self, msg: &EntryListData) {
+    fn priv_serve_HandleGetGossipingEntryListResult(
+        &mut self,
+        msg: &EntryListData,
+    ) -> NetResult<()> {
         let chain_id = self
             .priv_check_request(&msg.request_id)
             .expect("Not our request")
@@ -701,6 +941,20 @@ impl InMemoryServer {
             "---- HandleGetHoldingEntryListResult: chain_id = '{}'",
             chain_id,
         ));
+        // Sharded: book what the agent holds and pass it on to the agents that should hold it
+        if self.redundancy.is_some() {
+            for (entry_address, aspect_address_list) in msg.address_map.iter() {
+                for aspect_address in aspect_address_list {
+                    bookkeep_with_chain_id(
+                        &mut self.stored_book,
+                        chain_id.clone(),
+                        entry_address,
+                        aspect_address,
+                    );
+                }
+            }
+            return self.priv_hand_off_list(&chain_id, msg);
+        }
         // Compare with current stored_book
         // For each data not already holding, add it to stored_data_book?
         for (entry_address, aspect_address_list) in msg.address_map.clone() {
@@ -734,5 +988,164 @@ impl InMemoryServer {
                 );
             }
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::{EntryAspectData, EntryData, TrackDnaData};
+
+    fn dna_address() -> Address {
+        "test-dna".into()
+    }
+
+    /// Sets up a server with the given agents tracking the same dna
+    fn test_server(
+        agent_count: usize,
+        redundancy: Option<usize>,
+        faults: FaultProfile,
+    ) -> (InMemoryServer, Vec<(Address, mpsc::Receiver<Protocol>)>) {
+        let mut server = InMemoryServer::new("test".to_string(), redundancy, faults);
+        let mut agents = Vec::new();
+        for i in 0..agent_count {
+            let agent_id = Address::from(format!("agent-{}", i));
+            let (sender, receiver) = mpsc::channel();
+            server
+                .register_chain(&dna_address(), &agent_id, sender)
+                .unwrap();
+            server
+                .serve(
+                    JsonProtocol::TrackDna(TrackDnaData {
+                        dna_address: dna_address(),
+                        agent_id: agent_id.clone(),
+                    })
+                    .into(),
+                )
+                .unwrap();
+            agents.push((agent_id, receiver));
+        }
+        (server, agents)
+    }
+
+    /// Agents that received a message of the given kind, ignoring all other messages
+    fn receivers_of(
+        agents: &[(Address, mpsc::Receiver<Protocol>)],
+        is_kind: impl Fn(&JsonProtocol) -> bool,
+    ) -> Vec<Address> {
+        agents
+            .iter()
+            .filter(|(_, receiver)| {
+                receiver
+                    .try_iter()
+                    .filter_map(|data| JsonProtocol::try_from(data).ok())
+                    .filter(|msg| is_kind(msg))
+                    .count()
+                    > 0
+            })
+            .map(|(agent_id, _)| agent_id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn sharded_publish_and_query_only_reach_nearest_agents() {
+        let (mut server, agents) = test_server(5, Some(2), FaultProfile::default());
+        receivers_of(&agents, |_| false);
+
+        let entry_address = Address::from("QmShardedEntry");
+        let agent_ids = agents
+            .iter()
+            .map(|(agent_id, _)| agent_id.clone())
+            .collect::<Vec<_>>();
+        let mut holders = nearest_agents(&entry_address, agent_ids.iter(), 2);
+        holders.sort_by_key(|agent_id| agent_id.to_string());
+
+        server
+            .serve(
+                JsonProtocol::PublishEntry(ProvidedEntryData {
+                    dna_address: dna_address(),
+                    provider_agent_id: agent_ids[0].clone(),
+                    entry: EntryData {
+                        entry_address: entry_address.clone(),
+                        aspect_list: vec![EntryAspectData {
+                            aspect_address: entry_address.clone(),
+                            type_hint: "content".to_string(),
+                            aspect: vec![],
+                            publish_ts: 0,
+                        }],
+                    },
+                })
+                .into(),
+            )
+            .unwrap();
+        let mut stored_by = receivers_of(&agents, |msg| match msg {
+            JsonProtocol::HandleStoreEntryAspect(_) => true,
+            _ => false,
+        });
+        stored_by.sort_by_key(|agent_id| agent_id.to_string());
+        assert_eq!(stored_by, holders);
+
+        let requester = agent_ids
+            .iter()
+            .find(|agent_id| !holders.contains(agent_id))
+            .unwrap()
+            .clone();
+        server
+            .serve(
+                JsonProtocol::QueryEntry(QueryEntryData {
+                    dna_address: dna_address(),
+                    entry_address: entry_address.clone(),
+                    request_id: "query".to_string(),
+                    requester_agent_id: requester,
+                    query: vec![],
+                })
+                .into(),
+            )
+            .unwrap();
+        let queried = receivers_of(&agents, |msg| match msg {
+            JsonProtocol::HandleQueryEntry(_) => true,
+            _ => false,
+        });
+        assert_eq!(queried.len(), 1);
+        assert!(holders.contains(&queried[0]));
+    }
+
+    #[test]
+    fn active_partitions_drop_messages_across_them() {
+        let mut partitions = HashMap::new();
+        partitions.insert("split".to_string(), vec![Address::from("agent-0")]);
+        let (mut server, agents) = test_server(
+            2,
+            None,
+            FaultProfile {
+                partitions,
+                ..Default::default()
+            },
+        );
+        receivers_of(&agents, |_| false);
+        let send_message = |server: &mut InMemoryServer| {
+            server
+                .serve(
+                    JsonProtocol::SendMessage(MessageData {
+                        dna_address: dna_address(),
+                        request_id: "message".to_string(),
+                        to_agent_id: agents[1].0.clone(),
+                        from_agent_id: agents[0].0.clone(),
+                        content: vec![],
+                    })
+                    .into(),
+                )
+                .unwrap();
+            receivers_of(&agents, |msg| match msg {
+                JsonProtocol::HandleSendMessage(_) => true,
+                _ => false,
+            })
+        };
+
+        server.set_partition_active("split", true).unwrap();
+        assert!(send_message(&mut server).is_empty());
+        server.set_partition_active("split", false).unwrap();
+        assert_eq!(send_message(&mut server), vec![agents[1].0.clone()]);
     }
 }
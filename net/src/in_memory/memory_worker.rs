@@ -1,6 +1,6 @@
 //! provides fake in-memory p2p worker for use in scenario testing
 
-use super::memory_server::*;
+use super::{memory_faults::FaultProfile, memory_server::*};
 use crate::connection::{
     json_protocol::JsonProtocol,
     net_connection::{NetHandler, NetWorker},
@@ -93,6 +93,16 @@ impl NetWorker for InMemoryWorker {
             self.can_send_P2pReady = false;
             self.handler.handle(Ok(Protocol::P2pReady))?;
         }
+        // have the server send the messages that were delayed until now
+        {
+            let server_map = MEMORY_SERVER_MAP.read().unwrap();
+            let mut server = server_map
+                .get(&self.server_name)
+                .expect("InMemoryServer should have been initialized by now")
+                .lock()
+                .unwrap();
+            server.deliver_due()?;
+        }
         // check for messages from our InMemoryServer
         let mut did_something = false;
         for (_, receiver) in self.receiver_per_dna.iter_mut() {
@@ -125,12 +135,23 @@ impl InMemoryWorker {
             .as_str()
             .unwrap_or("(unnamed)")
             .to_string();
-        // Create server with that name if it doesn't already exist
+        // Without a redundancy factor, every node holds everything
+        let redundancy = config["redundancy"]
+            .as_u64()
+            .filter(|redundancy| *redundancy > 0)
+            .map(|redundancy| redundancy as usize);
+        // Without a fault profile, the network is perfect
+        let faults = match &config["faults"] {
+            serde_json::Value::Null => FaultProfile::default(),
+            faults => serde_json::from_value(faults.clone())?,
+        };
+        // Create server with that name if it doesn't already exist.
+        // The server keeps the redundancy and faults of the worker that created it.
         let mut server_map = MEMORY_SERVER_MAP.write().unwrap();
         if !server_map.contains_key(&server_name) {
             server_map.insert(
                 server_name.clone(),
-                Mutex::new(InMemoryServer::new(server_name.clone())),
+                Mutex::new(InMemoryServer::new(server_name.clone(), redundancy, faults)),
             );
         }
         let mut server = server_map
@@ -1,5 +1,7 @@
 //! Module for the in-memory network module
 
+pub mod memory_arc;
 pub mod memory_book;
+pub mod memory_faults;
 pub mod memory_server;
 pub mod memory_worker;
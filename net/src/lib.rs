@@ -44,3 +44,4 @@ pub mod ipc_net_worker;
 pub mod lib3h_worker;
 pub mod p2p_config;
 pub mod p2p_network;
+pub mod tcp;
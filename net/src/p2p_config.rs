@@ -1,3 +1,4 @@
+use crate::{connection::wire_encoding::WireEncoding, in_memory::memory_faults::FaultProfile};
 use holochain_json_api::{error::JsonError, json::JsonString};
 use snowflake;
 use std::{fs::File, io::prelude::*, str::FromStr};
@@ -11,6 +12,7 @@ pub enum P2pBackendKind {
     MEMORY,
     IPC,
     LIB3H,
+    TCP,
 }
 
 impl FromStr for P2pBackendKind {
@@ -19,6 +21,7 @@ impl FromStr for P2pBackendKind {
         match s {
             "MEMORY" => Ok(P2pBackendKind::MEMORY),
             "IPC" => Ok(P2pBackendKind::IPC),
+            "TCP" => Ok(P2pBackendKind::TCP),
             _ => Err(()),
         }
     }
@@ -30,6 +33,7 @@ impl From<P2pBackendKind> for String {
             P2pBackendKind::MEMORY => "MEMORY",
             P2pBackendKind::IPC => "IPC",
             P2pBackendKind::LIB3H => "LIB3H",
+            P2pBackendKind::TCP => "TCP",
         })
     }
 }
@@ -153,6 +157,31 @@ impl P2pConfig {
         )
     }
 
+    /// Memory backend where each address is only held by the `redundancy` nodes nearest to it
+    pub fn new_with_sharded_memory_backend(server_name: &str, redundancy: usize) -> Self {
+        P2pConfig::new(
+            P2pBackendKind::MEMORY,
+            &Self::sharded_memory_backend_string(server_name, redundancy),
+            None,
+        )
+    }
+
+    /// Makes a memory backend inject the network faults of the given profile into its messages.
+    /// The profile is taken from the first node that connects to the in-memory network.
+    pub fn with_fault_profile(mut self, faults: &FaultProfile) -> Self {
+        self.backend_config["faults"] =
+            serde_json::to_value(faults).expect("FaultProfile should serialize");
+        self
+    }
+
+    /// Makes a TCP backend send MessagePack or another WireEncoding instead of JSON
+    /// to the nodes that accept it. The others still get sent JSON.
+    pub fn with_wire_encoding(mut self, wire_encoding: WireEncoding) -> Self {
+        self.backend_config["wireEncoding"] =
+            serde_json::to_value(wire_encoding).expect("WireEncoding should serialize");
+        self
+    }
+
     pub fn new_with_unique_memory_backend() -> Self {
         Self::new_with_memory_backend(&format!(
             "memory-auto-{}",
@@ -167,6 +196,34 @@ impl P2pConfig {
         ))
     }
 
+    /// Backend connecting directly to other nodes over TCP, starting with the bootstrap nodes.
+    /// Other nodes get told the advertise address, which is required if the bind address is
+    /// unspecified (like 0.0.0.0). A port of 0 in it stands for the bound port.
+    pub fn new_with_tcp_backend(
+        bind_address: &str,
+        advertise_address: Option<&str>,
+        bootstrap_nodes: &[String],
+    ) -> Self {
+        P2pConfig::new(
+            P2pBackendKind::TCP,
+            &Self::tcp_backend_string(bind_address, advertise_address, bootstrap_nodes),
+            None,
+        )
+    }
+
+    pub fn tcp_backend_string(
+        bind_address: &str,
+        advertise_address: Option<&str>,
+        bootstrap_nodes: &[String],
+    ) -> String {
+        json!({
+            "bindAddress": bind_address,
+            "advertiseAddress": advertise_address,
+            "bootstrapNodes": bootstrap_nodes
+        })
+        .to_string()
+    }
+
     pub fn memory_backend_string(server_name: &str) -> String {
         format!(
             r#"{{
@@ -175,6 +232,14 @@ impl P2pConfig {
             server_name
         )
     }
+
+    pub fn sharded_memory_backend_string(server_name: &str, redundancy: usize) -> String {
+        json!({
+            "serverName": server_name,
+            "redundancy": redundancy
+        })
+        .to_string()
+    }
 }
 
 /// end_user config
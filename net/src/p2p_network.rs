@@ -13,6 +13,7 @@ use crate::{
     ipc_net_worker::IpcNetWorker,
     lib3h_worker::Lib3hWorker,
     p2p_config::*,
+    tcp::tcp_worker::TcpWorker,
     tweetlog::*,
 };
 use crossbeam_channel;
@@ -64,6 +65,9 @@ impl P2pNetwork {
             P2pBackendKind::MEMORY => Box::new(move |h| {
                 Ok(Box::new(InMemoryWorker::new(h, &backend_config)?) as Box<NetWorker>)
             }),
+            P2pBackendKind::TCP => Box::new(move |h| {
+                Ok(Box::new(TcpWorker::new(h, &backend_config)?) as Box<NetWorker>)
+            }),
         };
 
         let (t, rx) = crossbeam_channel::unbounded();
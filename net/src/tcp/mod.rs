@@ -0,0 +1,6 @@
+//! Module for the TCP network backend: nodes connect to each other directly over TCP,
+//! without an external networking process.
+
+pub mod tcp_node;
+pub mod tcp_wire;
+pub mod tcp_worker;
@@ -0,0 +1,924 @@
+//! A node of the TCP network: listens on one address, keeps connections to the other nodes
+//! and routes JsonProtocol messages between the local chains and the network.
+//! All TcpWorkers of a process that bind the same address share one node.
+//!
+//! Every node holds all data of the DNAs it tracks: #fullsync
+//! Publishing stores the entry on all local chains and all connected nodes tracking the DNA,
+//! and queries are answered by the requester itself.
+//! Nodes that start tracking a DNA get sent everything the other nodes hold for it.
+//!
+//! The first `Connect` a node sends on a connection announces the address it listens on,
+//! every later one is the address of another node to connect to, so nodes find each other
+//! through their bootstrap nodes.
+//! Nodes announce their advertise address, which has to be one the others can reach:
+//! binding an unspecified address like 0.0.0.0 needs an advertise address.
+//! Addresses announced by other nodes are queued and only dialed while the node has less
+//! than MAX_CONNECTIONS connections, and connects run on their own threads so a slow or
+//! unreachable address never blocks a tick.
+//! Accepted connections that do not announce their address within HANDSHAKE_TIMEOUT get
+//! closed, so idle sockets can't use up the connections a node keeps.
+
+#![allow(non_snake_case)]
+
+use super::tcp_wire::TcpConnection;
+use crate::{
+    connection::{
+        json_protocol::{
+            ConnectData, EntryListData, FetchEntryData, FetchEntryResultData, GenericResultData,
+            GetListData, JsonProtocol, MessageData, PeerData, ProvidedEntryData,
+            StoreEntryAspectData, TrackDnaData,
+        },
+        protocol::Protocol,
+        wire_encoding::WireEncoding,
+        NetResult,
+    },
+    in_memory::memory_book::{into_chain_id, undo_chain_id, ChainId},
+    tweetlog::*,
+};
+use holochain_persistence_api::cas::content::Address;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::ErrorKind,
+    net::{SocketAddr, TcpListener},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+type RequestId = String;
+
+/// How often unreachable bootstrap nodes are tried again
+const BOOTSTRAP_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Connections a node keeps at most, counting the ones being opened.
+/// Further accepted connections get closed right away.
+pub(crate) const MAX_CONNECTIONS: usize = 64;
+
+/// How long a connection may take to announce its address before it gets closed
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connect threads running at the same time
+const MAX_CONNECTING: usize = 8;
+
+/// Addresses announced by other nodes that wait to be dialed, later ones are ignored
+const MAX_DISCOVERED: usize = 256;
+
+/// Type for holding a map of 'bind address -> TcpNode'
+type TcpNodeMap = HashMap<String, Arc<Mutex<TcpNode>>>;
+
+/// the nodes of this process
+lazy_static! {
+    pub(crate) static ref TCP_NODE_MAP: Mutex<TcpNodeMap> = Mutex::new(HashMap::new());
+}
+
+/// Where data fetched to bring a node up to date goes
+#[derive(Clone, Debug, PartialEq)]
+enum SyncTarget {
+    Peer(String),
+    Local(ChainId),
+}
+
+pub(crate) struct TcpNode {
+    listener: TcpListener,
+    // address the other nodes connect to
+    listen_address: String,
+    bootstrap_nodes: Vec<String>,
+    // encoding to send with to nodes that accept it
+    wire_encoding: WireEncoding,
+    last_bootstrap: Option<Instant>,
+    // connect threads by the address they dial
+    connecting: HashMap<String, mpsc::Receiver<NetResult<TcpConnection>>>,
+    // addresses announced by other nodes that we did not dial yet
+    discovered: VecDeque<String>,
+    // connections whose other side did not announce its address yet, with when they got added
+    pending: Vec<(TcpConnection, Instant)>,
+    // how long pending connections get to announce their address
+    handshake_timeout: Duration,
+    // connections by the address their other side listens on
+    peers: HashMap<String, TcpConnection>,
+    // keep track of local senders by ChainId (dna_address::agent_id)
+    senders: HashMap<ChainId, mpsc::Sender<Protocol>>,
+    // local chains tracking their dna
+    tracked: HashSet<ChainId>,
+    // chains tracked by other nodes: chain_id -> peer address
+    remote_chains: HashMap<ChainId, String>,
+    // requests for data that brings a node up to date: request_id -> where the data goes
+    sync_requests: HashMap<RequestId, SyncTarget>,
+    // used for making unique request ids
+    request_count: usize,
+    // Keep track of connected workers
+    client_count: usize,
+    log: TweetProxy,
+}
+
+/// Public API
+impl TcpNode {
+    /// Listens on the bind address and connects to the bootstrap nodes that can be reached.
+    /// Other nodes get told the advertise address, or the bound one if there is none.
+    /// A port of 0 in the advertise address stands for the bound port.
+    /// Fails if the address to announce is unspecified (0.0.0.0 or ::),
+    /// since other nodes could not connect to it.
+    pub fn new(
+        bind_address: &str,
+        maybe_advertise_address: Option<String>,
+        bootstrap_nodes: Vec<String>,
+        wire_encoding: WireEncoding,
+    ) -> NetResult<Self> {
+        let listener = TcpListener::bind(bind_address)?;
+        listener.set_nonblocking(true)?;
+        let listen_address = advertised_address(listener.local_addr()?, maybe_advertise_address)?;
+        let mut node = TcpNode {
+            listener,
+            listen_address,
+            bootstrap_nodes,
+            wire_encoding,
+            last_bootstrap: None,
+            connecting: HashMap::new(),
+            discovered: VecDeque::new(),
+            pending: Vec::new(),
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+            peers: HashMap::new(),
+            senders: HashMap::new(),
+            tracked: HashSet::new(),
+            remote_chains: HashMap::new(),
+            sync_requests: HashMap::new(),
+            request_count: 0,
+            client_count: 0,
+            log: TweetProxy::new("tcp_node"),
+        };
+        node.priv_bootstrap();
+        Ok(node)
+    }
+
+    /// Address the other nodes connect to
+    pub fn listen_address(&self) -> String {
+        self.listen_address.clone()
+    }
+
+    /// Lets tests wait less than HANDSHAKE_TIMEOUT for idle connections to get closed
+    #[cfg(test)]
+    pub(crate) fn set_handshake_timeout(&mut self, handshake_timeout: Duration) {
+        self.handshake_timeout = handshake_timeout;
+    }
+
+    /// A worker starts using this node
+    pub fn clock_in(&mut self) {
+        self.client_count += 1;
+    }
+
+    /// A worker stops using this node. Returns true if it was the last one.
+    pub fn clock_out(&mut self) -> bool {
+        assert!(self.client_count > 0);
+        self.client_count -= 1;
+        self.client_count == 0
+    }
+
+    /// register a chain's handler with the node (for message routing)
+    pub fn register_chain(
+        &mut self,
+        dna_address: &Address,
+        agent_id: &Address,
+        sender: mpsc::Sender<Protocol>,
+    ) {
+        self.senders
+            .insert(into_chain_id(dna_address, agent_id), sender);
+    }
+
+    /// unregister a chain's handler with the node
+    pub fn unregister_chain(&mut self, dna_address: &Address, agent_id: &Address) {
+        let chain_id = into_chain_id(dna_address, agent_id);
+        self.senders.remove(&chain_id);
+        self.tracked.remove(&chain_id);
+    }
+
+    /// process a message sent by a local chain to the network
+    pub fn serve(&mut self, msg: JsonProtocol) -> NetResult<()> {
+        self.log
+            .d(&format!(">>>> '{}' recv: {:?}", self.listen_address, msg));
+        // Note: use same order as the enum
+        match msg {
+            JsonProtocol::SuccessResult(msg) => {
+                if !self.priv_is_sync_response(&msg) {
+                    self.priv_route(
+                        &msg.dna_address.clone(),
+                        &msg.to_agent_id.clone(),
+                        JsonProtocol::SuccessResult(msg),
+                    );
+                }
+            }
+            JsonProtocol::FailureResult(msg) => {
+                if !self.priv_is_sync_response(&msg) {
+                    self.priv_route(
+                        &msg.dna_address.clone(),
+                        &msg.to_agent_id.clone(),
+                        JsonProtocol::FailureResult(msg),
+                    );
+                }
+            }
+            JsonProtocol::TrackDna(msg) => self.priv_serve_TrackDna(msg),
+            JsonProtocol::UntrackDna(msg) => {
+                self.tracked
+                    .remove(&into_chain_id(&msg.dna_address, &msg.agent_id));
+                self.priv_send_all_peers(&JsonProtocol::UntrackDna(msg));
+            }
+            JsonProtocol::Connect(msg) => self.priv_connect(&msg.peer_address),
+
+            JsonProtocol::SendMessage(msg) => self.priv_serve_SendMessage(msg),
+            JsonProtocol::HandleSendMessageResult(msg) => {
+                self.priv_route(
+                    &msg.dna_address.clone(),
+                    &msg.to_agent_id.clone(),
+                    JsonProtocol::SendMessageResult(msg),
+                );
+            }
+
+            JsonProtocol::HandleFetchEntryResult(msg) => {
+                self.priv_serve_HandleFetchEntryResult(msg)
+            }
+            JsonProtocol::PublishEntry(msg) => self.priv_serve_PublishEntry(msg),
+            // #fullsync
+            // Have the requester respond to itself
+            JsonProtocol::QueryEntry(msg) => {
+                self.priv_send_local(
+                    &into_chain_id(&msg.dna_address, &msg.requester_agent_id),
+                    JsonProtocol::HandleQueryEntry(msg),
+                );
+            }
+            JsonProtocol::HandleQueryEntryResult(msg) => {
+                self.priv_send_local(
+                    &into_chain_id(&msg.dna_address, &msg.requester_agent_id),
+                    JsonProtocol::QueryEntryResult(msg),
+                );
+            }
+
+            JsonProtocol::HandleGetAuthoringEntryListResult(msg)
+            | JsonProtocol::HandleGetGossipingEntryListResult(msg) => {
+                self.priv_serve_EntryListResult(msg);
+            }
+
+            msg => self.log.w(&format!("unexpected {:?}", msg)),
+        }
+        Ok(())
+    }
+
+    /// Accepts new connections, handles the messages of other nodes
+    /// and retries unreachable bootstrap nodes.
+    /// Returns true if anything happened.
+    /// Failing connections only get logged and dropped.
+    pub fn tick(&mut self) -> NetResult<bool> {
+        let mut did_something = false;
+        loop {
+            match self.listener.accept() {
+                Ok((_, remote_address)) if self.priv_connection_count() >= MAX_CONNECTIONS => {
+                    self.log.w(&format!(
+                        "---- '{}' too many connections, closing the one from {}",
+                        self.listen_address, remote_address
+                    ));
+                }
+                Ok((stream, remote_address)) => {
+                    self.log.d(&format!(
+                        "---- '{}' accepted connection from {}",
+                        self.listen_address, remote_address
+                    ));
+                    match TcpConnection::new(stream, None, self.wire_encoding) {
+                        Ok(connection) => self.priv_add_connection(connection),
+                        Err(error) => self.log.d(&format!(
+                            "---- '{}' connection from {} failed: {}",
+                            self.listen_address, remote_address, error
+                        )),
+                    }
+                    did_something = true;
+                }
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    self.log.w(&format!(
+                        "---- '{}' could not accept connection: {}",
+                        self.listen_address, error
+                    ));
+                    break;
+                }
+            }
+        }
+
+        if self
+            .last_bootstrap
+            .map(|last| last.elapsed() >= BOOTSTRAP_RETRY_INTERVAL)
+            .unwrap_or(true)
+        {
+            self.priv_bootstrap();
+        }
+        did_something |= self.priv_poll_connecting();
+        self.priv_dial_discovered();
+
+        // Connections that announce their address become peers
+        let mut still_pending = Vec::new();
+        for (mut connection, added) in self.pending.drain(..).collect::<Vec<_>>() {
+            let messages = match connection.flush().and_then(|_| connection.receive()) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    self.log.d(&format!(
+                        "---- '{}' connection failed: {}",
+                        self.listen_address, error
+                    ));
+                    continue;
+                }
+            };
+            let mut messages = messages.into_iter();
+            match messages.next() {
+                Some(JsonProtocol::Connect(msg)) => {
+                    did_something = true;
+                    let peer_address = msg.peer_address;
+                    connection.peer_address = Some(peer_address.clone());
+                    if self.priv_add_peer(peer_address.clone(), connection) {
+                        for msg in messages {
+                            self.priv_handle_peer_message(&peer_address, msg);
+                        }
+                    }
+                }
+                Some(msg) => self.log.w(&format!(
+                    "---- '{}' dropping connection that did not announce itself: {:?}",
+                    self.listen_address, msg
+                )),
+                None if added.elapsed() >= self.handshake_timeout => {
+                    did_something = true;
+                    self.log.d(&format!(
+                        "---- '{}' closing connection that did not announce itself in time",
+                        self.listen_address
+                    ));
+                }
+                None => still_pending.push((connection, added)),
+            }
+        }
+        self.pending.extend(still_pending);
+
+        let peer_addresses = self.peers.keys().cloned().collect::<Vec<_>>();
+        for peer_address in peer_addresses {
+            let result = match self.peers.get_mut(&peer_address) {
+                Some(connection) => connection.flush().and_then(|_| connection.receive()),
+                None => continue,
+            };
+            match result {
+                Ok(messages) => {
+                    did_something |= !messages.is_empty();
+                    for msg in messages {
+                        self.priv_handle_peer_message(&peer_address, msg);
+                    }
+                }
+                Err(error) => {
+                    self.log.d(&format!(
+                        "---- '{}' lost connection to {}: {}",
+                        self.listen_address, peer_address, error
+                    ));
+                    self.priv_drop_peer(&peer_address);
+                    did_something = true;
+                }
+            }
+        }
+        Ok(did_something)
+    }
+}
+
+/// Connections
+impl TcpNode {
+    /// Connects to all bootstrap nodes we are not connected to
+    fn priv_bootstrap(&mut self) {
+        self.last_bootstrap = Some(Instant::now());
+        for address in self.bootstrap_nodes.clone() {
+            self.priv_connect(&address);
+        }
+    }
+
+    /// true if we have or are opening a connection to the node listening on the address
+    fn priv_is_connected(&self, address: &str) -> bool {
+        address == self.listen_address
+            || self.peers.contains_key(address)
+            || self.connecting.contains_key(address)
+            || self.pending.iter().any(|(connection, _)| {
+                connection.dialed_address.as_ref().map(String::as_str) == Some(address)
+            })
+    }
+
+    /// Connections we have or are opening
+    fn priv_connection_count(&self) -> usize {
+        self.peers.len() + self.pending.len() + self.connecting.len()
+    }
+
+    /// Starts a thread connecting to the address, picked up by priv_poll_connecting()
+    fn priv_connect(&mut self, address: &str) {
+        if self.priv_is_connected(address) {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let dial_address = address.to_string();
+        let wire_encoding = self.wire_encoding;
+        let spawned = thread::Builder::new()
+            .name(format!("tcp_connect_{}", address))
+            .spawn(move || {
+                // the node may be gone by now
+                let _ = sender.send(TcpConnection::connect(&dial_address, wire_encoding));
+            });
+        match spawned {
+            Ok(_) => {
+                self.connecting.insert(address.to_string(), receiver);
+            }
+            Err(error) => self.log.w(&format!(
+                "---- '{}' could not start connecting to {}: {}",
+                self.listen_address, address, error
+            )),
+        }
+    }
+
+    /// Adds the connections the connect threads opened.
+    /// Returns true if any connect finished.
+    fn priv_poll_connecting(&mut self) -> bool {
+        let mut finished = Vec::new();
+        for (address, receiver) in self.connecting.iter() {
+            match receiver.try_recv() {
+                Ok(result) => finished.push((address.clone(), result)),
+                Err(mpsc::TryRecvError::Empty) => (),
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    finished.push((address.clone(), Err(format_err!("connect thread stopped"))))
+                }
+            }
+        }
+        let did_something = !finished.is_empty();
+        for (address, result) in finished {
+            self.connecting.remove(&address);
+            match result {
+                Ok(connection) => self.priv_add_connection(connection),
+                Err(error) => self.log.d(&format!(
+                    "---- '{}' could not connect to {}: {}",
+                    self.listen_address, address, error
+                )),
+            }
+        }
+        did_something
+    }
+
+    /// Queues an address another node told us about
+    fn priv_discover(&mut self, address: String) {
+        if is_unspecified(&address) {
+            self.log.w(&format!(
+                "---- '{}' ignoring unspecified address {}",
+                self.listen_address, address
+            ));
+            return;
+        }
+        if self.discovered.len() >= MAX_DISCOVERED
+            || self.discovered.contains(&address)
+            || self.priv_is_connected(&address)
+        {
+            return;
+        }
+        self.discovered.push_back(address);
+    }
+
+    /// Dials queued addresses while there is room for more connections
+    fn priv_dial_discovered(&mut self) {
+        while self.connecting.len() < MAX_CONNECTING
+            && self.priv_connection_count() < MAX_CONNECTIONS
+        {
+            match self.discovered.pop_front() {
+                Some(address) => self.priv_connect(&address),
+                None => break,
+            }
+        }
+    }
+
+    /// New connections start with announcing our address
+    fn priv_add_connection(&mut self, mut connection: TcpConnection) {
+        let hello = JsonProtocol::Connect(ConnectData {
+            peer_address: self.listen_address.clone(),
+        });
+        match connection.send(&hello) {
+            Ok(()) => self.pending.push((connection, Instant::now())),
+            Err(error) => self.log.d(&format!(
+                "---- '{}' connection failed: {}",
+                self.listen_address, error
+            )),
+        }
+    }
+
+    /// Makes the connection the one to the node listening on the address.
+    /// If both nodes connected to each other, both keep the connection
+    /// opened by the node with the lower address.
+    /// Returns false if the connection got dropped.
+    fn priv_add_peer(&mut self, peer_address: String, connection: TcpConnection) -> bool {
+        if peer_address == self.listen_address {
+            return false;
+        }
+        if let Some(existing) = self.peers.get(&peer_address) {
+            let opened_by_lower = |connection: &TcpConnection| {
+                connection.is_outbound() == (self.listen_address < peer_address)
+            };
+            if opened_by_lower(existing) && !opened_by_lower(&connection) {
+                return false;
+            }
+        }
+        self.log.i(&format!(
+            "---- '{}' connected to {}",
+            self.listen_address, peer_address
+        ));
+        self.peers.insert(peer_address.clone(), connection);
+
+        // Tell the new peer about the other nodes and our chains
+        let mut introductions = self
+            .peers
+            .keys()
+            .filter(|address| **address != peer_address)
+            .map(|address| {
+                JsonProtocol::Connect(ConnectData {
+                    peer_address: address.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        introductions.extend(self.tracked.iter().map(|chain_id| {
+            let (dna_address, agent_id) = undo_chain_id(chain_id);
+            JsonProtocol::TrackDna(TrackDnaData {
+                dna_address,
+                agent_id,
+            })
+        }));
+        for msg in introductions {
+            self.priv_send_peer(&peer_address, &msg);
+        }
+        true
+    }
+
+    /// Forgets a peer and the chains it tracked
+    fn priv_drop_peer(&mut self, peer_address: &str) {
+        self.peers.remove(peer_address);
+        self.remote_chains
+            .retain(|_, chain_peer_address| chain_peer_address != peer_address);
+        self.sync_requests
+            .retain(|_, target| *target != SyncTarget::Peer(peer_address.to_string()));
+    }
+}
+
+/// Sends
+impl TcpNode {
+    /// generate a new request_id
+    fn priv_generate_request_id(&mut self) -> RequestId {
+        self.request_count += 1;
+        format!("tcp_req_{}", self.request_count)
+    }
+
+    /// Local chains tracking the dna
+    fn priv_local_chains(&self, dna_address: &Address) -> Vec<ChainId> {
+        let mut chain_ids = self
+            .tracked
+            .iter()
+            .filter(|chain_id| undo_chain_id(chain_id).0 == *dna_address)
+            .cloned()
+            .collect::<Vec<_>>();
+        chain_ids.sort();
+        chain_ids
+    }
+
+    /// Peers with chains tracking the dna
+    fn priv_peers_tracking(&self, dna_address: &Address) -> Vec<String> {
+        let peers = self
+            .remote_chains
+            .iter()
+            .filter(|(chain_id, _)| undo_chain_id(chain_id).0 == *dna_address)
+            .map(|(_, peer_address)| peer_address.clone())
+            .collect::<HashSet<_>>();
+        peers.into_iter().collect()
+    }
+
+    fn priv_send_local(&mut self, chain_id: &ChainId, msg: JsonProtocol) {
+        match self.senders.get(chain_id) {
+            Some(sender) => {
+                self.log
+                    .d(&format!("<<<< '{}' send: {:?}", self.listen_address, msg));
+                if sender.send(msg.into()).is_err() {
+                    self.log.w(&format!(
+                        "---- '{}' chain gone: {}",
+                        self.listen_address, chain_id
+                    ));
+                }
+            }
+            None => self.log.w(&format!(
+                "#### '{}' no sender channel found for {}",
+                self.listen_address, chain_id
+            )),
+        }
+    }
+
+    fn priv_send_peer(&mut self, peer_address: &str, msg: &JsonProtocol) {
+        let result = match self.peers.get_mut(peer_address) {
+            Some(connection) => connection.send(msg),
+            None => return,
+        };
+        if let Err(error) = result {
+            self.log.d(&format!(
+                "---- '{}' lost connection to {}: {}",
+                self.listen_address, peer_address, error
+            ));
+            self.priv_drop_peer(peer_address);
+        }
+    }
+
+    fn priv_send_all_peers(&mut self, msg: &JsonProtocol) {
+        for peer_address in self.peers.keys().cloned().collect::<Vec<_>>() {
+            self.priv_send_peer(&peer_address, msg);
+        }
+    }
+
+    /// Sends the message to the agent's chain, here or on another node.
+    /// Returns false if no node has the agent.
+    fn priv_route(
+        &mut self,
+        dna_address: &Address,
+        to_agent_id: &Address,
+        msg: JsonProtocol,
+    ) -> bool {
+        let chain_id = into_chain_id(dna_address, to_agent_id);
+        if self.tracked.contains(&chain_id) {
+            self.priv_send_local(&chain_id, msg);
+            true
+        } else if let Some(peer_address) = self.remote_chains.get(&chain_id).cloned() {
+            self.priv_send_peer(&peer_address, &msg);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Have every local chain and every peer tracking the dna store the aspect
+    fn priv_store(&mut self, store_msg: StoreEntryAspectData, target: Option<SyncTarget>) {
+        let msg = JsonProtocol::HandleStoreEntryAspect(store_msg.clone());
+        match target {
+            Some(SyncTarget::Peer(peer_address)) => self.priv_send_peer(&peer_address, &msg),
+            Some(SyncTarget::Local(chain_id)) => self.priv_send_local(&chain_id, msg),
+            None => {
+                for chain_id in self.priv_local_chains(&store_msg.dna_address) {
+                    self.priv_send_local(&chain_id, msg.clone());
+                }
+                for peer_address in self.priv_peers_tracking(&store_msg.dna_address) {
+                    self.priv_send_peer(&peer_address, &msg);
+                }
+            }
+        }
+    }
+
+    /// Asks a local chain for everything it holds, to send it to the target
+    fn priv_request_lists(&mut self, chain_id: &ChainId, target: SyncTarget) {
+        let (dna_address, agent_id) = undo_chain_id(chain_id);
+        let request_id = self.priv_generate_request_id();
+        self.sync_requests
+            .insert(request_id.clone(), target.clone());
+        self.priv_send_local(
+            chain_id,
+            JsonProtocol::HandleGetAuthoringEntryList(GetListData {
+                request_id,
+                provider_agent_id: agent_id.clone(),
+                dna_address: dna_address.clone(),
+            }),
+        );
+        let request_id = self.priv_generate_request_id();
+        self.sync_requests.insert(request_id.clone(), target);
+        self.priv_send_local(
+            chain_id,
+            JsonProtocol::HandleGetGossipingEntryList(GetListData {
+                request_id,
+                provider_agent_id: agent_id,
+                dna_address,
+            }),
+        );
+    }
+
+    /// true if the result answers one of our sync requests, which only get logged
+    fn priv_is_sync_response(&mut self, msg: &GenericResultData) -> bool {
+        if self.sync_requests.remove(&msg.request_id).is_some() {
+            self.log.d(&format!(
+                "---- '{}' internal request answered: {:?}",
+                self.listen_address, msg
+            ));
+            return true;
+        }
+        false
+    }
+}
+
+/// Serving local chains
+impl TcpNode {
+    fn priv_serve_TrackDna(&mut self, msg: TrackDnaData) {
+        let chain_id = into_chain_id(&msg.dna_address, &msg.agent_id);
+        if self.tracked.contains(&chain_id) {
+            self.log.e(&format!(
+                "({}) ##### DNA already tracked: {}",
+                self.listen_address, chain_id
+            ));
+            return;
+        }
+        // Bring the new chain up to date with another local chain of the dna.
+        // Other nodes do the same when they get the TrackDna below.
+        if let Some(local_chain_id) = self.priv_local_chains(&msg.dna_address).first().cloned() {
+            self.priv_request_lists(&local_chain_id, SyncTarget::Local(chain_id.clone()));
+        }
+        self.tracked.insert(chain_id);
+        for local_chain_id in self.priv_local_chains(&msg.dna_address) {
+            self.priv_send_local(
+                &local_chain_id,
+                JsonProtocol::PeerConnected(PeerData {
+                    agent_id: msg.agent_id.clone(),
+                }),
+            );
+        }
+        self.priv_send_all_peers(&JsonProtocol::TrackDna(msg));
+    }
+
+    fn priv_serve_SendMessage(&mut self, msg: MessageData) {
+        let is_routed = self.priv_route(
+            &msg.dna_address.clone(),
+            &msg.to_agent_id.clone(),
+            JsonProtocol::HandleSendMessage(msg.clone()),
+        );
+        if !is_routed {
+            let failure = GenericResultData {
+                dna_address: msg.dna_address.clone(),
+                request_id: msg.request_id.clone(),
+                to_agent_id: msg.from_agent_id.clone(),
+                result_info: "No node found for the receiving agent".into(),
+            };
+            self.priv_send_local(
+                &into_chain_id(&msg.dna_address, &msg.from_agent_id),
+                JsonProtocol::FailureResult(failure),
+            );
+        }
+    }
+
+    /// on publish, we send store requests to all local chains and peers tracking the dna
+    fn priv_serve_PublishEntry(&mut self, msg: ProvidedEntryData) {
+        for aspect in msg.entry.aspect_list {
+            let store_msg = StoreEntryAspectData {
+                request_id: self.priv_generate_request_id(),
+                dna_address: msg.dna_address.clone(),
+                provider_agent_id: msg.provider_agent_id.clone(),
+                entry_address: msg.entry.entry_address.clone(),
+                entry_aspect: aspect,
+            };
+            self.priv_store(store_msg, None);
+        }
+    }
+
+    /// A local chain told us what it holds: fetch it for the sync target
+    fn priv_serve_EntryListResult(&mut self, msg: EntryListData) {
+        let target = match self.sync_requests.remove(&msg.request_id) {
+            Some(target) => target,
+            None => {
+                self.log.w(&format!(
+                    "---- '{}' unexpected list: {:?}",
+                    self.listen_address, msg
+                ));
+                return;
+            }
+        };
+        let chain_id = into_chain_id(&msg.dna_address, &msg.provider_agent_id);
+        for (entry_address, aspect_address_list) in msg.address_map {
+            let request_id = self.priv_generate_request_id();
+            self.sync_requests
+                .insert(request_id.clone(), target.clone());
+            self.priv_send_local(
+                &chain_id,
+                JsonProtocol::HandleFetchEntry(FetchEntryData {
+                    dna_address: msg.dna_address.clone(),
+                    provider_agent_id: msg.provider_agent_id.clone(),
+                    request_id,
+                    entry_address,
+                    aspect_address_list: Some(aspect_address_list),
+                }),
+            );
+        }
+    }
+
+    /// A local chain sent data we fetched for a sync target
+    fn priv_serve_HandleFetchEntryResult(&mut self, msg: FetchEntryResultData) {
+        let target = match self.sync_requests.remove(&msg.request_id) {
+            Some(target) => target,
+            None => {
+                self.log.w(&format!(
+                    "---- '{}' unexpected fetch result: {:?}",
+                    self.listen_address, msg
+                ));
+                return;
+            }
+        };
+        for aspect in msg.entry.aspect_list {
+            let store_msg = StoreEntryAspectData {
+                request_id: self.priv_generate_request_id(),
+                dna_address: msg.dna_address.clone(),
+                provider_agent_id: msg.provider_agent_id.clone(),
+                entry_address: msg.entry.entry_address.clone(),
+                entry_aspect: aspect,
+            };
+            self.priv_store(store_msg, Some(target.clone()));
+        }
+    }
+}
+
+/// Serving other nodes
+impl TcpNode {
+    fn priv_handle_peer_message(&mut self, peer_address: &str, msg: JsonProtocol) {
+        self.log.d(&format!(
+            ">>>> '{}' recv from {}: {:?}",
+            self.listen_address, peer_address, msg
+        ));
+        match msg {
+            JsonProtocol::SuccessResult(msg) => {
+                let chain_id = into_chain_id(&msg.dna_address, &msg.to_agent_id);
+                self.priv_send_local_if_tracked(&chain_id, JsonProtocol::SuccessResult(msg));
+            }
+            JsonProtocol::FailureResult(msg) => {
+                let chain_id = into_chain_id(&msg.dna_address, &msg.to_agent_id);
+                self.priv_send_local_if_tracked(&chain_id, JsonProtocol::FailureResult(msg));
+            }
+            JsonProtocol::TrackDna(msg) => {
+                let chain_id = into_chain_id(&msg.dna_address, &msg.agent_id);
+                self.remote_chains
+                    .insert(chain_id, peer_address.to_string());
+                let local_chain_ids = self.priv_local_chains(&msg.dna_address);
+                for local_chain_id in local_chain_ids.iter() {
+                    self.priv_send_local(
+                        local_chain_id,
+                        JsonProtocol::PeerConnected(PeerData {
+                            agent_id: msg.agent_id.clone(),
+                        }),
+                    );
+                }
+                // Send the new chain's node everything we hold for the dna
+                if let Some(local_chain_id) = local_chain_ids.first() {
+                    self.priv_request_lists(
+                        local_chain_id,
+                        SyncTarget::Peer(peer_address.to_string()),
+                    );
+                }
+            }
+            JsonProtocol::UntrackDna(msg) => {
+                self.remote_chains
+                    .remove(&into_chain_id(&msg.dna_address, &msg.agent_id));
+            }
+            JsonProtocol::Connect(msg) => self.priv_discover(msg.peer_address),
+            JsonProtocol::HandleSendMessage(msg) => {
+                let chain_id = into_chain_id(&msg.dna_address, &msg.to_agent_id);
+                self.priv_send_local_if_tracked(&chain_id, JsonProtocol::HandleSendMessage(msg));
+            }
+            JsonProtocol::SendMessageResult(msg) => {
+                let chain_id = into_chain_id(&msg.dna_address, &msg.to_agent_id);
+                self.priv_send_local_if_tracked(&chain_id, JsonProtocol::SendMessageResult(msg));
+            }
+            JsonProtocol::HandleStoreEntryAspect(msg) => {
+                for chain_id in self.priv_local_chains(&msg.dna_address) {
+                    self.priv_send_local(
+                        &chain_id,
+                        JsonProtocol::HandleStoreEntryAspect(msg.clone()),
+                    );
+                }
+            }
+            msg => self.log.w(&format!(
+                "---- '{}' unexpected message from {}: {:?}",
+                self.listen_address, peer_address, msg
+            )),
+        }
+    }
+
+    fn priv_send_local_if_tracked(&mut self, chain_id: &ChainId, msg: JsonProtocol) {
+        if self.tracked.contains(chain_id) {
+            self.priv_send_local(chain_id, msg);
+        }
+    }
+}
+
+/// true if the address is an IP address no other node can connect to
+fn is_unspecified(address: &str) -> bool {
+    address
+        .parse::<SocketAddr>()
+        .map(|address| address.ip().is_unspecified())
+        .unwrap_or(false)
+}
+
+/// The address to tell other nodes: the advertise address, with the bound port if it has
+/// port 0, or the bound address
+fn advertised_address(
+    bound_address: SocketAddr,
+    maybe_advertise_address: Option<String>,
+) -> NetResult<String> {
+    let address = match maybe_advertise_address {
+        Some(address) => match address.parse::<SocketAddr>() {
+            Ok(mut socket_address) if socket_address.port() == 0 => {
+                socket_address.set_port(bound_address.port());
+                socket_address.to_string()
+            }
+            _ => address,
+        },
+        None => bound_address.to_string(),
+    };
+    if is_unspecified(&address) {
+        bail!(
+            "Other nodes can't connect to the unspecified address {}, set an advertise address",
+            address
+        );
+    }
+    Ok(address)
+}
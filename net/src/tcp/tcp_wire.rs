@@ -0,0 +1,280 @@
+//! Non-blocking TCP connections between TcpNodes.
+//! Nodes exchange JsonProtocol messages, each one framed as its length (4 bytes, big endian),
+//! the tag of its WireEncoding (1 byte) and the encoded message.
+//!
+//! Both sides start a connection with a frame offering the encodings they accept.
+//! Until the offer of the other side arrived, and if it doesn't include the preferred
+//! encoding, messages get sent as JSON.
+//!
+//! Both buffers are bounded: frames are decoded as soon as they are complete, so the read
+//! buffer never holds more than one frame and a chunk, and a connection whose other side
+//! doesn't read fails once MAX_WRITE_BUFFER_SIZE bytes are queued.
+
+use crate::connection::{json_protocol::JsonProtocol, wire_encoding::WireEncoding, NetResult};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// Frames larger than this close the connection
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Queuing more than this for a node that doesn't read closes the connection
+const MAX_WRITE_BUFFER_SIZE: usize = 4 * MAX_FRAME_SIZE;
+
+/// Messages returned by one receive(), the rest waits in the socket
+const MAX_MESSAGES_PER_RECEIVE: usize = 1024;
+
+/// Bytes read from the socket at once
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How long connect() waits for the other node
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Tag of the frame offering the encodings a node accepts, as a JSON list
+const OFFER_TAG: u8 = 0xFF;
+
+/// A connection to another node
+pub(crate) struct TcpConnection {
+    stream: TcpStream,
+    read_buffer: Vec<u8>,
+    write_buffer: Vec<u8>,
+    // encoding to send with if the other node accepts it
+    preferred_encoding: WireEncoding,
+    // encoding messages get sent with
+    encoding: WireEncoding,
+    /// Address we dialed if we opened the connection
+    pub dialed_address: Option<String>,
+    /// Address the other node listens on, once it announced it
+    pub peer_address: Option<String>,
+}
+
+impl TcpConnection {
+    /// Wraps an accepted or connected stream and offers the encodings we accept
+    pub fn new(
+        stream: TcpStream,
+        dialed_address: Option<String>,
+        preferred_encoding: WireEncoding,
+    ) -> NetResult<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        let mut connection = TcpConnection {
+            stream,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+            preferred_encoding,
+            encoding: WireEncoding::Json,
+            dialed_address,
+            peer_address: None,
+        };
+        let offer = serde_json::to_vec(&WireEncoding::supported())?;
+        connection.priv_queue_frame(OFFER_TAG, &offer);
+        connection.flush()?;
+        Ok(connection)
+    }
+
+    /// Connects to a node listening on the given address
+    pub fn connect(address: &str, preferred_encoding: WireEncoding) -> NetResult<Self> {
+        let socket_address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format_err!("Could not resolve {}", address))?;
+        let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT)?;
+        TcpConnection::new(stream, Some(address.to_string()), preferred_encoding)
+    }
+
+    /// true if we opened the connection
+    pub fn is_outbound(&self) -> bool {
+        self.dialed_address.is_some()
+    }
+
+    /// Encoding messages currently get sent with
+    pub fn encoding(&self) -> WireEncoding {
+        self.encoding
+    }
+
+    /// Queues the message and writes as much as the socket takes right now.
+    /// Fails if the message is too large or the other node stopped reading.
+    pub fn send(&mut self, msg: &JsonProtocol) -> NetResult<()> {
+        let payload = self.encoding.encode(msg)?;
+        if payload.len() + 1 > MAX_FRAME_SIZE {
+            bail!("message of {} bytes exceeds the maximum", payload.len());
+        }
+        if self.write_buffer.len() + payload.len() + 5 > MAX_WRITE_BUFFER_SIZE {
+            bail!(
+                "{} bytes queued for a node that doesn't read",
+                self.write_buffer.len()
+            );
+        }
+        self.priv_queue_frame(self.encoding.tag(), &payload);
+        self.flush()
+    }
+
+    fn priv_queue_frame(&mut self, tag: u8, payload: &[u8]) {
+        self.write_buffer
+            .extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        self.write_buffer.push(tag);
+        self.write_buffer.extend_from_slice(payload);
+    }
+
+    /// Writes queued bytes until the socket would block
+    pub fn flush(&mut self) -> NetResult<()> {
+        while !self.write_buffer.is_empty() {
+            match self.stream.write(&self.write_buffer) {
+                Ok(0) => bail!("connection closed"),
+                Ok(written) => {
+                    self.write_buffer.drain(..written);
+                }
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads what is available, up to MAX_MESSAGES_PER_RECEIVE messages, and returns the
+    /// complete messages.
+    /// Fails if the connection got closed or sent something that is not a framed JsonProtocol
+    /// in an encoding we know.
+    pub fn receive(&mut self) -> NetResult<Vec<JsonProtocol>> {
+        let mut messages = Vec::new();
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        while messages.len() < MAX_MESSAGES_PER_RECEIVE {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => bail!("connection closed"),
+                Ok(read) => {
+                    self.read_buffer.extend_from_slice(&chunk[..read]);
+                    self.priv_decode_frames(&mut messages)?;
+                }
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(ref error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Moves the complete frames of the read buffer into messages
+    fn priv_decode_frames(&mut self, messages: &mut Vec<JsonProtocol>) -> NetResult<()> {
+        while self.read_buffer.len() >= 4 {
+            let mut size_bytes = [0; 4];
+            size_bytes.copy_from_slice(&self.read_buffer[..4]);
+            let size = u32::from_be_bytes(size_bytes) as usize;
+            if size > MAX_FRAME_SIZE {
+                bail!("frame of {} bytes exceeds the maximum", size);
+            }
+            if size == 0 {
+                bail!("frame without encoding tag");
+            }
+            if self.read_buffer.len() < 4 + size {
+                break;
+            }
+            let frame = self
+                .read_buffer
+                .drain(..4 + size)
+                .skip(4)
+                .collect::<Vec<_>>();
+            if frame[0] == OFFER_TAG {
+                let offered: Vec<WireEncoding> = serde_json::from_slice(&frame[1..])?;
+                self.encoding = WireEncoding::negotiate(self.preferred_encoding, &offered);
+                continue;
+            }
+            messages.push(WireEncoding::from_tag(frame[0])?.decode(&frame[1..])?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::json_protocol::ConnectData;
+    use std::{net::TcpListener, thread, time::Instant};
+
+    fn connected_pair(
+        client_encoding: WireEncoding,
+        server_encoding: WireEncoding,
+    ) -> (TcpConnection, TcpConnection) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let client = TcpConnection::connect(&address, client_encoding).unwrap();
+        let server =
+            TcpConnection::new(listener.accept().unwrap().0, None, server_encoding).unwrap();
+        (client, server)
+    }
+
+    /// sends the messages and returns what arrives on the other side
+    fn transmit(
+        from: &mut TcpConnection,
+        to: &mut TcpConnection,
+        messages: &[JsonProtocol],
+    ) -> Vec<JsonProtocol> {
+        for msg in messages.iter() {
+            from.send(msg).unwrap();
+        }
+        let mut received = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while received.len() < messages.len() && Instant::now() < deadline {
+            from.flush().unwrap();
+            received.extend(to.receive().unwrap());
+            thread::sleep(Duration::from_millis(10));
+        }
+        received
+    }
+
+    fn messages() -> Vec<JsonProtocol> {
+        (0..3)
+            .map(|i| {
+                JsonProtocol::Connect(ConnectData {
+                    peer_address: format!("127.0.0.1:{}", i),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn framed_messages_arrive_complete_and_in_order() {
+        let (mut client, mut server) = connected_pair(WireEncoding::Json, WireEncoding::Json);
+
+        assert_eq!(transmit(&mut client, &mut server, &messages()), messages());
+        assert!(client.is_outbound());
+        assert!(!server.is_outbound());
+
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut closed = false;
+        while !closed && Instant::now() < deadline {
+            closed = server.receive().is_err();
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(closed);
+    }
+
+    #[test]
+    fn sending_to_a_node_that_does_not_read_fails() {
+        let (mut client, _server) = connected_pair(WireEncoding::Json, WireEncoding::Json);
+        let large = JsonProtocol::Connect(ConnectData {
+            peer_address: "x".repeat(1024 * 1024),
+        });
+        let sent = (0..200).take_while(|_| client.send(&large).is_ok()).count();
+        assert!(sent < 200);
+        assert!(client.write_buffer.len() <= MAX_WRITE_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn connections_switch_to_the_negotiated_encoding() {
+        let (mut client, mut server) =
+            connected_pair(WireEncoding::MessagePack, WireEncoding::Json);
+        // messages sent before the offers arrived go out as JSON
+        assert_eq!(client.encoding(), WireEncoding::Json);
+        assert_eq!(transmit(&mut client, &mut server, &messages()), messages());
+        assert_eq!(transmit(&mut server, &mut client, &messages()), messages());
+
+        // both accept MessagePack, but only the client prefers it
+        assert_eq!(client.encoding(), WireEncoding::MessagePack);
+        assert_eq!(server.encoding(), WireEncoding::Json);
+        assert_eq!(transmit(&mut client, &mut server, &messages()), messages());
+    }
+}
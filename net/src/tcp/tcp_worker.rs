@@ -0,0 +1,426 @@
+//! provides a p2p worker that talks to other nodes over plain TCP connections
+
+use super::tcp_node::{TcpNode, TCP_NODE_MAP};
+use crate::connection::{
+    json_protocol::JsonProtocol,
+    net_connection::{NetHandler, NetWorker},
+    protocol::Protocol,
+    wire_encoding::WireEncoding,
+    NetResult,
+};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::Address;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    convert::TryFrom,
+    sync::{mpsc, Arc, Mutex},
+};
+
+/// Address the node binds to if the config has none: any free port on localhost
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:0";
+
+/// a p2p worker using the TcpNode of its bind address
+#[allow(non_snake_case)]
+pub struct TcpWorker {
+    handler: NetHandler,
+    receiver_per_dna: HashMap<Address, mpsc::Receiver<Protocol>>,
+    node: Arc<Mutex<TcpNode>>,
+    // key of the node in TCP_NODE_MAP
+    node_key: String,
+    can_send_P2pReady: bool,
+}
+
+impl NetWorker for TcpWorker {
+    /// we got a message from holochain core
+    /// forward to our TcpNode
+    fn receive(&mut self, data: Protocol) -> NetResult<()> {
+        if data == Protocol::Shutdown {
+            self.handler.handle(Ok(Protocol::Terminated))?;
+            return Ok(());
+        }
+        let json_msg = match JsonProtocol::try_from(&data) {
+            Ok(json_msg) => json_msg,
+            Err(_) => return Ok(()),
+        };
+        let mut node = self.node.lock().unwrap();
+        if let JsonProtocol::TrackDna(track_msg) = &json_msg {
+            if let Entry::Vacant(e) = self
+                .receiver_per_dna
+                .entry(track_msg.dna_address.to_owned())
+            {
+                let (tx, rx) = mpsc::channel();
+                node.register_chain(&track_msg.dna_address, &track_msg.agent_id, tx);
+                e.insert(rx);
+            }
+        }
+        // Serve
+        node.serve(json_msg.clone())?;
+        // After serve
+        if let JsonProtocol::UntrackDna(untrack_msg) = json_msg {
+            if let Entry::Occupied(e) = self
+                .receiver_per_dna
+                .entry(untrack_msg.dna_address.to_owned())
+            {
+                node.unregister_chain(&untrack_msg.dna_address, &untrack_msg.agent_id);
+                e.remove();
+            }
+        }
+        Ok(())
+    }
+
+    /// handle the network and check for messages from our TcpNode
+    fn tick(&mut self) -> NetResult<bool> {
+        // Send p2pready on first tick
+        if self.can_send_P2pReady {
+            self.can_send_P2pReady = false;
+            self.handler.handle(Ok(Protocol::P2pReady))?;
+        }
+        let mut did_something = self.node.lock().unwrap().tick()?;
+        for (_, receiver) in self.receiver_per_dna.iter_mut() {
+            while let Ok(data) = receiver.try_recv() {
+                did_something = true;
+                self.handler.handle(Ok(data))?;
+            }
+        }
+        Ok(did_something)
+    }
+
+    /// stop the net worker
+    fn stop(self: Box<Self>) -> NetResult<()> {
+        Ok(())
+    }
+
+    /// Set the address other nodes connect to as worker's endpoint
+    fn endpoint(&self) -> Option<String> {
+        Some(self.node.lock().unwrap().listen_address())
+    }
+}
+
+impl TcpWorker {
+    /// create a new worker using the node of the configured bind address,
+    /// which gets started if this process doesn't have it yet
+    pub fn new(handler: NetHandler, backend_config: &JsonString) -> NetResult<Self> {
+        let config: serde_json::Value = serde_json::from_str(backend_config.into())?;
+        let bind_address = config["bindAddress"]
+            .as_str()
+            .unwrap_or(DEFAULT_BIND_ADDRESS)
+            .to_string();
+        let advertise_address = config["advertiseAddress"].as_str().map(String::from);
+        let bootstrap_nodes = match &config["bootstrapNodes"] {
+            serde_json::Value::Null => Vec::new(),
+            bootstrap_nodes => serde_json::from_value(bootstrap_nodes.clone())?,
+        };
+        // Without a wire encoding, nodes talk JSON
+        let wire_encoding = match &config["wireEncoding"] {
+            serde_json::Value::Null => WireEncoding::default(),
+            wire_encoding => serde_json::from_value(wire_encoding.clone())?,
+        };
+
+        let mut node_map = TCP_NODE_MAP.lock().unwrap();
+        // Workers binding port 0 asked for a node of their own
+        let is_own_node = bind_address.ends_with(":0");
+        let existing_node = node_map
+            .get(&bind_address)
+            .filter(|_| !is_own_node)
+            .cloned();
+        let (node_key, node) = match existing_node {
+            Some(node) => (bind_address, node),
+            None => {
+                let node = TcpNode::new(
+                    &bind_address,
+                    advertise_address,
+                    bootstrap_nodes,
+                    wire_encoding,
+                )?;
+                let node_key = if is_own_node {
+                    node.listen_address()
+                } else {
+                    bind_address
+                };
+                let node = Arc::new(Mutex::new(node));
+                node_map.insert(node_key.clone(), node.clone());
+                (node_key, node)
+            }
+        };
+        node.lock().unwrap().clock_in();
+
+        Ok(TcpWorker {
+            handler,
+            receiver_per_dna: HashMap::new(),
+            node,
+            node_key,
+            can_send_P2pReady: true,
+        })
+    }
+}
+
+// unregister on Drop, the last worker of a node shuts it down
+impl Drop for TcpWorker {
+    fn drop(&mut self) {
+        let is_last = self.node.lock().unwrap().clock_out();
+        if is_last {
+            TCP_NODE_MAP.lock().unwrap().remove(&self.node_key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        connection::json_protocol::{
+            EntryAspectData, EntryData, EntryListData, FetchEntryResultData, ProvidedEntryData,
+            TrackDnaData,
+        },
+        p2p_config::P2pConfig,
+        tcp::tcp_node::MAX_CONNECTIONS,
+    };
+    use crossbeam_channel::{unbounded, Receiver};
+    use std::{
+        net::TcpStream,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    fn example_dna_address() -> Address {
+        "blabladnaAddress".into()
+    }
+
+    fn try_tcp_worker(
+        bind_address: &str,
+        advertise_address: Option<&str>,
+        bootstrap_nodes: &[String],
+        wire_encoding: WireEncoding,
+    ) -> NetResult<(Box<TcpWorker>, Receiver<Protocol>)> {
+        let config =
+            P2pConfig::new_with_tcp_backend(bind_address, advertise_address, bootstrap_nodes)
+                .with_wire_encoding(wire_encoding);
+        let (handler_send, handler_recv) = unbounded::<Protocol>();
+        let worker = TcpWorker::new(
+            NetHandler::new(Box::new(move |r| {
+                handler_send.send(r?)?;
+                Ok(())
+            })),
+            &JsonString::from_json(&config.backend_config.to_string()),
+        )?;
+        Ok((Box::new(worker), handler_recv))
+    }
+
+    fn tcp_worker(
+        bootstrap_nodes: &[String],
+        wire_encoding: WireEncoding,
+    ) -> (Box<TcpWorker>, Receiver<Protocol>) {
+        try_tcp_worker("127.0.0.1:0", None, bootstrap_nodes, wire_encoding).unwrap()
+    }
+
+    /// ticks the workers until the receiver gets a message matching the predicate
+    fn tick_until<F>(
+        workers: &mut [&mut Box<TcpWorker>],
+        receiver: &Receiver<Protocol>,
+        predicate: F,
+    ) -> JsonProtocol
+    where
+        F: Fn(&JsonProtocol) -> bool,
+    {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            for worker in workers.iter_mut() {
+                worker.tick().unwrap();
+            }
+            while let Ok(data) = receiver.try_recv() {
+                if let Ok(msg) = JsonProtocol::try_from(&data) {
+                    if predicate(&msg) {
+                        return msg;
+                    }
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("expected message did not arrive");
+    }
+
+    fn example_entry() -> EntryData {
+        EntryData {
+            entry_address: Address::from("QmEntry"),
+            aspect_list: vec![EntryAspectData {
+                aspect_address: Address::from("QmAspect"),
+                type_hint: "content".to_string(),
+                aspect: b"hello".to_vec(),
+                publish_ts: 0,
+            }],
+        }
+    }
+
+    fn track(worker: &mut Box<TcpWorker>, agent_id: &str) {
+        worker
+            .receive(
+                JsonProtocol::TrackDna(TrackDnaData {
+                    dna_address: example_dna_address(),
+                    agent_id: Address::from(agent_id),
+                })
+                .into(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(tarpaulin, skip)]
+    fn nodes_bound_to_an_unspecified_address_announce_the_advertise_address() {
+        assert!(try_tcp_worker("0.0.0.0:0", None, &[], WireEncoding::Json).is_err());
+        assert!(try_tcp_worker("0.0.0.0:0", Some("0.0.0.0:0"), &[], WireEncoding::Json).is_err());
+
+        let (mut worker_1, recv_1) =
+            try_tcp_worker("0.0.0.0:0", Some("127.0.0.1:0"), &[], WireEncoding::Json).unwrap();
+        let endpoint = worker_1.endpoint().expect("node should listen");
+        assert!(endpoint.starts_with("127.0.0.1:"));
+        assert_ne!(endpoint, "127.0.0.1:0");
+
+        // the other node can connect to the announced address
+        let (mut worker_2, _recv_2) = tcp_worker(&[endpoint], WireEncoding::Json);
+        track(&mut worker_1, "alex");
+        track(&mut worker_2, "billy");
+        tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_1,
+            |msg| match msg {
+                JsonProtocol::PeerConnected(peer) => peer.agent_id == Address::from("billy"),
+                _ => false,
+            },
+        );
+    }
+
+    #[test]
+    #[cfg_attr(tarpaulin, skip)]
+    fn tcp_workers_find_each_other_and_sync_published_entries() {
+        // one node sends MessagePack, the other one JSON
+        let (mut worker_1, recv_1) = tcp_worker(&[], WireEncoding::MessagePack);
+        let (mut worker_2, recv_2) = tcp_worker(
+            &[worker_1.endpoint().expect("node should listen")],
+            WireEncoding::Json,
+        );
+        assert_ne!(worker_1.endpoint(), worker_2.endpoint());
+
+        track(&mut worker_1, "alex");
+        worker_1
+            .receive(
+                JsonProtocol::PublishEntry(ProvidedEntryData {
+                    dna_address: example_dna_address(),
+                    provider_agent_id: Address::from("alex"),
+                    entry: example_entry(),
+                })
+                .into(),
+            )
+            .unwrap();
+        track(&mut worker_2, "billy");
+
+        // alex learns about billy over the network
+        tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_1,
+            |msg| match msg {
+                JsonProtocol::PeerConnected(peer) => peer.agent_id == Address::from("billy"),
+                _ => false,
+            },
+        );
+
+        // billy's node asks alex's for what it holds, which alex's core has to answer
+        let request = tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_1,
+            |msg| match msg {
+                JsonProtocol::HandleGetAuthoringEntryList(_) => true,
+                _ => false,
+            },
+        );
+        let request = match request {
+            JsonProtocol::HandleGetAuthoringEntryList(request) => request,
+            _ => unreachable!(),
+        };
+        let mut address_map = HashMap::new();
+        address_map.insert(Address::from("QmEntry"), vec![Address::from("QmAspect")]);
+        worker_1
+            .receive(
+                JsonProtocol::HandleGetAuthoringEntryListResult(EntryListData {
+                    dna_address: example_dna_address(),
+                    provider_agent_id: Address::from("alex"),
+                    request_id: request.request_id,
+                    address_map,
+                })
+                .into(),
+            )
+            .unwrap();
+        let fetch = tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_1,
+            |msg| match msg {
+                JsonProtocol::HandleFetchEntry(_) => true,
+                _ => false,
+            },
+        );
+        let fetch = match fetch {
+            JsonProtocol::HandleFetchEntry(fetch) => fetch,
+            _ => unreachable!(),
+        };
+        worker_1
+            .receive(
+                JsonProtocol::HandleFetchEntryResult(FetchEntryResultData {
+                    dna_address: example_dna_address(),
+                    provider_agent_id: Address::from("alex"),
+                    request_id: fetch.request_id,
+                    entry: example_entry(),
+                })
+                .into(),
+            )
+            .unwrap();
+
+        // and billy gets to store it
+        let store = tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_2,
+            |msg| match msg {
+                JsonProtocol::HandleStoreEntryAspect(_) => true,
+                _ => false,
+            },
+        );
+        match store {
+            JsonProtocol::HandleStoreEntryAspect(store) => {
+                assert_eq!(store.entry_address, Address::from("QmEntry"));
+                assert_eq!(store.entry_aspect.aspect, b"hello".to_vec());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(tarpaulin, skip)]
+    fn connections_that_do_not_announce_themselves_get_closed() {
+        let (mut worker_1, recv_1) = tcp_worker(&[], WireEncoding::Json);
+        let endpoint = worker_1.endpoint().expect("node should listen");
+        worker_1
+            .node
+            .lock()
+            .unwrap()
+            .set_handshake_timeout(Duration::from_millis(200));
+
+        // idle sockets take up all connections the node keeps
+        let _idle_sockets = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(&endpoint).unwrap())
+            .collect::<Vec<_>>();
+        worker_1.tick().unwrap();
+        thread::sleep(Duration::from_millis(300));
+        worker_1.tick().unwrap();
+
+        // after the handshake timeout other nodes can connect again
+        let (mut worker_2, _recv_2) = tcp_worker(&[endpoint], WireEncoding::Json);
+        track(&mut worker_1, "alex");
+        track(&mut worker_2, "billy");
+        tick_until(
+            &mut [&mut worker_1, &mut worker_2],
+            &recv_1,
+            |msg| match msg {
+                JsonProtocol::PeerConnected(peer) => peer.agent_id == Address::from("billy"),
+                _ => false,
+            },
+        );
+    }
+}
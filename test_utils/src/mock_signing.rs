@@ -11,7 +11,7 @@ use holochain_dpki::{
 use lib3h_sodium::secbuf::SecBuf;
 use jsonrpc_ws_server::jsonrpc_core::{self, types::params::Params, IoHandler};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
@@ -72,11 +72,30 @@ pub fn mock_signer(payload: String, agent_id: &AgentId) -> String { TEST_AGENT_K
         .unwrap()
 }
 
+fn mock_keybundle(agent_id: &AgentId) -> Arc<Mutex<KeyBundle>> {
+    TEST_AGENT_KEYBUNDLES
+        .lock()
+        .unwrap()
+        .get(&agent_id.address())
+        .expect(format!(
+                "Agent {:?} not found in mock registry. \
+                 Test agent keys need to be registered first.", agent_id).as_str())
+        .clone()
+}
+
+/// Public encrypting key of a registered test agent, as returned by the mock of
+/// the `agent/encryption_key` conductor API function.
+pub fn mock_encryption_key(agent_id: &AgentId) -> String {
+    mock_keybundle(agent_id).lock().unwrap().enc_keys.public.clone()
+}
+
 /// Wraps `fn mock_signer(String) -> String` in an `IoHandler` to mock the conductor API
 /// in a way that core can safely assume the conductor API to be present with at least
 /// the `agent/sign` method.
+/// The `blocklist/*` methods work on a blocklist of their own for every mocked API.
 pub fn mock_conductor_api(agent_id: AgentId) -> IoHandler {
     let mut handler = IoHandler::new();
+    let encrypting_agent_id = agent_id.clone();
     handler.add_method("agent/sign", move |params| {
         let params_map = match params {
             Params::Map(map) => Ok(map),
@@ -100,5 +119,61 @@ pub fn mock_conductor_api(agent_id: AgentId) -> IoHandler {
 
         Ok(json!({"payload": payload, "signature": mock_signer(payload, &agent_id)}))
     });
+
+    let id = encrypting_agent_id.clone();
+    handler.add_method("agent/encryption_key", move |_params| {
+        Ok(json!({ "pub_key": mock_encryption_key(&id) }))
+    });
+
+    handler.add_method("agent/decrypt", move |params| {
+        let params_map = match params {
+            Params::Map(map) => Ok(map),
+            _ => Err(jsonrpc_core::Error::invalid_params("expected params map")),
+        }?;
+        let get = |key: &str| {
+            params_map
+                .get(key)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string())
+                .ok_or(jsonrpc_core::Error::invalid_params(format!(
+                    "`{}` param not provided",
+                    key
+                )))
+        };
+        let sender = get("sender")?;
+        let cipher = base64::decode(&get("payload")?)
+            .map_err(|_| jsonrpc_core::Error::invalid_params("payload is not valid base64"))?;
+        let mut message = mock_keybundle(&encrypting_agent_id)
+            .lock()
+            .unwrap()
+            .enc_keys
+            .decrypt_from(&sender, &cipher)
+            .map_err(|err| jsonrpc_core::Error::invalid_params(err.to_string()))?;
+        let message = message.read_lock();
+        Ok(json!({ "payload": base64::encode(&**message) }))
+    });
+
+    let blocklist: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+    let agent_id_param = |params: Params| {
+        match params {
+            Params::Map(map) => map
+                .get("agent_id")
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string())
+                .ok_or(jsonrpc_core::Error::invalid_params("`agent_id` param not provided")),
+            _ => Err(jsonrpc_core::Error::invalid_params("expected params map")),
+        }
+    };
+
+    let b = blocklist.clone();
+    handler.add_method("blocklist/add", move |params| {
+        b.lock().unwrap().insert(agent_id_param(params)?);
+        Ok(json!({"success": true}))
+    });
+
+    handler.add_method("blocklist/contains", move |params| {
+        let blocked = blocklist.lock().unwrap().contains(&agent_id_param(params)?);
+        Ok(json!({ "blocked": blocked }))
+    });
     handler
 }
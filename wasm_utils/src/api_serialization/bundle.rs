@@ -0,0 +1,23 @@
+use holochain_json_api::{error::JsonError, json::*};
+
+/// Allowed input for close_bundle()
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash, DefaultJson)]
+pub enum BundleOnClose {
+    Commit,
+    Discard,
+}
+
+// arguments required for calling start_bundle.
+// `timeout` is in milliseconds, `user_param` is kept with the bundle
+// and shows up in the logs when the bundle gets closed.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash, DefaultJson)]
+pub struct StartBundleArgs {
+    pub timeout: u64,
+    pub user_param: JsonString,
+}
+
+// arguments required for calling close_bundle
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash, DefaultJson)]
+pub struct CloseBundleArgs {
+    pub action: BundleOnClose,
+}
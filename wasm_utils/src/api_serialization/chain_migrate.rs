@@ -0,0 +1,12 @@
+use holochain_core_types::chain_migrate::MigrationDirection;
+use holochain_json_api::{error::JsonError, json::*};
+use holochain_persistence_api::cas::content::Address;
+
+// arguments required for calling commit_chain_migrate.
+// `dna_address` is the DNA on the other end of the migration,
+// the own DNA and the agent get filled in by core.
+#[derive(Deserialize, Debug, Serialize, DefaultJson)]
+pub struct CommitChainMigrateArgs {
+    pub direction: MigrationDirection,
+    pub dna_address: Address,
+}
@@ -1,4 +1,4 @@
-use holochain_core_types::{entry::Entry, signature::Provenance};
+use holochain_core_types::{agent::Base32, entry::Entry, signature::Provenance, time::Timeout};
 
 use holochain_json_api::{error::JsonError, json::*};
 use holochain_persistence_api::cas::content::Address;
@@ -7,23 +7,45 @@ use holochain_persistence_api::cas::content::Address;
 #[derive(Deserialize, Debug, Serialize, DefaultJson, PartialEq, Clone)]
 pub struct CommitEntryOptions {
     pub provenance: Vec<Provenance>,
+    /// Public encrypting keys of the agents that can read an entry of an `encrypted` entry type,
+    /// in addition to the author. Ignored for other entry types.
+    #[serde(default)]
+    pub recipients: Vec<Base32>,
 }
 
 impl Default for CommitEntryOptions {
-    /// The default CommitEntryOptions has no additional provenance.
+    /// The default CommitEntryOptions has no additional provenance and no recipients.
     fn default() -> Self {
-        CommitEntryOptions { provenance: vec![] }
+        CommitEntryOptions {
+            provenance: vec![],
+            recipients: vec![],
+        }
     }
 }
 
 impl CommitEntryOptions {
     pub fn new(provenance: Vec<Provenance>) -> Self {
-        Self { provenance }
+        Self {
+            provenance,
+            recipients: vec![],
+        }
+    }
+
+    /// Options to commit an entry of an `encrypted` entry type that the given agents can read
+    pub fn with_recipients(recipients: Vec<Base32>) -> Self {
+        Self {
+            provenance: vec![],
+            recipients,
+        }
     }
 
     pub fn provenance(&self) -> Vec<Provenance> {
         self.provenance.clone()
     }
+
+    pub fn recipients(&self) -> Vec<Base32> {
+        self.recipients.clone()
+    }
 }
 
 /// The arguments required to execute a commit_entry_result() call.
@@ -47,6 +69,24 @@ impl CommitEntryArgs {
     }
 }
 
+/// The arguments required to execute a commit_countersigned_entry() call.
+#[derive(Deserialize, Debug, Serialize, DefaultJson)]
+pub struct CommitCountersignedEntryArgs {
+    pub entry: Entry,
+    /// Address of the agent that has to countersign the entry
+    pub counterparty: Address,
+    /// How long to wait for the countersignature
+    pub timeout: Timeout,
+}
+
+/// The parameters of the accept_countersignature callback, which decides if the agent
+/// countersigns the entry proposed by agent `from`.
+#[derive(Deserialize, Debug, Serialize, DefaultJson)]
+pub struct AcceptCountersignatureParams {
+    pub from: Address,
+    pub entry: Entry,
+}
+
 /// Represents any useful information to return after
 /// entries are committed
 #[derive(Deserialize, Debug, Clone, Serialize, DefaultJson)]
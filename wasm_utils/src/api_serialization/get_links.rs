@@ -10,6 +10,22 @@ pub struct GetLinksArgs {
     pub options: GetLinksOptions,
 }
 
+/// The arguments of a get_links_count() call. Base, link type and tag regexes and the
+/// status filter are the same as the ones of GetLinksArgs.
+#[derive(Deserialize, Default, Debug, Serialize, Clone, PartialEq, Eq, Hash, DefaultJson)]
+pub struct GetLinksCountArgs {
+    pub entry_address: Address,
+    pub link_type: String,
+    pub tag: String,
+    pub status_request: LinksStatusRequestKind,
+    pub timeout: Timeout,
+}
+
+#[derive(Deserialize, Serialize, Debug, DefaultJson, Clone, PartialEq)]
+pub struct GetLinksCountResult {
+    pub count: usize,
+}
+
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq, Eq, Hash)]
 pub enum LinksStatusRequestKind {
     Live,
@@ -22,11 +38,32 @@ impl Default for LinksStatusRequestKind {
     }
 }
 
+/// Order of the links returned by get_links
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq, Eq, Hash)]
+pub enum LinksSortOrder {
+    /// By the timestamp of the LinkAdd header, oldest link first
+    OldestFirst,
+    /// By the timestamp of the LinkAdd header, latest link first
+    LatestFirst,
+    /// Alphabetically by tag
+    Tag,
+}
+
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq, Hash, Eq)]
 pub struct GetLinksOptions {
     pub status_request: LinksStatusRequestKind,
     pub headers: bool,
     pub timeout: Timeout,
+    /// Order of the returned links. Without one, links come in the order the DHT holds them
+    #[serde(default)]
+    pub sort: Option<LinksSortOrder>,
+    /// Maximum number of links to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Return the links after the one with this cursor,
+    /// as returned in the GetLinksResult of the previous page
+    #[serde(default)]
+    pub cursor: Option<Address>,
 }
 impl Default for GetLinksOptions {
     fn default() -> Self {
@@ -34,6 +71,9 @@ impl Default for GetLinksOptions {
             status_request: LinksStatusRequestKind::default(),
             headers: false,
             timeout: Default::default(),
+            sort: None,
+            limit: None,
+            cursor: None,
         }
     }
 }
@@ -49,11 +89,26 @@ pub struct LinksResult {
 #[derive(Deserialize, Serialize, Debug, DefaultJson)]
 pub struct GetLinksResult {
     links: Vec<LinksResult>,
+    /// Set if the result got cut off by the limit of the options.
+    /// Pass it as the cursor of the options to get the next page.
+    #[serde(default)]
+    cursor: Option<Address>,
 }
 
 impl GetLinksResult {
     pub fn new(links: Vec<LinksResult>) -> GetLinksResult {
-        GetLinksResult { links }
+        GetLinksResult {
+            links,
+            cursor: None,
+        }
+    }
+
+    pub fn with_cursor(links: Vec<LinksResult>, cursor: Option<Address>) -> GetLinksResult {
+        GetLinksResult { links, cursor }
+    }
+
+    pub fn cursor(&self) -> Option<Address> {
+        self.cursor.clone()
     }
 
     pub fn addresses(&self) -> Vec<Address> {
@@ -9,7 +9,9 @@ mod call;
 ///
 /// For the case of HDK-rust we can use the exact same types by
 /// importing this module.
+pub mod bundle;
 pub mod capabilities;
+pub mod chain_migrate;
 pub mod commit_entry;
 pub mod emit_signal;
 pub mod get_entry;
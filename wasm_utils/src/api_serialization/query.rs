@@ -1,6 +1,7 @@
 use holochain_core_types::{
     chain_header::ChainHeader,
     entry::{entry_type::EntryType, Entry},
+    time::Iso8601,
 };
 
 use holochain_json_api::{error::JsonError, json::*};
@@ -58,14 +59,38 @@ pub struct QueryArgs {
     pub options: QueryArgsOptions,
 }
 
+/// Order in which matching chain entries are returned
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum QueryOrder {
+    /// Latest first, the order in which the chain gets walked
+    LatestFirst,
+    /// Oldest first
+    OldestFirst,
+}
+
+impl Default for QueryOrder {
+    fn default() -> QueryOrder {
+        QueryOrder::LatestFirst
+    }
+}
+
 #[derive(Deserialize, Default, Debug, Serialize, DefaultJson)]
 pub struct QueryArgsOptions {
     pub start: usize,
     pub limit: usize,
-    // pub ordering: bool,  // forward or reverse (requires doubly-linked chain perhaps)
-    // pub filter_by: ???,
     pub headers: bool,
     pub entries: bool,
+    /// Only entries whose header timestamp is at or after this time
+    #[serde(default)]
+    pub since: Option<Iso8601>,
+    /// Only entries whose header timestamp is at or before this time
+    #[serde(default)]
+    pub until: Option<Iso8601>,
+    #[serde(default)]
+    pub order: QueryOrder,
+    /// Resume after the header with this address, as returned in QueryPage::cursor
+    #[serde(default)]
+    pub cursor: Option<Address>,
 }
 
 #[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
@@ -75,3 +100,11 @@ pub enum QueryResult {
     Entries(Vec<(Address, Entry)>),
     HeadersWithEntries(Vec<(ChainHeader, Entry)>),
 }
+
+/// What hc_query_page returns: one page of results and, if more entries match, the cursor to
+/// pass in QueryArgsOptions::cursor to get the next page
+#[derive(Deserialize, Debug, Serialize, DefaultJson, Clone, PartialEq)]
+pub struct QueryPage {
+    pub result: QueryResult,
+    pub cursor: Option<Address>,
+}
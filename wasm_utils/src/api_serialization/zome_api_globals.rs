@@ -10,6 +10,7 @@ pub struct ZomeApiGlobals {
     pub agent_address: Address,
     pub agent_initial_hash: HashString,
     pub agent_latest_hash: HashString,
+    pub agent_encryption_key: String,
     pub public_token: Address,
     pub cap_request: Option<CapabilityRequest>,
     pub properties: JsonString,